@@ -0,0 +1,172 @@
+use crate::hooks::HookCall;
+use anyhow::{bail, Result};
+use std::collections::BTreeSet;
+
+const MARKER: &str = "// requires:";
+
+/// A capability a script asks for in its `// requires:` header.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Capability {
+    /// Calling a registered op, `op:fetch_data`
+    Op(String),
+    /// Network access to a host, `net:api.example.com`
+    Net(String),
+}
+
+impl Capability {
+    fn parse(text: &str) -> Result<Self> {
+        let (kind, value) = match text.split_once(':') {
+            Some((kind, value)) if !value.trim().is_empty() => (kind.trim(), value.trim()),
+            _ => bail!("Invalid capability `{}`, expected `kind:value`", text),
+        };
+
+        match kind {
+            "op" => Ok(Capability::Op(value.to_string())),
+            "net" => Ok(Capability::Net(value.to_string())),
+            _ => bail!(
+                "Invalid capability `{}`, unknown kind `{}` (expected op or net)",
+                text,
+                kind
+            ),
+        }
+    }
+}
+
+/// What a run is limited to, the capabilities its header declared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Grant {
+    pub(crate) ops: Vec<String>,
+    /// Hosts `fetch` may reach
+    pub(crate) hosts: BTreeSet<String>,
+}
+
+impl Grant {
+    /// Hook call limiting the run to the granted ops.
+    pub(crate) fn restrict_call(&self) -> HookCall {
+        HookCall::new("restrictOps", format!("{:?}", self.ops))
+    }
+}
+
+/// Check the capabilities declared by `// requires:` comments against the
+/// ones the host approved and return what the run is limited to. Once the
+/// host approved capabilities, a script without the header gets none;
+/// otherwise it runs unrestricted.
+pub(crate) fn enforce(code: &str, approved: &BTreeSet<String>) -> Result<Option<Grant>> {
+    let mut required = BTreeSet::new();
+    let mut declared = false;
+
+    for line in code.lines() {
+        let caps = match line.trim().strip_prefix(MARKER) {
+            Some(caps) => caps,
+            None => continue,
+        };
+        declared = true;
+
+        for cap in caps.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+            required.insert(Capability::parse(cap)?);
+        }
+    }
+
+    if !declared && approved.is_empty() {
+        return Ok(None);
+    }
+
+    let approved = approved
+        .iter()
+        .map(|cap| Capability::parse(cap))
+        .collect::<Result<BTreeSet<_>>>()?;
+    let denied: Vec<_> = required.difference(&approved).collect();
+    if !denied.is_empty() {
+        bail!(
+            "Script requires capabilities the host has not approved: {}",
+            denied
+                .iter()
+                .map(|cap| match cap {
+                    Capability::Op(name) => format!("op:{}", name),
+                    Capability::Net(host) => format!("net:{}", host),
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    let mut grant = Grant {
+        ops: vec![],
+        hosts: BTreeSet::new(),
+    };
+    for cap in required {
+        match cap {
+            Capability::Op(name) => grant.ops.push(name),
+            Capability::Net(host) => {
+                grant.hosts.insert(host);
+            }
+        }
+    }
+    Ok(Some(grant))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approved(caps: &[&str]) -> BTreeSet<String> {
+        caps.iter().map(|cap| cap.to_string()).collect()
+    }
+
+    #[test]
+    fn test_no_header() {
+        assert_eq!(enforce("a + b", &approved(&[])).unwrap(), None);
+
+        let grant = enforce("a + b", &approved(&["op:save"])).unwrap().unwrap();
+        assert_eq!(grant.restrict_call(), HookCall::new("restrictOps", "[]"));
+        assert!(grant.hosts.is_empty());
+    }
+
+    #[test]
+    fn test_approved() {
+        let code = "// requires: net:api.example.com, op:fetch_data\n// requires: op:save\nsave(fetch_data())";
+        let grant = enforce(
+            code,
+            &approved(&[
+                "op:fetch_data",
+                "op:save",
+                "net:api.example.com",
+                "op:other",
+            ]),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            grant.restrict_call(),
+            HookCall::new("restrictOps", r#"["fetch_data", "save"]"#)
+        );
+        assert_eq!(grant.hosts, BTreeSet::from(["api.example.com".to_string()]));
+    }
+
+    #[test]
+    fn test_empty_header_allows_no_ops() {
+        let grant = enforce("// requires:\n1", &approved(&[])).unwrap().unwrap();
+        assert_eq!(grant.restrict_call(), HookCall::new("restrictOps", "[]"));
+    }
+
+    #[test]
+    fn test_not_approved() {
+        let err = enforce(
+            "// requires: op:fetch_data, net:evil.com",
+            &approved(&["op:fetch_data"]),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Script requires capabilities the host has not approved: net:evil.com"
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(enforce("// requires: fs:/etc", &approved(&[])).is_err());
+        assert!(enforce("// requires: op", &approved(&[])).is_err());
+    }
+}
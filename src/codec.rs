@@ -0,0 +1,45 @@
+use anyhow::Result;
+use deno_core::v8;
+use std::fmt::Debug;
+
+/// Controls how bound Rust values become JS values and how the script's
+/// final value becomes the returned string, see [`Builder::codec`](crate::Builder::codec).
+///
+/// For example a codec can bind a decimal type as an instance of a JS class
+/// instead of a lossy number:
+///
+/// ```
+/// use deno_runner::ValueCodec;
+///
+/// struct DecimalCodec;
+///
+/// impl ValueCodec for DecimalCodec {
+///     fn encode(&self, value: &dyn std::fmt::Debug) -> String {
+///         format!("new Decimal({:?})", format!("{:?}", value))
+///     }
+/// }
+/// ```
+pub trait ValueCodec {
+    /// JS expression a bound variable is initialized with.
+    ///
+    /// Defaults to the `Debug` output, which is a valid JS literal for
+    /// numbers, booleans and strings.
+    fn encode(&self, value: &dyn Debug) -> String {
+        format!("{:?}", value)
+    }
+
+    /// Convert the script's final value. Returning `None` falls back to the
+    /// default conversion, including [`RunOptions::number_format`](crate::RunOptions::number_format).
+    fn decode(
+        &self,
+        _scope: &mut v8::HandleScope,
+        _value: v8::Local<v8::Value>,
+    ) -> Result<Option<String>> {
+        Ok(None)
+    }
+}
+
+/// Codec used when the builder isn't given one.
+pub struct DefaultCodec;
+
+impl ValueCodec for DefaultCodec {}
@@ -0,0 +1,145 @@
+use arrow::{
+    array::{Array, ArrayRef, AsArray},
+    datatypes::{
+        DataType, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type,
+        UInt32Type, UInt64Type, UInt8Type,
+    },
+    record_batch::RecordBatch,
+};
+use deno_core::v8;
+use std::collections::BTreeMap;
+
+/// Whether a column of this type can be exposed to scripts.
+pub(crate) fn is_supported(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Boolean
+            | DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Float32
+            | DataType::Float64
+            | DataType::Utf8
+            | DataType::LargeUtf8
+    )
+}
+
+/// Values of a primitive column as a typed array over a copy of its buffer.
+macro_rules! typed_array {
+    ($scope:expr, $column:expr, $arrow:ty, $v8:ident) => {{
+        let values = $column.as_primitive::<$arrow>().values();
+        let bytes = values.inner().as_slice().to_vec().into_boxed_slice();
+        let store = v8::ArrayBuffer::new_backing_store_from_boxed_slice(bytes).make_shared();
+        let buffer = v8::ArrayBuffer::with_backing_store($scope, &store);
+        v8::$v8::new($scope, buffer, 0, values.len())
+            .unwrap()
+            .into()
+    }};
+}
+
+fn uint8_array<'s>(scope: &mut v8::HandleScope<'s>, bytes: Vec<u8>) -> v8::Local<'s, v8::Value> {
+    let len = bytes.len();
+    let store = v8::ArrayBuffer::new_backing_store_from_boxed_slice(bytes.into_boxed_slice());
+    let buffer = v8::ArrayBuffer::with_backing_store(scope, &store.make_shared());
+    v8::Uint8Array::new(scope, buffer, 0, len).unwrap().into()
+}
+
+fn strings<'s, 'a>(
+    scope: &mut v8::HandleScope<'s>,
+    values: impl Iterator<Item = Option<&'a str>>,
+) -> v8::Local<'s, v8::Value> {
+    let elements: Vec<v8::Local<v8::Value>> = values
+        .map(|value| match value {
+            Some(value) => v8::String::new(scope, value).unwrap().into(),
+            None => v8::null(scope).into(),
+        })
+        .collect();
+    v8::Array::new_with_elements(scope, &elements).into()
+}
+
+fn column_values<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    column: &ArrayRef,
+) -> v8::Local<'s, v8::Value> {
+    match column.data_type() {
+        DataType::Int8 => typed_array!(scope, column, Int8Type, Int8Array),
+        DataType::Int16 => typed_array!(scope, column, Int16Type, Int16Array),
+        DataType::Int32 => typed_array!(scope, column, Int32Type, Int32Array),
+        DataType::Int64 => typed_array!(scope, column, Int64Type, BigInt64Array),
+        DataType::UInt8 => typed_array!(scope, column, UInt8Type, Uint8Array),
+        DataType::UInt16 => typed_array!(scope, column, UInt16Type, Uint16Array),
+        DataType::UInt32 => typed_array!(scope, column, UInt32Type, Uint32Array),
+        DataType::UInt64 => typed_array!(scope, column, UInt64Type, BigUint64Array),
+        DataType::Float32 => typed_array!(scope, column, Float32Type, Float32Array),
+        DataType::Float64 => typed_array!(scope, column, Float64Type, Float64Array),
+        DataType::Boolean => {
+            let values = column.as_boolean().values();
+            uint8_array(scope, values.iter().map(u8::from).collect())
+        }
+        DataType::Utf8 => strings(scope, column.as_string::<i32>().iter()),
+        DataType::LargeUtf8 => strings(scope, column.as_string::<i64>().iter()),
+        other => unreachable!("unsupported column type {}", other),
+    }
+}
+
+fn set<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    object: v8::Local<'s, v8::Object>,
+    key: &str,
+    value: v8::Local<'s, v8::Value>,
+) {
+    let key = v8::String::new(scope, key).unwrap();
+    object.set(scope, key.into(), value);
+}
+
+/// Define each record batch as a frozen global table object with its name.
+pub(crate) fn bind(scope: &mut v8::HandleScope, tables: &BTreeMap<String, RecordBatch>) {
+    let global = scope.get_current_context().global(scope);
+
+    for (name, batch) in tables {
+        let columns = v8::Object::new(scope);
+        let nulls = v8::Object::new(scope);
+        let mut schema = vec![];
+
+        for (field, column) in batch.schema().fields().iter().zip(batch.columns()) {
+            let values = column_values(scope, column);
+            set(scope, columns, field.name(), values);
+
+            if column.null_count() > 0 {
+                let mask = (0..column.len()).map(|i| column.is_null(i) as u8).collect();
+                let mask = uint8_array(scope, mask);
+                set(scope, nulls, field.name(), mask);
+            }
+
+            let entry = v8::Object::new(scope);
+            let field_name = v8::String::new(scope, field.name()).unwrap().into();
+            set(scope, entry, "name", field_name);
+            let data_type = v8::String::new(scope, &field.data_type().to_string())
+                .unwrap()
+                .into();
+            set(scope, entry, "type", data_type);
+            entry.set_integrity_level(scope, v8::IntegrityLevel::Frozen);
+            schema.push(entry.into());
+        }
+        columns.set_integrity_level(scope, v8::IntegrityLevel::Frozen);
+        nulls.set_integrity_level(scope, v8::IntegrityLevel::Frozen);
+
+        let table = v8::Object::new(scope);
+        let num_rows = v8::Number::new(scope, batch.num_rows() as f64).into();
+        set(scope, table, "numRows", num_rows);
+        let schema = v8::Array::new_with_elements(scope, &schema);
+        schema.set_integrity_level(scope, v8::IntegrityLevel::Frozen);
+        set(scope, table, "schema", schema.into());
+        set(scope, table, "columns", columns.into());
+        set(scope, table, "nulls", nulls.into());
+        table.set_integrity_level(scope, v8::IntegrityLevel::Frozen);
+
+        let key = v8::String::new(scope, name).unwrap();
+        global.set(scope, key.into(), table.into());
+    }
+}
@@ -0,0 +1,76 @@
+use anyhow::{bail, Result};
+use deno_core::serde_json;
+use std::collections::BTreeSet;
+
+/// Combine named snippets into one script evaluating to an object with
+/// each snippet's result under its name.
+///
+/// Every snippet runs as the body of its own async function, in order: its
+/// declarations don't leak into the other snippets, it can `await`, and the
+/// value it `return`s becomes its result. A snippet that throws fails the
+/// whole script. Names must be unique.
+///
+/// ```
+/// use deno_runner::{compose, serde_json::json, Builder};
+/// use std::collections::HashMap;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let code = compose(&[
+///     ("eligible", "const min = 18; return age >= min"),
+///     ("discount", "const min = 65; return age >= min ? 0.2 : 0"),
+/// ])
+/// .unwrap();
+///
+/// let mut runner = Builder::new().build();
+/// let vars = HashMap::from([("age", 70)]);
+/// let result = runner.run_json(&code, Some(vars)).await.unwrap();
+/// assert_eq!(result, json!({ "eligible": true, "discount": 0.2 }));
+/// # }
+/// ```
+pub fn compose<N, C>(snippets: &[(N, C)]) -> Result<String>
+where
+    N: AsRef<str>,
+    C: AsRef<str>,
+{
+    let mut names = BTreeSet::new();
+    let mut code = String::from("(async () => {\nconst results = {};\n");
+
+    for (name, snippet) in snippets {
+        let name = name.as_ref();
+        if !names.insert(name) {
+            bail!("Snippet `{}` is composed more than once", name);
+        }
+        code.push_str(&format!(
+            "results[{}] = await (async () => {{\n{}\n}})();\n",
+            serde_json::to_string(name)?,
+            snippet.as_ref()
+        ));
+    }
+
+    code.push_str("return results;\n})()");
+    Ok(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compose() {
+        let code = compose(&[("a", "return 1"), ("b\"", "return 2")]).unwrap();
+        assert_eq!(
+            code,
+            "(async () => {\nconst results = {};\n\
+             results[\"a\"] = await (async () => {\nreturn 1\n})();\n\
+             results[\"b\\\"\"] = await (async () => {\nreturn 2\n})();\n\
+             return results;\n})()"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_names() {
+        let err = compose(&[("a", "return 1"), ("a", "return 2")]).unwrap_err();
+        assert_eq!(err.to_string(), "Snippet `a` is composed more than once");
+    }
+}
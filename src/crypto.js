@@ -0,0 +1,15 @@
+;((globalThis) => {
+  // deno_crypto only registers its classes on `__bootstrap`, expose them
+  // the way browsers do
+  const { crypto } = globalThis.__bootstrap
+  const globals = {
+    crypto: crypto.crypto,
+    Crypto: crypto.Crypto,
+    CryptoKey: crypto.CryptoKey,
+    SubtleCrypto: crypto.SubtleCrypto,
+  }
+
+  for (const [name, value] of Object.entries(globals)) {
+    Object.defineProperty(globalThis, name, { value, writable: true, enumerable: false, configurable: true })
+  }
+})(globalThis)
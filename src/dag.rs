@@ -0,0 +1,193 @@
+use crate::{eval::JsonLiteral, DenoRunner};
+use anyhow::{bail, Result};
+use deno_core::{futures::future::join_all, serde_json::Value};
+use std::collections::HashMap;
+
+/// Named scripts with data dependencies between them.
+///
+/// Each node runs on its own runner once all of its dependencies finished,
+/// with the DAG inputs and the JSON results of its dependencies bound as
+/// variables named after them.
+///
+/// ```
+/// use deno_runner::{serde_json::json, Builder, Dag};
+/// use std::collections::HashMap;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let dag = Dag::new()
+///     .node("subtotal", "items.reduce((sum, i) => sum + i.price, 0)", &[])
+///     .node("tax", "subtotal * 0.1", &["subtotal"])
+///     .node("total", "subtotal + tax", &["subtotal", "tax"]);
+///
+/// let inputs = HashMap::from([("items".to_string(), json!([{"price": 10}, {"price": 20}]))]);
+/// let outputs = dag.execute(|| Builder::new().build(), inputs).await.unwrap();
+///
+/// assert_eq!(outputs["total"], json!(33));
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Dag {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    name: String,
+    code: String,
+    deps: Vec<String>,
+}
+
+impl Dag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a script `name` whose code reads the results of `deps`.
+    pub fn node(mut self, name: impl ToString, code: impl ToString, deps: &[&str]) -> Self {
+        self.nodes.push(Node {
+            name: name.to_string(),
+            code: code.to_string(),
+            deps: deps.iter().map(|dep| dep.to_string()).collect(),
+        });
+        self
+    }
+
+    /// Node indexes grouped in levels, every node only depends on nodes of
+    /// earlier levels. Fails on duplicate names, unknown dependencies and
+    /// cycles.
+    fn levels(&self) -> Result<Vec<Vec<usize>>> {
+        let mut index = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if index.insert(node.name.as_str(), i).is_some() {
+                bail!("Duplicate DAG node '{}'", node.name);
+            }
+        }
+
+        let mut pending = vec![0; self.nodes.len()];
+        let mut dependents = vec![vec![]; self.nodes.len()];
+        for (i, node) in self.nodes.iter().enumerate() {
+            for dep in &node.deps {
+                match index.get(dep.as_str()) {
+                    Some(&d) => {
+                        pending[i] += 1;
+                        dependents[d].push(i);
+                    }
+                    None => bail!("DAG node '{}' depends on unknown node '{}'", node.name, dep),
+                }
+            }
+        }
+
+        let mut levels = vec![];
+        let mut ready: Vec<usize> = (0..self.nodes.len()).filter(|&i| pending[i] == 0).collect();
+        let mut done = 0;
+
+        while !ready.is_empty() {
+            let mut next = vec![];
+            for &i in &ready {
+                for &dependent in &dependents[i] {
+                    pending[dependent] -= 1;
+                    if pending[dependent] == 0 {
+                        next.push(dependent);
+                    }
+                }
+            }
+            done += ready.len();
+            levels.push(ready);
+            ready = next;
+        }
+
+        if done != self.nodes.len() {
+            let stuck: Vec<_> = (0..self.nodes.len())
+                .filter(|&i| pending[i] > 0)
+                .map(|i| self.nodes[i].name.as_str())
+                .collect();
+            bail!("DAG has a cycle between nodes {}", stuck.join(", "));
+        }
+
+        Ok(levels)
+    }
+
+    /// Run every node in dependency order and return each node's result.
+    ///
+    /// `make_runner` is called once per node. Nodes of the same level don't
+    /// depend on each other and run concurrently on the calling task, each
+    /// on its own runner.
+    pub async fn execute<F>(
+        &self,
+        make_runner: F,
+        inputs: HashMap<String, Value>,
+    ) -> Result<HashMap<String, Value>>
+    where
+        F: Fn() -> DenoRunner,
+    {
+        let mut outputs = HashMap::new();
+
+        for level in self.levels()? {
+            let mut runners: Vec<DenoRunner> = level.iter().map(|_| make_runner()).collect();
+
+            let runs = level.iter().zip(&mut runners).map(|(&i, runner)| {
+                let node = &self.nodes[i];
+
+                let mut vars: HashMap<String, JsonLiteral> = inputs
+                    .iter()
+                    .map(|(name, value)| (name.clone(), JsonLiteral(value.clone())))
+                    .collect();
+                for dep in &node.deps {
+                    vars.insert(dep.clone(), JsonLiteral(outputs[dep].clone()));
+                }
+
+                async move {
+                    runner
+                        .run_json(&node.code, Some(vars))
+                        .await
+                        .map_err(|err| err.context(format!("DAG node '{}' failed", node.name)))
+                }
+            });
+            let results = join_all(runs).await;
+
+            // Isolates are entered when created and must be dropped in
+            // reverse order
+            while let Some(runner) = runners.pop() {
+                drop(runner);
+            }
+
+            for (&i, output) in level.iter().zip(results) {
+                outputs.insert(self.nodes[i].name.clone(), output?);
+            }
+        }
+
+        Ok(outputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels() {
+        let dag = Dag::new()
+            .node("c", "a + b", &["a", "b"])
+            .node("a", "1", &[])
+            .node("b", "a + 1", &["a"]);
+
+        assert_eq!(dag.levels().unwrap(), vec![vec![1], vec![2], vec![0]]);
+    }
+
+    #[test]
+    fn test_invalid_graphs() {
+        let unknown = Dag::new().node("a", "b", &["b"]);
+        assert!(unknown.levels().is_err());
+
+        let duplicate = Dag::new().node("a", "1", &[]).node("a", "2", &[]);
+        assert!(duplicate.levels().is_err());
+
+        let cycle = Dag::new()
+            .node("a", "b", &["b"])
+            .node("b", "a", &["a"])
+            .node("c", "1", &[]);
+        let err = cycle.levels().unwrap_err();
+        assert_eq!(err.to_string(), "DAG has a cycle between nodes a, b");
+    }
+}
@@ -0,0 +1,163 @@
+use crate::secret::Redactor;
+use anyhow::{bail, Result};
+use deno_core::v8;
+use polars::prelude::{DataFrame, NamedFrom, Series};
+use std::collections::HashMap;
+
+/// Values of a column, typed by the first non-null value.
+enum Values {
+    /// Only nulls so far
+    Null(usize),
+    Boolean(Vec<Option<bool>>),
+    Number(Vec<Option<f64>>),
+    String(Vec<Option<String>>),
+}
+
+impl Values {
+    fn len(&self) -> usize {
+        match self {
+            Values::Null(len) => *len,
+            Values::Boolean(values) => values.len(),
+            Values::Number(values) => values.len(),
+            Values::String(values) => values.len(),
+        }
+    }
+
+    fn push_null(&mut self) {
+        match self {
+            Values::Null(len) => *len += 1,
+            Values::Boolean(values) => values.push(None),
+            Values::Number(values) => values.push(None),
+            Values::String(values) => values.push(None),
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            Values::Null(_) => "null",
+            Values::Boolean(_) => "boolean",
+            Values::Number(_) => "number",
+            Values::String(_) => "string",
+        }
+    }
+
+    fn into_series(self, name: &str) -> Series {
+        match self {
+            Values::Null(len) => Series::new_null(name, len),
+            Values::Boolean(values) => Series::new(name, values),
+            // Integral numbers in the safe range are kept as integers
+            Values::Number(values) if values.iter().flatten().all(|n| is_safe_integer(*n)) => {
+                let values: Vec<Option<i64>> =
+                    values.into_iter().map(|n| n.map(|n| n as i64)).collect();
+                Series::new(name, values)
+            }
+            Values::Number(values) => Series::new(name, values),
+            Values::String(values) => Series::new(name, values),
+        }
+    }
+}
+
+fn is_safe_integer(n: f64) -> bool {
+    n.fract() == 0.0 && n.abs() <= 9_007_199_254_740_991.0
+}
+
+struct Column {
+    name: String,
+    values: Values,
+}
+
+impl Column {
+    fn push(
+        &mut self,
+        scope: &mut v8::HandleScope,
+        redactor: &Redactor,
+        row: usize,
+        value: v8::Local<v8::Value>,
+    ) -> Result<()> {
+        if value.is_null_or_undefined() {
+            self.values.push_null();
+            return Ok(());
+        }
+
+        if let Values::Null(len) = self.values {
+            self.values = if value.is_boolean() {
+                Values::Boolean(vec![None; len])
+            } else if value.is_number() {
+                Values::Number(vec![None; len])
+            } else {
+                Values::String(vec![None; len])
+            };
+        }
+
+        match &mut self.values {
+            Values::Boolean(values) if value.is_boolean() => {
+                values.push(Some(value.boolean_value(scope)))
+            }
+            Values::Number(values) if value.is_number() => values.push(value.number_value(scope)),
+            Values::String(values) if value.is_string() => {
+                values.push(Some(redactor.text(value.to_rust_string_lossy(scope))))
+            }
+            values => bail!(
+                "Row {} has a {} in the {} column `{}`",
+                row,
+                value.type_of(scope).to_rust_string_lossy(scope),
+                values.type_name(),
+                self.name
+            ),
+        }
+        Ok(())
+    }
+}
+
+/// Convert an array of row objects column by column, reading the values
+/// straight from the isolate. Columns are in the order their keys first
+/// appear; rows without a key get a null. Secrets are redacted from
+/// column names and string values.
+pub(crate) fn from_rows(
+    scope: &mut v8::HandleScope,
+    rows: v8::Local<v8::Value>,
+    redactor: &Redactor,
+) -> Result<DataFrame> {
+    let rows = match v8::Local::<v8::Array>::try_from(rows) {
+        Ok(rows) => rows,
+        Err(_) => bail!("The script must return an array of objects for a DataFrame"),
+    };
+
+    let mut columns: Vec<Column> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for i in 0..rows.length() {
+        let row = rows.get_index(scope, i).unwrap();
+        let row = match v8::Local::<v8::Object>::try_from(row) {
+            Ok(row) if !row.is_array() => row,
+            _ => bail!("Row {} is not an object", i),
+        };
+        let keys = row
+            .get_own_property_names(scope, Default::default())
+            .unwrap();
+
+        for k in 0..keys.length() {
+            let key = keys.get_index(scope, k).unwrap();
+            let name = redactor.text(key.to_rust_string_lossy(scope));
+            let column = *index.entry(name.clone()).or_insert_with(|| {
+                columns.push(Column {
+                    name,
+                    values: Values::Null(i as usize),
+                });
+                columns.len() - 1
+            });
+            let value = row.get(scope, key).unwrap();
+            columns[column].push(scope, redactor, i as usize, value)?;
+        }
+        for column in &mut columns {
+            if column.values.len() <= i as usize {
+                column.values.push_null();
+            }
+        }
+    }
+
+    let series = columns
+        .into_iter()
+        .map(|column| column.values.into_series(&column.name))
+        .collect();
+    Ok(DataFrame::new(series)?)
+}
@@ -0,0 +1,93 @@
+use crate::{Builder, Permissions};
+use serde::Serialize;
+
+/// Effective configuration of a [`Builder`] or runner, see
+/// [`Builder::describe`]. Serializes to JSON for attaching to bug reports;
+/// values of bindings, buffers and files are never included.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Description {
+    /// Version of this crate
+    pub version: &'static str,
+    /// Extensions the runtime is created with, in order
+    pub extensions: Vec<&'static str>,
+    /// User registered ops, in registration order
+    pub ops: Vec<&'static str>,
+    /// Ops bound through the fast call path
+    pub fast_ops: Vec<&'static str>,
+    pub limits: Limits,
+    /// `custom` for one set with [`Builder::module_loader`](crate::Builder::module_loader),
+    /// `virtual_fs` for a [`VirtualFs`](crate::VirtualFs) and `none` when imports fail
+    pub module_loader: &'static str,
+    pub streams: Vec<String>,
+    pub lazy_bindings: Vec<String>,
+    /// Shared buffer names with their size in bytes
+    pub shared_buffers: Vec<(String, usize)>,
+    pub string_table_len: usize,
+    pub node_compat: bool,
+    /// See [`Builder::permissions`], `None` when not restricted
+    pub permissions: Option<Permissions>,
+    /// Whether runners are built from a startup snapshot
+    pub snapshot: bool,
+    /// Whether runners can be suspended, see [`Builder::persistent`]
+    pub persistent: bool,
+    /// Names of the JavaScript features turned off, see
+    /// [`Builder::disable_language_feature`]
+    pub disabled_language_features: Vec<&'static str>,
+    /// Number of [`Builder::warmup`] scripts
+    pub warmup_scripts: usize,
+    /// Time to live of memoized results in milliseconds, if memoizing
+    pub memoize_ttl_ms: Option<u128>,
+}
+
+/// Resource limits of a runner, `None` when unlimited.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Limits {
+    pub timeout_ms: Option<u128>,
+    pub max_heap_size: Option<usize>,
+    pub op_payload_limit: Option<usize>,
+    pub parallel_limit: Option<usize>,
+}
+
+pub(crate) fn describe(builder: &Builder) -> Description {
+    let mut extensions = vec!["deno_console"];
+    #[cfg(feature = "url")]
+    extensions.extend(crate::web::extension_names(builder));
+    extensions.extend(["ops", "deno_runner"]);
+
+    Description {
+        version: env!("CARGO_PKG_VERSION"),
+        extensions,
+        ops: builder.ops.iter().map(|op| op.name).collect(),
+        fast_ops: builder.fast_ops.clone(),
+        limits: Limits {
+            timeout_ms: builder.timeout.map(|limit| limit.as_millis()),
+            max_heap_size: builder.max_heap_size,
+            op_payload_limit: builder.op_payload_limit,
+            parallel_limit: builder.parallel_limit,
+        },
+        module_loader: match (&builder.module_loader, &builder.virtual_fs) {
+            (Some(_), _) => "custom",
+            (None, Some(_)) => "virtual_fs",
+            (None, None) => "none",
+        },
+        streams: builder.streams.names(),
+        lazy_bindings: builder.lazy_bindings.names(),
+        shared_buffers: builder
+            .shared_buffers
+            .iter()
+            .map(|(name, buffer)| (name.clone(), buffer.len()))
+            .collect(),
+        string_table_len: builder.string_table.len(),
+        node_compat: builder.node_compat.is_some(),
+        permissions: builder.permissions.clone(),
+        snapshot: builder.snapshot.is_some(),
+        persistent: builder.persistent,
+        disabled_language_features: builder
+            .disabled_features
+            .iter()
+            .map(|feature| feature.name())
+            .collect(),
+        warmup_scripts: builder.warmup.len(),
+        memoize_ttl_ms: builder.memo.as_ref().map(|(_, ttl)| ttl.as_millis()),
+    }
+}
@@ -0,0 +1,171 @@
+use deno_core::serde_json::Value;
+use serde::Serialize;
+
+/// One difference between two JSON results, see [`diff`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    /// JSON pointer (RFC 6901) to the changed value, `""` for the root
+    pub path: String,
+    #[serde(flatten)]
+    pub kind: ChangeKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// Only present in the second result
+    Added { value: Value },
+    /// Only present in the first result
+    Removed { value: Value },
+    /// Present in both with different values
+    Changed { from: Value, to: Value },
+}
+
+/// Structured diff of two JSON results, e.g. the outputs of an old and a new
+/// version of the same script.
+///
+/// Objects are compared key by key and arrays index by index; any other
+/// difference is reported as a single [`ChangeKind::Changed`]. An empty
+/// result means the values are equal.
+///
+/// ```
+/// use deno_runner::{diff, serde_json::json};
+///
+/// let changes = diff(&json!({"total": 10, "tags": ["a"]}), &json!({"total": 12, "tags": ["a", "b"]}));
+///
+/// assert_eq!(changes.len(), 2);
+/// assert_eq!(changes[0].path, "/tags/1");
+/// assert_eq!(changes[1].path, "/total");
+/// ```
+pub fn diff(a: &Value, b: &Value) -> Vec<Change> {
+    let mut changes = vec![];
+    diff_at(&mut String::new(), a, b, &mut changes);
+    changes
+}
+
+fn diff_at(path: &mut String, a: &Value, b: &Value, changes: &mut Vec<Change>) {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                let escaped = key.replace('~', "~0").replace('/', "~1");
+                with_segment(path, &escaped, |path| match (a.get(key), b.get(key)) {
+                    (Some(a), Some(b)) => diff_at(path, a, b, changes),
+                    (Some(a), None) => changes.push(removed(path, a)),
+                    (None, Some(b)) => changes.push(added(path, b)),
+                    (None, None) => unreachable!(),
+                });
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                with_segment(path, &i.to_string(), |path| match (a.get(i), b.get(i)) {
+                    (Some(a), Some(b)) => diff_at(path, a, b, changes),
+                    (Some(a), None) => changes.push(removed(path, a)),
+                    (None, Some(b)) => changes.push(added(path, b)),
+                    (None, None) => unreachable!(),
+                });
+            }
+        }
+        (a, b) if a != b => changes.push(Change {
+            path: path.clone(),
+            kind: ChangeKind::Changed {
+                from: a.clone(),
+                to: b.clone(),
+            },
+        }),
+        _ => {}
+    }
+}
+
+fn with_segment(path: &mut String, segment: &str, f: impl FnOnce(&mut String)) {
+    let len = path.len();
+    path.push('/');
+    path.push_str(segment);
+    f(path);
+    path.truncate(len);
+}
+
+fn added(path: &str, value: &Value) -> Change {
+    Change {
+        path: path.to_string(),
+        kind: ChangeKind::Added {
+            value: value.clone(),
+        },
+    }
+}
+
+fn removed(path: &str, value: &Value) -> Change {
+    Change {
+        path: path.to_string(),
+        kind: ChangeKind::Removed {
+            value: value.clone(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deno_core::serde_json::json;
+
+    #[test]
+    fn test_equal_values() {
+        assert!(diff(
+            &json!({"a": [1, {"b": null}]}),
+            &json!({"a": [1, {"b": null}]})
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_nested_changes() {
+        let a = json!({"a": {"b": 1, "c": 2}, "list": [1, 2, 3], "a/b": 1});
+        let b = json!({"a": {"b": 1, "d": 4}, "list": [1, 5], "a/b": "1"});
+
+        assert_eq!(
+            diff(&a, &b),
+            vec![
+                Change {
+                    path: "/a/c".to_string(),
+                    kind: ChangeKind::Removed { value: json!(2) },
+                },
+                Change {
+                    path: "/a/d".to_string(),
+                    kind: ChangeKind::Added { value: json!(4) },
+                },
+                Change {
+                    path: "/a~1b".to_string(),
+                    kind: ChangeKind::Changed {
+                        from: json!(1),
+                        to: json!("1"),
+                    },
+                },
+                Change {
+                    path: "/list/1".to_string(),
+                    kind: ChangeKind::Changed {
+                        from: json!(2),
+                        to: json!(5),
+                    },
+                },
+                Change {
+                    path: "/list/2".to_string(),
+                    kind: ChangeKind::Removed { value: json!(3) },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_root_change_and_serialization() {
+        let changes = diff(&json!(1), &json!([1]));
+
+        assert_eq!(
+            json!(changes),
+            json!([{"path": "", "op": "changed", "from": 1, "to": [1]}])
+        );
+    }
+}
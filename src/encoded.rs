@@ -0,0 +1,61 @@
+use crate::hooks;
+use anyhow::{anyhow, Result};
+use deno_core::{op, v8, OpDecl, OpState, ZeroCopyBuf};
+
+/// Wire format of the bindings and result of
+/// [`DenoRunner::run_encoded`](crate::DenoRunner::run_encoded), picked with
+/// [`RunOptions::codec`](crate::RunOptions::codec).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Codec {
+    /// UTF-8 JSON text
+    #[default]
+    Json,
+    /// MessagePack, needs the `msgpack` feature. Binary values are passed
+    /// as `Uint8Array`, 64 bit integers outside the safe range as `BigInt`.
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+}
+
+impl Codec {
+    /// Name the codec is registered with in `runtime.js`.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Codec::Json => "json",
+            #[cfg(feature = "msgpack")]
+            Codec::MsgPack => "msgpack",
+        }
+    }
+}
+
+/// Encoded bindings waiting to be read by the `bindEncoded` hook.
+pub(crate) struct EncodedBindings(pub(crate) Vec<u8>);
+
+pub(crate) fn decls() -> Vec<OpDecl> {
+    vec![op_encoded_bindings::decl()]
+}
+
+#[op]
+fn op_encoded_bindings(state: &mut OpState) -> Result<ZeroCopyBuf> {
+    let bindings = state
+        .try_take::<EncodedBindings>()
+        .ok_or_else(|| anyhow!("No encoded bindings for this run"))?;
+
+    Ok(bindings.0.into())
+}
+
+/// Encode `value` with the `encodeResult` hook.
+pub(crate) fn encode_result(
+    scope: &mut v8::HandleScope,
+    codec: Codec,
+    value: v8::Local<v8::Value>,
+) -> Result<Vec<u8>> {
+    let codec_name = v8::String::new(scope, codec.name()).unwrap();
+    let encoded = hooks::call(scope, "encodeResult", &[codec_name.into(), value])
+        .map_err(|err| anyhow!("Failed to encode result as {}: {}", codec.name(), err))?;
+
+    let bytes = v8::Local::<v8::Uint8Array>::try_from(encoded)
+        .map_err(|_| anyhow!("Codec {} did not return a Uint8Array", codec.name()))?;
+    let mut buf = vec![0; bytes.byte_length()];
+    bytes.copy_contents(&mut buf);
+    Ok(buf)
+}
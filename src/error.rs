@@ -0,0 +1,220 @@
+use deno_core::{error::JsError as CoreJsError, serde_json, sourcemap::SourceMap};
+use serde::Serialize;
+use std::{fmt, time::Duration};
+
+/// Errors with a cause the host may want to handle, returned inside the
+/// [`anyhow::Error`] of a run; match them with `err.downcast_ref()`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RunnerError {
+    /// The script ran, but its result does not fit the type asked for with
+    /// [`DenoRunner::run_as`](crate::DenoRunner::run_as).
+    ResultDeserialization {
+        type_name: &'static str,
+        source: serde_json::Error,
+    },
+    /// The run took longer than its time limit, see
+    /// [`Builder::timeout`](crate::Builder::timeout).
+    Timeout(Duration),
+    /// The script was stopped for using more memory than the heap limit,
+    /// in bytes, see [`Builder::max_heap_size`](crate::Builder::max_heap_size).
+    HeapLimitExceeded(usize),
+    /// The script threw, or returned a promise that rejected.
+    Execution(JsError),
+    /// A variable passed to the run has the name of an existing global,
+    /// see [`ConflictPolicy`](crate::ConflictPolicy). `existing` is the
+    /// `typeof` of the global, or `accessor` for a getter.
+    BindingConflict { name: String, existing: String },
+    /// A [`Builder::result_guard`](crate::Builder::result_guard) refused
+    /// the script's result.
+    ResultRejected(RejectReason),
+}
+
+impl fmt::Display for RunnerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunnerError::ResultDeserialization { type_name, source } => {
+                write!(f, "Script result is not a valid {}: {}", type_name, source)
+            }
+            RunnerError::Timeout(limit) => write!(f, "Script timed out after {:?}", limit),
+            RunnerError::HeapLimitExceeded(bytes) => {
+                write!(f, "Script exceeded the heap limit of {} bytes", bytes)
+            }
+            RunnerError::Execution(error) => error.fmt(f),
+            RunnerError::BindingConflict { name, existing } => write!(
+                f,
+                "Variable `{}` would replace the existing global `{}` ({})",
+                name, name, existing
+            ),
+            RunnerError::ResultRejected(reason) => {
+                write!(f, "Script result was rejected: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RunnerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RunnerError::ResultDeserialization { source, .. } => Some(source),
+            RunnerError::Timeout(_)
+            | RunnerError::HeapLimitExceeded(_)
+            | RunnerError::Execution(_)
+            | RunnerError::BindingConflict { .. }
+            | RunnerError::ResultRejected(_) => None,
+        }
+    }
+}
+
+/// Why a [`Builder::result_guard`](crate::Builder::result_guard) refused a
+/// result, shown to the script author in [`ProblemDetails`](crate::ProblemDetails).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RejectReason(String);
+
+impl RejectReason {
+    pub fn new(reason: impl ToString) -> Self {
+        Self(reason.to_string())
+    }
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// An exception thrown by a script, see [`RunnerError::Execution`].
+///
+/// Displays the same way the uncaught exception is reported by V8, e.g.
+/// `Uncaught Error: boom` followed by the stack.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JsError {
+    pub message: String,
+    /// Name of the thrown error's class, e.g. `TypeError`; `None` when
+    /// something other than an error object was thrown
+    pub class: Option<String>,
+    /// Script name where the exception was thrown, see
+    /// [`RunOptions::script_name`](crate::RunOptions::script_name)
+    pub resource_name: Option<String>,
+    /// 1-based line of the throw in the script
+    pub line: Option<i64>,
+    /// 1-based column of the throw in the script
+    pub column: Option<i64>,
+    /// Call stack at the throw, innermost frame first
+    pub frames: Vec<StackFrame>,
+    #[serde(skip)]
+    display: String,
+}
+
+/// A frame of a [`JsError`] stack.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StackFrame {
+    /// `None` for top level code and anonymous functions
+    pub function_name: Option<String>,
+    pub resource_name: Option<String>,
+    pub line: Option<i64>,
+    pub column: Option<i64>,
+}
+
+impl From<CoreJsError> for JsError {
+    fn from(error: CoreJsError) -> Self {
+        let display = error.to_string();
+        let frames: Vec<StackFrame> = error
+            .frames
+            .into_iter()
+            .map(|frame| StackFrame {
+                function_name: frame.function_name.filter(|name| !name.is_empty()),
+                resource_name: frame.file_name,
+                line: frame.line_number,
+                column: frame.column_number,
+            })
+            .collect();
+        let top = frames.first();
+
+        JsError {
+            message: error.message.unwrap_or(error.exception_message),
+            class: error.name,
+            resource_name: top.and_then(|frame| frame.resource_name.clone()),
+            line: top.and_then(|frame| frame.line),
+            column: top.and_then(|frame| frame.column),
+            frames,
+            display,
+        }
+    }
+}
+
+impl JsError {
+    /// Point the frames in `script` at the original source `map` was
+    /// generated from, and the error at its new top frame.
+    fn apply_source_map(&mut self, script: &str, map: &SourceMap) {
+        for frame in &mut self.frames {
+            if frame.resource_name.as_deref() != Some(script) {
+                continue;
+            }
+            let (line, column) = match (frame.line, frame.column) {
+                (Some(line), Some(column)) if line > 0 && column > 0 => (line, column),
+                _ => continue,
+            };
+            if let Some(token) = map.lookup_token(line as u32 - 1, column as u32 - 1) {
+                frame.line = Some(i64::from(token.get_src_line()) + 1);
+                frame.column = Some(i64::from(token.get_src_col()) + 1);
+                if let Some(source) = token.get_source() {
+                    frame.resource_name = Some(source.to_string());
+                }
+            }
+        }
+
+        let top = self.frames.first();
+        self.resource_name = top.and_then(|frame| frame.resource_name.clone());
+        self.line = top.and_then(|frame| frame.line);
+        self.column = top.and_then(|frame| frame.column);
+
+        // Same format as V8's, with the mapped positions
+        let message = match self.display.find("\n    at ") {
+            Some(end) => &self.display[..end],
+            None => &self.display,
+        };
+        let mut display = message.to_string();
+        for frame in &self.frames {
+            let location = format!(
+                "{}:{}:{}",
+                frame.resource_name.as_deref().unwrap_or("<anonymous>"),
+                frame.line.unwrap_or(0),
+                frame.column.unwrap_or(0)
+            );
+            match &frame.function_name {
+                Some(name) => display.push_str(&format!("\n    at {} ({})", name, location)),
+                None => display.push_str(&format!("\n    at {}", location)),
+            }
+        }
+        self.display = display;
+    }
+}
+
+impl fmt::Display for JsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.display)
+    }
+}
+
+/// Wrap an exception thrown by a script in [`RunnerError::Execution`], other
+/// errors are returned as they are.
+pub(crate) fn execution_error(err: anyhow::Error) -> anyhow::Error {
+    match err.downcast::<CoreJsError>() {
+        Ok(error) => RunnerError::Execution(error.into()).into(),
+        Err(err) => err,
+    }
+}
+
+/// Apply `map` to the stack of a [`RunnerError::Execution`] thrown in
+/// `script`, see [`RunOptions::source_map`](crate::RunOptions::source_map).
+pub(crate) fn source_mapped(err: anyhow::Error, script: &str, map: &SourceMap) -> anyhow::Error {
+    match err.downcast::<RunnerError>() {
+        Ok(RunnerError::Execution(mut error)) => {
+            error.apply_source_map(script, map);
+            RunnerError::Execution(error).into()
+        }
+        Ok(error) => error.into(),
+        Err(err) => err,
+    }
+}
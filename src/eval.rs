@@ -0,0 +1,81 @@
+use crate::{Builder, DenoRunner};
+use anyhow::{bail, Result};
+use deno_core::serde_json::Value;
+use std::{cell::RefCell, collections::HashMap, fmt};
+
+thread_local! {
+    /// Default runner of [`eval`] and [`eval_with`], built on the first call
+    /// on each thread. Bound variables are removed before every later run.
+    static RUNNER: RefCell<Option<DenoRunner>> = RefCell::new(None);
+}
+
+/// Run `code` on the thread's default runner. Calls overlapping on the same
+/// thread get a runner of their own.
+async fn run<C, V>(code: C, vars: Option<HashMap<String, V>>) -> Result<String>
+where
+    C: ToString,
+    V: fmt::Display + fmt::Debug,
+{
+    let mut runner = RUNNER
+        .with(|runner| runner.borrow_mut().take())
+        .unwrap_or_else(|| Builder::default().build());
+    let result = runner.run(code, vars).await;
+    RUNNER.with(|cached| *cached.borrow_mut() = Some(runner));
+    result
+}
+
+/// Evaluate `code` on a default runner and return its result. The runner is
+/// reused by later calls on the same thread, so globals a script sets stay
+/// visible to them.
+///
+/// ```
+/// # #[tokio::main]
+/// # async fn main() {
+/// let result = deno_runner::eval("1 + 1").await.unwrap();
+/// assert_eq!(result, "2");
+/// # }
+/// ```
+pub async fn eval<C: ToString>(code: C) -> Result<String> {
+    run::<C, String>(code, None).await
+}
+
+/// Evaluate `code` on a default runner with the keys of the `vars` object
+/// bound as variables.
+///
+/// ```
+/// use deno_runner::serde_json::json;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let result = deno_runner::eval_with("a + b", json!({"a": 1, "b": 2}))
+///     .await
+///     .unwrap();
+/// assert_eq!(result, "3");
+/// # }
+/// ```
+pub async fn eval_with<C: ToString>(code: C, vars: Value) -> Result<String> {
+    let vars: HashMap<String, JsonLiteral> = match vars {
+        Value::Object(map) => map.into_iter().map(|(k, v)| (k, JsonLiteral(v))).collect(),
+        other => bail!(
+            "eval_with expects a JSON object of variables, got {}",
+            other
+        ),
+    };
+
+    run(code, Some(vars)).await
+}
+
+/// Prints the JSON text of a value, which is also a valid JS literal.
+pub(crate) struct JsonLiteral(pub(crate) Value);
+
+impl fmt::Display for JsonLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Debug for JsonLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
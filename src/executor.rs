@@ -0,0 +1,121 @@
+//! The async executor the runner's timers, timeouts and threads use.
+//!
+//! Tokio by default. With the `async-std` or `smol` feature the runner
+//! uses that executor instead, so it can be embedded in projects not on
+//! tokio; ops of the host can use these functions to stay independent of
+//! the executor too:
+//!
+//! ```
+//! use deno_runner::{executor, op};
+//! use std::time::Duration;
+//!
+//! #[op]
+//! async fn wait_ms(ms: u64) {
+//!     executor::sleep(Duration::from_millis(ms)).await;
+//! }
+//! ```
+//!
+//! When both features are enabled `async-std` is used.
+
+use std::{future::Future, time::Duration};
+
+/// Wait for `duration` to pass.
+pub async fn sleep(duration: Duration) {
+    backend::sleep(duration).await
+}
+
+/// Wait for `future` for at most `duration`, `None` if it took longer.
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    backend::timeout(duration, future).await
+}
+
+/// Run `future` in the background on the executor. With tokio this must be
+/// called from within a tokio runtime.
+pub fn spawn<F>(future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    backend::spawn(future)
+}
+
+/// Run `future` to completion on the current thread, for threads that own
+/// a runner like the ones of a [`RunnerPool`](crate::RunnerPool). Must not
+/// be called from async code.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    backend::block_on(future)
+}
+
+#[cfg(not(any(feature = "async-std", feature = "smol")))]
+mod backend {
+    use std::{future::Future, time::Duration};
+
+    thread_local! {
+        /// Built on first use, then reused by every `block_on` of the thread
+        static THREAD_RUNTIME: tokio::runtime::Runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to build the thread's tokio runtime");
+    }
+
+    pub(super) async fn sleep(duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+
+    pub(super) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+        tokio::time::timeout(duration, future).await.ok()
+    }
+
+    pub(super) fn spawn<F: Future<Output = ()> + Send + 'static>(future: F) {
+        tokio::spawn(future);
+    }
+
+    pub(super) fn block_on<F: Future>(future: F) -> F::Output {
+        THREAD_RUNTIME.with(|runtime| runtime.block_on(future))
+    }
+}
+
+#[cfg(feature = "async-std")]
+mod backend {
+    use std::{future::Future, time::Duration};
+
+    pub(super) async fn sleep(duration: Duration) {
+        async_std::task::sleep(duration).await
+    }
+
+    pub(super) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+        async_std::future::timeout(duration, future).await.ok()
+    }
+
+    pub(super) fn spawn<F: Future<Output = ()> + Send + 'static>(future: F) {
+        async_std::task::spawn(future);
+    }
+
+    pub(super) fn block_on<F: Future>(future: F) -> F::Output {
+        async_std::task::block_on(future)
+    }
+}
+
+#[cfg(all(feature = "smol", not(feature = "async-std")))]
+mod backend {
+    use std::{future::Future, time::Duration};
+
+    pub(super) async fn sleep(duration: Duration) {
+        smol::Timer::after(duration).await;
+    }
+
+    pub(super) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+        let timer = async {
+            smol::Timer::after(duration).await;
+            None
+        };
+        smol::future::or(async { Some(future.await) }, timer).await
+    }
+
+    pub(super) fn spawn<F: Future<Output = ()> + Send + 'static>(future: F) {
+        smol::spawn(future).detach();
+    }
+
+    pub(super) fn block_on<F: Future>(future: F) -> F::Output {
+        smol::block_on(future)
+    }
+}
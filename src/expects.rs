@@ -0,0 +1,97 @@
+use anyhow::{bail, Result};
+
+const MARKER: &str = "// @expects";
+const TYPES: &[&str] = &[
+    "any", "array", "bigint", "boolean", "function", "null", "number", "object", "string",
+];
+
+/// Collect the variables declared by `// @expects` comments in the script,
+/// e.g. `// @expects a: number, b?: string`, and return the `expects({...})`
+/// call enforcing them, if any.
+pub(crate) fn from_comments(code: &str) -> Result<Option<String>> {
+    let mut spec = vec![];
+
+    for line in code.lines() {
+        let line = line.trim();
+        let decls = match line.strip_prefix(MARKER) {
+            Some(decls) => decls,
+            None => continue,
+        };
+
+        for decl in decls.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let (name, ty) = match decl.split_once(':') {
+                Some((name, ty)) => (name.trim(), ty.trim()),
+                None => bail!(
+                    "Invalid @expects declaration `{}`, expected `name: type`",
+                    decl
+                ),
+            };
+
+            if !is_identifier(name.trim_end_matches('?')) {
+                bail!(
+                    "Invalid @expects declaration `{}`, `{}` is not a valid name",
+                    decl,
+                    name
+                );
+            }
+            if !TYPES.contains(&ty) {
+                bail!(
+                    "Invalid @expects declaration `{}`, unknown type `{}` (expected one of {})",
+                    decl,
+                    ty,
+                    TYPES.join(", ")
+                );
+            }
+
+            spec.push(format!("{:?}: {:?}", name, ty));
+        }
+    }
+
+    if spec.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(format!("expects({{ {} }})", spec.join(", "))))
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => {}
+        _ => return false,
+    }
+
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_expectations() {
+        assert_eq!(from_comments("a + b").unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_expectations() {
+        let code = r#"
+            // @expects a: number, b?: string
+            // @expects items: array
+            a + b
+        "#;
+
+        assert_eq!(
+            from_comments(code).unwrap().unwrap(),
+            r#"expects({ "a": "number", "b?": "string", "items": "array" })"#
+        );
+    }
+
+    #[test]
+    fn test_invalid_expectations() {
+        assert!(from_comments("// @expects a").is_err());
+        assert!(from_comments("// @expects a: integer").is_err());
+        assert!(from_comments("// @expects a-b: number").is_err());
+    }
+}
@@ -0,0 +1,379 @@
+//! Evaluation of tiny arithmetic and boolean expressions without V8.
+//!
+//! Only side-effect-free expressions over number/boolean literals and bound
+//! variables are handled, e.g. `a * 2 + b > 10 && !flag`. Anything else,
+//! or any case where the result could differ from V8 (type coercion,
+//! exponent formatting), yields `None` and the caller runs the script
+//! normally.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    Number(f64),
+    Bool(bool),
+}
+
+impl Value {
+    fn truthy(self) -> bool {
+        match self {
+            Value::Number(n) => n != 0.0 && !n.is_nan(),
+            Value::Bool(b) => b,
+        }
+    }
+
+    /// Same output as JS `String(value)`, or `None` where Rust and V8
+    /// formatting disagree.
+    fn to_js_string(self) -> Option<String> {
+        let n = match self {
+            Value::Bool(b) => return Some(b.to_string()),
+            Value::Number(n) => n,
+        };
+
+        if n.is_nan() {
+            return Some("NaN".to_string());
+        }
+        if n.is_infinite() {
+            return Some(if n > 0.0 { "Infinity" } else { "-Infinity" }.to_string());
+        }
+        if n == 0.0 {
+            // Also covers -0, which JS prints as "0"
+            return Some("0".to_string());
+        }
+        if n.abs() >= 1e21 || n.abs() < 1e-6 {
+            // JS switches to exponent notation here, Rust doesn't
+            return None;
+        }
+
+        Some(n.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(&'static str),
+}
+
+/// Longest first, so `++` isn't read as two `+`
+const OPERATORS: &[&str] = &[
+    "===", "!==", "==", "!=", "<=", ">=", "&&", "||", "++", "--", "<", ">", "+", "-", "*", "/",
+    "%", "!", "(", ")",
+];
+
+/// Operators assigning to a variable, left to V8
+const UPDATE_OPERATORS: &[&str] = &["++", "--"];
+
+fn tokenize(code: &str) -> Option<Vec<Token>> {
+    let code = code.trim().trim_end_matches(';').trim_end();
+    let mut tokens = vec![];
+    let mut rest = code;
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c.is_ascii_digit() || c == '.' {
+            let len = number_len(rest)?;
+            tokens.push(Token::Number(rest[..len].parse().ok()?));
+            rest = &rest[len..];
+        } else if c.is_ascii_alphabetic() || c == '_' || c == '$' {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..len].to_string()));
+            rest = &rest[len..];
+        } else {
+            let op = OPERATORS.iter().find(|op| rest.starts_with(**op))?;
+            if UPDATE_OPERATORS.contains(op) {
+                return None;
+            }
+            tokens.push(Token::Op(*op));
+            rest = &rest[op.len()..];
+        }
+    }
+
+    Some(tokens)
+}
+
+/// Length of the decimal number literal at the start of `s`, if it is one
+/// this module understands (no hex, octal, BigInt or separators).
+fn number_len(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    let digits = |i: &mut usize| {
+        let start = *i;
+        while *i < bytes.len() && bytes[*i].is_ascii_digit() {
+            *i += 1;
+        }
+        *i - start
+    };
+
+    let int = digits(&mut i);
+    let mut frac = 0;
+    if i < bytes.len() && bytes[i] == b'.' {
+        i += 1;
+        frac = digits(&mut i);
+    }
+    if int + frac == 0 {
+        return None;
+    }
+    if int > 1 && bytes[0] == b'0' {
+        // Legacy octal literal
+        return None;
+    }
+    if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+        i += 1;
+        if i < bytes.len() && (bytes[i] == b'+' || bytes[i] == b'-') {
+            i += 1;
+        }
+        if digits(&mut i) == 0 {
+            return None;
+        }
+    }
+    if i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+        return None;
+    }
+
+    Some(i)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    vars: &'a HashMap<String, Value>,
+}
+
+impl Parser<'_> {
+    fn peek_op(&self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) if ops.contains(op) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn binary(
+        &mut self,
+        ops: &[&'static str],
+        next: fn(&mut Self) -> Option<Value>,
+        apply: fn(&'static str, Value, Value) -> Option<Value>,
+    ) -> Option<Value> {
+        let mut left = next(self)?;
+        while let Some(op) = self.peek_op(ops) {
+            self.pos += 1;
+            let right = next(self)?;
+            left = apply(op, left, right)?;
+        }
+        Some(left)
+    }
+
+    fn or(&mut self) -> Option<Value> {
+        // Both sides are side-effect free, so no short circuit is needed
+        self.binary(&["||"], Self::and, |_, l, r| {
+            Some(if l.truthy() { l } else { r })
+        })
+    }
+
+    fn and(&mut self) -> Option<Value> {
+        self.binary(&["&&"], Self::equality, |_, l, r| {
+            Some(if l.truthy() { r } else { l })
+        })
+    }
+
+    fn equality(&mut self) -> Option<Value> {
+        self.binary(&["===", "!==", "==", "!="], Self::comparison, |op, l, r| {
+            let equal = match (op, l, r) {
+                (_, Value::Number(l), Value::Number(r)) => l == r,
+                (_, Value::Bool(l), Value::Bool(r)) => l == r,
+                // Strict equality never coerces, loose equality would
+                ("===" | "!==", _, _) => false,
+                _ => return None,
+            };
+            Some(Value::Bool(if op.starts_with('!') {
+                !equal
+            } else {
+                equal
+            }))
+        })
+    }
+
+    fn comparison(&mut self) -> Option<Value> {
+        self.binary(&["<=", ">=", "<", ">"], Self::additive, |op, l, r| {
+            let (l, r) = numbers(l, r)?;
+            Some(Value::Bool(match op {
+                "<" => l < r,
+                "<=" => l <= r,
+                ">" => l > r,
+                _ => l >= r,
+            }))
+        })
+    }
+
+    fn additive(&mut self) -> Option<Value> {
+        self.binary(&["+", "-"], Self::multiplicative, |op, l, r| {
+            let (l, r) = numbers(l, r)?;
+            Some(Value::Number(if op == "+" { l + r } else { l - r }))
+        })
+    }
+
+    fn multiplicative(&mut self) -> Option<Value> {
+        self.binary(&["*", "/", "%"], Self::unary, |op, l, r| {
+            let (l, r) = numbers(l, r)?;
+            Some(Value::Number(match op {
+                "*" => l * r,
+                "/" => l / r,
+                _ => l % r,
+            }))
+        })
+    }
+
+    fn unary(&mut self) -> Option<Value> {
+        match self.peek_op(&["!", "-", "+"]) {
+            Some(op) => {
+                self.pos += 1;
+                let value = self.unary()?;
+                match (op, value) {
+                    ("!", value) => Some(Value::Bool(!value.truthy())),
+                    ("-", Value::Number(n)) => Some(Value::Number(-n)),
+                    ("+", Value::Number(n)) => Some(Value::Number(n)),
+                    _ => None,
+                }
+            }
+            None => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Option<Value> {
+        let token = self.tokens.get(self.pos)?.clone();
+        self.pos += 1;
+
+        match token {
+            Token::Number(n) => Some(Value::Number(n)),
+            Token::Ident(name) => match name.as_str() {
+                "true" => Some(Value::Bool(true)),
+                "false" => Some(Value::Bool(false)),
+                _ => self.vars.get(&name).copied(),
+            },
+            Token::Op("(") => {
+                let value = self.or()?;
+                match self.tokens.get(self.pos) {
+                    Some(Token::Op(")")) => {
+                        self.pos += 1;
+                        Some(value)
+                    }
+                    _ => None,
+                }
+            }
+            Token::Op(_) => None,
+        }
+    }
+}
+
+fn numbers(l: Value, r: Value) -> Option<(f64, f64)> {
+    match (l, r) {
+        (Value::Number(l), Value::Number(r)) => Some((l, r)),
+        _ => None,
+    }
+}
+
+/// Parse a bound variable from the JS literal it would be bound as.
+fn parse_var(literal: &str) -> Option<Value> {
+    match literal {
+        "true" => Some(Value::Bool(true)),
+        "false" => Some(Value::Bool(false)),
+        _ => match tokenize(literal)?.as_slice() {
+            [Token::Number(n)] => Some(Value::Number(*n)),
+            [Token::Op("-"), Token::Number(n)] => Some(Value::Number(-n)),
+            _ => None,
+        },
+    }
+}
+
+/// Evaluate `code` if it is a simple expression, with `vars` given as the JS
+/// literals they would be bound as. Returns what V8 would return as the
+/// result string, or `None` when the script has to run in V8.
+pub(crate) fn evaluate<I>(code: &str, vars: I) -> Option<String>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let vars = vars
+        .into_iter()
+        .filter_map(|(name, literal)| Some((name, parse_var(&literal)?)))
+        .collect();
+
+    let mut parser = Parser {
+        tokens: tokenize(code)?,
+        pos: 0,
+        vars: &vars,
+    };
+
+    let value = parser.or()?;
+    if parser.pos != parser.tokens.len() {
+        return None;
+    }
+
+    value.to_js_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(code: &str) -> Option<String> {
+        evaluate(
+            code,
+            [
+                ("a", "1"),
+                ("b", "2.5"),
+                ("flag", "true"),
+                ("name", "\"duyet\""),
+            ]
+            .map(|(name, literal)| (name.to_string(), literal.to_string())),
+        )
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(eval("1 + 1").as_deref(), Some("2"));
+        assert_eq!(eval("0.1 + 0.2").as_deref(), Some("0.30000000000000004"));
+        assert_eq!(eval("a + b * 2").as_deref(), Some("6"));
+        assert_eq!(eval("(a + b) * 2;").as_deref(), Some("7"));
+        assert_eq!(eval("-a % 2").as_deref(), Some("-1"));
+        assert_eq!(eval("1 / 0").as_deref(), Some("Infinity"));
+        assert_eq!(eval("0 / 0").as_deref(), Some("NaN"));
+        assert_eq!(eval("-0").as_deref(), Some("0"));
+        assert_eq!(eval("1.5e3").as_deref(), Some("1500"));
+    }
+
+    #[test]
+    fn test_boolean() {
+        assert_eq!(eval("a < b && flag").as_deref(), Some("true"));
+        assert_eq!(eval("!flag || a").as_deref(), Some("1"));
+        assert_eq!(eval("a === 1").as_deref(), Some("true"));
+        assert_eq!(eval("a !== flag").as_deref(), Some("true"));
+        assert_eq!(eval("0 && flag").as_deref(), Some("0"));
+    }
+
+    #[test]
+    fn test_falls_back_to_v8() {
+        // Strings, coercion, unknown globals, calls and statements
+        assert_eq!(eval("name + 1"), None);
+        assert_eq!(eval("a + flag"), None);
+        assert_eq!(eval("a == flag"), None);
+        assert_eq!(eval("Math.max(a, b)"), None);
+        assert_eq!(eval("let x = 1; x"), None);
+        assert_eq!(eval("undefinedVar + 1"), None);
+        assert_eq!(eval("(a + 1"), None);
+        // Increments and decrements, even where V8 rejects them
+        assert_eq!(eval("a++"), None);
+        assert_eq!(eval("--a"), None);
+        assert_eq!(eval("a+++b"), None);
+        assert_eq!(eval("1--1"), None);
+        // Formats V8 prints with an exponent
+        assert_eq!(eval("1e21"), None);
+        assert_eq!(eval("0.0000001"), None);
+        // Literals V8 reads differently
+        assert_eq!(eval("010"), None);
+        assert_eq!(eval("0x10"), None);
+    }
+}
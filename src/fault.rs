@@ -0,0 +1,105 @@
+use anyhow::Result;
+use deno_core::{op, OpDecl};
+use serde::Serialize;
+use std::{collections::BTreeMap, time::Duration};
+
+/// Failures and delays injected into op calls, for testing how scripts (and
+/// their retry logic) behave when host APIs misbehave. Applied to a run with
+/// [`RunOptions::faults`](crate::RunOptions::faults).
+///
+/// ```
+/// use deno_runner::FaultPlan;
+/// use std::time::Duration;
+///
+/// let plan = FaultPlan::fail_nth("fetch_data", 3)
+///     .and(FaultPlan::delay("fetch_data", Duration::from_millis(50)));
+/// ```
+///
+/// Calls are counted per op from the start of the run. A failing call
+/// throws an `Error` in the script instead of reaching the op.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FaultPlan(BTreeMap<String, Vec<Fault>>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+enum Fault {
+    FailNth { nth: u32 },
+    FailAlways,
+    Delay { ms: u64 },
+}
+
+impl FaultPlan {
+    /// Fail only the `nth` call of `op`, counting from 1.
+    pub fn fail_nth(op: impl ToString, nth: u32) -> Self {
+        assert!(nth > 0, "calls are counted from 1");
+        Self::single(op, Fault::FailNth { nth })
+    }
+
+    /// Fail every call of `op`.
+    pub fn fail_always(op: impl ToString) -> Self {
+        Self::single(op, Fault::FailAlways)
+    }
+
+    /// Hold every call of `op` for `delay` before it runs (or fails).
+    /// Sync ops block the runner for that long.
+    pub fn delay(op: impl ToString, delay: Duration) -> Self {
+        Self::single(
+            op,
+            Fault::Delay {
+                ms: u64::try_from(delay.as_millis()).unwrap_or(u64::MAX),
+            },
+        )
+    }
+
+    /// Combine with the faults of `other`.
+    pub fn and(mut self, other: FaultPlan) -> Self {
+        for (op, faults) in other.0 {
+            self.0.entry(op).or_default().extend(faults);
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn single(op: impl ToString, fault: Fault) -> Self {
+        Self(BTreeMap::from([(op.to_string(), vec![fault])]))
+    }
+}
+
+pub(crate) fn decls() -> Vec<OpDecl> {
+    vec![op_fault_sleep::decl(), op_fault_delay::decl()]
+}
+
+#[op]
+fn op_fault_sleep(ms: u64) {
+    std::thread::sleep(Duration::from_millis(ms));
+}
+
+#[op]
+async fn op_fault_delay(ms: u64) -> Result<()> {
+    crate::executor::sleep(Duration::from_millis(ms)).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deno_core::serde_json::{self, json};
+
+    #[test]
+    fn test_plan_json() {
+        let plan = FaultPlan::fail_nth("fetch", 2)
+            .and(FaultPlan::delay("fetch", Duration::from_millis(10)))
+            .and(FaultPlan::fail_always("save"));
+
+        assert_eq!(
+            serde_json::to_value(&plan).unwrap(),
+            json!({
+                "fetch": [{ "kind": "failNth", "nth": 2 }, { "kind": "delay", "ms": 10 }],
+                "save": [{ "kind": "failAlways" }],
+            })
+        );
+    }
+}
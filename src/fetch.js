@@ -0,0 +1,18 @@
+;((globalThis) => {
+  // deno_fetch only registers its classes on `__bootstrap`, expose them the
+  // way browsers do
+  const { fetch, headers, file, formData } = globalThis.__bootstrap
+  const globals = {
+    fetch: fetch.fetch,
+    Request: fetch.Request,
+    Response: fetch.Response,
+    Headers: headers.Headers,
+    Blob: file.Blob,
+    File: file.File,
+    FormData: formData.FormData,
+  }
+
+  for (const [name, value] of Object.entries(globals)) {
+    Object.defineProperty(globalThis, name, { value, writable: true, enumerable: false, configurable: true })
+  }
+})(globalThis)
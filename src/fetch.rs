@@ -0,0 +1,109 @@
+use crate::Permissions;
+use anyhow::{bail, Result};
+use deno_core::{url::Url, Extension, OpState};
+use std::{collections::BTreeSet, path::Path};
+
+/// Settings of the `fetch` global, see [`Builder::enable_fetch`](crate::Builder::enable_fetch).
+///
+/// Without [`allow_host`](Self::allow_host) calls scripts can fetch from any
+/// host. Reading local files with `file:` URLs is not allowed, unless the
+/// builder's [`Permissions`] allow reading them. With permissions hosts must
+/// be allowed by both.
+#[derive(Debug, Clone, Default)]
+pub struct FetchOptions {
+    pub(crate) user_agent: Option<String>,
+    pub(crate) allowed_hosts: BTreeSet<String>,
+}
+
+impl FetchOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `User-Agent` header sent with every request.
+    pub fn user_agent(mut self, user_agent: impl ToString) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    /// Only let scripts fetch from `host` and the other allowed hosts.
+    pub fn allow_host(mut self, host: impl ToString) -> Self {
+        self.allowed_hosts.insert(host.to_string());
+        self
+    }
+}
+
+/// What scripts may reach through `fetch`, kept in the `OpState`.
+struct FetchPermissions {
+    allowed_hosts: BTreeSet<String>,
+    /// The builder's, checked as well
+    permissions: Option<Permissions>,
+    /// Hosts the running script declared with `net:` capabilities, `None`
+    /// when it isn't restricted
+    declared_hosts: Option<BTreeSet<String>>,
+}
+
+/// Limit `fetch` to `hosts` for the next run, see
+/// [`RunOptions::approve_capabilities`](crate::RunOptions::approve_capabilities).
+pub(crate) fn restrict_hosts(state: &mut OpState, hosts: Option<BTreeSet<String>>) {
+    if let Some(permissions) = state.try_borrow_mut::<FetchPermissions>() {
+        permissions.declared_hosts = hosts;
+    }
+}
+
+impl deno_fetch::FetchPermissions for FetchPermissions {
+    fn check_net_url(&mut self, url: &Url, _api_name: &str) -> Result<()> {
+        let host = url.host_str().unwrap_or_default();
+        if !self.allowed_hosts.is_empty() && !self.allowed_hosts.contains(host) {
+            bail!("fetch from {} is not allowed for this runner", host);
+        }
+        if let Some(hosts) = &self.declared_hosts {
+            if !hosts.contains(host) {
+                bail!(
+                    "fetch from {} is not declared in the script's // requires: header",
+                    host
+                );
+            }
+        }
+        match &self.permissions {
+            Some(permissions) => permissions.check_net(url),
+            None => Ok(()),
+        }
+    }
+
+    fn check_read(&mut self, path: &Path, _api_name: &str) -> Result<()> {
+        match &self.permissions {
+            Some(permissions) => permissions.check_read(path),
+            None => bail!("fetch can't read local files"),
+        }
+    }
+}
+
+/// Extensions providing `fetch`, registered after `deno_web`.
+pub(crate) fn extensions(
+    options: &FetchOptions,
+    permissions: Option<&Permissions>,
+) -> Vec<Extension> {
+    let allowed_hosts = options.allowed_hosts.clone();
+    let permissions = permissions.cloned();
+
+    vec![
+        deno_fetch::init::<FetchPermissions>(deno_fetch::Options {
+            user_agent: options
+                .user_agent
+                .clone()
+                .unwrap_or_else(|| format!("deno_runner/{}", env!("CARGO_PKG_VERSION"))),
+            ..Default::default()
+        }),
+        Extension::builder()
+            .state(move |state| {
+                state.put(FetchPermissions {
+                    allowed_hosts: allowed_hosts.clone(),
+                    permissions: permissions.clone(),
+                    declared_hosts: None,
+                });
+                Ok(())
+            })
+            .build(),
+    ]
+}
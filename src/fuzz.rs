@@ -0,0 +1,85 @@
+//! Harness for fuzzing the runner with arbitrary scripts, e.g. from a
+//! `cargo fuzz` target:
+//!
+//! ```ignore
+//! #![no_main]
+//! libfuzzer_sys::fuzz_target!(|data: &[u8]| {
+//!     let _ = deno_runner::fuzz::run_untrusted_bytes(data);
+//! });
+//! ```
+//!
+//! Crates with their own ops fuzz them with
+//! [`run_untrusted_bytes_with`] and a [`Builder`] registering the ops.
+//! Every input gets a new runner with the limits below, so one input can't
+//! affect the next and a slow or hungry script fails instead of stalling
+//! the fuzzer. A failing script is an `Err`, the fuzzer should only report
+//! crashes.
+
+use crate::{executor, Builder, LanguageFeature, VirtualFs};
+use anyhow::{anyhow, Result};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    time::Duration,
+};
+
+/// Longest an input may run.
+pub const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Largest heap an input may use, in bytes.
+pub const MAX_HEAP_SIZE: usize = 64 * 1024 * 1024;
+
+/// Largest op payload an input may send or receive, in bytes.
+pub const OP_PAYLOAD_LIMIT: usize = 1024 * 1024;
+
+/// Run `bytes`, decoded as lossy UTF-8, as a script on a hardened default
+/// runner.
+pub fn run_untrusted_bytes(bytes: &[u8]) -> Result<String> {
+    run_untrusted_bytes_with(Builder::new(), bytes)
+}
+
+/// Run `bytes` as a script on a runner built from `builder` with the
+/// limits of this module applied, keeping any stricter ones the builder
+/// has. Panics while building or running the runner, e.g. from a failing
+/// [`warmup`](Builder::warmup) script, are returned as errors. Ops
+/// panicking inside V8 still abort the process.
+pub fn run_untrusted_bytes_with(builder: Builder, bytes: &[u8]) -> Result<String> {
+    let code = String::from_utf8_lossy(bytes).into_owned();
+    let builder = harden(builder);
+
+    let outcome = panic::catch_unwind(AssertUnwindSafe(move || {
+        executor::block_on(async move {
+            let mut runner = builder.build();
+            runner.run::<_, String, String>(code, None).await
+        })
+    }));
+
+    outcome.unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Err(anyhow!("deno_runner panicked: {}", message))
+    })
+}
+
+fn harden(mut builder: Builder) -> Builder {
+    builder.timeout = Some(builder.timeout.map_or(TIMEOUT, |limit| limit.min(TIMEOUT)));
+    builder.max_heap_size = Some(
+        builder
+            .max_heap_size
+            .map_or(MAX_HEAP_SIZE, |bytes| bytes.min(MAX_HEAP_SIZE)),
+    );
+    builder.op_payload_limit = Some(
+        builder
+            .op_payload_limit
+            .map_or(OP_PAYLOAD_LIMIT, |bytes| bytes.min(OP_PAYLOAD_LIMIT)),
+    );
+    if builder.virtual_fs.is_none() {
+        // Nothing to import from the host's filesystem
+        builder = builder.virtual_fs(&VirtualFs::new());
+    }
+    builder
+        .disable_language_feature(LanguageFeature::Eval)
+        .disable_language_feature(LanguageFeature::DynamicImport)
+}
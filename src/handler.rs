@@ -0,0 +1,217 @@
+//! Edge-function style hosting: a module exports `handle(request)`, and
+//! every HTTP request an embedder receives is handed to it on a
+//! [`RunnerPool`].
+//!
+//! ```
+//! use deno_runner::{
+//!     handler::{Handler, Request},
+//!     Builder,
+//! };
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let handler = Handler::new(
+//!     2,
+//!     r#"
+//!         export async function handle(request) {
+//!             const { name } = JSON.parse(request.body)
+//!             return { status: 201, headers: { "x-greeting": "1" }, body: { hello: name } }
+//!         }
+//!     "#,
+//!     Builder::new,
+//! );
+//!
+//! let request = Request::new("POST", "https://example.com/greet").body(r#"{"name":"duyet"}"#);
+//! let response = handler.handle(request).await.unwrap();
+//! assert_eq!(response.status, 201);
+//! assert_eq!(response.headers["content-type"], "application/json");
+//! assert_eq!(response.body, r#"{"hello":"duyet"}"#);
+//! # }
+//! ```
+//!
+//! The HTTP server itself is left to the embedder: map its request into a
+//! [`Request`] and the returned [`Response`] back.
+
+use crate::{options::ResultFormat, Builder, MemoryModuleLoader, RunOptions, RunnerPool};
+use anyhow::{anyhow, Result};
+use deno_core::serde_json::{self, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Specifier of the module given to [`Handler::new`]
+const HANDLER_MODULE: &str = "handler:///main.js";
+
+/// An HTTP request, as the handler's `handle` receives it.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Request {
+    pub method: String,
+    pub url: String,
+    /// Lowercase names when set with [`header`](Self::header)
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn new(method: impl ToString, url: impl ToString) -> Self {
+        Self {
+            method: method.to_string(),
+            url: url.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn header(mut self, name: impl ToString, value: impl ToString) -> Self {
+        self.headers
+            .insert(name.to_string().to_lowercase(), value.to_string());
+        self
+    }
+
+    pub fn body(mut self, body: impl ToString) -> Self {
+        self.body = body.to_string();
+        self
+    }
+}
+
+/// An HTTP response, from what the handler's `handle` returned.
+///
+/// `handle` returns `{ status, headers, body }`, each optional, or just
+/// the body. A body that isn't a string is sent as JSON, with a
+/// `content-type: application/json` header unless `headers` set one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Response {
+    pub status: u16,
+    /// Header names are lowercase
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+}
+
+/// What `handle` returned, as an object.
+#[derive(Deserialize)]
+struct ScriptResponse {
+    #[serde(default = "ok")]
+    status: u16,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    body: Value,
+}
+
+fn ok() -> u16 {
+    200
+}
+
+impl Response {
+    fn from_script(value: Value) -> Result<Self> {
+        let response = match value {
+            Value::Object(fields)
+                if ["status", "headers", "body"]
+                    .iter()
+                    .any(|field| fields.contains_key(*field)) =>
+            {
+                serde_json::from_value(Value::Object(fields))
+                    .map_err(|err| anyhow!("Invalid response from handle(): {}", err))?
+            }
+            body => ScriptResponse {
+                status: ok(),
+                headers: BTreeMap::new(),
+                body,
+            },
+        };
+
+        let mut headers: BTreeMap<_, _> = response
+            .headers
+            .into_iter()
+            .map(|(name, value)| (name.to_lowercase(), value))
+            .collect();
+        let body = match response.body {
+            Value::Null => String::new(),
+            Value::String(body) => body,
+            json => {
+                headers
+                    .entry("content-type".to_string())
+                    .or_insert_with(|| "application/json".to_string());
+                json.to_string()
+            }
+        };
+
+        Ok(Self {
+            status: response.status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// Calls the `handle` export of a module for each request, on the runners
+/// of a [`RunnerPool`]. The module is evaluated once per runner, so its
+/// top-level state lives as long as the runner.
+pub struct Handler {
+    pool: RunnerPool,
+    specifier: String,
+}
+
+impl Handler {
+    /// Handle requests with the module `source` on `size` runners, each
+    /// built from `builder()`. The module is served by the runners' module
+    /// loader, which replaces one set on the builder, so it can't import
+    /// other modules: see [`from_pool`](Self::from_pool) for that.
+    pub fn new<F>(size: usize, source: impl ToString, builder: F) -> Self
+    where
+        F: Fn() -> Builder + Send + Sync + 'static,
+    {
+        let source = source.to_string();
+        let pool = RunnerPool::new(size, move || {
+            let loader = MemoryModuleLoader::new().with_module(HANDLER_MODULE, &source);
+            builder().module_loader(loader).build()
+        });
+        Self::from_pool(pool, HANDLER_MODULE)
+    }
+
+    /// Handle requests with the module the runners of `pool` load as
+    /// `specifier`, e.g. from a [`VirtualFs`](crate::VirtualFs) or with
+    /// their own [`ModuleLoader`](crate::ModuleLoader).
+    pub fn from_pool(pool: RunnerPool, specifier: impl ToString) -> Self {
+        Self {
+            pool,
+            specifier: specifier.to_string(),
+        }
+    }
+
+    /// Runners requests are handled on, e.g. to
+    /// [`shutdown`](RunnerPool::shutdown) before a redeploy.
+    pub fn pool(&self) -> &RunnerPool {
+        &self.pool
+    }
+
+    /// Call `handle(request)` on the next free runner. A thrown exception,
+    /// a missing `handle` export or an invalid response fail.
+    pub async fn handle(&self, request: Request) -> Result<Response> {
+        self.handle_with_options(request, RunOptions::default())
+            .await
+    }
+
+    /// Same as [`handle`](Self::handle), with per-request [`RunOptions`]
+    /// such as tags or a timeout.
+    pub async fn handle_with_options(
+        &self,
+        request: Request,
+        mut options: RunOptions,
+    ) -> Result<Response> {
+        let code = format!(
+            r#"(async () => {{
+                const {{ handle }} = await import({:?})
+                if (typeof handle !== "function") throw new TypeError("{} doesn't export a handle function")
+                return handle(request)
+            }})()"#,
+            self.specifier, self.specifier
+        );
+        options.result_format = ResultFormat::Json;
+        let vars = HashMap::from([("request", request)]);
+        let report = self
+            .pool
+            .run_with_options(code, Some(vars), options)
+            .await?;
+
+        Response::from_script(serde_json::from_str(&report.result)?)
+    }
+}
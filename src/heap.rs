@@ -0,0 +1,294 @@
+use crate::hooks::HookCall;
+use anyhow::Result;
+use deno_core::{serde_v8, v8, JsRuntime};
+use std::{
+    cell::Cell,
+    collections::{BTreeMap, BTreeSet},
+    ffi::c_void,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Share of the heap limit in use after a collection from which
+/// [`PressureLevel::Elevated`] is reported
+const ELEVATED_PRESSURE: f64 = 0.8;
+
+/// A garbage collection of a runner's heap, see
+/// [`Builder::on_gc`](crate::Builder::on_gc).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GcEvent {
+    pub kind: GcKind,
+    pub duration: Duration,
+    /// Bytes in use before the collection
+    pub used_before: usize,
+    /// Bytes in use after the collection
+    pub used_after: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GcKind {
+    /// Collection of the young generation
+    Scavenge,
+    MinorMarkSweep,
+    /// Full collection of the heap
+    MarkSweepCompact,
+    IncrementalMarking,
+    ProcessWeakCallbacks,
+}
+
+impl GcKind {
+    fn from_v8(gc_type: v8::GCType) -> Self {
+        match gc_type {
+            v8::GCType::kGCTypeScavenge => GcKind::Scavenge,
+            v8::GCType::kGCTypeMinorMarkSweep => GcKind::MinorMarkSweep,
+            v8::GCType::kGCTypeIncrementalMarking => GcKind::IncrementalMarking,
+            v8::GCType::kGCTypeProcessWeakCallbacks => GcKind::ProcessWeakCallbacks,
+            _ => GcKind::MarkSweepCompact,
+        }
+    }
+}
+
+/// A runner's heap getting close to its limit, see
+/// [`Builder::on_heap_pressure`](crate::Builder::on_heap_pressure).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapPressure {
+    pub level: PressureLevel,
+    /// Bytes in use
+    pub used: usize,
+    /// Bytes the heap may grow to
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PressureLevel {
+    /// Over 80% of the limit is still in use after a collection
+    Elevated,
+    /// The limit was reached, the running script is terminated right after.
+    /// Only reported for runners with a
+    /// [`max_heap_size`](crate::Builder::max_heap_size).
+    Critical,
+}
+
+/// State a pooled run left behind, see
+/// [`RunnerPool::on_heap_leak`](crate::RunnerPool::on_heap_leak).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeapLeak {
+    /// Name of the script, see [`RunOptions::script_name`](crate::RunOptions::script_name)
+    pub script_name: String,
+    /// Tags of the run, see [`RunOptions::tag`](crate::RunOptions::tag)
+    pub tags: BTreeMap<String, String>,
+    /// Bytes in use after a full collection, before the run
+    pub used_before: usize,
+    /// Bytes in use after a full collection, after the run
+    pub used_after: usize,
+    /// Globals the run added, other than its bindings
+    pub new_globals: Vec<String>,
+}
+
+impl HeapLeak {
+    /// Bytes the run left in use.
+    pub fn growth(&self) -> usize {
+        self.used_after.saturating_sub(self.used_before)
+    }
+}
+
+/// What a runner's heap retains between runs.
+pub(crate) struct Retained {
+    used: usize,
+    globals: BTreeSet<String>,
+}
+
+impl Retained {
+    /// Collect all garbage, then measure. Takes as long as a full
+    /// collection.
+    pub(crate) fn measure(runtime: &mut JsRuntime) -> Result<Self> {
+        let isolate = runtime.v8_isolate();
+        isolate.low_memory_notification();
+        let mut stats = v8::HeapStatistics::default();
+        isolate.get_heap_statistics(&mut stats);
+
+        let names = HookCall::new("globalNames", "").run(runtime)?;
+        let scope = &mut runtime.handle_scope();
+        let names = v8::Local::new(scope, names);
+        Ok(Self {
+            used: stats.used_heap_size(),
+            globals: serde_v8::from_v8(scope, names)?,
+        })
+    }
+
+    /// What `self`, measured after a run, retains over `before`.
+    pub(crate) fn leak(
+        &self,
+        before: &Retained,
+        script_name: String,
+        tags: BTreeMap<String, String>,
+    ) -> HeapLeak {
+        HeapLeak {
+            script_name,
+            tags,
+            used_before: before.used,
+            used_after: self.used,
+            new_globals: self.globals.difference(&before.globals).cloned().collect(),
+        }
+    }
+}
+
+pub(crate) type GcCallback = Rc<dyn Fn(&GcEvent)>;
+pub(crate) type PressureCallback = Rc<dyn Fn(&HeapPressure)>;
+
+/// Host callbacks for heap events registered on a [`Builder`](crate::Builder).
+#[derive(Clone, Default)]
+pub(crate) struct HeapHooks {
+    pub(crate) gc: Vec<GcCallback>,
+    pub(crate) pressure: Vec<PressureCallback>,
+}
+
+impl HeapHooks {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.gc.is_empty() && self.pressure.is_empty()
+    }
+
+    /// Call the hooks on the collections of `runtime`'s isolate.
+    pub(crate) fn install(&self, runtime: &mut JsRuntime) {
+        if self.is_empty() {
+            return;
+        }
+
+        let observer = Rc::new(GcObserver {
+            hooks: self.clone(),
+            started: Cell::new(None),
+            pressured: Cell::new(false),
+        });
+        let data = Rc::as_ptr(&observer) as *mut c_void;
+
+        let isolate = runtime.v8_isolate();
+        // The slot keeps the observer alive as long as the isolate
+        isolate.set_slot(observer);
+        isolate.add_gc_prologue_callback(gc_prologue, data, v8::GCType::kGCTypeAll);
+        isolate.add_gc_epilogue_callback(gc_epilogue, data, v8::GCType::kGCTypeAll);
+    }
+
+    fn pressure(&self, pressure: HeapPressure) {
+        for hook in &self.pressure {
+            hook(&pressure);
+        }
+    }
+}
+
+struct GcObserver {
+    hooks: HeapHooks,
+    /// Start and heap usage of the running collection
+    started: Cell<Option<(Instant, usize)>>,
+    /// Whether elevated pressure was reported and usage didn't drop since
+    pressured: Cell<bool>,
+}
+
+fn heap_usage(isolate: *mut v8::Isolate) -> (usize, usize) {
+    let isolate = unsafe { &mut *isolate };
+    let mut stats = v8::HeapStatistics::default();
+    isolate.get_heap_statistics(&mut stats);
+    (stats.used_heap_size(), stats.heap_size_limit())
+}
+
+extern "C" fn gc_prologue(
+    isolate: *mut v8::Isolate,
+    _gc_type: v8::GCType,
+    _flags: v8::GCCallbackFlags,
+    data: *mut c_void,
+) {
+    let observer = unsafe { &*(data as *const GcObserver) };
+    let (used, _) = heap_usage(isolate);
+    observer.started.set(Some((Instant::now(), used)));
+}
+
+extern "C" fn gc_epilogue(
+    isolate: *mut v8::Isolate,
+    gc_type: v8::GCType,
+    _flags: v8::GCCallbackFlags,
+    data: *mut c_void,
+) {
+    let observer = unsafe { &*(data as *const GcObserver) };
+    let (used, limit) = heap_usage(isolate);
+
+    if let Some((started, used_before)) = observer.started.take() {
+        let event = GcEvent {
+            kind: GcKind::from_v8(gc_type),
+            duration: started.elapsed(),
+            used_before,
+            used_after: used,
+        };
+        for hook in &observer.hooks.gc {
+            hook(&event);
+        }
+    }
+
+    let elevated = used as f64 >= limit as f64 * ELEVATED_PRESSURE;
+    if elevated && !observer.pressured.get() {
+        observer.hooks.pressure(HeapPressure {
+            level: PressureLevel::Elevated,
+            used,
+            limit,
+        });
+    }
+    observer.pressured.set(elevated);
+}
+
+/// Heap limit of a runner, see [`Builder::max_heap_size`](crate::Builder::max_heap_size).
+pub(crate) struct HeapLimit {
+    pub(crate) bytes: usize,
+    exceeded: Arc<AtomicBool>,
+    hooks: HeapHooks,
+}
+
+impl HeapLimit {
+    pub(crate) fn create_params(bytes: usize) -> v8::CreateParams {
+        v8::CreateParams::default().heap_limits(0, bytes)
+    }
+
+    /// Terminate the running script when the heap gets close to `bytes`.
+    pub(crate) fn watch(runtime: &mut JsRuntime, bytes: usize, hooks: &HeapHooks) -> Self {
+        let limit = Self {
+            bytes,
+            exceeded: Arc::new(AtomicBool::new(false)),
+            hooks: hooks.clone(),
+        };
+        limit.arm(runtime);
+        limit
+    }
+
+    fn arm(&self, runtime: &mut JsRuntime) {
+        let exceeded = self.exceeded.clone();
+        let isolate = runtime.v8_isolate().thread_safe_handle();
+        let hooks = self.hooks.clone();
+
+        runtime.add_near_heap_limit_callback(move |current, _initial| {
+            hooks.pressure(HeapPressure {
+                level: PressureLevel::Critical,
+                used: current,
+                limit: current,
+            });
+            exceeded.store(true, Ordering::SeqCst);
+            isolate.terminate_execution();
+            // Headroom for the terminated script to unwind
+            current.saturating_mul(2)
+        });
+    }
+
+    /// Whether the limit was hit since the last call. If so, lets the
+    /// isolate run scripts again and restores the original limit.
+    pub(crate) fn take_exceeded(&self, runtime: &mut JsRuntime) -> bool {
+        if !self.exceeded.swap(false, Ordering::SeqCst) {
+            return false;
+        }
+
+        runtime.v8_isolate().cancel_terminate_execution();
+        runtime.remove_near_heap_limit_callback(self.bytes);
+        self.arm(runtime);
+        true
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::{anyhow, Result};
+use deno_core::{v8, JsRuntime};
+
+/// Key of the hooks object on the global object. Scripts can't read
+/// private properties, only the host can.
+const HOOKS_KEY: &str = "deno_runner#hooks";
+
+fn hooks_key<'s>(scope: &mut v8::HandleScope<'s>) -> v8::Local<'s, v8::Private> {
+    let name = v8::String::new(scope, HOOKS_KEY).unwrap();
+    v8::Private::for_api(scope, Some(name))
+}
+
+/// Keep the hooks object `runtime.js` evaluates to, out of the script's
+/// reach. Private properties are part of the heap, so snapshots and
+/// sessions keep it too.
+pub(crate) fn install(runtime: &mut JsRuntime, hooks: v8::Global<v8::Value>) {
+    let scope = &mut runtime.handle_scope();
+    let global = scope.get_current_context().global(scope);
+    let key = hooks_key(scope);
+    let hooks = v8::Local::new(scope, hooks);
+    global.set_private(scope, key, hooks).unwrap();
+}
+
+fn hooks<'s>(scope: &mut v8::HandleScope<'s>) -> Result<v8::Local<'s, v8::Object>> {
+    let global = scope.get_current_context().global(scope);
+    let key = hooks_key(scope);
+    global
+        .get_private(scope, key)
+        .and_then(|hooks| v8::Local::<v8::Object>::try_from(hooks).ok())
+        .ok_or_else(|| anyhow!("runtime.js hooks are not installed"))
+}
+
+/// Names of the hooks, to check none of them reached scripts.
+#[cfg(feature = "sandbox-tests")]
+pub(crate) fn names(runtime: &mut JsRuntime) -> Result<Vec<String>> {
+    let scope = &mut runtime.handle_scope();
+    let hooks = hooks(scope)?;
+    let names = hooks
+        .get_own_property_names(scope, Default::default())
+        .ok_or_else(|| anyhow!("Can't list the hooks"))?;
+    Ok((0..names.length())
+        .map(|i| {
+            names
+                .get_index(scope, i)
+                .unwrap()
+                .to_rust_string_lossy(scope)
+        })
+        .collect())
+}
+
+/// Evaluate a prelude script adding hooks, e.g. `msgpack.js`. It evaluates
+/// to a function, called with the hooks object.
+pub(crate) fn load(runtime: &mut JsRuntime, name: &str, source: &str) -> Result<()> {
+    let prelude = runtime.execute_script(name, source)?;
+    let scope = &mut runtime.handle_scope();
+    let prelude = v8::Local::<v8::Function>::try_from(v8::Local::new(scope, prelude))
+        .map_err(|_| anyhow!("{} must evaluate to a function", name))?;
+    let hooks = hooks(scope)?;
+    let scope = &mut v8::TryCatch::new(scope);
+    let undefined = v8::undefined(scope).into();
+    match prelude.call(scope, undefined, &[hooks.into()]) {
+        Some(_) => Ok(()),
+        None => Err(anyhow!(exception_message(scope))),
+    }
+}
+
+/// Call the `<name>` hook defined by `runtime.js`, turning a thrown
+/// exception into an error.
+pub(crate) fn call<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    name: &str,
+    args: &[v8::Local<v8::Value>],
+) -> Result<v8::Local<'s, v8::Value>> {
+    let scope = &mut v8::TryCatch::new(scope);
+    let hooks = hooks(scope)?;
+    let key = v8::String::new(scope, name).unwrap();
+    let hook = hooks
+        .get(scope, key.into())
+        .and_then(|hook| v8::Local::<v8::Function>::try_from(hook).ok())
+        .ok_or_else(|| anyhow!("Hook {} is not defined", name))?;
+
+    let undefined = v8::undefined(scope).into();
+    match hook.call(scope, undefined, args) {
+        Some(value) => Ok(value),
+        None => Err(anyhow!(exception_message(scope))),
+    }
+}
+
+fn exception_message(scope: &mut v8::TryCatch<v8::HandleScope>) -> String {
+    scope
+        .exception()
+        .map(|e| e.to_rust_string_lossy(scope))
+        .unwrap_or_default()
+}
+
+/// A hook call with its arguments written as JS, usually JSON, for the
+/// settings applied from Rust.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HookCall {
+    pub(crate) name: &'static str,
+    /// Comma separated argument expressions
+    pub(crate) args: String,
+}
+
+impl HookCall {
+    pub(crate) fn new(name: &'static str, args: impl Into<String>) -> Self {
+        Self {
+            name,
+            args: args.into(),
+        }
+    }
+
+    /// Evaluate the arguments and call the hook with them.
+    pub(crate) fn run(&self, runtime: &mut JsRuntime) -> Result<v8::Global<v8::Value>> {
+        let args = if self.args.is_empty() {
+            None
+        } else {
+            let args = format!("[{}\n]", self.args);
+            Some(runtime.execute_script("[runner:hooks]", &args)?)
+        };
+
+        let scope = &mut runtime.handle_scope();
+        let args = match args {
+            Some(args) => {
+                let args = v8::Local::<v8::Array>::try_from(v8::Local::new(scope, args))
+                    .map_err(|_| anyhow!("Arguments of hook {} are not a list", self.name))?;
+                (0..args.length())
+                    .map(|i| args.get_index(scope, i).unwrap())
+                    .collect()
+            }
+            None => vec![],
+        };
+        let value = call(scope, self.name, &args)?;
+        Ok(v8::Global::new(scope, value))
+    }
+}
@@ -0,0 +1,108 @@
+use crate::hooks;
+use anyhow::{anyhow, Result};
+use deno_core::{serde_json, v8};
+use serde::Serialize;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// Most payloads a runner keeps parsed, the least recently used one is
+/// dropped to make room
+const MAX_INTERNED: usize = 16;
+
+/// JSON of a value bound with
+/// [`RunOptions::cached_binding`](crate::RunOptions::cached_binding).
+/// Clones share the text.
+#[derive(Clone)]
+pub(crate) struct Payload {
+    hash: u64,
+    pub(crate) json: Arc<str>,
+}
+
+impl Payload {
+    pub(crate) fn new(value: impl Serialize) -> Result<Self> {
+        let json = serde_json::to_string(&value)?;
+        let mut hasher = DefaultHasher::new();
+        json.hash(&mut hasher);
+        Ok(Self {
+            hash: hasher.finish(),
+            json: Arc::from(json),
+        })
+    }
+}
+
+impl fmt::Debug for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Payload")
+            .field("hash", &self.hash)
+            .field("len", &self.json.len())
+            .finish()
+    }
+}
+
+/// Payloads a runner already parsed, by hash.
+#[derive(Default)]
+pub(crate) struct InternedPayloads {
+    entries: HashMap<u64, Interned>,
+    /// Bumped on every lookup, to find the least recently used entry
+    clock: u64,
+}
+
+struct Interned {
+    json: Arc<str>,
+    value: v8::Global<v8::Value>,
+    used: u64,
+}
+
+impl InternedPayloads {
+    /// Value of `payload`, parsed and deeply frozen the first time it is
+    /// seen. Runs share it, so none of them can change it for the others.
+    pub(crate) fn value<'s>(
+        &mut self,
+        scope: &mut v8::HandleScope<'s>,
+        payload: &Payload,
+    ) -> Result<v8::Local<'s, v8::Value>> {
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(&payload.hash) {
+            // A hash collision parses the payload again
+            if entry.json == payload.json {
+                entry.used = self.clock;
+                return Ok(v8::Local::new(scope, &entry.value));
+            }
+        }
+
+        let json = v8::String::new(scope, &payload.json)
+            .ok_or_else(|| anyhow!("Cached binding is too large"))?;
+        let value =
+            v8::json::parse(scope, json).ok_or_else(|| anyhow!("Invalid cached binding"))?;
+        hooks::call(scope, "deepFreeze", &[value])?;
+
+        if self.entries.len() >= MAX_INTERNED && !self.entries.contains_key(&payload.hash) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(hash, _)| *hash);
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(
+            payload.hash,
+            Interned {
+                json: payload.json.clone(),
+                value: v8::Global::new(scope, value),
+                used: self.clock,
+            },
+        );
+        Ok(value)
+    }
+
+    /// Drop the parsed values, before the runtime is snapshotted.
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
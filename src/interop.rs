@@ -0,0 +1,129 @@
+use crate::RunnerError;
+use anyhow::{anyhow, Result};
+use deno_core::serde_json::{self, Map, Value};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
+
+/// Binds any serializable value, e.g. a Rust enum, as the JS value of its
+/// serde representation.
+///
+/// ```
+/// use deno_runner::{Builder, Json};
+/// use serde::Serialize;
+/// use std::collections::HashMap;
+///
+/// #[derive(Serialize)]
+/// #[serde(tag = "kind", rename_all = "snake_case")]
+/// enum Shape {
+///     Circle { radius: f64 },
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut runner = Builder::new().build();
+/// let vars = HashMap::from([("shape", Json(Shape::Circle { radius: 2.0 }))]);
+/// let result = runner.run("shape.kind + ':' + shape.radius", Some(vars)).await.unwrap();
+///
+/// assert_eq!(result, "circle:2");
+/// # }
+/// ```
+///
+/// A value serde can't write as JSON, like a map with non-string keys, fails
+/// the run when it is bound.
+pub struct Json<T>(pub T);
+
+impl<T: Serialize> fmt::Display for Json<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match serde_json::to_string(&self.0) {
+            Ok(json) => f.write_str(&json),
+            // Evaluated when bound, failing with the reason
+            Err(err) => write!(
+                f,
+                "(() => {{ throw new TypeError({:?}) }})()",
+                err.to_string()
+            ),
+        }
+    }
+}
+
+impl<T: Serialize> fmt::Debug for Json<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Convert a JS discriminated union, an object whose `tag` field names its
+/// variant, into a Rust enum with serde's default (externally tagged)
+/// representation. Other fields of the object are the fields of a struct
+/// variant; an object with only the tag can also be a unit variant.
+///
+/// ```
+/// use deno_runner::{from_union, serde_json::json};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, PartialEq, Deserialize)]
+/// #[serde(rename_all = "snake_case")]
+/// enum Event {
+///     Click { x: i32, y: i32 },
+///     Close,
+/// }
+///
+/// let event: Event = from_union(json!({ "type": "click", "x": 1, "y": 2 }), "type").unwrap();
+/// assert_eq!(event, Event::Click { x: 1, y: 2 });
+///
+/// let event: Event = from_union(json!({ "type": "close" }), "type").unwrap();
+/// assert_eq!(event, Event::Close);
+/// ```
+///
+/// Fails when `value` isn't an object with a string `tag` field, and with
+/// [`RunnerError::ResultDeserialization`] when the variant is unknown or its
+/// fields don't fit.
+pub fn from_union<T: DeserializeOwned>(value: Value, tag: &str) -> Result<T> {
+    let mut fields = match value {
+        Value::Object(fields) => fields,
+        other => {
+            return Err(anyhow!(
+                "Expected an object with a `{}` field for {}, got {}",
+                tag,
+                std::any::type_name::<T>(),
+                other
+            ))
+        }
+    };
+    let variant = match fields.remove(tag) {
+        Some(Value::String(variant)) => variant,
+        Some(other) => {
+            return Err(anyhow!(
+                "The `{}` field must be a string naming a variant of {}, got {}",
+                tag,
+                std::any::type_name::<T>(),
+                other
+            ))
+        }
+        None => {
+            return Err(anyhow!(
+                "Missing the `{}` field naming a variant of {}",
+                tag,
+                std::any::type_name::<T>()
+            ))
+        }
+    };
+
+    let unit = fields.is_empty();
+    let tagged = Value::Object(Map::from_iter([(variant.clone(), Value::Object(fields))]));
+    match serde_json::from_value(tagged) {
+        Ok(value) => Ok(value),
+        Err(_) if unit => {
+            serde_json::from_value(Value::String(variant)).map_err(deserialization::<T>)
+        }
+        Err(source) => Err(deserialization::<T>(source)),
+    }
+}
+
+fn deserialization<T>(source: serde_json::Error) -> anyhow::Error {
+    RunnerError::ResultDeserialization {
+        type_name: std::any::type_name::<T>(),
+        source,
+    }
+    .into()
+}
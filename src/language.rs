@@ -0,0 +1,120 @@
+use crate::hooks::HookCall;
+use anyhow::{bail, Result};
+use deno_core::{
+    futures::future::{self, FutureExt},
+    ModuleLoader, ModuleSourceFuture, ModuleSpecifier,
+};
+use std::{collections::BTreeSet, pin::Pin, rc::Rc};
+
+/// JavaScript features a runner can turn off for the scripts it runs, see
+/// [`Builder::disable_language_feature`](crate::Builder::disable_language_feature).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum LanguageFeature {
+    /// `eval()`, `new Function()` and every other way of compiling code
+    /// from a string, which throw an `EvalError`
+    Eval,
+    /// `import()` expressions, which reject
+    DynamicImport,
+    /// `(?<=...)` and `(?<!...)` in regular expressions. Scripts containing
+    /// either are refused before they run, even inside strings or comments,
+    /// and the `RegExp` constructor throws a `SyntaxError` for them.
+    RegExpLookbehind,
+    /// The `WeakRef` and `FinalizationRegistry` globals, whose behavior
+    /// depends on when the garbage collector runs
+    WeakRefs,
+}
+
+impl LanguageFeature {
+    pub fn name(self) -> &'static str {
+        match self {
+            LanguageFeature::Eval => "eval",
+            LanguageFeature::DynamicImport => "dynamic_import",
+            LanguageFeature::RegExpLookbehind => "regexp_lookbehind",
+            LanguageFeature::WeakRefs => "weak_refs",
+        }
+    }
+}
+
+/// Hook call disabling the features that are removed from the JS side.
+pub(crate) fn init_call(disabled: &BTreeSet<LanguageFeature>) -> Option<HookCall> {
+    let names: Vec<_> = disabled
+        .iter()
+        .filter(|feature| {
+            matches!(
+                feature,
+                LanguageFeature::RegExpLookbehind | LanguageFeature::WeakRefs
+            )
+        })
+        .map(|feature| feature.name())
+        .collect();
+
+    if names.is_empty() {
+        None
+    } else {
+        Some(HookCall::new(
+            "disableLanguageFeatures",
+            format!("{:?}", names),
+        ))
+    }
+}
+
+/// Refuse a script using a feature that can only be caught in its source.
+pub(crate) fn check_script(disabled: &BTreeSet<LanguageFeature>, code: &str) -> Result<()> {
+    if disabled.contains(&LanguageFeature::RegExpLookbehind)
+        && (code.contains("(?<=") || code.contains("(?<!"))
+    {
+        bail!("RegExp lookbehind is disabled for this runner");
+    }
+    Ok(())
+}
+
+/// Module loader failing dynamic imports, wrapped around the runner's own.
+pub(crate) struct NoDynamicImport(pub(crate) Rc<dyn ModuleLoader>);
+
+impl ModuleLoader for NoDynamicImport {
+    fn resolve(&self, specifier: &str, referrer: &str, is_main: bool) -> Result<ModuleSpecifier> {
+        self.0.resolve(specifier, referrer, is_main)
+    }
+
+    fn load(
+        &self,
+        module_specifier: &ModuleSpecifier,
+        maybe_referrer: Option<ModuleSpecifier>,
+        is_dyn_import: bool,
+    ) -> Pin<Box<ModuleSourceFuture>> {
+        if is_dyn_import {
+            let err = anyhow::anyhow!(
+                "Dynamic import is disabled for this runner: {}",
+                module_specifier
+            );
+            return future::ready(Err(err)).boxed_local();
+        }
+        self.0.load(module_specifier, maybe_referrer, is_dyn_import)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_script() {
+        let disabled = BTreeSet::from([LanguageFeature::RegExpLookbehind]);
+
+        assert!(check_script(&disabled, r"/(?<=\$)\d+/.exec(price)").is_err());
+        assert!(check_script(&disabled, r"/(?<!-)\d+/.exec(price)").is_err());
+        assert!(check_script(&disabled, r"/(?<amount>\d+)/.exec(price)").is_ok());
+        assert!(check_script(&BTreeSet::new(), r"/(?<=\$)\d+/").is_ok());
+    }
+
+    #[test]
+    fn test_init_call() {
+        let disabled = BTreeSet::from([LanguageFeature::Eval, LanguageFeature::WeakRefs]);
+        assert_eq!(
+            init_call(&disabled).unwrap(),
+            HookCall::new("disableLanguageFeatures", r#"["weak_refs"]"#)
+        );
+        assert_eq!(init_call(&BTreeSet::from([LanguageFeature::Eval])), None);
+    }
+}
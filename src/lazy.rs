@@ -0,0 +1,67 @@
+use crate::hooks::HookCall;
+use anyhow::{anyhow, Result};
+use deno_core::{
+    futures::{future::LocalBoxFuture, FutureExt},
+    op,
+    serde_json::{self, Value},
+    OpDecl, OpState,
+};
+use serde::Serialize;
+use std::{cell::RefCell, collections::BTreeMap, future::Future, rc::Rc};
+
+type Resolver = Rc<dyn Fn() -> LocalBoxFuture<'static, Result<Value>>>;
+
+/// Bindings registered with [`Builder::lazy_binding`](crate::Builder::lazy_binding),
+/// resolved the first time a script reads them.
+#[derive(Clone, Default)]
+pub(crate) struct LazyBindings(BTreeMap<String, Resolver>);
+
+impl LazyBindings {
+    pub(crate) fn insert<F, Fut, T>(&mut self, name: String, resolve: F)
+    where
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = Result<T>> + 'static,
+        T: Serialize,
+    {
+        let resolver: Resolver = Rc::new(move || {
+            resolve()
+                .map(|value| Ok(serde_json::to_value(value?)?))
+                .boxed_local()
+        });
+
+        self.0.insert(name, resolver);
+    }
+
+    pub(crate) fn names(&self) -> Vec<String> {
+        self.0.keys().cloned().collect()
+    }
+
+    pub(crate) fn init_call(&self) -> Option<HookCall> {
+        if self.0.is_empty() {
+            return None;
+        }
+
+        let names: Vec<_> = self.0.keys().collect();
+        Some(HookCall::new(
+            "defineLazyBindings",
+            serde_json::to_string(&names).unwrap(),
+        ))
+    }
+}
+
+pub(crate) fn decls() -> Vec<OpDecl> {
+    vec![op_lazy_binding::decl()]
+}
+
+#[op]
+async fn op_lazy_binding(state: Rc<RefCell<OpState>>, name: String) -> Result<Value> {
+    let resolve = state
+        .borrow()
+        .borrow::<LazyBindings>()
+        .0
+        .get(&name)
+        .cloned()
+        .ok_or_else(|| anyhow!("Lazy binding '{}' is not registered with this runner", name))?;
+
+    resolve().await
+}
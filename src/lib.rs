@@ -1,20 +1,303 @@
 #![doc = include_str!("../README.md")]
 
 use anyhow::Result;
-use deno_core::{FsModuleLoader, JsRuntime, RuntimeOptions};
-use std::{collections::HashMap, fmt::Display, rc::Rc};
+use deno_core::{
+    futures::{stream, Stream, StreamExt},
+    JsRuntime, RuntimeOptions,
+};
+use options::ResultFormat;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::Display,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
-pub use deno_core::{anyhow, op};
+mod capabilities;
+mod codec;
+#[cfg(feature = "arrow")]
+mod columnar;
+mod compose;
+mod dag;
+#[cfg(feature = "polars")]
+mod dataframe;
+mod describe;
+mod diff;
+mod encoded;
+mod error;
+mod eval;
+pub mod executor;
+mod expects;
+mod fast_path;
+mod fault;
+#[cfg(feature = "fetch")]
+mod fetch;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+pub mod handler;
+mod heap;
+mod hooks;
+mod intern;
+mod interop;
+mod language;
+mod lazy;
+mod memo;
+mod module;
+mod node_compat;
+#[cfg(feature = "npm")]
+mod npm;
+mod options;
+mod permissions;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+mod pool;
+mod problem;
+mod progress;
+mod registry;
+mod report;
+mod resolver;
+#[cfg(feature = "sandbox-tests")]
+pub mod sandbox_tests;
+mod scheduler;
+#[cfg(feature = "schemars")]
+mod schema;
+mod secret;
+mod session;
+mod shared;
+mod snapshot;
+mod stream;
+mod strings;
+mod telemetry;
+pub mod testing;
+mod tier;
+mod timeout;
+mod timers;
+mod var_name;
+mod vfs;
+#[cfg(feature = "url")]
+mod web;
+
+pub use codec::{DefaultCodec, ValueCodec};
+pub use compose::compose;
+pub use dag::Dag;
+pub use deno_core::{anyhow, op, serde_json, v8, FsModuleLoader, ModuleLoader, OpState};
+pub use describe::{Description, Limits};
+pub use diff::{diff, Change, ChangeKind};
+pub use encoded::Codec;
+pub use error::{JsError, RejectReason, RunnerError, StackFrame};
+pub use eval::{eval, eval_with};
+pub use fault::FaultPlan;
+#[cfg(feature = "fetch")]
+pub use fetch::FetchOptions;
+pub use heap::{GcEvent, GcKind, HeapLeak, HeapPressure, PressureLevel};
+pub use interop::{from_union, Json};
+pub use language::LanguageFeature;
+pub use memo::MemoCache;
+pub use module::{MemoryModuleLoader, Module};
+pub use node_compat::NodeCompat;
+#[cfg(feature = "npm")]
+pub use npm::NpmModuleLoader;
+pub use options::{BindingMode, ConflictPolicy, Cycles, NonFinite, NumberFormat, RunOptions};
+pub use permissions::Permissions;
+pub use pool::{RunnerPool, ShutdownOutcome};
+pub use problem::ProblemDetails;
+pub use progress::Progress;
+pub use registry::{NamedRunner, RunnerRegistry};
+pub use report::{BuildReport, ConsoleEntry, ConsoleLevel, OpCall, RunReport, ShadowReport};
+pub use resolver::Resolvers;
+pub use scheduler::{Scheduler, SessionId, SessionMetrics};
+pub use session::{Checkpoint, SessionSnapshot};
+pub use shared::SharedBuffer;
+pub use snapshot::Snapshot;
+pub use strings::StringTable;
+#[cfg(feature = "log")]
+pub use telemetry::LogExporter;
+#[cfg(feature = "tracing")]
+pub use telemetry::TracingExporter;
+pub use telemetry::{NoopExporter, RunInfo, RunStats, StartupKind, TelemetryExporter};
+pub use tier::{set_execution_tier, ExecutionTier};
 pub use tokio::runtime::Runtime;
+pub use var_name::VarName;
+pub use vfs::VirtualFs;
+
+/// What a run produced, before it is turned into a [`RunReport`].
+#[derive(Default)]
+struct Outcome {
+    result: String,
+    exit_code: Option<i32>,
+    op_calls: Vec<OpCall>,
+    console: Vec<ConsoleEntry>,
+}
+
+/// Most items [`DenoRunner::map_stream`] hands to the script at once
+const MAP_BATCH_SIZE: usize = 256;
 
 /// Deno runtime
 pub struct DenoRunner {
     runtime: JsRuntime,
+    /// Configuration this runner was built from, to build sandbox copies
+    config: Builder,
+    codec: Rc<dyn ValueCodec>,
+    telemetry: Rc<dyn TelemetryExporter>,
+    memo: Option<(MemoCache, Duration)>,
+    timeout: Option<Duration>,
+    heap_limit: Option<heap::HeapLimit>,
+    /// Scripts executed so far, settings are reset before every later one
+    runs: usize,
+    /// Secrets bound by the runs so far. Scripts can keep them in globals,
+    /// so they are redacted from everything a later call hands back too.
+    secrets: HashSet<secret::Secret>,
+    /// Values of [`RunOptions::cached_binding`] parsed so far
+    interned: intern::InternedPayloads,
+    build_report: BuildReport,
+    #[cfg(feature = "schemars")]
+    binding_schemas: schema::BindingSchemas,
+    /// Dropped after `runtime`, which holds ops from these libraries
+    #[cfg(feature = "plugins")]
+    _plugins: Vec<plugin::Plugin>,
 }
 
 impl DenoRunner {
+    /// Effective configuration this runner was built with, see
+    /// [`Builder::describe`].
+    pub fn describe(&self) -> Description {
+        self.config.describe()
+    }
+
+    /// Timings collected while this runner was built.
+    pub fn build_report(&self) -> &BuildReport {
+        &self.build_report
+    }
+
+    /// Save this runner, with everything its scripts left behind, to bring
+    /// it back later with [`Builder::resume`]. Only for runners built with
+    /// [`Builder::persistent`].
+    ///
+    /// ```
+    /// use deno_runner::{Builder, SessionSnapshot};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut runner = Builder::new().persistent().build();
+    /// runner
+    ///     .run::<_, String, String>("globalThis.visits = 1", None)
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// let bytes = runner.suspend().unwrap().to_bytes();
+    ///
+    /// let session = SessionSnapshot::from_bytes(&bytes).unwrap();
+    /// let mut runner = Builder::new().resume(&session).unwrap();
+    /// let result = runner
+    ///     .run::<_, String, String>("visits + 1", None)
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(result, "2");
+    /// # }
+    /// ```
+    pub fn suspend(mut self) -> Result<SessionSnapshot> {
+        if !self.config.persistent {
+            anyhow::bail!("Only runners built with Builder::persistent can be suspended");
+        }
+
+        let ops = self
+            .config
+            .op_names()
+            .into_iter()
+            .map(|op| op.to_string())
+            .collect();
+        self.interned.clear();
+        let bytes = self.runtime.snapshot();
+        Ok(SessionSnapshot::new(&bytes, ops, self.runs))
+    }
+
+    /// Save the globals scripts set on this runner, to go back to them
+    /// later with [`restore`](Self::restore), e.g. to undo the last action
+    /// of an interactive session or try something and come back.
+    ///
+    /// ```
+    /// use deno_runner::Builder;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut runner = Builder::new().build();
+    /// runner
+    ///     .run::<_, String, String>("globalThis.cart = ['apple']", None)
+    ///     .await
+    ///     .unwrap();
+    /// let checkpoint = runner.checkpoint().unwrap();
+    ///
+    /// runner
+    ///     .run::<_, String, String>("cart.push('pear'); globalThis.coupon = 'X'", None)
+    ///     .await
+    ///     .unwrap();
+    /// runner.restore(&checkpoint).unwrap();
+    ///
+    /// let result = runner
+    ///     .run::<_, String, String>("`${cart} ${typeof coupon}`", None)
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(result, "apple undefined");
+    /// # }
+    /// ```
+    ///
+    /// Only values with a JSON representation are saved. Functions a script
+    /// defined are left out, and kept as they are by `restore`. Fails when
+    /// a global can't be written as JSON, e.g. it contains itself.
+    pub fn checkpoint(&mut self) -> Result<Checkpoint> {
+        let globals = hooks::HookCall::new("checkpoint", "").run(&mut self.runtime)?;
+        let scope = &mut self.runtime.handle_scope();
+        let globals = v8::Local::new(scope, globals);
+        let globals: BTreeMap<String, String> = deno_core::serde_v8::from_v8(scope, globals)?;
+        Checkpoint::new(globals)
+    }
+
+    /// Put back the globals saved by [`checkpoint`](Self::checkpoint):
+    /// values are reset to the saved ones and globals set since are
+    /// removed.
+    pub fn restore(&mut self, checkpoint: &Checkpoint) -> Result<()> {
+        let globals = serde_json::to_string(&serde_json::to_string(checkpoint.globals())?)?;
+        hooks::HookCall::new("restore", globals).run(&mut self.runtime)?;
+        Ok(())
+    }
+
+    /// Whether a run could read state its memo key doesn't cover: globals
+    /// earlier scripts left behind, or buffers shared with other runners.
+    fn has_persistent_state(&mut self) -> Result<bool> {
+        if !self.config.shared_buffers.is_empty() {
+            return Ok(true);
+        }
+        let globals = hooks::HookCall::new("hasScriptGlobals", "").run(&mut self.runtime)?;
+        let scope = &mut self.runtime.handle_scope();
+        Ok(v8::Local::new(scope, globals).is_true())
+    }
+
+    /// JSON schema document describing every variable declared with
+    /// [`Builder::declare_binding`], for rendering docs to script authors.
+    #[cfg(feature = "schemars")]
+    pub fn describe_bindings(&self) -> serde_json::Value {
+        self.binding_schemas.document()
+    }
+
+    /// Run a script and return its final value as a string.
+    ///
+    /// A runner can run any number of scripts, keeping its isolate warm.
+    /// Top-level `let`, `const` and `class` declarations are scoped to the
+    /// script, bindings and per-run [`RunOptions`] are reset for each run;
+    /// anything a script stores on `globalThis` (or with `var`) stays
+    /// visible to the next one.
+    ///
+    /// A script that evaluates to a promise gives the value the promise
+    /// resolves to, running the event loop (async ops, timers) until it
+    /// settles. A script using top-level `await` runs as the body of an
+    /// async function, so its result is the value it `return`s.
     pub async fn run<C, K, V>(
-        mut self,
+        &mut self,
         custom_code: C,
         vars: Option<HashMap<K, V>>,
     ) -> Result<String>
@@ -23,31 +306,1450 @@ impl DenoRunner {
         K: Display,
         V: Display + std::fmt::Debug,
     {
-        // Bind variable to Deno runtime
-        if let Some(vars) = vars {
-            for (key, value) in vars {
-                self.runtime
-                    .execute_script("[runner]", &format!("let {} = {:?}", key, value))?;
+        let report = self
+            .run_with_options(custom_code, vars, RunOptions::default())
+            .await?;
+
+        Ok(report.result)
+    }
+
+    /// Same as [`run`](Self::run), with per-run [`RunOptions`] and a
+    /// [`RunReport`] describing how the run ended.
+    pub async fn run_with_options<C, K, V>(
+        &mut self,
+        custom_code: C,
+        vars: Option<HashMap<K, V>>,
+        options: RunOptions,
+    ) -> Result<RunReport>
+    where
+        C: ToString,
+        K: Display,
+        V: Display + std::fmt::Debug,
+    {
+        self.run_report(&custom_code.to_string(), vars, options)
+            .await
+    }
+
+    /// Body of [`run_with_options`](Self::run_with_options), keeping the
+    /// runner around so callers can inspect it afterwards.
+    pub(crate) async fn run_report<K, V>(
+        &mut self,
+        custom_code: &str,
+        vars: Option<HashMap<K, V>>,
+        options: RunOptions,
+    ) -> Result<RunReport>
+    where
+        K: Display,
+        V: Display + std::fmt::Debug,
+    {
+        if options.idempotency_key.is_some() && self.memo.is_none() {
+            anyhow::bail!("Idempotency keys need a MemoCache, see Builder::memoize");
+        }
+        let memo_key = match self.memo.is_some() && options.memoizable() {
+            true => {
+                let mut bindings: Vec<_> = vars
+                    .iter()
+                    .flatten()
+                    .map(|(key, value)| (key.to_string(), self.codec.encode(value)))
+                    .collect();
+                // Only pure runs are cached, a simple expression can't call
+                // ops so it is pure too
+                let pure = options.pure_call(custom_code).is_some()
+                    || fast_path::evaluate(custom_code, bindings.clone()).is_some();
+                bindings.extend(
+                    options
+                        .cached_bindings
+                        .iter()
+                        .map(|(name, payload)| (name.clone(), payload.json.to_string())),
+                );
+                let key = memo::key(custom_code, bindings, &options, &self.config);
+                match &options.idempotency_key {
+                    Some(idempotency_key) => Some(key.idempotent(idempotency_key)),
+                    None if pure && !self.has_persistent_state()? => Some(key),
+                    None => None,
+                }
+            }
+            false => None,
+        };
+        let hit = match (&self.memo, &memo_key) {
+            (Some((cache, _)), Some(key)) => cache.get(key)?,
+            _ => None,
+        };
+        let cached = hit.is_some();
+        let startup_kind = if cached {
+            StartupKind::Cached
+        } else {
+            self.startup_kind()
+        };
+
+        let run_id = report::new_run_id();
+        let info = RunInfo {
+            run_id: &run_id,
+            tags: &options.tags,
+        };
+        self.telemetry.run_started(&info);
+        let started = Instant::now();
+
+        let mut binding_names: Vec<_> = vars
+            .iter()
+            .flatten()
+            .map(|(key, _)| key.to_string())
+            .collect();
+        binding_names.sort();
+
+        let outcome = match hit {
+            Some((result, exit_code)) => Ok(Outcome {
+                result,
+                exit_code,
+                ..Outcome::default()
+            }),
+            None => self.run_outcome(custom_code, vars, &options).await,
+        };
+
+        self.secrets.extend(options.secrets.values().cloned());
+        let redactor = self.redactor();
+        let outcome = outcome
+            .map(|outcome| Outcome {
+                result: match options.result_format {
+                    ResultFormat::Text => redactor.text(outcome.result),
+                    ResultFormat::Json => redactor.json(outcome.result),
+                },
+                exit_code: outcome.exit_code,
+                op_calls: outcome
+                    .op_calls
+                    .into_iter()
+                    .map(|call| OpCall {
+                        op: call.op,
+                        args: call
+                            .args
+                            .into_iter()
+                            .map(|arg| redactor.value(arg))
+                            .collect(),
+                    })
+                    .collect(),
+                console: outcome
+                    .console
+                    .into_iter()
+                    .map(|entry| ConsoleEntry {
+                        message: redactor.text(entry.message),
+                        ..entry
+                    })
+                    .collect(),
+            })
+            .map_err(|err| {
+                let err = error::execution_error(err);
+                let err = match &options.source_map {
+                    Some(map) => error::source_mapped(err, options.script_name_or_default(), map),
+                    None => err,
+                };
+                redactor.error(err)
+            });
+
+        match outcome {
+            Ok(Outcome {
+                result,
+                exit_code,
+                op_calls,
+                console,
+            }) => {
+                let lines = |stream| {
+                    console
+                        .iter()
+                        .filter(|entry| entry.stream() == stream)
+                        .flat_map(ConsoleEntry::lines)
+                        .collect()
+                };
+                let stdout = lines(testing::ConsoleStream::Stdout);
+                let stderr = lines(testing::ConsoleStream::Stderr);
+
+                if let (Some((cache, ttl)), Some(key), false) = (&self.memo, memo_key, cached) {
+                    cache.insert(key, result.clone(), exit_code, *ttl);
+                }
+
+                let stats = RunStats {
+                    duration: started.elapsed(),
+                    exit_code,
+                    startup_kind,
+                };
+                self.telemetry.run_finished(&info, &stats);
+
+                Ok(RunReport {
+                    result,
+                    exit_code,
+                    run_id,
+                    tags: options.tags,
+                    op_calls,
+                    cached,
+                    stdout,
+                    stderr,
+                    console,
+                })
+            }
+            Err(err) => {
+                let err = if options.error_context {
+                    err.context(format!(
+                        "script '{}' failed (bindings: {})",
+                        options.script_name_or_default(),
+                        if binding_names.is_empty() {
+                            "none".to_string()
+                        } else {
+                            binding_names.join(", ")
+                        }
+                    ))
+                } else {
+                    err
+                };
+                let err = if options.tags.is_empty() {
+                    err
+                } else {
+                    err.context(format!("run {} ({}) failed", run_id, options.tags_label()))
+                };
+                let stats = RunStats {
+                    duration: started.elapsed(),
+                    exit_code: None,
+                    startup_kind,
+                };
+                self.telemetry.run_failed(&info, &stats, &err);
+
+                Err(err)
+            }
+        }
+    }
+
+    /// Formatted result, exit code, recorded op calls and console output of
+    /// a run.
+    async fn run_outcome<K, V>(
+        &mut self,
+        custom_code: &str,
+        vars: Option<HashMap<K, V>>,
+        options: &RunOptions,
+    ) -> Result<Outcome>
+    where
+        K: Display,
+        V: Display + std::fmt::Debug,
+    {
+        // Guards need the value the fast path doesn't produce, and it only
+        // gives the default text of a result. Scripts the language gates or
+        // the binding checks would refuse take the normal path, which fails
+        // them.
+        if options.fast_path
+            && options.number_format == NumberFormat::Default
+            && options.non_finite.is_none()
+            && options.result_format == ResultFormat::Text
+            && options.secrets.is_empty()
+            && options.cached_bindings.is_empty()
+            && self.config.result_guards.is_empty()
+            && language::check_script(&self.config.disabled_features, custom_code).is_ok()
+        {
+            let literals: Vec<(String, String)> = vars
+                .iter()
+                .flatten()
+                .map(|(key, value)| (key.to_string(), self.codec.encode(value)))
+                .collect();
+
+            if !self.taken_global(&literals, options.binding_conflicts)? {
+                if let Some(result) = fast_path::evaluate(custom_code, literals) {
+                    return Ok(Outcome {
+                        result,
+                        ..Outcome::default()
+                    });
+                }
+            }
+        }
+
+        let (result, exit_code) = self.execute(custom_code, vars, options).await?;
+
+        let op_calls = if options.dry_run {
+            self.take_op_calls()?
+        } else {
+            vec![]
+        };
+        let console = if options.capture_console {
+            self.take_console()?
+        } else {
+            vec![]
+        };
+
+        let mut scope = self.runtime.handle_scope();
+        let result = v8::Local::new(&mut scope, result);
+        let result = match options.result_format {
+            ResultFormat::Json => {
+                json_value(&mut scope, result, options.non_finite, options.cycles)?.to_string()
+            }
+            ResultFormat::Text => match options.non_finite_text(&mut scope, result)? {
+                Some(text) => text,
+                None => match self.codec.decode(&mut scope, result)? {
+                    Some(result) => result,
+                    None => options.number_format.to_string(&mut scope, result)?,
+                },
+            },
+        };
+
+        Ok(Outcome {
+            result,
+            exit_code,
+            op_calls,
+            console,
+        })
+    }
+
+    /// Build a new runner from this runner's configuration, keeping only the
+    /// capabilities listed: `op:<name>` for a registered op, `stream:<name>`
+    /// for a stream and `fs` for the virtual filesystem and module loader.
+    /// Everything else is left out of the child's isolate, so its scripts
+    /// cannot reach it even through `Deno.core.opSync`. The child doesn't
+    /// share the parent's [`MemoCache`].
+    ///
+    /// Fails if this runner doesn't have one of the capabilities, so a child
+    /// never gets more than its parent.
+    pub fn restricted_child<I, S>(&self, caps: I) -> Result<DenoRunner>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut config = self.config.clone();
+        let mut ops = BTreeSet::new();
+        let mut streams = BTreeSet::new();
+        let mut fs = false;
+
+        for cap in caps {
+            let cap = cap.as_ref();
+            let granted = match cap.split_once(':') {
+                Some(("op", name)) => {
+                    ops.insert(name.to_string());
+                    config.ops.iter().any(|op| op.name == name)
+                }
+                Some(("stream", name)) => {
+                    streams.insert(name.to_string());
+                    config.streams.contains(name)
+                }
+                None if cap == "fs" => {
+                    fs = true;
+                    config.virtual_fs.is_some() || config.module_loader.is_some()
+                }
+                _ => anyhow::bail!(
+                    "Invalid capability `{}`, expected op:<name>, stream:<name> or fs",
+                    cap
+                ),
+            };
+
+            if !granted {
+                anyhow::bail!("Parent runner has no capability `{}` to grant", cap);
+            }
+        }
+
+        config.ops.retain(|op| ops.contains(op.name));
+        config.fast_ops.retain(|op| ops.contains(*op));
+        config.streams.retain(|name| streams.contains(name));
+        if !fs {
+            config.virtual_fs = None;
+            config.module_loader = None;
+        }
+        // Results cached by the parent came from runs with more capabilities
+        config.memo = None;
+
+        Ok(config.build())
+    }
+
+    /// Run `old_code` as usual and `new_code` on a fresh runner built from the
+    /// same configuration, then compare both JSON results with [`diff`].
+    ///
+    /// Only the old script decides the outcome: its error is returned as is,
+    /// while the new script's error is captured in the report. The new
+    /// script runs as a [dry run](RunOptions::dry_run), so its op calls get
+    /// the stub answer instead of reaching the host, and without the memo
+    /// cache.
+    pub async fn shadow_run<K, V>(
+        &mut self,
+        old_code: &str,
+        new_code: &str,
+        vars: Option<HashMap<K, V>>,
+    ) -> Result<ShadowReport>
+    where
+        K: Display + Clone,
+        V: Display + std::fmt::Debug + Clone,
+    {
+        let mut config = self.config.clone();
+        config.memo = None;
+        let mut sandbox = config.build();
+        let sandbox_vars = vars.clone();
+
+        let result = self.run_json(old_code, vars).await?;
+        let shadow = sandbox
+            .run_json_with_options(new_code, sandbox_vars, RunOptions::new().dry_run(true))
+            .await
+            .map_err(|err| format!("{:#}", err));
+        // The sandbox doesn't know the secrets bound on this runner
+        let redactor = self.redactor();
+        let shadow = shadow
+            .map(|shadow| redactor.value(shadow))
+            .map_err(|err| redactor.text(err));
+        let changes = match &shadow {
+            Ok(shadow) => diff(&result, shadow),
+            Err(_) => vec![],
+        };
+
+        Ok(ShadowReport {
+            result,
+            shadow,
+            changes,
+        })
+    }
+
+    /// Run the script and return its final value as JSON, so numbers,
+    /// arrays and objects keep their structure. The value goes through
+    /// `JSON.stringify`, so `toJSON()` applies and it gives `null` when the
+    /// value has no JSON representation (`undefined`, functions, symbols).
+    /// A reference closing a cycle is written as `"[Circular]"`, see
+    /// [`RunOptions::cycles`] to fail instead.
+    pub async fn run_json<K, V>(
+        &mut self,
+        custom_code: &str,
+        vars: Option<HashMap<K, V>>,
+    ) -> Result<serde_json::Value>
+    where
+        K: Display,
+        V: Display + std::fmt::Debug,
+    {
+        self.run_json_with_options(custom_code, vars, RunOptions::default())
+            .await
+    }
+
+    /// Run the script and deserialize its final value, as given by
+    /// [`run_json`](Self::run_json), into `T`. Fails with
+    /// [`RunnerError::ResultDeserialization`] when the value doesn't fit.
+    pub async fn run_as<T, K, V>(
+        &mut self,
+        custom_code: &str,
+        vars: Option<HashMap<K, V>>,
+    ) -> Result<T>
+    where
+        T: DeserializeOwned,
+        K: Display,
+        V: Display + std::fmt::Debug,
+    {
+        let value = self.run_json(custom_code, vars).await?;
+
+        serde_json::from_value(value).map_err(|source| {
+            RunnerError::ResultDeserialization {
+                type_name: std::any::type_name::<T>(),
+                source,
+            }
+            .into()
+        })
+    }
+
+    /// Run a script returning an array of objects, e.g. rows of an
+    /// aggregation, and convert it column by column into a polars
+    /// `DataFrame` without going through JSON.
+    ///
+    /// Columns are in the order their keys first appear, rows missing a key
+    /// get a null there, as do `null` and `undefined` values. A column's
+    /// type is that of its first value: booleans give `Boolean`, strings
+    /// `String` and numbers `Int64` when they are all integers or `Float64`
+    /// otherwise. Fails when the result isn't an array of objects or a
+    /// column mixes types. Like every call returning script output, secrets
+    /// bound on this runner are redacted and result guards apply.
+    #[cfg(feature = "polars")]
+    pub async fn run_dataframe<K, V>(
+        &mut self,
+        custom_code: &str,
+        vars: Option<HashMap<K, V>>,
+    ) -> Result<polars::frame::DataFrame>
+    where
+        K: Display,
+        V: Display + std::fmt::Debug,
+    {
+        // `execute` checks the result guards
+        let executed = self
+            .execute(custom_code, vars, &RunOptions::default())
+            .await;
+
+        let redactor = secret::Redactor::new(self.secrets.iter());
+        let frame = executed.and_then(|(result, _)| {
+            let scope = &mut self.runtime.handle_scope();
+            let result = v8::Local::new(scope, result);
+            dataframe::from_rows(scope, result, &redactor)
+        });
+        frame.map_err(|err| redactor.error(error::execution_error(err)))
+    }
+
+    /// Set the global `name` to `value`. Unlike bound variables it stays
+    /// across runs until a script or another call changes it.
+    ///
+    /// ```
+    /// use deno_runner::{serde_json::json, Builder};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut runner = Builder::new().build();
+    /// runner.set_global("cfg", &json!({ "step": 2 })).unwrap();
+    /// runner
+    ///     .run::<_, String, String>("globalThis.total = (globalThis.total ?? 0) + cfg.step", None)
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// let total: u32 = runner.get_global("total").unwrap();
+    /// assert_eq!(total, 2);
+    /// # }
+    /// ```
+    pub fn set_global<T: Serialize + ?Sized>(&mut self, name: &str, value: &T) -> Result<()> {
+        let scope = &mut self.runtime.handle_scope();
+        let value = deno_core::serde_v8::to_v8(scope, value)?;
+        let key = v8::String::new(scope, name).unwrap();
+        let global = scope.get_current_context().global(scope);
+        global.set(scope, key.into(), value);
+        Ok(())
+    }
+
+    /// Read the global `name` into `T`. A global that isn't set reads as
+    /// `undefined`, use an `Option` for ones that may be missing.
+    pub fn get_global<T: DeserializeOwned>(&mut self, name: &str) -> Result<T> {
+        let scope = &mut self.runtime.handle_scope();
+        let key = v8::String::new(scope, name).unwrap();
+        let global = scope.get_current_context().global(scope);
+        let value = global
+            .get(scope, key.into())
+            .unwrap_or_else(|| v8::undefined(scope).into());
+
+        deno_core::serde_v8::from_v8(scope, value).map_err(|err| {
+            anyhow::anyhow!(
+                "Global `{}` is not a valid {}: {}",
+                name,
+                std::any::type_name::<T>(),
+                err
+            )
+        })
+    }
+
+    /// Call the global function `name` with `args` and return its result
+    /// as JSON, waiting for it if it returns a promise. For scripts that
+    /// define handlers once and are called many times:
+    ///
+    /// ```
+    /// use deno_runner::{serde_json::json, Builder};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut runner = Builder::new().build();
+    /// runner
+    ///     .run::<_, String, String>(
+    ///         "globalThis.handler = async (order) => ({ total: order.qty * order.price })",
+    ///         None,
+    ///     )
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// let result = runner
+    ///     .call_function("handler", &[json!({ "qty": 2, "price": 5 })])
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(result, json!({ "total": 10 }));
+    /// # }
+    /// ```
+    ///
+    /// The call is limited by [`Builder::timeout`] and
+    /// [`Builder::max_heap_size`] like a run. A thrown exception fails with
+    /// [`RunnerError::Execution`].
+    pub async fn call_function(
+        &mut self,
+        name: &str,
+        args: &[serde_json::Value],
+    ) -> Result<serde_json::Value> {
+        let value = self.call_function_value(name, args).await;
+        self.redacted(value)
+    }
+
+    async fn call_function_value(
+        &mut self,
+        name: &str,
+        args: &[serde_json::Value],
+    ) -> Result<serde_json::Value> {
+        let limit = self.timeout;
+        let watchdog = limit.map(|limit| {
+            let handle = self.runtime.v8_isolate().thread_safe_handle();
+            timeout::Watchdog::start(handle, limit)
+        });
+
+        let called = async {
+            let value = self.call_global(name, args)?;
+            self.runtime.resolve_value(value).await
+        };
+        let called = match limit {
+            Some(limit) => executor::timeout(limit, called).await,
+            None => Some(called.await),
+        };
+
+        let fired = watchdog.map_or(false, timeout::Watchdog::stop);
+        let called = match called {
+            Some(called) if !fired => called,
+            _ => {
+                self.runtime.v8_isolate().cancel_terminate_execution();
+                return Err(RunnerError::Timeout(limit.unwrap()).into());
+            }
+        };
+
+        if let Some(heap_limit) = &self.heap_limit {
+            if heap_limit.take_exceeded(&mut self.runtime) {
+                return Err(RunnerError::HeapLimitExceeded(heap_limit.bytes).into());
+            }
+        }
+
+        let value = called.map_err(error::execution_error)?;
+        let scope = &mut self.runtime.handle_scope();
+        let value = v8::Local::new(scope, value);
+        check_result(&self.config.result_guards, scope, value)?;
+        json_value(scope, value, None, Cycles::Mark)
+    }
+
+    /// Call the global function `name` without waiting for a returned promise.
+    fn call_global(
+        &mut self,
+        name: &str,
+        args: &[serde_json::Value],
+    ) -> Result<v8::Global<v8::Value>> {
+        let scope = &mut self.runtime.handle_scope();
+        let key = v8::String::new(scope, name).unwrap();
+        let global = scope.get_current_context().global(scope);
+        let function = global
+            .get(scope, key.into())
+            .and_then(|value| v8::Local::<v8::Function>::try_from(value).ok())
+            .ok_or_else(|| anyhow::anyhow!("`{}` is not a global function", name))?;
+
+        let args = args
+            .iter()
+            .map(|arg| deno_core::serde_v8::to_v8(scope, arg))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let scope = &mut v8::TryCatch::new(scope);
+        let undefined = v8::undefined(scope).into();
+        match function.call(scope, undefined, &args) {
+            Some(value) => Ok(v8::Global::new(scope, value)),
+            None => {
+                let exception = scope
+                    .exception()
+                    .unwrap_or_else(|| v8::undefined(scope).into());
+                let error = deno_core::error::JsError::from_v8_exception(scope, exception);
+                Err(RunnerError::Execution(error.into()).into())
             }
         }
+    }
+
+    /// Same as [`run_json`](Self::run_json), with per-run [`RunOptions`].
+    /// [`NumberFormat`] doesn't apply to JSON results.
+    pub async fn run_json_with_options<K, V>(
+        &mut self,
+        custom_code: &str,
+        vars: Option<HashMap<K, V>>,
+        mut options: RunOptions,
+    ) -> Result<serde_json::Value>
+    where
+        K: Display,
+        V: Display + std::fmt::Debug,
+    {
+        options.result_format = ResultFormat::Json;
+        let report = self.run_report(custom_code, vars, options).await?;
+        Ok(serde_json::from_str(&report.result)?)
+    }
+
+    /// Evaluate an ES module and return its default export, or its
+    /// namespace object (all named exports) when it has none, as JSON.
+    ///
+    /// A [`Module::Specifier`] is loaded through the module loader, so
+    /// imports work the same as for any other module. Relative imports in a
+    /// [`Module::Source`] resolve from `/`, the root of a [`VirtualFs`].
+    /// Variables are bound as globals, like for [`run`](Self::run).
+    pub async fn run_module<K, V>(
+        &mut self,
+        module: Module<'_>,
+        vars: Option<HashMap<K, V>>,
+    ) -> Result<serde_json::Value>
+    where
+        K: Display,
+        V: Display + std::fmt::Debug,
+    {
+        let value = self.module_value(module, vars).await;
+        self.redacted(value)
+    }
+
+    async fn module_value<K, V>(
+        &mut self,
+        module: Module<'_>,
+        vars: Option<HashMap<K, V>>,
+    ) -> Result<serde_json::Value>
+    where
+        K: Display,
+        V: Display + std::fmt::Debug,
+    {
+        self.begin_run()?;
+        self.bind_vars(vars, &RunOptions::default())?;
+
+        let id = match module {
+            Module::Specifier(specifier) => {
+                let specifier = deno_core::resolve_url_or_path(specifier)?;
+                self.runtime.load_side_module(&specifier, None).await?
+            }
+            Module::Source(source) => {
+                // Modules are cached by specifier, every run needs its own
+                let specifier = deno_core::ModuleSpecifier::parse(&format!(
+                    "file:///$deno_runner_module_{}.js",
+                    self.runs
+                ))?;
+                self.runtime
+                    .load_side_module(&specifier, Some(source.to_string()))
+                    .await?
+            }
+        };
+
+        let evaluated = self.runtime.mod_evaluate(id);
+        self.runtime.run_event_loop(false).await?;
+        evaluated.await??;
+
+        let namespace = self.runtime.get_module_namespace(id)?;
+        let scope = &mut self.runtime.handle_scope();
+        let namespace = v8::Local::new(scope, namespace);
+        let default = v8::String::new(scope, "default").unwrap().into();
+        let result = match namespace.has_own_property(scope, default) {
+            Some(true) => namespace.get(scope, default).unwrap(),
+            _ => namespace.into(),
+        };
+
+        check_result(&self.config.result_guards, scope, result)?;
+        json_value(scope, result, None, Cycles::Mark)
+    }
+
+    /// Run the script with bindings and result encoded with
+    /// [`RunOptions::codec`], skipping the text round trip of
+    /// [`run`](Self::run) for large values such as numeric arrays.
+    ///
+    /// `bindings` is a map of variable names to values, e.g. a serialized
+    /// `HashMap<String, Vec<f64>>`.
+    pub async fn run_encoded(
+        &mut self,
+        custom_code: &str,
+        bindings: &[u8],
+        options: RunOptions,
+    ) -> Result<Vec<u8>> {
+        self.runtime
+            .op_state()
+            .borrow_mut()
+            .put(encoded::EncodedBindings(bindings.to_vec()));
 
         let result = self
+            .execute::<String, String>(custom_code, None, &options)
+            .await;
+        // Not read when the script failed before it was bound
+        self.runtime
+            .op_state()
+            .borrow_mut()
+            .try_take::<encoded::EncodedBindings>();
+        let redactor = self.redactor();
+        let (result, _) = result.map_err(|err| redactor.error(err))?;
+
+        let scope = &mut self.runtime.handle_scope();
+        let result = v8::Local::new(scope, result);
+        let bytes = encoded::encode_result(scope, options.codec, result)
+            .map_err(|err| redactor.error(err))?;
+        redactor.encoded(options.codec, bytes)
+    }
+
+    /// Apply a JS function to every item of `items`, for ETL-style pipelines.
+    ///
+    /// `script` must evaluate to a function, e.g. `(row) => ({ ...row, total:
+    /// row.price * row.qty })`; it is compiled once and called with each item
+    /// (and its index in the batch), and may return a promise. Items that
+    /// are ready together are sent to the isolate in batches of up to 256,
+    /// converted to and from JSON. A failing batch gives a single error item.
+    pub fn map_stream<'a, S, T, U>(
+        &'a mut self,
+        script: &str,
+        items: S,
+    ) -> impl Stream<Item = Result<U>> + 'a
+    where
+        S: Stream<Item = T> + 'a,
+        T: Serialize + 'a,
+        U: DeserializeOwned + 'a,
+    {
+        let script = script.to_string();
+        let state = (self, items.ready_chunks(MAP_BATCH_SIZE).boxed_local(), None);
+
+        stream::unfold(state, move |(runner, mut batches, mapper)| {
+            let script = script.clone();
+            async move {
+                let batch = batches.next().await?;
+
+                let mapper = match mapper {
+                    Some(mapper) => mapper,
+                    None => match runner.batch_mapper(&script) {
+                        Ok(mapper) => mapper,
+                        Err(err) => return Some((vec![Err(err)], (runner, batches, None))),
+                    },
+                };
+
+                let mapped = match runner.map_batch(&mapper, &batch).await {
+                    Ok(items) => items.into_iter().map(Ok).collect(),
+                    Err(err) => vec![Err(err)],
+                };
+                Some((mapped, (runner, batches, Some(mapper))))
+            }
+        })
+        .flat_map(stream::iter)
+    }
+
+    /// Run a script evaluating to an async iterable, like an async generator,
+    /// and yield its items as JSON while the script produces them, so large
+    /// outputs don't have to be collected first.
+    ///
+    /// ```
+    /// use deno_runner::{serde_json::json, Builder};
+    /// use futures::TryStreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut runner = Builder::new().build();
+    /// let items: Vec<_> = runner
+    ///     .run_stream::<String, String>(
+    ///         "(async function* () { for (let i = 1; i <= 3; i++) yield { i } })()",
+    ///         None,
+    ///     )
+    ///     .try_collect()
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// assert_eq!(items, vec![json!({ "i": 1 }), json!({ "i": 2 }), json!({ "i": 3 })]);
+    /// # }
+    /// ```
+    ///
+    /// The stream ends after the first error. [`Builder::timeout`] applies to
+    /// evaluating the script, not to producing each item.
+    pub fn run_stream<'a, K, V>(
+        &'a mut self,
+        custom_code: &str,
+        vars: Option<HashMap<K, V>>,
+    ) -> impl Stream<Item = Result<serde_json::Value>> + 'a
+    where
+        K: Display + 'a,
+        V: Display + std::fmt::Debug + 'a,
+    {
+        let start = Some((custom_code.to_string(), vars));
+
+        stream::unfold(
+            (self, start, None, false),
+            |(runner, start, iterator, failed)| async move {
+                if failed {
+                    return None;
+                }
+
+                let iterator = match (start, iterator) {
+                    (Some((custom_code, vars)), _) => {
+                        match runner.stream_iterator(&custom_code, vars).await {
+                            Ok(iterator) => iterator,
+                            Err(err) => {
+                                let err = runner.redactor().error(err);
+                                return Some((Err(err), (runner, None, None, true)));
+                            }
+                        }
+                    }
+                    (None, Some(iterator)) => iterator,
+                    (None, None) => return None,
+                };
+
+                match runner.stream_next(&iterator).await {
+                    Ok(Some(item)) => {
+                        let item = runner.redactor().value(item);
+                        Some((Ok(item), (runner, None, Some(iterator), false)))
+                    }
+                    Ok(None) => None,
+                    Err(err) => {
+                        let err = runner.redactor().error(err);
+                        Some((Err(err), (runner, None, None, true)))
+                    }
+                }
+            },
+        )
+    }
+
+    /// Iterator over the items of the script's async iterable result.
+    async fn stream_iterator<K, V>(
+        &mut self,
+        custom_code: &str,
+        vars: Option<HashMap<K, V>>,
+    ) -> Result<v8::Global<v8::Value>>
+    where
+        K: Display,
+        V: Display + std::fmt::Debug,
+    {
+        let (iterable, _) = self
+            .execute(custom_code, vars, &RunOptions::default())
+            .await
+            .map_err(error::execution_error)?;
+
+        let scope = &mut self.runtime.handle_scope();
+        let iterable = v8::Local::new(scope, iterable);
+        let iterator = hooks::call(scope, "asyncIterator", &[iterable])?;
+        Ok(v8::Global::new(scope, iterator))
+    }
+
+    /// Next item of a [`run_stream`](Self::run_stream) iterator, `None`
+    /// once it is done.
+    async fn stream_next(
+        &mut self,
+        iterator: &v8::Global<v8::Value>,
+    ) -> Result<Option<serde_json::Value>> {
+        let step = {
+            let scope = &mut self.runtime.handle_scope();
+            let iterator = v8::Local::new(scope, iterator);
+            let step = hooks::call(scope, "iteratorNext", &[iterator])?;
+            v8::Global::new(scope, step)
+        };
+        let step = self
             .runtime
-            .execute_script("code.js", &custom_code.to_string())?;
+            .resolve_value(step)
+            .await
+            .map_err(error::execution_error)?;
 
-        let mut scope = self.runtime.handle_scope();
+        let scope = &mut self.runtime.handle_scope();
+        let step = v8::Local::<v8::Object>::try_from(v8::Local::new(scope, step))?;
+        let done_key = v8::String::new(scope, "done").unwrap();
+        if step
+            .get(scope, done_key.into())
+            .map_or(true, |done| done.is_true())
+        {
+            return Ok(None);
+        }
+
+        let value_key = v8::String::new(scope, "value").unwrap();
+        let value = step
+            .get(scope, value_key.into())
+            .unwrap_or_else(|| v8::undefined(scope).into());
+        json_value(scope, value, None, Cycles::Mark).map(Some)
+    }
+
+    /// Compile the function of [`map_stream`](Self::map_stream) into one
+    /// mapping a JSON array of items.
+    fn batch_mapper(&mut self, script: &str) -> Result<v8::Global<v8::Function>> {
+        self.begin_run()?;
+
+        let map = self
+            .runtime
+            .execute_script("[runner:map]", &format!("({}\n)", script))?;
+
+        let scope = &mut self.runtime.handle_scope();
+        let map = v8::Local::new(scope, map);
+        let mapper = hooks::call(scope, "batchMapper", &[map])?;
+        let mapper = v8::Local::<v8::Function>::try_from(mapper)
+            .map_err(|_| anyhow::anyhow!("batchMapper did not return a function"))?;
+        Ok(v8::Global::new(scope, mapper))
+    }
 
-        unsafe { Ok(result.into_raw().as_ref().to_rust_string_lossy(&mut scope)) }
+    async fn map_batch<T, U>(
+        &mut self,
+        mapper: &v8::Global<v8::Function>,
+        batch: &[T],
+    ) -> Result<Vec<U>>
+    where
+        T: Serialize,
+        U: DeserializeOwned,
+    {
+        let batch = serde_json::to_string(batch)?;
+
+        let promise = {
+            let scope = &mut self.runtime.handle_scope();
+            let scope = &mut v8::TryCatch::new(scope);
+            let mapper = v8::Local::new(scope, mapper);
+            let batch = v8::String::new(scope, &batch)
+                .ok_or_else(|| anyhow::anyhow!("Batch is too large for a V8 string"))?;
+            let undefined = v8::undefined(scope).into();
+
+            match mapper.call(scope, undefined, &[batch.into()]) {
+                Some(promise) => v8::Global::new(scope, promise),
+                None => {
+                    let message = scope
+                        .exception()
+                        .map(|e| e.to_rust_string_lossy(scope))
+                        .unwrap_or_default();
+                    anyhow::bail!("map_stream script failed: {}", message);
+                }
+            }
+        };
+
+        let mapped = self.runtime.resolve_value(promise).await?;
+        let scope = &mut self.runtime.handle_scope();
+        let mapped = v8::Local::new(scope, mapped).to_rust_string_lossy(scope);
+
+        serde_json::from_str(&mapped).map_err(|source| {
+            RunnerError::ResultDeserialization {
+                type_name: std::any::type_name::<U>(),
+                source,
+            }
+            .into()
+        })
+    }
+
+    /// Bind variables and evaluate the script, returning its final value and
+    /// the exit code if it called `exit()`.
+    async fn execute<K, V>(
+        &mut self,
+        custom_code: &str,
+        vars: Option<HashMap<K, V>>,
+        options: &RunOptions,
+    ) -> Result<(v8::Global<v8::Value>, Option<i32>)>
+    where
+        K: Display,
+        V: Display + std::fmt::Debug,
+    {
+        language::check_script(&self.config.disabled_features, custom_code)?;
+        self.begin_run()?;
+
+        self.bind_vars(vars, options)?;
+        for (name, payload) in &options.cached_bindings {
+            let name = VarName::parse(name.as_str())?;
+            let scope = &mut self.runtime.handle_scope();
+            let value = self.interned.value(scope, payload)?;
+            bind(
+                scope,
+                name.as_str(),
+                value,
+                options.binding_mode,
+                options.binding_conflicts,
+            )?;
+        }
+
+        if self
+            .runtime
+            .op_state()
+            .borrow()
+            .has::<encoded::EncodedBindings>()
+        {
+            hooks::HookCall::new("bindEncoded", format!("{:?}", options.codec.name()))
+                .run(&mut self.runtime)?;
+        }
+
+        self.secrets.extend(options.secrets.values().cloned());
+        for (name, secret) in &options.secrets {
+            let name = VarName::parse(name.as_str())?;
+            let scope = &mut self.runtime.handle_scope();
+            let value = v8::String::new(scope, &secret.0).unwrap();
+            bind(
+                scope,
+                name.as_str(),
+                value.into(),
+                BindingMode::Mutable,
+                options.binding_conflicts,
+            )?;
+        }
+
+        if options.capture_console {
+            hooks::HookCall::new("captureConsole", "").run(&mut self.runtime)?;
+        }
+
+        if let Some(features) = options.features_call() {
+            features.run(&mut self.runtime)?;
+        }
+
+        if let Some(pure) = options.pure_call(custom_code) {
+            pure.run(&mut self.runtime)?;
+        }
+
+        let grant = capabilities::enforce(custom_code, &options.capabilities)?;
+        if let Some(grant) = &grant {
+            grant.restrict_call().run(&mut self.runtime)?;
+        }
+        #[cfg(feature = "fetch")]
+        fetch::restrict_hosts(
+            &mut self.runtime.op_state().borrow_mut(),
+            grant.map(|grant| grant.hosts),
+        );
+
+        if let Some(faults) = options.faults_call() {
+            faults.run(&mut self.runtime)?;
+        }
+
+        if let Some(dry_run) = options.dry_run_call() {
+            dry_run.run(&mut self.runtime)?;
+        }
+
+        if let Some(check) = expects::from_comments(custom_code)? {
+            self.runtime.execute_script("[runner:expects]", &check)?;
+        }
+
+        let name = options.script_name_or_default();
+        let evaluated = match options.timeout.or(self.timeout) {
+            Some(limit) => {
+                let handle = self.runtime.v8_isolate().thread_safe_handle();
+                let watchdog = timeout::Watchdog::start(handle, limit);
+                // The watchdog stops busy JS, this stops waiting on the event loop
+                let evaluated = executor::timeout(limit, self.evaluate(name, custom_code)).await;
+
+                match (watchdog.stop(), evaluated) {
+                    (false, Some(evaluated)) => evaluated,
+                    _ => {
+                        self.runtime.v8_isolate().cancel_terminate_execution();
+                        return Err(RunnerError::Timeout(limit).into());
+                    }
+                }
+            }
+            None => self.evaluate(name, custom_code).await,
+        };
+
+        if let Some(heap_limit) = &self.heap_limit {
+            if heap_limit.take_exceeded(&mut self.runtime) {
+                return Err(RunnerError::HeapLimitExceeded(heap_limit.bytes).into());
+            }
+        }
+
+        let (result, exit_code) = match evaluated {
+            Ok(result) => (result, None),
+            Err(err) => match self.take_exit_status()? {
+                Some((code, value)) => (value, Some(code)),
+                None => return Err(err),
+            },
+        };
+
+        let scope = &mut self.runtime.handle_scope();
+        let value = v8::Local::new(scope, &result);
+        check_result(&self.config.result_guards, scope, value)?;
+        Ok((result, exit_code))
+    }
+
+    /// Bind variables to the Deno runtime for the next script.
+    fn bind_vars<K, V>(&mut self, vars: Option<HashMap<K, V>>, options: &RunOptions) -> Result<()>
+    where
+        K: Display,
+        V: Display + std::fmt::Debug,
+    {
+        for (key, value) in vars.into_iter().flatten() {
+            let expression = self.codec.encode(&value);
+            let scope = &mut self.runtime.handle_scope();
+            let value = match serde_json::from_str::<serde_json::Value>(&expression) {
+                Ok(json) => deno_core::serde_v8::to_v8(scope, json)?,
+                Err(_) => eval_expression(scope, &expression)
+                    .map_err(|err| err.context(format!("Invalid value for variable `{}`", key)))?,
+            };
+            bind(
+                scope,
+                &key.to_string(),
+                value,
+                options.binding_mode,
+                options.binding_conflicts,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Redacts the secrets bound so far.
+    fn redactor(&self) -> secret::Redactor<'_> {
+        secret::Redactor::new(self.secrets.iter())
+    }
+
+    /// Exit of the calls returning JSON, with the secrets bound so far
+    /// redacted from the value or the error.
+    fn redacted(&self, value: Result<serde_json::Value>) -> Result<serde_json::Value> {
+        let redactor = self.redactor();
+        value
+            .map(|value| redactor.value(value))
+            .map_err(|err| redactor.error(err))
+    }
+
+    /// Whether one of the variables would be refused with a
+    /// [`RunnerError::BindingConflict`]. Variables of the last run count as
+    /// taken, they are only removed when the next run starts.
+    fn taken_global(
+        &mut self,
+        vars: &[(String, String)],
+        conflicts: ConflictPolicy,
+    ) -> Result<bool> {
+        if conflicts != ConflictPolicy::Error {
+            return Ok(false);
+        }
+
+        let scope = &mut self.runtime.handle_scope();
+        for (name, _) in vars {
+            let key = v8::String::new(scope, name).unwrap();
+            if !hooks::call(scope, "existingGlobal", &[key.into()])?.is_undefined() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// What the next script runs on, see [`StartupKind`].
+    fn startup_kind(&self) -> StartupKind {
+        if self.runs > 0 {
+            StartupKind::Warm
+        } else if self.build_report.snapshot_load.is_some() {
+            StartupKind::Snapshot
+        } else {
+            StartupKind::Cold
+        }
+    }
+
+    /// Reset per-run settings if an earlier script ran on this isolate.
+    fn begin_run(&mut self) -> Result<()> {
+        if self.runs > 0 {
+            hooks::HookCall::new("resetRun", "").run(&mut self.runtime)?;
+        }
+        self.runtime
+            .op_state()
+            .borrow_mut()
+            .borrow_mut::<progress::ProgressReporter>()
+            .reset();
+        self.runs += 1;
+        Ok(())
+    }
+
+    /// Evaluate the script and wait for the value of a returned promise.
+    async fn evaluate(&mut self, name: &str, custom_code: &str) -> Result<v8::Global<v8::Value>> {
+        // A block scopes the script's top-level declarations to this run
+        // and still evaluates to the value of its last expression. The
+        // brace shares the first line so stack trace lines stay the same.
+        let block = format!("{{{}\n}}", custom_code);
+        let evaluated = match self.runtime.execute_script(name, &block) {
+            // Nothing ran yet: a syntax error fails before evaluation
+            Err(err) if is_top_level_await(&err) => {
+                let body = format!("(async () => {{{}\n}})()", custom_code);
+                self.runtime.execute_script(name, &body)
+            }
+            evaluated => evaluated,
+        };
+
+        // Pump the event loop until a returned promise settles, then until
+        // pending timers and ops are done
+        let result = self.runtime.resolve_value(evaluated?).await?;
+        self.runtime.run_event_loop(false).await?;
+        Ok(result)
+    }
+
+    /// Console calls made since the last call, once captured with the
+    /// `captureConsole` hook.
+    pub(crate) fn take_console(&mut self) -> Result<Vec<ConsoleEntry>> {
+        let entries = hooks::HookCall::new("takeConsole", "").run(&mut self.runtime)?;
+
+        let scope = &mut self.runtime.handle_scope();
+        let entries = v8::Local::new(scope, entries).to_rust_string_lossy(scope);
+        Ok(serde_json::from_str(&entries)?)
+    }
+
+    /// Op calls recorded since the last call, see [`RunOptions::dry_run`].
+    fn take_op_calls(&mut self) -> Result<Vec<OpCall>> {
+        let calls = hooks::HookCall::new("takeOpCalls", "").run(&mut self.runtime)?;
+
+        let scope = &mut self.runtime.handle_scope();
+        let calls = v8::Local::new(scope, calls).to_rust_string_lossy(scope);
+        Ok(serde_json::from_str(&calls)?)
+    }
+
+    /// Code and value passed to `exit()`, if the last script called it.
+    fn take_exit_status(&mut self) -> Result<Option<(i32, v8::Global<v8::Value>)>> {
+        let status = hooks::HookCall::new("takeExitStatus", "").run(&mut self.runtime)?;
+
+        let scope = &mut self.runtime.handle_scope();
+        let status = match v8::Local::<v8::Object>::try_from(v8::Local::new(scope, status)) {
+            Ok(status) => status,
+            Err(_) => return Ok(None),
+        };
+
+        let code_key = v8::String::new(scope, "code").unwrap();
+        let code = status
+            .get(scope, code_key.into())
+            .and_then(|code| code.int32_value(scope))
+            .unwrap_or_default();
+
+        let value_key = v8::String::new(scope, "value").unwrap();
+        let value = status.get(scope, value_key.into()).unwrap();
+
+        Ok(Some((code, v8::Global::new(scope, value))))
+    }
+}
+
+/// Define `name` on `globalThis` for the current run.
+fn bind(
+    scope: &mut v8::HandleScope,
+    name: &str,
+    value: v8::Local<v8::Value>,
+    mode: BindingMode,
+    conflicts: ConflictPolicy,
+) -> Result<()> {
+    let key = v8::String::new(scope, name).unwrap();
+    if conflicts == ConflictPolicy::Error {
+        let existing = hooks::call(scope, "existingGlobal", &[key.into()])?;
+        if !existing.is_undefined() {
+            return Err(RunnerError::BindingConflict {
+                name: name.to_string(),
+                existing: existing.to_rust_string_lossy(scope),
+            }
+            .into());
+        }
+    }
+    let mode = v8::String::new(scope, mode.name()).unwrap();
+    hooks::call(scope, "bind", &[key.into(), value, mode.into()])?;
+    Ok(())
+}
+
+/// Value of a JS expression a [`ValueCodec`] produced that isn't plain JSON.
+fn eval_expression<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    expression: &str,
+) -> Result<v8::Local<'s, v8::Value>> {
+    let scope = &mut v8::EscapableHandleScope::new(scope);
+    let scope = &mut v8::TryCatch::new(scope);
+    let source = v8::String::new(scope, &format!("({}\n)", expression)).unwrap();
+    let value = v8::Script::compile(scope, source, None).and_then(|script| script.run(scope));
+    match value {
+        Some(value) => Ok(scope.escape(value)),
+        None => {
+            let message = scope
+                .exception()
+                .map(|e| e.to_rust_string_lossy(scope))
+                .unwrap_or_default();
+            Err(anyhow::anyhow!(message))
+        }
     }
 }
 
+/// Fail with [`RunnerError::ResultRejected`] when one of `guards` refuses
+/// the JSON value of `result`.
+fn check_result(
+    guards: &[(u64, Rc<ResultGuard>)],
+    scope: &mut v8::HandleScope,
+    result: v8::Local<v8::Value>,
+) -> Result<()> {
+    if guards.is_empty() {
+        return Ok(());
+    }
+
+    let value = json_value(scope, result, None, Cycles::Mark)?;
+    for (_, guard) in guards {
+        guard(&value).map_err(RunnerError::ResultRejected)?;
+    }
+    Ok(())
+}
+
+/// A value as JSON, `null` when it has no JSON representation. Non-finite
+/// numbers become `null` unless `non_finite` says otherwise, cycles are
+/// handled as `cycles` says.
+fn json_value(
+    scope: &mut v8::HandleScope,
+    value: v8::Local<v8::Value>,
+    non_finite: Option<NonFinite>,
+    cycles: Cycles,
+) -> Result<serde_json::Value> {
+    if value.is_function() || value.is_symbol() {
+        return Ok(serde_json::Value::Null);
+    }
+
+    let policy = v8::String::new(scope, non_finite.unwrap_or(NonFinite::Null).name()).unwrap();
+    let cycles = v8::String::new(scope, cycles.name()).unwrap();
+    let json = hooks::call(scope, "resultJson", &[value, policy.into(), cycles.into()])?;
+    if json.is_undefined() {
+        return Ok(serde_json::Value::Null);
+    }
+
+    Ok(serde_json::from_str(&json.to_rust_string_lossy(scope))?)
+}
+
+/// Whether the script failed to compile only because it uses `await`
+/// outside of an async function.
+fn is_top_level_await(err: &anyhow::Error) -> bool {
+    err.to_string()
+        .contains("await is only valid in async functions")
+}
+
+type ResultGuard = dyn Fn(&serde_json::Value) -> std::result::Result<(), RejectReason>;
+
+/// Id of the next guard added with [`Builder::result_guard`]
+static NEXT_GUARD_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone)]
 pub struct Builder {
     pub ops: Vec<deno_core::OpDecl>,
+    state: Vec<Rc<dyn Fn(&mut OpState)>>,
+    codec: Rc<dyn ValueCodec>,
+    telemetry: Rc<dyn TelemetryExporter>,
+    memo: Option<(MemoCache, Duration)>,
+    timeout: Option<Duration>,
+    max_heap_size: Option<usize>,
+    heap_hooks: heap::HeapHooks,
+    progress: progress::ProgressReporter,
+    /// With the id they were added under, which memo keys tell them apart by
+    result_guards: Vec<(u64, Rc<ResultGuard>)>,
+    op_payload_limit: Option<usize>,
+    fast_ops: Vec<&'static str>,
+    required_ops: Vec<String>,
+    op_signatures: BTreeMap<String, Vec<String>>,
+    parallel_limit: Option<usize>,
+    node_compat: Option<NodeCompat>,
+    permissions: Option<Permissions>,
+    #[cfg(feature = "fetch")]
+    fetch: Option<FetchOptions>,
+    /// `Some` when Web Crypto is enabled, with the random seed
+    #[cfg(feature = "crypto")]
+    crypto: Option<Option<u64>>,
+    streams: stream::StreamFactories,
+    lazy_bindings: lazy::LazyBindings,
+    shared_buffers: BTreeMap<String, SharedBuffer>,
+    string_table: StringTable,
+    virtual_fs: Option<VirtualFs>,
+    module_loader: Option<Rc<dyn ModuleLoader>>,
+    /// Set along with `module_loader` when it is one, which fetches
+    /// packages itself and so checks `permissions` on its own
+    #[cfg(feature = "npm")]
+    npm_loader: Option<NpmModuleLoader>,
+    #[cfg(feature = "arrow")]
+    record_batches: BTreeMap<String, arrow::record_batch::RecordBatch>,
+    #[cfg(feature = "plugins")]
+    plugins: Vec<plugin::Plugin>,
+    snapshot: Option<snapshot::StartupSnapshot>,
+    persistent: bool,
+    disabled_features: BTreeSet<LanguageFeature>,
+    warmup: Vec<String>,
+    #[cfg(feature = "schemars")]
+    binding_schemas: schema::BindingSchemas,
 }
 
 impl Builder {
     pub fn new() -> Self {
-        Self { ops: vec![] }
+        Self {
+            ops: vec![],
+            state: vec![],
+            codec: Rc::new(DefaultCodec),
+            telemetry: Rc::new(NoopExporter),
+            memo: None,
+            timeout: None,
+            max_heap_size: None,
+            heap_hooks: Default::default(),
+            progress: Default::default(),
+            result_guards: vec![],
+            op_payload_limit: None,
+            fast_ops: vec![],
+            required_ops: vec![],
+            op_signatures: BTreeMap::new(),
+            parallel_limit: None,
+            node_compat: None,
+            permissions: None,
+            #[cfg(feature = "fetch")]
+            fetch: None,
+            #[cfg(feature = "crypto")]
+            crypto: None,
+            streams: Default::default(),
+            lazy_bindings: Default::default(),
+            shared_buffers: BTreeMap::new(),
+            string_table: StringTable::default(),
+            virtual_fs: None,
+            module_loader: None,
+            #[cfg(feature = "npm")]
+            npm_loader: None,
+            #[cfg(feature = "arrow")]
+            record_batches: BTreeMap::new(),
+            #[cfg(feature = "plugins")]
+            plugins: vec![],
+            snapshot: None,
+            persistent: false,
+            disabled_features: BTreeSet::new(),
+            warmup: vec![],
+            #[cfg(feature = "schemars")]
+            binding_schemas: Default::default(),
+        }
     }
 
     pub fn add_op(mut self, op: deno_core::OpDecl) -> Self {
@@ -55,26 +1757,805 @@ impl Builder {
         self
     }
 
-    pub fn build(self) -> DenoRunner {
-        let extensions = vec![
-            deno_console::init(),
-            deno_core::Extension::builder().ops(self.ops).build(),
+    /// Put application data into the [`OpState`] of every runner built, so
+    /// ops can reach it with `state.borrow::<T>()`:
+    ///
+    /// ```
+    /// use deno_runner::{op, Builder, OpState};
+    ///
+    /// struct Config {
+    ///     region: String,
+    /// }
+    ///
+    /// #[op]
+    /// fn region(state: &mut OpState) -> String {
+    ///     state.borrow::<Config>().region.clone()
+    /// }
+    ///
+    /// let runner = Builder::new()
+    ///     .add_op(region::decl())
+    ///     .state(|state| {
+    ///         state.put(Config {
+    ///             region: "eu-west-1".to_string(),
+    ///         })
+    ///     })
+    ///     .build();
+    /// ```
+    ///
+    /// `init` runs once per runner and can be given more than once, later
+    /// calls overwrite values of the same type.
+    pub fn state<F>(mut self, init: F) -> Self
+    where
+        F: Fn(&mut OpState) + 'static,
+    {
+        self.state.push(Rc::new(init));
+        self
+    }
+
+    /// Use a custom [`ValueCodec`] for bound variables and results.
+    pub fn codec<T: ValueCodec + 'static>(mut self, codec: T) -> Self {
+        self.codec = Rc::new(codec);
+        self
+    }
+
+    /// Send run started/finished/failed events to `exporter`.
+    pub fn telemetry<T: TelemetryExporter + 'static>(mut self, exporter: T) -> Self {
+        self.telemetry = Rc::new(exporter);
+        self
+    }
+
+    /// Reuse results of earlier runs with the same script, bindings and
+    /// result-affecting options for `ttl`, see [`MemoCache`]. Only pure runs
+    /// are cached, since a cached result skips the script entirely.
+    pub fn memoize(mut self, cache: &MemoCache, ttl: Duration) -> Self {
+        self.memo = Some((cache.clone(), ttl));
+        self
+    }
+
+    /// Stop any run that takes longer than `limit`, including time spent
+    /// waiting for a returned promise, with [`RunnerError::Timeout`]. The
+    /// runner can be used again afterwards. Override it per run with
+    /// [`RunOptions::timeout`].
+    ///
+    /// The wait is timed on the [`executor`](crate::executor) the runner is
+    /// built for: Tokio, async-std or smol.
+    pub fn timeout(mut self, limit: Duration) -> Self {
+        self.timeout = Some(limit);
+        self
+    }
+
+    /// Limit the V8 heap to `bytes`. A script that allocates past it is
+    /// terminated with [`RunnerError::HeapLimitExceeded`] instead of taking
+    /// the host process down; the runner can be used again afterwards.
+    pub fn max_heap_size(mut self, bytes: usize) -> Self {
+        self.max_heap_size = Some(bytes);
+        self
+    }
+
+    /// Call `hook` after every garbage collection of the runner's heap, with
+    /// its kind, duration and the heap usage before and after.
+    ///
+    /// Hooks run inside the collection on the runner's thread, so they
+    /// should be cheap: record a metric or set a flag, don't block.
+    pub fn on_gc<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&GcEvent) + 'static,
+    {
+        self.heap_hooks.gc.push(Rc::new(hook));
+        self
+    }
+
+    /// Call `hook` when the heap is running out, to shed load or recycle the
+    /// runner before a script fails with
+    /// [`RunnerError::HeapLimitExceeded`], see [`PressureLevel`].
+    ///
+    /// Runs on the runner's thread like [`on_gc`](Self::on_gc) hooks.
+    pub fn on_heap_pressure<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&HeapPressure) + 'static,
+    {
+        self.heap_hooks.pressure.push(Rc::new(hook));
+        self
+    }
+
+    /// Call `hook` with the updates scripts send with
+    /// `progress.report(stage, pct)`, to show the progress of long-running
+    /// scripts.
+    ///
+    /// ```
+    /// use deno_runner::Builder;
+    ///
+    /// let builder = Builder::new().on_progress(|progress| {
+    ///     println!("{}: {:.0}%", progress.stage, progress.percent);
+    /// });
+    /// // progress.report("load", 40)
+    /// ```
+    ///
+    /// Updates of the same stage are throttled to one per
+    /// [`progress_interval`](Self::progress_interval), the rest are dropped.
+    /// A new stage or 100% is always delivered.
+    pub fn on_progress<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Progress) + 'static,
+    {
+        self.progress.hooks.push(Rc::new(hook));
+        self
+    }
+
+    /// Minimum time between two progress updates of the same stage, 100ms
+    /// by default.
+    pub fn progress_interval(mut self, interval: Duration) -> Self {
+        self.progress.interval = interval;
+        self
+    }
+
+    /// Check every result against a host policy, e.g. its size, forbidden
+    /// strings or a schema, before it's returned. A result `guard` rejects
+    /// fails the run with [`RunnerError::ResultRejected`].
+    ///
+    /// ```
+    /// use deno_runner::{Builder, RejectReason};
+    ///
+    /// let builder = Builder::new().result_guard(|result| {
+    ///     match result.to_string().contains("BEGIN PRIVATE KEY") {
+    ///         true => Err(RejectReason::new("results can't contain keys")),
+    ///         false => Ok(()),
+    ///     }
+    /// });
+    /// ```
+    ///
+    /// Guards see the result as JSON, of scripts, modules and
+    /// [`call_function`](DenoRunner::call_function), and are called in the
+    /// order they were added. Items of streams aren't checked. Results
+    /// served from a [`MemoCache`] were checked when they were recorded.
+    pub fn result_guard<F>(mut self, guard: F) -> Self
+    where
+        F: Fn(&serde_json::Value) -> std::result::Result<(), RejectReason> + 'static,
+    {
+        let id = NEXT_GUARD_ID.fetch_add(1, Ordering::Relaxed);
+        self.result_guards.push((id, Rc::new(guard)));
+        self
+    }
+
+    /// Limit what a single op call can move between the script and the host
+    /// to `bytes`, counted for the arguments and the result separately.
+    ///
+    /// Sizes are estimated from the JS values: strings by length, typed
+    /// arrays by `byteLength`, objects and arrays by their keys and values.
+    /// A call over the limit throws a `RangeError` with `code` set to
+    /// `"ERR_OP_PAYLOAD_TOO_LARGE"` and the `op` and `limit` as properties,
+    /// without the arguments reaching Rust. Not checked for ops added with
+    /// [`add_fast_op`](Self::add_fast_op).
+    pub fn op_payload_limit(mut self, bytes: usize) -> Self {
+        self.op_payload_limit = Some(bytes);
+        self
+    }
+
+    /// Add the ops of the plugin library at `path`, see [`plugin`].
+    ///
+    /// # Safety
+    ///
+    /// Loading a library runs its initialization code, and its ops run in
+    /// the host process without any checks. Only load trusted plugins built
+    /// with the same compiler as the host.
+    #[cfg(feature = "plugins")]
+    pub unsafe fn load_plugin(mut self, path: impl AsRef<std::ffi::OsStr>) -> Result<Self> {
+        let (plugin, ops) = plugin::load(path.as_ref())?;
+        self.ops.extend(ops);
+        self.plugins.push(plugin);
+        Ok(self)
+    }
+
+    /// Register an op declared with `#[op(fast)]`.
+    ///
+    /// The global function for it is the op itself instead of a wrapper
+    /// going through `opSync`, so V8 can use the fast API call path when
+    /// the signature permits (numbers, bools, no `OpState`).
+    pub fn add_fast_op(mut self, op: deno_core::OpDecl) -> Self {
+        self.fast_ops.push(op.name);
+        self.ops.push(op);
+        self
+    }
+
+    /// Ops the scripts for this runner expect. [`build`](Self::build) panics
+    /// naming the first one that isn't registered, so a missing host
+    /// function shows up when the runner is built instead of mid-run.
+    pub fn require_ops(mut self, ops: &[&str]) -> Self {
+        self.required_ops
+            .extend(ops.iter().map(|op| op.to_string()));
+        self
+    }
+
+    /// Declare the JS types an op expects, one per argument: `"number"`,
+    /// `"string"`, `"boolean"`, `"object"`, `"array"`, `"null"` or `"any"`.
+    ///
+    /// Calls with other types throw a `TypeError` naming the op, the argument
+    /// and both types, e.g. `add: argument 2 expected number, received string`,
+    /// before anything reaches Rust. Not checked for ops added with
+    /// [`add_fast_op`](Self::add_fast_op).
+    pub fn op_signature(mut self, op: &str, params: &[&str]) -> Self {
+        const TYPES: &[&str] = &[
+            "any", "array", "boolean", "null", "number", "object", "string",
         ];
 
+        for param in params {
+            assert!(
+                TYPES.contains(param),
+                "unknown type `{}` in signature of `{}`",
+                param,
+                op
+            );
+        }
+
+        self.op_signatures.insert(
+            op.to_string(),
+            params.iter().map(|param| param.to_string()).collect(),
+        );
+        self
+    }
+
+    /// Expose a Rust [`Stream`] to scripts as an async iterable, so paginated
+    /// host APIs can be consumed without buffering everything up front.
+    ///
+    /// `factory` receives the arguments the script passed to `stream()`:
+    ///
+    /// ```js
+    /// for await (const user of stream("users", { pageSize: 100 })) { ... }
+    /// ```
+    pub fn add_stream<F, A, S, T>(mut self, name: impl ToString, factory: F) -> Self
+    where
+        F: Fn(A) -> S + 'static,
+        A: DeserializeOwned,
+        S: Stream<Item = Result<T>> + 'static,
+        T: Serialize,
+    {
+        self.streams.insert(name.to_string(), factory);
+        self
+    }
+
+    /// Bind a variable whose value is produced by `resolve` only if a script
+    /// reads it, for data that is expensive to fetch. Reading it gives a
+    /// promise, resolved once per run and shared by the run's later reads:
+    ///
+    /// ```js
+    /// const user = await profile
+    /// ```
+    pub fn lazy_binding<F, Fut, T>(mut self, name: impl ToString, resolve: F) -> Self
+    where
+        F: Fn() -> Fut + 'static,
+        Fut: std::future::Future<Output = Result<T>> + 'static,
+        T: Serialize,
+    {
+        self.lazy_bindings.insert(name.to_string(), resolve);
+        self
+    }
+
+    /// Expose `buffer` to scripts as a global `SharedArrayBuffer` named
+    /// `name`. Every runner given a clone of the same [`SharedBuffer`] sees
+    /// the same memory, see its docs for the safety constraints.
+    ///
+    /// ```js
+    /// const rates = new Float64Array(table)
+    /// ```
+    pub fn shared_buffer(mut self, name: impl ToString, buffer: &SharedBuffer) -> Self {
+        self.shared_buffers.insert(name.to_string(), buffer.clone());
+        self
+    }
+
+    /// Expose an Arrow record batch to scripts as a frozen global table
+    /// named `name`. Numeric columns are typed arrays (`Int64`/`UInt64` as
+    /// `BigInt64Array`/`BigUint64Array`, booleans as `Uint8Array` of 0/1),
+    /// string columns arrays of strings; each column is copied into V8 once,
+    /// with no per-value conversion.
+    ///
+    /// ```js
+    /// const { numRows, columns, nulls } = sales
+    /// let total = 0
+    /// for (let i = 0; i < numRows; i++) if (!nulls.amount?.[i]) total += columns.amount[i]
+    /// ```
+    ///
+    /// `nulls` has a `Uint8Array` (1 = null) for each column with nulls,
+    /// `schema` lists `{ name, type }` per column.
+    #[cfg(feature = "arrow")]
+    pub fn record_batch(
+        mut self,
+        name: impl ToString,
+        batch: arrow::record_batch::RecordBatch,
+    ) -> Self {
+        for field in batch.schema().fields() {
+            assert!(
+                columnar::is_supported(field.data_type()),
+                "unsupported type {} of column `{}`",
+                field.data_type(),
+                field.name()
+            );
+        }
+
+        self.record_batches.insert(name.to_string(), batch);
+        self
+    }
+
+    /// Share `table` between ops (through `OpState`) and scripts (as the
+    /// `strings` global), so repeated strings can cross as `u32` ids.
+    pub fn string_table(mut self, table: StringTable) -> Self {
+        self.string_table = table;
+        self
+    }
+
+    /// Give scripts the in-memory `fs` instead of any real file access:
+    /// `fs.readTextFile`, `writeTextFile`, `readFile`, `writeFile`, `remove`,
+    /// `exists` and `readDir`. Modules are imported from it too.
+    pub fn virtual_fs(mut self, fs: &VirtualFs) -> Self {
+        self.virtual_fs = Some(fs.clone());
+        self
+    }
+
+    /// Load the modules scripts `import` with `loader`, e.g.
+    /// [`FsModuleLoader`] to read them from the host's files. Without one,
+    /// imports fail unless a [`virtual_fs`](Self::virtual_fs) is set, which
+    /// `loader` takes precedence over. It's wrapped to check
+    /// [`permissions`](Self::permissions) when they are set.
+    ///
+    /// ```
+    /// use deno_runner::{Builder, FsModuleLoader};
+    ///
+    /// let builder = Builder::new().module_loader(FsModuleLoader);
+    /// ```
+    pub fn module_loader<L: ModuleLoader + 'static>(mut self, loader: L) -> Self {
+        #[cfg(feature = "npm")]
+        {
+            self.npm_loader = (&loader as &dyn std::any::Any)
+                .downcast_ref::<NpmModuleLoader>()
+                .cloned();
+        }
+        self.module_loader = Some(Rc::new(loader));
+        self
+    }
+
+    /// Default number of tasks the `parallel()` helper keeps in flight
+    /// when the script doesn't pass its own `limit`.
+    pub fn parallel_limit(mut self, limit: usize) -> Self {
+        assert!(limit > 0, "parallel limit must be greater than zero");
+        self.parallel_limit = Some(limit);
+        self
+    }
+
+    /// Declare a variable the scripts will receive, so it shows up in
+    /// [`DenoRunner::describe_bindings`] with the schema of `T`.
+    #[cfg(feature = "schemars")]
+    pub fn declare_binding<T: schemars::JsonSchema>(mut self, name: impl ToString) -> Self {
+        self.binding_schemas.add::<T>(name.to_string());
+        self
+    }
+
+    /// Evaluate `code` once on every runner built, after the prelude, to
+    /// define and exercise hot functions before the first real run so it
+    /// doesn't pay for compiling them. Functions and globals it defines stay
+    /// available to scripts.
+    ///
+    /// ```
+    /// use deno_runner::Builder;
+    ///
+    /// let builder = Builder::new().warmup(
+    ///     r#"
+    ///     function score(order) { return order.items * 10 + order.total }
+    ///     for (let i = 0; i < 1000; i++) score({ items: i, total: i })
+    ///     "#,
+    /// );
+    /// ```
+    ///
+    /// Returned promises are not awaited. [`build`](Self::build) panics if
+    /// the script throws.
+    pub fn warmup(mut self, code: impl ToString) -> Self {
+        self.warmup.push(code.to_string());
+        self
+    }
+
+    /// Turn off a JavaScript feature for scripts run by this runner, e.g.
+    /// `eval` for untrusted code or `WeakRef` for reproducible results. Call
+    /// it once per feature; [`describe`](Self::describe) lists them.
+    pub fn disable_language_feature(mut self, feature: LanguageFeature) -> Self {
+        self.disabled_features.insert(feature);
+        self
+    }
+
+    /// Deny scripts host access that `permissions` doesn't allow: network
+    /// hosts for `fetch`, files for module imports and host environment
+    /// variables for [`NodeCompat`]. Without it these are only limited by
+    /// their own settings. The permissions are put in the `OpState` for
+    /// the host's ops to check as well.
+    pub fn permissions(mut self, permissions: Permissions) -> Self {
+        self.permissions = Some(permissions);
+        self
+    }
+
+    /// Install the minimal Node.js compatibility layer, see [`NodeCompat`].
+    pub fn node_compat(mut self, compat: NodeCompat) -> Self {
+        self.node_compat = Some(compat);
+        self
+    }
+
+    /// Give scripts the `fetch` global and the `Request`, `Response` and
+    /// `Headers` classes, see [`FetchOptions`]. Without it scripts can't make
+    /// HTTP requests.
+    #[cfg(feature = "fetch")]
+    pub fn enable_fetch(mut self, options: FetchOptions) -> Self {
+        self.fetch = Some(options);
+        self
+    }
+
+    /// Give scripts the Web Crypto API: `crypto.getRandomValues()`,
+    /// `crypto.randomUUID()` and `crypto.subtle` for hashing, signing and
+    /// encryption. With a `seed` the random values are the same on every
+    /// run, for tests only.
+    #[cfg(feature = "crypto")]
+    pub fn enable_crypto(mut self, seed: Option<u64>) -> Self {
+        self.crypto = Some(seed);
+        self
+    }
+
+    /// Create a V8 startup snapshot of this configuration's runtime, to
+    /// build runners from with [`from_snapshot`](Self::from_snapshot).
+    ///
+    /// The snapshot covers `runtime.js` and the registered extensions and
+    /// ops; everything else (bindings, buffers, filesystem, ...) is applied
+    /// again to each runner built from it. Scripts can be added on top with
+    /// [`Snapshot::layer`].
+    pub fn snapshot(&self) -> Snapshot {
         let mut runtime = JsRuntime::new(RuntimeOptions {
-            module_loader: Some(Rc::new(FsModuleLoader)),
+            extensions: self.extensions(),
+            will_snapshot: true,
+            ..Default::default()
+        });
+        load_prelude(&mut runtime);
+
+        let bytes = runtime.snapshot();
+        Snapshot {
+            startup: snapshot::StartupSnapshot {
+                bytes: Arc::from(&*bytes),
+                ops: self.op_names(),
+            },
+            config: self.clone(),
+            layers: 0,
+        }
+    }
+
+    /// Builder with the configuration `snapshot` was taken with, building
+    /// runners from the snapshot. More settings can be added, but not ops:
+    /// the snapshot only works with the ops it was taken with.
+    pub fn from_snapshot(snapshot: &Snapshot) -> Self {
+        let mut builder = snapshot.config.clone();
+        builder.snapshot = Some(snapshot.startup.clone());
+        builder
+    }
+
+    /// Build runners that can be saved with [`DenoRunner::suspend`] and
+    /// brought back later with [`resume`](Self::resume), so sessions used
+    /// now and then don't hold memory in between.
+    ///
+    /// Shared buffers and record batches live outside the V8 heap and can't
+    /// be saved; building panics if any were added.
+    pub fn persistent(mut self) -> Self {
+        self.persistent = true;
+        self
+    }
+
+    /// Rebuild a runner saved with [`DenoRunner::suspend`], with everything
+    /// its scripts left behind. The builder must register the same ops, in
+    /// the same order, as the one the session was built with.
+    pub fn resume(self, session: &SessionSnapshot) -> Result<DenoRunner> {
+        let ops = self.op_names();
+        if session.header.ops != ops {
+            anyhow::bail!(
+                "Session was saved with ops [{}], the builder registers [{}]",
+                session.header.ops.join(", "),
+                ops.join(", ")
+            );
+        }
+        Ok(self.persistent().build_runner(Some(session)))
+    }
+
+    /// Structured description of the effective configuration (extensions,
+    /// ops, limits, module loader, ...), for bug reports and tests.
+    pub fn describe(&self) -> Description {
+        describe::describe(self)
+    }
+
+    fn op_names(&self) -> Vec<&'static str> {
+        self.ops.iter().map(|op| op.name).collect()
+    }
+
+    fn extensions(&self) -> Vec<deno_core::Extension> {
+        let streams = self.streams.clone();
+        let lazy_bindings = self.lazy_bindings.clone();
+        let string_table = self.string_table.clone();
+        let virtual_fs = self.virtual_fs.clone();
+        let permissions = self.permissions.clone();
+        let state = self.state.clone();
+        let progress = self.progress.clone();
+
+        let mut extensions = vec![deno_console::init()];
+        #[cfg(feature = "url")]
+        extensions.extend(web::extensions(self));
+
+        extensions.extend([
+            deno_core::Extension::builder()
+                .ops(self.ops.clone())
+                .state(move |op_state| {
+                    for init in &state {
+                        init(op_state);
+                    }
+                    Ok(())
+                })
+                .build(),
+            deno_core::Extension::builder()
+                .ops(
+                    [
+                        stream::decls(),
+                        lazy::decls(),
+                        fault::decls(),
+                        vfs::decls(),
+                        encoded::decls(),
+                        timers::decls(),
+                        progress::decls(),
+                    ]
+                    .concat(),
+                )
+                .state(move |state| {
+                    state.put(streams.clone());
+                    state.put(lazy_bindings.clone());
+                    state.put(string_table.clone());
+                    state.put(progress.clone());
+                    if let Some(fs) = &virtual_fs {
+                        state.put(fs.clone());
+                    }
+                    if let Some(permissions) = &permissions {
+                        state.put(permissions.clone());
+                    }
+                    Ok(())
+                })
+                .build(),
+        ]);
+        extensions
+    }
+
+    pub fn build(self) -> DenoRunner {
+        self.build_runner(None)
+    }
+
+    /// Build a runner, from a suspended `session` if given. A session's
+    /// heap already went through [`init_runtime`](Self::init_runtime).
+    fn build_runner(self, session: Option<&SessionSnapshot>) -> DenoRunner {
+        let started = Instant::now();
+        let config = self.clone();
+        let ops = self.ops.len();
+
+        let op_names = self.op_names();
+        for op in &self.required_ops {
+            assert!(
+                op_names.contains(&op.as_str()),
+                "required op `{}` is not registered",
+                op
+            );
+        }
+
+        if let Some(snapshot) = &self.snapshot {
+            assert_eq!(
+                snapshot.ops, op_names,
+                "ops must be registered before taking the snapshot"
+            );
+        }
+
+        if self.persistent {
+            assert!(
+                self.shared_buffers.is_empty(),
+                "shared buffers can't be used with a persistent runner"
+            );
+            #[cfg(feature = "arrow")]
+            assert!(
+                self.record_batches.is_empty(),
+                "record batches can't be used with a persistent runner"
+            );
+        }
+
+        let extensions = self.extensions();
+        let extension_count = extensions.len();
+
+        let mut module_loader: Rc<dyn ModuleLoader> = match (&self.module_loader, &self.virtual_fs)
+        {
+            (Some(loader), _) => match &self.permissions {
+                Some(permissions) => {
+                    // The npm loader fetches packages while resolving, so
+                    // it checks their hosts itself
+                    #[cfg(feature = "npm")]
+                    let loader: Rc<dyn ModuleLoader> = match &self.npm_loader {
+                        Some(npm) => Rc::new(npm.clone().checked(permissions.clone())),
+                        None => loader.clone(),
+                    };
+                    #[cfg(not(feature = "npm"))]
+                    let loader = loader.clone();
+                    Rc::new(permissions::CheckedModuleLoader(
+                        loader,
+                        permissions.clone(),
+                    ))
+                }
+                None => loader.clone(),
+            },
+            (None, Some(fs)) => Rc::new(vfs::VirtualFsModuleLoader(fs.clone())),
+            (None, None) => Rc::new(module::NoImports),
+        };
+        if self
+            .disabled_features
+            .contains(&LanguageFeature::DynamicImport)
+        {
+            module_loader = Rc::new(language::NoDynamicImport(module_loader));
+        }
+
+        let runtime_started = Instant::now();
+        tier::mark_started();
+        let mut runtime = JsRuntime::new(RuntimeOptions {
+            module_loader: Some(module_loader),
             extensions,
+            startup_snapshot: match session {
+                Some(session) => Some(session.to_deno()),
+                None => self.snapshot.as_ref().map(|snapshot| snapshot.to_deno()),
+            },
+            will_snapshot: self.persistent,
+            create_params: self.max_heap_size.map(heap::HeapLimit::create_params),
             ..Default::default()
         });
+        let heap_limit = self
+            .max_heap_size
+            .map(|bytes| heap::HeapLimit::watch(&mut runtime, bytes, &self.heap_hooks));
+        self.heap_hooks.install(&mut runtime);
+        if self.disabled_features.contains(&LanguageFeature::Eval) {
+            let scope = &mut runtime.handle_scope();
+            scope
+                .get_current_context()
+                .set_allow_generation_from_strings(false);
+        }
+        let runtime_init = runtime_started.elapsed();
+        let snapshot_load = match (session, &self.snapshot) {
+            (None, None) => None,
+            _ => Some(runtime_init),
+        };
+
+        let prelude_started = Instant::now();
+        if session.is_none() {
+            self.init_runtime(&mut runtime);
+        }
+        let prelude = prelude_started.elapsed();
+
+        let warmup_started = Instant::now();
+        if session.is_none() {
+            // What scripts add from here on is what checkpoints capture
+            hooks::HookCall::new("markRuntimeGlobals", "")
+                .run(&mut runtime)
+                .unwrap();
+            for code in &self.warmup {
+                if let Err(err) = runtime.execute_script("[runner:warmup]", code) {
+                    panic!("warmup script failed: {}", err);
+                }
+            }
+        }
+        let warmup = warmup_started.elapsed();
+
+        DenoRunner {
+            runtime,
+            config,
+            codec: self.codec,
+            telemetry: self.telemetry,
+            memo: self.memo,
+            timeout: self.timeout,
+            heap_limit,
+            runs: session.map_or(0, |session| session.header.runs),
+            secrets: HashSet::new(),
+            interned: intern::InternedPayloads::default(),
+            build_report: BuildReport {
+                total: started.elapsed(),
+                runtime_init,
+                snapshot_load,
+                prelude,
+                warmup,
+                extensions: extension_count,
+                ops,
+            },
+            #[cfg(feature = "schemars")]
+            binding_schemas: self.binding_schemas,
+            #[cfg(feature = "plugins")]
+            _plugins: self.plugins,
+        }
+    }
+
+    /// Scripts setting up a new runtime for this configuration.
+    fn init_runtime(&self, runtime: &mut JsRuntime) {
+        if self.snapshot.is_none() {
+            load_prelude(runtime);
+        }
 
-        runtime
-            .execute_script("[deno:runtime.js]", include_str!("./runtime.js"))
+        if !self.op_signatures.is_empty() {
+            hooks::HookCall::new(
+                "setOpSignatures",
+                serde_json::to_string(&self.op_signatures).unwrap(),
+            )
+            .run(runtime)
             .unwrap();
+        }
+
+        if let Some(limit) = self.op_payload_limit {
+            hooks::HookCall::new("setOpPayloadLimit", limit.to_string())
+                .run(runtime)
+                .unwrap();
+        }
+
+        if !self.shared_buffers.is_empty() {
+            shared::bind(&mut runtime.handle_scope(), &self.shared_buffers);
+        }
+
+        #[cfg(feature = "arrow")]
+        if !self.record_batches.is_empty() {
+            columnar::bind(&mut runtime.handle_scope(), &self.record_batches);
+        }
+
+        if self.virtual_fs.is_some() {
+            hooks::HookCall::new("defineFs", "").run(runtime).unwrap();
+        }
+
+        if let Some(call) = self.string_table.init_call() {
+            call.run(runtime).unwrap();
+        }
 
-        DenoRunner { runtime }
+        if let Some(call) = self.lazy_bindings.init_call() {
+            call.run(runtime).unwrap();
+        }
+
+        if !self.fast_ops.is_empty() {
+            hooks::HookCall::new("bindFastOps", format!("{:?}", self.fast_ops))
+                .run(runtime)
+                .unwrap();
+        }
+
+        if let Some(limit) = self.parallel_limit {
+            runtime
+                .execute_script("[runner]", &format!("parallel.defaultLimit = {}", limit))
+                .unwrap();
+        }
+
+        if let Some(call) = language::init_call(&self.disabled_features) {
+            call.run(runtime).unwrap();
+        }
+
+        #[cfg(feature = "url")]
+        for (name, script) in web::scripts(self) {
+            runtime.execute_script(name, script).unwrap();
+        }
+
+        if let Some(compat) = &self.node_compat {
+            hooks::load(
+                runtime,
+                "[deno:node_compat.js]",
+                include_str!("./node_compat.js"),
+            )
+            .unwrap();
+            compat
+                .init_call(self.permissions.as_ref())
+                .run(runtime)
+                .unwrap();
+        }
     }
 }
 
+/// Scripts every runtime starts with, part of a [`Snapshot`].
+fn load_prelude(runtime: &mut JsRuntime) {
+    let hooks = runtime
+        .execute_script("[deno:runtime.js]", include_str!("./runtime.js"))
+        .unwrap();
+    hooks::install(runtime, hooks);
+
+    #[cfg(feature = "msgpack")]
+    hooks::load(runtime, "[deno:msgpack.js]", include_str!("./msgpack.js")).unwrap();
+}
+
 impl Default for Builder {
     fn default() -> Self {
         Self::new()
@@ -87,7 +2568,7 @@ mod tests {
 
     macro_rules! gen_test {
         ($code:expr, $value:expr, $expected:expr) => {{
-            let runner = Builder::default().build();
+            let mut runner = Builder::default().build();
             let vars = HashMap::from([("value", $value)]);
             let actual = runner.run($code, Some(vars)).await.unwrap();
 
@@ -113,7 +2594,7 @@ mod tests {
     async fn test_bind_string() {
         let custom_code = r#"a + b"#;
 
-        let runner = Builder::default().build();
+        let mut runner = Builder::default().build();
         let vars = HashMap::from([("a", "11"), ("b", "22")]);
         let result = runner.run(custom_code, Some(vars)).await.unwrap();
 
@@ -124,7 +2605,7 @@ mod tests {
     async fn test_bind_numberic() {
         let custom_code = r#"a + b"#;
 
-        let runner = Builder::default().build();
+        let mut runner = Builder::default().build();
         let vars = HashMap::from([("a", 1), ("b", 2)]);
         let result = runner.run(custom_code, Some(vars)).await.unwrap();
 
@@ -139,7 +2620,7 @@ mod tests {
             value
         "#;
 
-        let runner = Builder::default().build();
+        let mut runner = Builder::default().build();
         let vars = HashMap::from([("value", "hello")]);
         let result = runner.run(custom_code, Some(vars)).await.unwrap();
 
@@ -153,7 +2634,7 @@ mod tests {
             a + 1
         "#;
 
-        let runner = Builder::default().build();
+        let mut runner = Builder::default().build();
         let vars = HashMap::from([("value", "")]);
         let _ = runner.run(custom_code, Some(vars)).await.unwrap();
     }
@@ -175,7 +2656,7 @@ mod tests {
             out
         "#;
 
-        let runner = Builder::default().add_op(add::decl()).build();
+        let mut runner = Builder::default().add_op(add::decl()).build();
         let vars = HashMap::from([("value", "")]);
         let result = runner.run(custom_code, Some(vars)).await.unwrap();
 
@@ -189,7 +2670,7 @@ mod tests {
             out
         "#;
 
-        let runner = Builder::default().add_op(add::decl()).build();
+        let mut runner = Builder::default().add_op(add::decl()).build();
         let result = runner
             .run::<&str, String, String>(custom_code, None)
             .await
@@ -206,12 +2687,11 @@ mod tests {
             })()
         "#;
 
-        let runner = Builder::default().add_op(add_async::decl()).build();
+        let mut runner = Builder::default().add_op(add_async::decl()).build();
         let vars = HashMap::from([("value", "")]);
         let result = runner.run(custom_code, Some(vars)).await.unwrap();
 
-        // TODO: async top-level
-        assert_eq!(result, "[object Promise]");
+        assert_eq!(result, "3");
     }
 
     #[tokio::test]
@@ -221,7 +2701,7 @@ mod tests {
             globalThis.a
         "#;
 
-        let runner = Builder::default().build();
+        let mut runner = Builder::default().build();
         let vars = HashMap::from([("value", "")]);
         let result = runner.run(custom_code, Some(vars)).await.unwrap();
 
@@ -0,0 +1,281 @@
+use crate::{Builder, RunOptions};
+use anyhow::{bail, Result};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Results of earlier runs, keyed by a hash of the script, its bindings, the
+/// options that change the result and the runner's configuration. Clones
+/// share the same entries, so one cache can serve runners on several
+/// threads.
+///
+/// Only pure runs are memoized: ones with [`RunOptions::pure`] or the
+/// `// @pure` comment, and simple expressions that can't call ops. A cache
+/// hit skips the script entirely. Runs with an
+/// [idempotency key](crate::RunOptions::idempotency_key) are recorded here
+/// too, under their key, whatever they do.
+#[derive(Debug, Clone, Default)]
+pub struct MemoCache(Arc<Mutex<HashMap<u64, Entry>>>);
+
+#[derive(Debug)]
+struct Entry {
+    /// [`MemoKey::fingerprint`] of the run that recorded it
+    fingerprint: String,
+    result: String,
+    exit_code: Option<i32>,
+    /// `None` when the TTL goes past what an `Instant` can hold
+    expires: Option<Instant>,
+}
+
+impl Entry {
+    fn live(&self, now: Instant) -> bool {
+        self.expires.map_or(true, |expires| expires > now)
+    }
+}
+
+impl MemoCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of entries, including expired ones not evicted yet.
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+
+    /// Recorded outcome under `key`. An entry recorded by another run is a
+    /// hash collision and missed, unless both runs used the same idempotency
+    /// key.
+    pub(crate) fn get(&self, key: &MemoKey) -> Result<Option<(String, Option<i32>)>> {
+        let mut entries = self.0.lock().unwrap();
+        match entries.get(&key.hash) {
+            Some(entry) if entry.live(Instant::now()) => {
+                if entry.fingerprint == key.fingerprint {
+                    Ok(Some((entry.result.clone(), entry.exit_code)))
+                } else if key.idempotent {
+                    bail!(
+                        "Idempotency key was already used by a run with another script or bindings"
+                    );
+                } else {
+                    Ok(None)
+                }
+            }
+            Some(_) => {
+                entries.remove(&key.hash);
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn insert(
+        &self,
+        key: MemoKey,
+        result: String,
+        exit_code: Option<i32>,
+        ttl: Duration,
+    ) {
+        let mut entries = self.0.lock().unwrap();
+        let now = Instant::now();
+        entries.retain(|_, entry| entry.live(now));
+        entries.insert(
+            key.hash,
+            Entry {
+                fingerprint: key.fingerprint,
+                result,
+                exit_code,
+                expires: now.checked_add(ttl),
+            },
+        );
+    }
+}
+
+/// Where a run's outcome is recorded in a [`MemoCache`].
+#[derive(Debug, Clone)]
+pub(crate) struct MemoKey {
+    hash: u64,
+    /// Everything the result depends on, compared on every hit so a hash
+    /// collision can't hand back another run's result
+    fingerprint: String,
+    /// Recorded under an idempotency key instead of the fingerprint's hash
+    idempotent: bool,
+}
+
+/// Cache key for running `code` with `bindings` (name and encoded value) on
+/// a runner built from `builder`.
+pub(crate) fn key(
+    code: &str,
+    mut bindings: Vec<(String, String)>,
+    options: &RunOptions,
+    builder: &Builder,
+) -> MemoKey {
+    bindings.sort();
+
+    // Only a hash of the secrets, so the cache doesn't hold them
+    let mut secrets = DefaultHasher::new();
+    options.secrets.hash(&mut secrets);
+    // Guards are told apart by the id they were added under, clones of a
+    // builder share them
+    let guards: Vec<_> = builder.result_guards.iter().map(|(id, _)| *id).collect();
+
+    let fingerprint = format!(
+        "{:?}",
+        (
+            code,
+            bindings,
+            (
+                options.number_format,
+                &options.feature_flags,
+                options.binding_mode,
+                options.non_finite,
+                options.cycles,
+                options.codec,
+                &options.capabilities,
+                options.pure,
+                options.result_format,
+                secrets.finish(),
+            ),
+            builder.describe(),
+            guards,
+        )
+    );
+
+    let mut hasher = DefaultHasher::new();
+    fingerprint.hash(&mut hasher);
+    MemoKey {
+        hash: hasher.finish(),
+        fingerprint,
+        idempotent: false,
+    }
+}
+
+impl MemoKey {
+    /// Record the run under `idempotency_key` instead, whatever its script.
+    pub(crate) fn idempotent(self, idempotency_key: &str) -> Self {
+        let mut hasher = DefaultHasher::new();
+        "idempotency".hash(&mut hasher);
+        idempotency_key.hash(&mut hasher);
+        MemoKey {
+            hash: hasher.finish(),
+            idempotent: true,
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BindingMode, Cycles, NonFinite};
+
+    fn bindings(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn memo_key(code: &str, pairs: &[(&str, &str)], options: &RunOptions) -> MemoKey {
+        key(code, bindings(pairs), options, &Builder::new())
+    }
+
+    #[test]
+    fn test_key_ignores_binding_order() {
+        let options = RunOptions::default();
+        let a = memo_key("a + b", &[("a", "1"), ("b", "2")], &options);
+        let b = memo_key("a + b", &[("b", "2"), ("a", "1")], &options);
+
+        assert_eq!(a.fingerprint, b.fingerprint);
+    }
+
+    #[test]
+    fn test_key_changes_with_inputs() {
+        let options = RunOptions::default();
+        let base = memo_key("a + b", &[("a", "1")], &options).fingerprint;
+        let changed = |code, value, options: RunOptions| {
+            memo_key(code, &[("a", value)], &options).fingerprint
+        };
+
+        assert_ne!(base, changed("a - b", "1", RunOptions::default()));
+        assert_ne!(base, changed("a + b", "2", RunOptions::default()));
+        for options in [
+            RunOptions::default().feature_flags([("beta", true)]),
+            RunOptions::default().binding_mode(BindingMode::Const),
+            RunOptions::default().non_finite(NonFinite::String),
+            RunOptions::default().cycles(Cycles::Error),
+            RunOptions::default().approve_capabilities(["op:save"]),
+            RunOptions::default().pure(true),
+        ] {
+            assert_ne!(base, changed("a + b", "1", options));
+        }
+
+        let other_builder = key(
+            "a + b",
+            bindings(&[("a", "1")]),
+            &options,
+            &Builder::new().disable_language_feature(crate::LanguageFeature::Eval),
+        );
+        assert_ne!(base, other_builder.fingerprint);
+    }
+
+    #[test]
+    fn test_key_tells_guards_apart() {
+        let guarded = || Builder::new().result_guard(|_| Ok(()));
+        let fingerprint =
+            |builder: &Builder| key("1", vec![], &RunOptions::default(), builder).fingerprint;
+        let (a, b) = (guarded(), guarded());
+
+        assert_ne!(fingerprint(&a), fingerprint(&b));
+        assert_eq!(fingerprint(&a), fingerprint(&a.clone()));
+    }
+
+    #[test]
+    fn test_expired_entries() {
+        let cache = MemoCache::new();
+        let (a, b) = (
+            memo_key("a", &[], &RunOptions::default()),
+            memo_key("b", &[], &RunOptions::default()),
+        );
+        cache.insert(a.clone(), "a".to_string(), None, Duration::from_secs(60));
+        cache.insert(b.clone(), "b".to_string(), None, Duration::ZERO);
+
+        assert_eq!(cache.get(&a).unwrap(), Some(("a".to_string(), None)));
+        assert_eq!(cache.get(&b).unwrap(), None);
+        assert_eq!(cache.len(), 1);
+
+        // Past what an `Instant` can hold, it never expires
+        cache.insert(b.clone(), "b".to_string(), None, Duration::MAX);
+        assert_eq!(cache.get(&b).unwrap(), Some(("b".to_string(), None)));
+    }
+
+    #[test]
+    fn test_fingerprint_mismatch() {
+        let cache = MemoCache::new();
+        let options = RunOptions::default();
+        let a = memo_key("a", &[], &options);
+        let collision = MemoKey {
+            fingerprint: "b".to_string(),
+            ..a.clone()
+        };
+        cache.insert(a.clone(), "a".to_string(), None, Duration::from_secs(60));
+
+        assert!(cache.get(&a).unwrap().is_some());
+        assert_eq!(cache.get(&collision).unwrap(), None);
+
+        let idempotent = memo_key("a", &[], &options).idempotent("order-1");
+        cache.insert(idempotent, "a".to_string(), None, Duration::from_secs(60));
+        let reused = memo_key("b", &[], &options).idempotent("order-1");
+        assert!(cache.get(&reused).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::{anyhow, Result};
+use deno_core::{
+    futures::future::{self, FutureExt},
+    ModuleLoader, ModuleSource, ModuleSourceFuture, ModuleSpecifier, ModuleType,
+};
+use std::{collections::BTreeMap, pin::Pin};
+
+/// ES module to evaluate with [`DenoRunner::run_module`](crate::DenoRunner::run_module).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Module<'a> {
+    /// URL or path of a module, loaded through the runner's module loader
+    Specifier(&'a str),
+    /// Module source code
+    Source(&'a str),
+}
+
+/// Module loader of runners without one set with
+/// [`Builder::module_loader`](crate::Builder::module_loader): module
+/// sources still run, but anything they import fails to load.
+pub(crate) struct NoImports;
+
+impl ModuleLoader for NoImports {
+    fn resolve(&self, specifier: &str, referrer: &str, _is_main: bool) -> Result<ModuleSpecifier> {
+        Ok(deno_core::resolve_import(specifier, referrer)?)
+    }
+
+    fn load(
+        &self,
+        module_specifier: &ModuleSpecifier,
+        _maybe_referrer: Option<ModuleSpecifier>,
+        _is_dyn_import: bool,
+    ) -> Pin<Box<ModuleSourceFuture>> {
+        let err = anyhow::anyhow!(
+            "Imports are disabled for this runner, set a loader with Builder::module_loader: {}",
+            module_specifier
+        );
+        future::ready(Err(err)).boxed_local()
+    }
+}
+
+/// Module loader serving sources registered by specifier, for script
+/// bundles embedded in the host binary, set with
+/// [`Builder::module_loader`](crate::Builder::module_loader).
+///
+/// ```
+/// use deno_runner::{Builder, MemoryModuleLoader};
+///
+/// let loader = MemoryModuleLoader::new()
+///     .with_module("app:utils", "export default (n) => n * 2;");
+/// let builder = Builder::new().module_loader(loader);
+/// // import helper from "app:utils";
+/// ```
+///
+/// Specifiers are URLs with any scheme, imports of anything that wasn't
+/// registered fail.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryModuleLoader(BTreeMap<ModuleSpecifier, String>);
+
+impl MemoryModuleLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the `source` of the module imported as `specifier`.
+    ///
+    /// Panics if `specifier` isn't a valid URL.
+    pub fn with_module(mut self, specifier: &str, source: impl ToString) -> Self {
+        let specifier = ModuleSpecifier::parse(specifier)
+            .unwrap_or_else(|err| panic!("invalid module specifier `{}`: {}", specifier, err));
+        self.0.insert(specifier, source.to_string());
+        self
+    }
+}
+
+impl<S: AsRef<str>, T: ToString> FromIterator<(S, T)> for MemoryModuleLoader {
+    fn from_iter<I: IntoIterator<Item = (S, T)>>(modules: I) -> Self {
+        modules
+            .into_iter()
+            .fold(Self::new(), |loader, (specifier, source)| {
+                loader.with_module(specifier.as_ref(), source)
+            })
+    }
+}
+
+impl ModuleLoader for MemoryModuleLoader {
+    fn resolve(&self, specifier: &str, referrer: &str, _is_main: bool) -> Result<ModuleSpecifier> {
+        Ok(deno_core::resolve_import(specifier, referrer)?)
+    }
+
+    fn load(
+        &self,
+        module_specifier: &ModuleSpecifier,
+        _maybe_referrer: Option<ModuleSpecifier>,
+        _is_dyn_import: bool,
+    ) -> Pin<Box<ModuleSourceFuture>> {
+        let specifier = module_specifier.to_string();
+        let source = self
+            .0
+            .get(module_specifier)
+            .ok_or_else(|| anyhow!("Module not registered with the loader: {}", specifier))
+            .map(|code| ModuleSource {
+                code: code.as_bytes().into(),
+                module_type: ModuleType::JavaScript,
+                module_url_specified: specifier.clone(),
+                module_url_found: specifier,
+            });
+
+        future::ready(source).boxed_local()
+    }
+}
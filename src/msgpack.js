@@ -0,0 +1,221 @@
+;((hooks) => {
+  const core = Deno.core
+  const { encode: encodeUtf8, decode: decodeUtf8 } = core
+
+  // MessagePack for `Codec::MsgPack`: nil, booleans, numbers, strings, binary
+  // (`Uint8Array`), arrays and maps. Other typed arrays are written as arrays
+  // of numbers, objects with `toJSON()` as what it returns.
+  function encode(value) {
+    let buf = new Uint8Array(256)
+    let view = new DataView(buf.buffer)
+    let pos = 0
+
+    const ensure = (n) => {
+      if (pos + n <= buf.length) return
+      let size = buf.length * 2
+      while (size < pos + n) size *= 2
+      const next = new Uint8Array(size)
+      next.set(buf)
+      buf = next
+      view = new DataView(buf.buffer)
+    }
+    const u8 = (n) => {
+      ensure(1)
+      buf[pos++] = n
+    }
+    const u16 = (n) => {
+      ensure(2)
+      view.setUint16(pos, n)
+      pos += 2
+    }
+    const u32 = (n) => {
+      ensure(4)
+      view.setUint32(pos, n)
+      pos += 4
+    }
+    const raw = (bytes) => {
+      ensure(bytes.length)
+      buf.set(bytes, pos)
+      pos += bytes.length
+    }
+    // Header of a string, binary, array or map of `length` items
+    const header = (length, tiny, tinyMax, b8, b16, b32) => {
+      if (tiny !== null && length <= tinyMax) return u8(tiny | length)
+      if (b8 !== null && length <= 0xff) {
+        u8(b8)
+        return u8(length)
+      }
+      if (length <= 0xffff) {
+        u8(b16)
+        return u16(length)
+      }
+      u8(b32)
+      u32(length)
+    }
+
+    const writeNumber = (n) => {
+      if (Number.isInteger(n)) {
+        if (n >= -32 && n <= 127) return u8(n & 0xff)
+        if (n >= 0 && n <= 0xffffffff) {
+          if (n <= 0xff) return u8(0xcc), u8(n)
+          if (n <= 0xffff) return u8(0xcd), u16(n)
+          return u8(0xce), u32(n)
+        }
+        if (n < 0 && n >= -0x80000000) {
+          if (n >= -0x80) return u8(0xd0), u8(n & 0xff)
+          if (n >= -0x8000) return u8(0xd1), u16(n & 0xffff)
+          return u8(0xd2), u32(n >>> 0)
+        }
+      }
+      u8(0xcb)
+      ensure(8)
+      view.setFloat64(pos, n)
+      pos += 8
+    }
+
+    const write = (v) => {
+      if (v === null || v === undefined) return u8(0xc0)
+      if (v === false) return u8(0xc2)
+      if (v === true) return u8(0xc3)
+      if (typeof v === 'number') return writeNumber(v)
+      if (typeof v === 'bigint') {
+        if (v < -(2n ** 63n) || v >= 2n ** 64n) throw new RangeError('BigInt does not fit in 64 bits')
+        u8(v < 0n ? 0xd3 : 0xcf)
+        ensure(8)
+        if (v < 0n) view.setBigInt64(pos, v)
+        else view.setBigUint64(pos, v)
+        pos += 8
+        return
+      }
+      if (typeof v === 'string') {
+        const bytes = encodeUtf8(v)
+        header(bytes.length, 0xa0, 31, 0xd9, 0xda, 0xdb)
+        return raw(bytes)
+      }
+      if (v instanceof Uint8Array) {
+        header(v.length, null, 0, 0xc4, 0xc5, 0xc6)
+        return raw(v)
+      }
+      if (ArrayBuffer.isView(v)) v = Array.from(v)
+      if (Array.isArray(v)) {
+        header(v.length, 0x90, 15, null, 0xdc, 0xdd)
+        for (const item of v) write(item)
+        return
+      }
+      if (typeof v === 'object' && typeof v.toJSON === 'function') return write(v.toJSON())
+      if (typeof v === 'object') {
+        const entries = Object.entries(v).filter(([, item]) => item !== undefined && typeof item !== 'function')
+        header(entries.length, 0x80, 15, null, 0xde, 0xdf)
+        for (const [key, item] of entries) {
+          write(key)
+          write(item)
+        }
+        return
+      }
+      throw new TypeError(`Cannot encode ${typeof v} as MessagePack`)
+    }
+
+    write(value)
+    return buf.slice(0, pos)
+  }
+
+  function decode(bytes) {
+    const view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength)
+    let pos = 0
+
+    const take = (n) => {
+      if (pos + n > bytes.length) throw new RangeError('Unexpected end of MessagePack data')
+      const start = pos
+      pos += n
+      return start
+    }
+    const u8 = () => bytes[take(1)]
+    const u16 = () => view.getUint16(take(2))
+    const u32 = () => view.getUint32(take(4))
+    const int64 = (signed) => {
+      const n = signed ? view.getBigInt64(take(8)) : view.getBigUint64(take(8))
+      return Number.isSafeInteger(Number(n)) ? Number(n) : n
+    }
+    const str = (n) => decodeUtf8(bytes.subarray(take(n), pos))
+    const bin = (n) => bytes.slice(take(n), pos)
+    const array = (n) => {
+      const out = new Array(n)
+      for (let i = 0; i < n; i++) out[i] = read()
+      return out
+    }
+    const map = (n) => {
+      const out = {}
+      for (let i = 0; i < n; i++) {
+        const key = `${read()}`
+        // defineProperty, so a `__proto__` key is plain data
+        Object.defineProperty(out, key, { value: read(), writable: true, enumerable: true, configurable: true })
+      }
+      return out
+    }
+
+    const read = () => {
+      const b = u8()
+      if (b <= 0x7f) return b
+      if (b >= 0xe0) return b - 0x100
+      if ((b & 0xf0) === 0x80) return map(b & 0x0f)
+      if ((b & 0xf0) === 0x90) return array(b & 0x0f)
+      if ((b & 0xe0) === 0xa0) return str(b & 0x1f)
+
+      switch (b) {
+        case 0xc0:
+          return null
+        case 0xc2:
+          return false
+        case 0xc3:
+          return true
+        case 0xc4:
+          return bin(u8())
+        case 0xc5:
+          return bin(u16())
+        case 0xc6:
+          return bin(u32())
+        case 0xca:
+          return view.getFloat32(take(4))
+        case 0xcb:
+          return view.getFloat64(take(8))
+        case 0xcc:
+          return u8()
+        case 0xcd:
+          return u16()
+        case 0xce:
+          return u32()
+        case 0xcf:
+          return int64(false)
+        case 0xd0:
+          return view.getInt8(take(1))
+        case 0xd1:
+          return view.getInt16(take(2))
+        case 0xd2:
+          return view.getInt32(take(4))
+        case 0xd3:
+          return int64(true)
+        case 0xd9:
+          return str(u8())
+        case 0xda:
+          return str(u16())
+        case 0xdb:
+          return str(u32())
+        case 0xdc:
+          return array(u16())
+        case 0xdd:
+          return array(u32())
+        case 0xde:
+          return map(u16())
+        case 0xdf:
+          return map(u32())
+      }
+      throw new TypeError(`Unsupported MessagePack type 0x${b.toString(16)}`)
+    }
+
+    const value = read()
+    if (pos !== bytes.length) throw new RangeError('Trailing bytes after MessagePack value')
+    return value
+  }
+
+  hooks.registerCodec('msgpack', { encode, decode })
+})
@@ -0,0 +1,196 @@
+;((hooks) => {
+  const core = Deno.core
+
+  const BASE64 = 'ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/'
+
+  function base64Encode(bytes) {
+    let out = ''
+    for (let i = 0; i < bytes.length; i += 3) {
+      const n = (bytes[i] << 16) | ((bytes[i + 1] ?? 0) << 8) | (bytes[i + 2] ?? 0)
+      out += BASE64[(n >> 18) & 63] + BASE64[(n >> 12) & 63]
+      out += i + 1 < bytes.length ? BASE64[(n >> 6) & 63] : '='
+      out += i + 2 < bytes.length ? BASE64[n & 63] : '='
+    }
+    return out
+  }
+
+  function base64Decode(text) {
+    const clean = text.replace(/[^A-Za-z0-9+/]/g, '')
+    const bytes = []
+    for (let i = 0; i < clean.length; i += 4) {
+      const chunk = clean.slice(i, i + 4)
+      let n = 0
+      for (let j = 0; j < 4; j++) {
+        n = (n << 6) | (j < chunk.length ? BASE64.indexOf(chunk[j]) : 0)
+      }
+      bytes.push((n >> 16) & 255)
+      if (chunk.length > 2) bytes.push((n >> 8) & 255)
+      if (chunk.length > 3) bytes.push(n & 255)
+    }
+    return bytes
+  }
+
+  class Buffer extends Uint8Array {
+    static from(value, encoding = 'utf8') {
+      if (typeof value === 'string') {
+        switch (encoding) {
+          case 'utf8':
+          case 'utf-8':
+            return Buffer.from(core.encode(value))
+          case 'hex':
+            return Buffer.from((value.match(/../g) ?? []).map((h) => parseInt(h, 16)))
+          case 'base64':
+            return Buffer.from(base64Decode(value))
+          default:
+            throw new TypeError(`Unknown encoding: ${encoding}`)
+        }
+      }
+      // Bytes of the buffer, it has no `length`
+      if (value instanceof ArrayBuffer) return Buffer.from(new Uint8Array(value))
+      const buf = new Buffer(value.length)
+      buf.set(value)
+      return buf
+    }
+
+    static alloc(size, fill = 0) {
+      return new Buffer(size).fill(fill)
+    }
+
+    static isBuffer(value) {
+      return value instanceof Buffer
+    }
+
+    static byteLength(value, encoding = 'utf8') {
+      return typeof value === 'string' ? Buffer.from(value, encoding).length : value.byteLength
+    }
+
+    static concat(list) {
+      const out = new Buffer(list.reduce((len, buf) => len + buf.length, 0))
+      let offset = 0
+      for (const buf of list) {
+        out.set(buf, offset)
+        offset += buf.length
+      }
+      return out
+    }
+
+    toString(encoding = 'utf8') {
+      switch (encoding) {
+        case 'utf8':
+        case 'utf-8':
+          return core.decode(new Uint8Array(this))
+        case 'hex':
+          return Array.from(this, (b) => b.toString(16).padStart(2, '0')).join('')
+        case 'base64':
+          return base64Encode(this)
+        default:
+          throw new TypeError(`Unknown encoding: ${encoding}`)
+      }
+    }
+
+    toJSON() {
+      return { type: 'Buffer', data: Array.from(this) }
+    }
+  }
+
+  function normalize(path) {
+    const absolute = path.startsWith('/')
+    const parts = []
+    for (const part of path.split('/')) {
+      if (part === '' || part === '.') continue
+      if (part === '..' && parts.length > 0 && parts[parts.length - 1] !== '..') parts.pop()
+      else if (part !== '..' || !absolute) parts.push(part)
+    }
+    const out = parts.join('/')
+    return absolute ? `/${out}` : out || '.'
+  }
+
+  const path = {
+    sep: '/',
+    delimiter: ':',
+    normalize,
+    isAbsolute: (p) => p.startsWith('/'),
+    join: (...parts) => normalize(parts.filter((p) => p !== '').join('/')),
+    resolve: (...parts) => {
+      let resolved = ''
+      for (const part of parts) {
+        resolved = part.startsWith('/') ? part : `${resolved}/${part}`
+      }
+      return normalize(`/${resolved}`)
+    },
+    dirname: (p) => {
+      const index = p.replace(/\/+$/, '').lastIndexOf('/')
+      if (index < 0) return '.'
+      return index === 0 ? '/' : p.slice(0, index)
+    },
+    basename: (p, ext) => {
+      const base = p.replace(/\/+$/, '').split('/').pop()
+      return ext && base.endsWith(ext) ? base.slice(0, -ext.length) : base
+    },
+    extname: (p) => {
+      const base = path.basename(p)
+      const index = base.lastIndexOf('.')
+      return index > 0 ? base.slice(index) : ''
+    },
+  }
+  path.posix = path
+
+  const querystring = {
+    escape: encodeURIComponent,
+    unescape: (s) => {
+      const text = s.replace(/\+/g, ' ')
+      try {
+        return decodeURIComponent(text)
+      } catch {
+        // Malformed `%` sequences are kept as they are, like Node
+        return text
+      }
+    },
+    parse: (str, sep = '&', eq = '=') => {
+      const out = Object.create(null)
+      for (const pair of str.split(sep)) {
+        if (pair === '') continue
+        const index = pair.indexOf(eq)
+        const key = querystring.unescape(index < 0 ? pair : pair.slice(0, index))
+        const value = index < 0 ? '' : querystring.unescape(pair.slice(index + eq.length))
+        if (key in out) out[key] = [].concat(out[key], value)
+        else out[key] = value
+      }
+      return out
+    },
+    stringify: (obj, sep = '&', eq = '=') =>
+      Object.entries(obj)
+        .flatMap(([key, value]) =>
+          [].concat(value).map((v) => `${querystring.escape(key)}${eq}${querystring.escape(String(v))}`),
+        )
+        .join(sep),
+  }
+
+  // Null prototype, so `require('constructor')` doesn't resolve to a builtin
+  const modules = { __proto__: null, buffer: { Buffer }, path, querystring }
+
+  function require(name) {
+    const module = modules[name.replace(/^node:/, '')]
+    if (module === undefined) {
+      const error = new Error(`Cannot find module '${name}'`)
+      error.code = 'MODULE_NOT_FOUND'
+      throw error
+    }
+    return module
+  }
+
+  hooks.initNodeCompat = ({ env }) => {
+    const exit = globalThis.exit
+
+    globalThis.Buffer = Buffer
+    globalThis.require = require
+    globalThis.process = {
+      env: Object.freeze(env),
+      argv: [],
+      platform: 'deno_runner',
+      cwd: () => '/',
+      exit: (code = 0) => exit(code),
+      nextTick: (fn, ...args) => Promise.resolve().then(() => fn(...args)),
+    }
+  }
+})
@@ -0,0 +1,63 @@
+use crate::{hooks::HookCall, Permissions};
+use deno_core::serde_json;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Opt-in shims for common Node.js globals, see [`Builder::node_compat`](crate::Builder::node_compat).
+///
+/// Installs `Buffer`, `process` and a `require()` limited to the bundled
+/// `buffer`, `path` and `querystring` modules. `process.env` only contains
+/// the variables the host allowed.
+#[derive(Debug, Clone, Default)]
+pub struct NodeCompat {
+    env: BTreeMap<String, String>,
+    /// Keys of `env` read from the host environment
+    host_env: BTreeSet<String>,
+}
+
+impl NodeCompat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expose a host environment variable in `process.env`, if it is set
+    /// and the builder's [`Permissions`] allow it.
+    pub fn allow_env(mut self, key: &str) -> Self {
+        if let Ok(value) = std::env::var(key) {
+            self.env.insert(key.to_string(), value);
+            self.host_env.insert(key.to_string());
+        }
+        self
+    }
+
+    /// Set a `process.env` entry without reading the host environment.
+    pub fn env<K: ToString, V: ToString>(mut self, key: K, value: V) -> Self {
+        let key = key.to_string();
+        self.host_env.remove(&key);
+        self.env.insert(key, value.to_string());
+        self
+    }
+
+    /// Host environment variables the embedder chose to expose.
+    #[cfg(feature = "sandbox-tests")]
+    pub(crate) fn host_env(&self) -> &BTreeSet<String> {
+        &self.host_env
+    }
+
+    pub(crate) fn init_call(&self, permissions: Option<&Permissions>) -> HookCall {
+        let env: BTreeMap<_, _> = self
+            .env
+            .iter()
+            .filter(|(key, _)| match permissions {
+                Some(permissions) if self.host_env.contains(*key) => {
+                    permissions.check_env(key).is_ok()
+                }
+                _ => true,
+            })
+            .collect();
+
+        HookCall::new(
+            "initNodeCompat",
+            format!("{{ env: {} }}", serde_json::to_string(&env).unwrap()),
+        )
+    }
+}
@@ -0,0 +1,430 @@
+use crate::Permissions;
+use anyhow::{anyhow, bail, Context, Result};
+use deno_core::{
+    futures::future::{self, FutureExt},
+    serde_json::{self, Value},
+    url::Url,
+    ModuleLoader, ModuleSource, ModuleSourceFuture, ModuleSpecifier, ModuleType,
+};
+use std::{
+    fs,
+    io::Read,
+    path::{Component, Path, PathBuf},
+    pin::Pin,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Downloads started by this process, to give each its own directory
+static DOWNLOADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Module loader resolving `npm:` imports, e.g. `import { chunk } from
+/// "npm:lodash-es@4"`, from packages in a cache directory, set with
+/// [`Builder::module_loader`](crate::Builder::module_loader).
+///
+/// ```no_run
+/// use deno_runner::{Builder, NpmModuleLoader};
+///
+/// let loader = NpmModuleLoader::new("/var/cache/deno_runner/npm")
+///     .registry("https://registry.npmjs.org");
+/// let builder = Builder::new().module_loader(loader);
+/// ```
+///
+/// Packages are kept as `<cache>/<name>/<version>/`. A version is the
+/// highest cached one matching the requested `4`, `4.17` or `4.17.21`, or
+/// any version without one. Without a [`registry`](Self::registry) the
+/// loader never touches the network, so the cache must be filled
+/// beforehand; with one, packages missing from the cache are downloaded
+/// into it on first import. With
+/// [`Builder::permissions`](crate::Builder::permissions) the registry and
+/// tarball hosts need net access, and the cache directory read access.
+///
+/// Only ES module packages work, and they can only import their own files:
+/// imports of their dependencies fail.
+#[derive(Debug, Clone)]
+pub struct NpmModuleLoader {
+    cache_dir: PathBuf,
+    registry: Option<String>,
+    /// Set by the builder from [`Builder::permissions`](crate::Builder::permissions)
+    permissions: Option<Permissions>,
+}
+
+/// `npm:<name>[@<version>][/<path>]`
+#[derive(Debug, PartialEq, Eq)]
+struct PackageReq<'a> {
+    name: &'a str,
+    version: Option<&'a str>,
+    path: Option<&'a str>,
+}
+
+impl<'a> PackageReq<'a> {
+    fn parse(specifier: &'a str) -> Result<Self> {
+        let req = specifier
+            .strip_prefix("npm:")
+            .ok_or_else(|| anyhow!("Not an npm specifier: {}", specifier))?;
+        // The name of a scoped package has a `/` of its own
+        let name_end = match req.strip_prefix('@') {
+            Some(scoped) => scoped.find('/').map(|i| i + 2).unwrap_or(req.len()),
+            None => 0,
+        };
+        let (package, path) = match req[name_end..].find('/') {
+            Some(i) => (&req[..name_end + i], Some(&req[name_end + i + 1..])),
+            None => (req, None),
+        };
+        let (name, version) = match package[name_end..].find('@') {
+            Some(i) => (&package[..name_end + i], Some(&package[name_end + i + 1..])),
+            None => (package, None),
+        };
+
+        if name.is_empty() || name.ends_with('/') || name.contains("..") {
+            bail!("Invalid npm specifier: {}", specifier);
+        }
+        Ok(Self {
+            name,
+            version: version.filter(|version| !version.is_empty() && *version != "latest"),
+            path: path.filter(|path| !path.is_empty()),
+        })
+    }
+}
+
+/// `major.minor.patch` of a release version, `None` for prereleases and
+/// anything else.
+fn parse_version(version: &str) -> Option<[u64; 3]> {
+    let mut parts = version.split('.').map(|part| part.parse().ok());
+    let version = [parts.next()??, parts.next()??, parts.next()??];
+    match parts.next() {
+        Some(_) => None,
+        None => Some(version),
+    }
+}
+
+/// Whether `version` matches `4`, `4.17`, `4.17.21` or any version for
+/// `None`.
+fn matches(version: [u64; 3], requested: Option<&str>) -> Result<bool> {
+    let requested = match requested {
+        Some(requested) => requested,
+        None => return Ok(true),
+    };
+    for (i, part) in requested.split('.').enumerate() {
+        let part: u64 = match (i, part.parse()) {
+            (0..=2, Ok(part)) => part,
+            _ => bail!(
+                "Unsupported npm version `{}`, use a version like 4, 4.17 or 4.17.21",
+                requested
+            ),
+        };
+        if version[i] != part {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Highest of `versions` matching `requested`.
+fn best_version<'a>(
+    versions: impl Iterator<Item = &'a str>,
+    requested: Option<&str>,
+) -> Result<Option<&'a str>> {
+    let mut best = None;
+    for version in versions {
+        if let Some(parsed) = parse_version(version) {
+            if matches(parsed, requested)? && best.map_or(true, |(best, _)| parsed > best) {
+                best = Some((parsed, version));
+            }
+        }
+    }
+    Ok(best.map(|(_, version)| version))
+}
+
+/// File of the package's main ES module, from `package.json`.
+fn entry_point(package_json: &Value) -> &str {
+    let exports = match &package_json["exports"] {
+        Value::Object(exports) if exports.contains_key(".") => &exports["."],
+        exports => exports,
+    };
+    let export = match exports {
+        Value::String(export) => Some(export.as_str()),
+        Value::Object(conditions) => ["import", "module", "default"]
+            .iter()
+            .find_map(|condition| conditions.get(*condition).and_then(Value::as_str)),
+        _ => None,
+    };
+
+    export
+        .or_else(|| package_json["module"].as_str())
+        .or_else(|| package_json["main"].as_str())
+        .unwrap_or("index.js")
+}
+
+impl NpmModuleLoader {
+    /// Load packages from `cache_dir`, without downloading any.
+    ///
+    /// Panics if `cache_dir` isn't an absolute path.
+    pub fn new(cache_dir: impl Into<PathBuf>) -> Self {
+        let cache_dir = cache_dir.into();
+        assert!(
+            cache_dir.is_absolute(),
+            "npm cache dir must be an absolute path, got {}",
+            cache_dir.display()
+        );
+
+        Self {
+            cache_dir,
+            registry: None,
+            permissions: None,
+        }
+    }
+
+    /// Download packages missing from the cache from the npm registry at
+    /// `url`, e.g. `https://registry.npmjs.org`.
+    pub fn registry(mut self, url: impl ToString) -> Self {
+        self.registry = Some(url.to_string().trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Check downloads against `permissions`.
+    pub(crate) fn checked(mut self, permissions: Permissions) -> Self {
+        self.permissions = Some(permissions);
+        self
+    }
+
+    /// GET `url`, if the permissions allow its host.
+    fn fetch(&self, url: &str) -> Result<ureq::Response> {
+        if let Some(permissions) = &self.permissions {
+            permissions.check_net(&Url::parse(url)?)?;
+        }
+        Ok(ureq::get(url).call()?)
+    }
+
+    /// Directory of the package, downloading it if needed.
+    fn package_dir(&self, name: &str, requested: Option<&str>) -> Result<PathBuf> {
+        let package_dir = self.cache_dir.join(name);
+        let cached: Vec<String> = match fs::read_dir(&package_dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+                .collect(),
+            Err(_) => vec![],
+        };
+        if let Some(version) = best_version(cached.iter().map(String::as_str), requested)? {
+            return Ok(package_dir.join(version));
+        }
+
+        match &self.registry {
+            Some(registry) => self.download(registry, name, requested),
+            None => bail!(
+                "npm package {}@{} is not in the cache at {}",
+                name,
+                requested.unwrap_or("latest"),
+                self.cache_dir.display()
+            ),
+        }
+    }
+
+    fn download(&self, registry: &str, name: &str, requested: Option<&str>) -> Result<PathBuf> {
+        let packument = self
+            .fetch(&format!("{}/{}", registry, name.replace('/', "%2f")))
+            .with_context(|| format!("Failed to fetch npm package {}", name))?
+            .into_string()?;
+        let packument: Value = serde_json::from_str(&packument)?;
+        let versions = packument["versions"]
+            .as_object()
+            .ok_or_else(|| anyhow!("npm package {} has no versions", name))?;
+        let version =
+            best_version(versions.keys().map(String::as_str), requested)?.ok_or_else(|| {
+                anyhow!(
+                    "npm package {} has no version matching {}",
+                    name,
+                    requested.unwrap_or("latest")
+                )
+            })?;
+        let tarball = versions[version]["dist"]["tarball"]
+            .as_str()
+            .ok_or_else(|| anyhow!("npm package {}@{} has no tarball", name, version))?;
+
+        let response = self
+            .fetch(tarball)
+            .with_context(|| format!("Failed to download npm package {}@{}", name, version))?;
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(response.into_reader()));
+
+        // Unpacked next to the cache entry and moved in place, so a failed
+        // download never leaves a partial package behind. Each download has
+        // its own directory, others may be unpacking the same package.
+        let dir = self.cache_dir.join(name).join(version);
+        let partial = dir.with_file_name(format!(
+            "{}.partial-{}-{}",
+            version,
+            std::process::id(),
+            DOWNLOADS.fetch_add(1, Ordering::Relaxed)
+        ));
+        let moved = unpack(&mut archive, &partial, name, version).and_then(|()| {
+            match fs::rename(&partial, &dir) {
+                Ok(()) => Ok(()),
+                // Another download got there first
+                Err(_) if dir.is_dir() => Ok(()),
+                Err(err) => Err(err.into()),
+            }
+        });
+        let _ = fs::remove_dir_all(&partial);
+        moved?;
+        Ok(dir)
+    }
+
+    /// File URL of the module an `npm:` specifier imports.
+    fn resolve_npm(&self, specifier: &str) -> Result<ModuleSpecifier> {
+        let req = PackageReq::parse(specifier)?;
+        let dir = self.package_dir(req.name, req.version)?;
+        let file = match req.path {
+            Some(path) => path.to_string(),
+            None => {
+                let package_json = fs::read_to_string(dir.join("package.json"))
+                    .with_context(|| format!("npm package {} has no package.json", req.name))?;
+                entry_point(&serde_json::from_str(&package_json)?).to_string()
+            }
+        };
+        let path = dir.join(file.trim_start_matches("./"));
+        if !path.starts_with(&dir) || file.contains("..") {
+            bail!("Invalid npm module path: {}", specifier);
+        }
+        ModuleSpecifier::from_file_path(&path)
+            .map_err(|_| anyhow!("Invalid npm module path: {}", path.display()))
+    }
+
+    fn read(&self, module_specifier: &ModuleSpecifier) -> Result<String> {
+        let path = module_specifier
+            .to_file_path()
+            .ok()
+            .filter(|path| is_within(path, &self.cache_dir))
+            .ok_or_else(|| anyhow!("Only npm modules can be imported: {}", module_specifier))?;
+        fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read npm module {}", module_specifier))
+    }
+}
+
+/// Unpack the files of a package tarball into `dir`.
+fn unpack<R: Read>(
+    archive: &mut tar::Archive<R>,
+    dir: &Path,
+    name: &str,
+    version: &str,
+) -> Result<()> {
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        // Tarballs have the package in a top-level directory, usually
+        // `package/`
+        let path: PathBuf = entry.path()?.components().skip(1).collect();
+        if !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            bail!("npm package {}@{} has an invalid path", name, version);
+        }
+        let target = dir.join(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        entry.unpack(&target)?;
+    }
+    Ok(())
+}
+
+/// Whether `path` is inside `dir`, without `..` to leave it.
+fn is_within(path: &Path, dir: &Path) -> bool {
+    path.starts_with(dir)
+        && !path
+            .components()
+            .any(|component| component == Component::ParentDir)
+}
+
+impl ModuleLoader for NpmModuleLoader {
+    fn resolve(&self, specifier: &str, referrer: &str, _is_main: bool) -> Result<ModuleSpecifier> {
+        if specifier.starts_with("npm:") {
+            return self.resolve_npm(specifier);
+        }
+        Ok(deno_core::resolve_import(specifier, referrer)?)
+    }
+
+    fn load(
+        &self,
+        module_specifier: &ModuleSpecifier,
+        _maybe_referrer: Option<ModuleSpecifier>,
+        _is_dyn_import: bool,
+    ) -> Pin<Box<ModuleSourceFuture>> {
+        let specifier = module_specifier.to_string();
+        let source = self.read(module_specifier).map(|code| ModuleSource {
+            code: code.into_bytes().into_boxed_slice(),
+            module_type: ModuleType::JavaScript,
+            module_url_specified: specifier.clone(),
+            module_url_found: specifier,
+        });
+
+        future::ready(source).boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deno_core::serde_json::json;
+
+    #[test]
+    fn test_parse_specifier() {
+        let parse = |specifier| PackageReq::parse(specifier).unwrap();
+
+        assert_eq!(
+            parse("npm:lodash-es@4/chunk.js"),
+            PackageReq {
+                name: "lodash-es",
+                version: Some("4"),
+                path: Some("chunk.js"),
+            }
+        );
+        assert_eq!(
+            parse("npm:@std/path@1.0"),
+            PackageReq {
+                name: "@std/path",
+                version: Some("1.0"),
+                path: None,
+            }
+        );
+        assert_eq!(
+            parse("npm:preact"),
+            PackageReq {
+                name: "preact",
+                version: None,
+                path: None,
+            }
+        );
+        assert!(PackageReq::parse("npm:../etc").is_err());
+    }
+
+    #[test]
+    fn test_best_version() {
+        let versions = ["3.10.1", "4.17.20", "4.17.21", "4.2.0", "5.0.0-beta.1"];
+        let best = |requested| best_version(versions.iter().copied(), requested).unwrap();
+
+        assert_eq!(best(Some("4")), Some("4.17.21"));
+        assert_eq!(best(Some("4.2")), Some("4.2.0"));
+        assert_eq!(best(Some("3.10.1")), Some("3.10.1"));
+        assert_eq!(best(Some("6")), None);
+        assert_eq!(best(None), Some("4.17.21"));
+        assert!(best_version(versions.iter().copied(), Some("^4.1")).is_err());
+    }
+
+    #[test]
+    fn test_entry_point() {
+        assert_eq!(
+            entry_point(&json!({ "module": "es/index.js" })),
+            "es/index.js"
+        );
+        assert_eq!(
+            entry_point(&json!({
+                "exports": { ".": { "import": "./index.mjs", "require": "./index.cjs" } },
+                "main": "index.cjs",
+            })),
+            "./index.mjs"
+        );
+        assert_eq!(entry_point(&json!({})), "index.js");
+    }
+}
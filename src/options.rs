@@ -0,0 +1,522 @@
+use crate::{hooks::HookCall, intern::Payload, secret::Secret, Codec, FaultPlan};
+use anyhow::{anyhow, Result};
+use deno_core::{serde_json, sourcemap, v8};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+
+const PURE_MARKER: &str = "// @pure";
+
+/// Per-run settings for [`DenoRunner::run_with_options`](crate::DenoRunner::run_with_options).
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    pub(crate) number_format: NumberFormat,
+    pub(crate) feature_flags: BTreeMap<String, bool>,
+    pub(crate) fast_path: bool,
+    pub(crate) tags: BTreeMap<String, String>,
+    pub(crate) dry_run: bool,
+    pub(crate) stubs: BTreeMap<String, serde_json::Value>,
+    pub(crate) faults: FaultPlan,
+    pub(crate) pure: bool,
+    pub(crate) script_name: Option<String>,
+    pub(crate) error_context: bool,
+    pub(crate) secrets: BTreeMap<String, Secret>,
+    pub(crate) cached_bindings: BTreeMap<String, Payload>,
+    pub(crate) capabilities: BTreeSet<String>,
+    pub(crate) codec: Codec,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) non_finite: Option<NonFinite>,
+    pub(crate) cycles: Cycles,
+    pub(crate) capture_console: bool,
+    pub(crate) binding_mode: BindingMode,
+    pub(crate) binding_conflicts: ConflictPolicy,
+    pub(crate) idempotency_key: Option<String>,
+    pub(crate) source_map: Option<sourcemap::SourceMap>,
+    pub(crate) result_format: ResultFormat,
+}
+
+impl RunOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How a numeric result is turned into the returned string.
+    pub fn number_format(mut self, format: NumberFormat) -> Self {
+        self.number_format = format;
+        self
+    }
+
+    /// Evaluate simple arithmetic/boolean expressions over number and
+    /// boolean variables (`a * 2 > b && !flag`) in Rust, without running V8.
+    /// Any other script runs normally.
+    pub fn fast_path(mut self, enabled: bool) -> Self {
+        self.fast_path = enabled;
+        self
+    }
+
+    /// Flags exposed to the script as the frozen `features` global, checked
+    /// with `features.isEnabled("name")`. Unknown flags are disabled.
+    pub fn feature_flags<I, K>(mut self, flags: I) -> Self
+    where
+        I: IntoIterator<Item = (K, bool)>,
+        K: ToString,
+    {
+        self.feature_flags = flags
+            .into_iter()
+            .map(|(name, enabled)| (name.to_string(), enabled))
+            .collect();
+        self
+    }
+
+    /// Don't call any registered op: calls are recorded in
+    /// [`RunReport::op_calls`](crate::RunReport::op_calls) and answered with
+    /// the value set with [`stub`](Self::stub), or `undefined`. Lets a host
+    /// preview what a script would do without its side effects.
+    pub fn dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
+        self
+    }
+
+    /// Value returned by `op` during a [`dry_run`](Self::dry_run).
+    pub fn stub(mut self, op: impl ToString, value: impl serde::Serialize) -> Self {
+        let value = serde_json::to_value(value).expect("stub value must serialize to JSON");
+        self.stubs.insert(op.to_string(), value);
+        self
+    }
+
+    /// Run the script as pure: calling ops, reading streams or lazy
+    /// bindings, `Math.random()`, reading the current time and timers all
+    /// throw, so the result depends only on the code and its bindings. A
+    /// script can ask for the same with a `// @pure` comment.
+    pub fn pure(mut self, enabled: bool) -> Self {
+        self.pure = enabled;
+        self
+    }
+
+    /// Inject the failures and delays of `plan` into op calls.
+    pub fn faults(mut self, plan: FaultPlan) -> Self {
+        self.faults = plan;
+        self
+    }
+
+    /// Keep what the script prints with `console` instead of writing it to
+    /// the process output, and return it in
+    /// [`RunReport::stdout`](crate::RunReport::stdout) and
+    /// [`RunReport::stderr`](crate::RunReport::stderr), call by call in
+    /// [`RunReport::console`](crate::RunReport::console). Secrets are
+    /// redacted.
+    pub fn capture_console(mut self, enabled: bool) -> Self {
+        self.capture_console = enabled;
+        self
+    }
+
+    /// Name of the script in stack traces and error messages, `code.js`
+    /// by default.
+    pub fn script_name(mut self, name: impl ToString) -> Self {
+        self.script_name = Some(name.to_string());
+        self
+    }
+
+    /// Source map of the script, for hosts that transpile or wrap the code
+    /// their users wrote. The stack of a [`JsError`](crate::JsError) the
+    /// run fails with then points at the user's original lines, and files
+    /// when the map names them. Fails if `json` isn't a valid source map.
+    ///
+    /// ```
+    /// use deno_runner::RunOptions;
+    ///
+    /// // `const x = 1;\nthrow new Error("boom")` with a line added on top
+    /// let map = r#"{"version":3,"sources":["input.ts"],"names":[],"mappings":";AAAA;AACA"}"#;
+    /// let options = RunOptions::new().source_map(map).unwrap();
+    /// ```
+    pub fn source_map(mut self, json: &str) -> Result<Self> {
+        let map = sourcemap::SourceMap::from_slice(json.as_bytes())
+            .map_err(|err| anyhow!("Invalid source map: {}", err))?;
+        self.source_map = Some(map);
+        Ok(self)
+    }
+
+    /// On failure, wrap the error with the script name and the names of the
+    /// bound variables, e.g. `script 'pricing.js' failed (bindings: price,
+    /// qty)`. Values are never included.
+    pub fn error_context(mut self, enabled: bool) -> Self {
+        self.error_context = enabled;
+        self
+    }
+
+    /// Bind `value` to the string variable `name`, like a regular binding,
+    /// but redact it (as `[REDACTED]`) from everything the run hands back:
+    /// the result, errors, recorded op calls and captured console output,
+    /// and from what later calls on the same runner return. It is also
+    /// hidden from this struct's `Debug` output.
+    pub fn secret(mut self, name: impl ToString, value: impl ToString) -> Self {
+        self.secrets
+            .insert(name.to_string(), Secret(value.to_string()));
+        self
+    }
+
+    /// Bind the JSON value of `value` to `name`, parsed only once per
+    /// runner: later runs binding the same payload, like a large config
+    /// every request passes, get the value the first one parsed. Since
+    /// runs share it, the value is deeply frozen.
+    ///
+    /// ```
+    /// use deno_runner::{serde_json::json, Builder, RunOptions};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let config = json!({ "rates": { "vip": 0.2 } });
+    /// let mut runner = Builder::new().build();
+    /// for _ in 0..2 {
+    ///     let options = RunOptions::new().cached_binding("config", &config).unwrap();
+    ///     let report = runner
+    ///         .run_with_options::<String, String>("100 * config.rates.vip", None, options)
+    ///         .await
+    ///         .unwrap();
+    ///     assert_eq!(report.result, "20");
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// Fails when `value` can't be serialized to JSON.
+    pub fn cached_binding(
+        mut self,
+        name: impl ToString,
+        value: impl serde::Serialize,
+    ) -> Result<Self> {
+        self.cached_bindings
+            .insert(name.to_string(), Payload::new(value)?);
+        Ok(self)
+    }
+
+    /// Capabilities (`op:fetch_data`, `net:api.example.com`) a script may
+    /// declare in a `// requires:` header. A script with the header fails
+    /// unless all it declares is approved, and can only call the ops and
+    /// `fetch` the hosts it declared. Once capabilities are approved, a
+    /// script without the header gets none of them.
+    pub fn approve_capabilities<I, S>(mut self, capabilities: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: ToString,
+    {
+        self.capabilities
+            .extend(capabilities.into_iter().map(|cap| cap.to_string()));
+        self
+    }
+
+    /// Format of the bindings and result of
+    /// [`DenoRunner::run_encoded`](crate::DenoRunner::run_encoded), JSON by
+    /// default.
+    pub fn codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// How `NaN`, `Infinity` and `-Infinity` in the result are handled, by
+    /// [`run`](crate::DenoRunner::run) as well as the JSON results. Without
+    /// a policy `run` returns them as JS prints them and JSON has `null`.
+    pub fn non_finite(mut self, policy: NonFinite) -> Self {
+        self.non_finite = Some(policy);
+        self
+    }
+
+    /// How objects of a JSON result that contain themselves are handled,
+    /// see [`Cycles`].
+    pub fn cycles(mut self, policy: Cycles) -> Self {
+        self.cycles = policy;
+        self
+    }
+
+    /// How the variables passed to the run are bound, see [`BindingMode`].
+    pub fn binding_mode(mut self, mode: BindingMode) -> Self {
+        self.binding_mode = mode;
+        self
+    }
+
+    /// What happens when a variable passed to the run has the name of an
+    /// existing global, see [`ConflictPolicy`].
+    pub fn binding_conflicts(mut self, policy: ConflictPolicy) -> Self {
+        self.binding_conflicts = policy;
+        self
+    }
+
+    /// Time limit for this run, instead of the one set with
+    /// [`Builder::timeout`](crate::Builder::timeout).
+    pub fn timeout(mut self, limit: Duration) -> Self {
+        self.timeout = Some(limit);
+        self
+    }
+
+    /// Record the outcome of this run under `key` in the runner's
+    /// [`MemoCache`](crate::MemoCache), so a retry with the same key gets
+    /// it back, with [`cached`](crate::RunReport::cached) set, instead of
+    /// calling the script's ops again. Unlike plain memoization this holds
+    /// for scripts with side effects, e.g. `charge(order)`.
+    ///
+    /// The run fails if the runner has no cache, see
+    /// [`Builder::memoize`](crate::Builder::memoize), or if the key was
+    /// used by a run with another script or bindings. Failed runs aren't
+    /// recorded, their retries run again.
+    pub fn idempotency_key(mut self, key: impl ToString) -> Self {
+        self.idempotency_key = Some(key.to_string());
+        self
+    }
+
+    /// Attach a key/value tag to the run, e.g. `tag("tenant", id)`. Tags are
+    /// copied into the [`RunReport`](crate::RunReport) and into the context
+    /// of any error the run returns.
+    pub fn tag(mut self, key: impl ToString, value: impl ToString) -> Self {
+        self.tags.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Tags formatted as `key=value` pairs, for error messages.
+    pub(crate) fn tags_label(&self) -> String {
+        self.tags
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Whether a result from a [`MemoCache`](crate::MemoCache) may stand in
+    /// for this run; dry runs and fault injection always execute.
+    pub(crate) fn memoizable(&self) -> bool {
+        !self.dry_run && self.faults.is_empty()
+    }
+
+    pub(crate) fn script_name_or_default(&self) -> &str {
+        self.script_name.as_deref().unwrap_or("code.js")
+    }
+
+    /// Result text of a non-finite number under the [`NonFinite`] policy,
+    /// `None` for any other value.
+    pub(crate) fn non_finite_text(
+        &self,
+        scope: &mut v8::HandleScope,
+        value: v8::Local<v8::Value>,
+    ) -> Result<Option<String>> {
+        let policy = match self.non_finite {
+            Some(policy) if value.is_number() => policy,
+            _ => return Ok(None),
+        };
+        if value.number_value(scope).map_or(true, f64::is_finite) {
+            return Ok(None);
+        }
+
+        let text = value.to_rust_string_lossy(scope);
+        match policy {
+            NonFinite::Null => Ok(Some("null".to_string())),
+            NonFinite::String => Ok(Some(text)),
+            NonFinite::Error => Err(anyhow!("Result is {}", text)),
+        }
+    }
+
+    pub(crate) fn pure_call(&self, code: &str) -> Option<HookCall> {
+        let declared = code.lines().any(|line| line.trim() == PURE_MARKER);
+        if !self.pure && !declared {
+            return None;
+        }
+
+        Some(HookCall::new("setPure", ""))
+    }
+
+    pub(crate) fn faults_call(&self) -> Option<HookCall> {
+        if self.faults.is_empty() {
+            return None;
+        }
+
+        Some(HookCall::new(
+            "setFaultPlan",
+            serde_json::to_string(&self.faults).unwrap(),
+        ))
+    }
+
+    pub(crate) fn dry_run_call(&self) -> Option<HookCall> {
+        if !self.dry_run {
+            return None;
+        }
+
+        Some(HookCall::new(
+            "setDryRun",
+            serde_json::to_string(&self.stubs).unwrap(),
+        ))
+    }
+
+    pub(crate) fn features_call(&self) -> Option<HookCall> {
+        if self.feature_flags.is_empty() {
+            return None;
+        }
+
+        Some(HookCall::new(
+            "setFeatures",
+            serde_json::to_string(&self.feature_flags).unwrap(),
+        ))
+    }
+}
+
+/// What the result string of a run holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub(crate) enum ResultFormat {
+    /// The value as text, see [`NumberFormat`] and the builder's codec
+    #[default]
+    Text,
+    /// JSON text, see [`DenoRunner::run_json`](crate::DenoRunner::run_json)
+    Json,
+}
+
+/// How the variables passed to a run are bound, see
+/// [`RunOptions::binding_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BindingMode {
+    /// Global the script can reassign, removed when the next script runs
+    #[default]
+    Mutable,
+    /// Like `const`: assigning to it throws a `TypeError`. Removed when the
+    /// next script runs.
+    Const,
+    /// Plain global property that stays set for later scripts, like one
+    /// set with [`DenoRunner::set_global`](crate::DenoRunner::set_global)
+    Global,
+}
+
+impl BindingMode {
+    /// Name passed to the `bind` hook.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            BindingMode::Mutable => "mutable",
+            BindingMode::Const => "const",
+            BindingMode::Global => "global",
+        }
+    }
+}
+
+/// What happens when a variable passed to a run has the name of a global
+/// that already exists: one from the runtime (`console`, `rustAsync`, a
+/// fast op, ...), one a warmup or earlier script set, or a
+/// [`BindingMode::Global`] binding of an earlier run. See
+/// [`RunOptions::binding_conflicts`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ConflictPolicy {
+    /// Fail the run with [`RunnerError::BindingConflict`](crate::RunnerError::BindingConflict)
+    /// before the script runs
+    #[default]
+    Error,
+    /// Replace the global with the variable
+    Override,
+}
+
+/// Policy for non-finite numbers (`NaN`, `Infinity`, `-Infinity`) in a
+/// result, see [`RunOptions::non_finite`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NonFinite {
+    /// Replace them with `null`, what `JSON.stringify` does
+    Null,
+    /// Fail the run, naming the value and where it is
+    Error,
+    /// Replace them with the strings `"NaN"`, `"Infinity"`, `"-Infinity"`
+    String,
+}
+
+impl NonFinite {
+    /// Name passed to the `resultJson` hook.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            NonFinite::Null => "null",
+            NonFinite::Error => "error",
+            NonFinite::String => "string",
+        }
+    }
+}
+
+/// Policy for a JSON result with an object that contains itself, like
+/// `const node = {}; node.self = node`, see [`RunOptions::cycles`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Cycles {
+    /// Replace the reference closing the cycle with the string
+    /// `"[Circular]"`. Objects referenced twice without a cycle are written
+    /// twice, as `JSON.stringify` does.
+    #[default]
+    Mark,
+    /// Fail the run, with the `TypeError` of `JSON.stringify`
+    Error,
+}
+
+impl Cycles {
+    /// Name passed to the `resultJson` hook.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Cycles::Mark => "mark",
+            Cycles::Error => "error",
+        }
+    }
+}
+
+/// Formatting applied when the script evaluates to a number.
+///
+/// Formatting is done by V8 itself, so the output is exactly what the
+/// matching `Number.prototype` method returns in JS.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum NumberFormat {
+    /// V8 default, e.g. `0.1 + 0.2` gives `"0.30000000000000004"`
+    #[default]
+    Default,
+    /// Digits after the decimal point, like `toFixed(digits)`
+    Fixed(u32),
+    /// Significant digits, like `toPrecision(digits)`
+    Precision(u32),
+    /// Locale aware formatting, like `toLocaleString("de-DE")`
+    Locale(String),
+}
+
+impl NumberFormat {
+    pub(crate) fn to_string(
+        &self,
+        scope: &mut v8::HandleScope,
+        value: v8::Local<v8::Value>,
+    ) -> Result<String> {
+        if !value.is_number() || *self == NumberFormat::Default {
+            return Ok(value.to_rust_string_lossy(scope));
+        }
+
+        let (method, arg): (&str, v8::Local<v8::Value>) = match self {
+            NumberFormat::Fixed(digits) => (
+                "toFixed",
+                v8::Integer::new_from_unsigned(scope, *digits).into(),
+            ),
+            NumberFormat::Precision(digits) => (
+                "toPrecision",
+                v8::Integer::new_from_unsigned(scope, *digits).into(),
+            ),
+            NumberFormat::Locale(locale) => (
+                "toLocaleString",
+                v8::String::new(scope, locale).unwrap().into(),
+            ),
+            NumberFormat::Default => unreachable!(),
+        };
+
+        let scope = &mut v8::TryCatch::new(scope);
+        let number = value.to_object(scope).unwrap();
+        let key = v8::String::new(scope, method).unwrap();
+        let func = number
+            .get(scope, key.into())
+            .and_then(|func| v8::Local::<v8::Function>::try_from(func).ok())
+            .ok_or_else(|| anyhow!("Number.prototype.{} is not a function", method))?;
+
+        match func.call(scope, value, &[arg]) {
+            Some(formatted) => Ok(formatted.to_rust_string_lossy(scope)),
+            None => {
+                let message = scope
+                    .exception()
+                    .map(|e| e.to_rust_string_lossy(scope))
+                    .unwrap_or_default();
+                Err(anyhow!(
+                    "Failed to format number with {:?}: {}",
+                    self,
+                    message
+                ))
+            }
+        }
+    }
+}
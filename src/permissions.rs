@@ -0,0 +1,181 @@
+use anyhow::{bail, Result};
+use deno_core::{
+    futures::future::{self, FutureExt},
+    url::Url,
+    ModuleLoader, ModuleSourceFuture, ModuleSpecifier,
+};
+use serde::Serialize;
+use std::{
+    collections::BTreeSet,
+    path::{Component, Path, PathBuf},
+    pin::Pin,
+    rc::Rc,
+};
+
+/// What scripts may access on the host, see
+/// [`Builder::permissions`](crate::Builder::permissions).
+///
+/// Everything not allowed is denied: hosts `fetch` may reach, files
+/// modules are imported from (or `fetch` reads with `file:` URLs) and host
+/// environment variables [`NodeCompat::allow_env`](crate::NodeCompat::allow_env)
+/// exposes. Ops of the host can consult the same permissions, they are in
+/// the `OpState`:
+///
+/// ```
+/// use deno_runner::{op, anyhow::Result, OpState, Permissions};
+///
+/// #[op]
+/// fn read_config(state: &mut OpState, path: String) -> Result<String> {
+///     state.borrow::<Permissions>().check_read(path.as_ref())?;
+///     Ok(std::fs::read_to_string(path)?)
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Permissions {
+    net: BTreeSet<String>,
+    read: Vec<PathBuf>,
+    env: BTreeSet<String>,
+}
+
+impl Permissions {
+    /// Permissions denying everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow network access to `hosts`, either a host name (any port) or
+    /// `host:port`.
+    pub fn allow_net<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: ToString,
+    {
+        self.net
+            .extend(hosts.into_iter().map(|host| host.to_string()));
+        self
+    }
+
+    /// Allow reading the files in and below `paths`, which should be
+    /// absolute.
+    pub fn allow_read<I, P>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        self.read
+            .extend(paths.into_iter().map(|path| path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Allow reading the host environment variables `keys`.
+    pub fn allow_env<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: ToString,
+    {
+        self.env.extend(keys.into_iter().map(|key| key.to_string()));
+        self
+    }
+
+    pub fn check_net(&self, url: &Url) -> Result<()> {
+        let host = url.host_str().unwrap_or_default();
+        let allowed = self.net.contains(host)
+            || url.port_or_known_default().map_or(false, |port| {
+                self.net.contains(&format!("{}:{}", host, port))
+            });
+        if !allowed {
+            bail!("Requires net access to \"{}\"", host);
+        }
+        Ok(())
+    }
+
+    /// Paths with `..` are always denied, they could leave the allowed
+    /// directories.
+    pub fn check_read(&self, path: &Path) -> Result<()> {
+        let escapes = path
+            .components()
+            .any(|component| component == Component::ParentDir);
+        if escapes || !self.read.iter().any(|allowed| path.starts_with(allowed)) {
+            bail!("Requires read access to \"{}\"", path.display());
+        }
+        Ok(())
+    }
+
+    pub fn check_env(&self, key: &str) -> Result<()> {
+        if !self.env.contains(key) {
+            bail!("Requires env access to \"{}\"", key);
+        }
+        Ok(())
+    }
+}
+
+/// Module loader checking files against [`Permissions::check_read`] and
+/// remote modules against [`Permissions::check_net`] before the runner's
+/// own loader loads them. Other schemes are up to that loader.
+pub(crate) struct CheckedModuleLoader(pub(crate) Rc<dyn ModuleLoader>, pub(crate) Permissions);
+
+impl ModuleLoader for CheckedModuleLoader {
+    fn resolve(&self, specifier: &str, referrer: &str, is_main: bool) -> Result<ModuleSpecifier> {
+        self.0.resolve(specifier, referrer, is_main)
+    }
+
+    fn load(
+        &self,
+        module_specifier: &ModuleSpecifier,
+        maybe_referrer: Option<ModuleSpecifier>,
+        is_dyn_import: bool,
+    ) -> Pin<Box<ModuleSourceFuture>> {
+        let checked = match module_specifier.scheme() {
+            "file" => match module_specifier.to_file_path() {
+                Ok(path) => self.1.check_read(&path),
+                Err(_) => Err(anyhow::anyhow!("Invalid file module: {}", module_specifier)),
+            },
+            "http" | "https" => self.1.check_net(module_specifier),
+            _ => Ok(()),
+        };
+        if let Err(err) = checked {
+            return future::ready(Err(err)).boxed_local();
+        }
+        self.0.load(module_specifier, maybe_referrer, is_dyn_import)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_net() {
+        let permissions = Permissions::new().allow_net(["api.example.com", "localhost:8080"]);
+        let check = |url: &str| permissions.check_net(&Url::parse(url).unwrap());
+
+        assert!(check("https://api.example.com/v1").is_ok());
+        assert!(check("http://localhost:8080/").is_ok());
+        assert!(check("http://localhost:9000/").is_err());
+        assert!(check("https://example.com/").is_err());
+    }
+
+    #[test]
+    fn test_check_read() {
+        let permissions = Permissions::new().allow_read(["/srv/scripts"]);
+
+        assert!(permissions
+            .check_read(Path::new("/srv/scripts/lib/a.js"))
+            .is_ok());
+        assert!(permissions.check_read(Path::new("/srv/other.js")).is_err());
+        assert!(permissions
+            .check_read(Path::new("/srv/scripts/../secrets.txt"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_deny_by_default() {
+        let permissions = Permissions::new();
+
+        assert!(permissions.check_env("HOME").is_err());
+        assert_eq!(
+            permissions.check_env("HOME").unwrap_err().to_string(),
+            "Requires env access to \"HOME\""
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! Ops loaded from dynamic libraries at runtime, see
+//! [`Builder::load_plugin`](crate::Builder::load_plugin).
+//!
+//! A plugin is a `cdylib` crate depending on `deno_runner` that exports its
+//! ops with [`export_plugin!`](crate::export_plugin):
+//!
+//! ```ignore
+//! use deno_runner::{op, plugin::PluginRegistrar};
+//!
+//! #[op]
+//! fn slugify(text: String) -> String {
+//!     text.to_lowercase().replace(' ', "-")
+//! }
+//!
+//! deno_runner::export_plugin!(|registrar: &mut PluginRegistrar| {
+//!     registrar.add_op(slugify::decl());
+//! });
+//! ```
+//!
+//! Ops cross the library boundary as Rust values, so a plugin must be built
+//! with the same compiler and the same `deno_runner` version as the host.
+//! The ABI and `deno_runner` versions are recorded in the plugin and checked
+//! when it is loaded; the compiler version is up to the host to match.
+
+use anyhow::{bail, Result};
+use deno_core::OpDecl;
+use libloading::Library;
+use std::{ffi::OsStr, sync::Arc};
+
+/// Version of the registration ABI, bumped whenever [`PluginDeclaration`]
+/// or [`PluginRegistrar`] change.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Version of this crate a plugin was built against.
+pub const DENO_RUNNER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Symbol [`export_plugin!`](crate::export_plugin) defines in a plugin.
+const DECLARATION_SYMBOL: &[u8] = b"deno_runner_plugin_declaration\0";
+
+/// What a plugin exports, written by [`export_plugin!`](crate::export_plugin).
+#[repr(C)]
+pub struct PluginDeclaration {
+    pub abi_version: u32,
+    pub deno_runner_version: &'static str,
+    pub register: fn(&mut PluginRegistrar),
+}
+
+/// Collects the ops of a plugin while it registers.
+#[derive(Default)]
+pub struct PluginRegistrar {
+    pub(crate) ops: Vec<OpDecl>,
+}
+
+impl PluginRegistrar {
+    pub fn add_op(&mut self, op: OpDecl) {
+        self.ops.push(op);
+    }
+}
+
+/// Export the ops a plugin registers so
+/// [`Builder::load_plugin`](crate::Builder::load_plugin) can load them.
+#[macro_export]
+macro_rules! export_plugin {
+    ($register:expr) => {
+        #[no_mangle]
+        pub static deno_runner_plugin_declaration: $crate::plugin::PluginDeclaration =
+            $crate::plugin::PluginDeclaration {
+                abi_version: $crate::plugin::PLUGIN_ABI_VERSION,
+                deno_runner_version: $crate::plugin::DENO_RUNNER_VERSION,
+                register: $register,
+            };
+    };
+}
+
+/// A loaded plugin library, kept alive for as long as runners use its ops.
+#[derive(Clone)]
+pub(crate) struct Plugin {
+    _library: Arc<Library>,
+}
+
+/// Load the library at `path` and collect the ops it registers.
+///
+/// # Safety
+///
+/// Loading a library runs its initialization code, and its ops run
+/// unchecked in the host process.
+pub(crate) unsafe fn load(path: &OsStr) -> Result<(Plugin, Vec<OpDecl>)> {
+    let library = Library::new(path)?;
+
+    let declaration = match library.get::<*const PluginDeclaration>(DECLARATION_SYMBOL) {
+        Ok(symbol) => &**symbol,
+        Err(_) => bail!(
+            "{} is not a deno_runner plugin (no export_plugin! declaration)",
+            path.to_string_lossy()
+        ),
+    };
+
+    if declaration.abi_version != PLUGIN_ABI_VERSION {
+        bail!(
+            "Plugin {} uses ABI version {}, expected {}",
+            path.to_string_lossy(),
+            declaration.abi_version,
+            PLUGIN_ABI_VERSION
+        );
+    }
+
+    if declaration.deno_runner_version != DENO_RUNNER_VERSION {
+        bail!(
+            "Plugin {} was built with deno_runner {}, expected {}",
+            path.to_string_lossy(),
+            declaration.deno_runner_version,
+            DENO_RUNNER_VERSION
+        );
+    }
+
+    let mut registrar = PluginRegistrar::default();
+    (declaration.register)(&mut registrar);
+
+    let plugin = Plugin {
+        _library: Arc::new(library),
+    };
+    Ok((plugin, registrar.ops))
+}
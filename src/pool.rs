@@ -0,0 +1,588 @@
+use crate::{
+    eval::JsonLiteral,
+    executor,
+    heap::{HeapLeak, Retained},
+    DenoRunner, RunOptions, RunReport,
+};
+use anyhow::{anyhow, Result};
+use deno_core::{
+    futures::{
+        channel::oneshot,
+        future::{AbortHandle, AbortRegistration, Abortable},
+    },
+    serde_json, v8,
+};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// How often [`RunnerPool::shutdown`] checks on the runner threads
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+type Factory = Box<dyn Fn() -> DenoRunner + Send + Sync>;
+type LeakHook = Arc<dyn Fn(&HeapLeak) + Send + Sync>;
+
+/// Set with [`RunnerPool::on_heap_leak`]
+#[derive(Clone)]
+struct LeakCheck {
+    min_growth: usize,
+    hook: LeakHook,
+}
+
+/// A run sent to a runner on another thread.
+pub(crate) struct Job {
+    code: String,
+    vars: Option<HashMap<String, JsonLiteral>>,
+    options: RunOptions,
+    reply: oneshot::Sender<Result<RunReport>>,
+}
+
+impl Job {
+    /// The job, and where its report arrives. Variables are bound as their
+    /// JSON value.
+    pub(crate) fn new<K, V>(
+        custom_code: impl ToString,
+        vars: Option<HashMap<K, V>>,
+        options: RunOptions,
+    ) -> Result<(Self, oneshot::Receiver<Result<RunReport>>)>
+    where
+        K: Display,
+        V: Serialize,
+    {
+        let vars = match vars {
+            Some(vars) => Some(
+                vars.into_iter()
+                    .map(|(key, value)| {
+                        Ok((key.to_string(), JsonLiteral(serde_json::to_value(value)?)))
+                    })
+                    .collect::<Result<HashMap<_, _>>>()?,
+            ),
+            None => None,
+        };
+
+        let (reply, report) = oneshot::channel();
+        let job = Job {
+            code: custom_code.to_string(),
+            vars,
+            options,
+            reply,
+        };
+        Ok((job, report))
+    }
+
+    /// Run on `runner` and send the report back, unless nobody is waiting
+    /// for it anymore. A run aborted with `abort` stops at its next await.
+    pub(crate) fn run(self, runner: &mut DenoRunner, abort: Option<AbortRegistration>) {
+        if self.reply.is_canceled() {
+            return;
+        }
+
+        let run = runner.run_with_options(self.code, self.vars, self.options);
+        let report = match abort {
+            Some(abort) => executor::block_on(Abortable::new(run, abort))
+                .unwrap_or_else(|_| Err(anyhow!("Runner pool shut down before the run finished"))),
+            None => executor::block_on(run),
+        };
+        let _ = self.reply.send(report);
+    }
+}
+
+/// Wait for the report of a job sent to a runner thread.
+pub(crate) async fn report(report: oneshot::Receiver<Result<RunReport>>) -> Result<RunReport> {
+    report
+        .await
+        .map_err(|_| anyhow!("Runner thread stopped before finishing the run"))?
+}
+
+/// Runners kept warm on dedicated threads, for running scripts from async
+/// code that can't hold a [`DenoRunner`] itself, like a web server.
+///
+/// Each of the `size` threads builds its runner with `factory` and takes
+/// runs from a shared queue, so at most `size` scripts run at once and the
+/// rest wait their turn. With [`max_size`](Self::max_size) the pool instead
+/// grows while runs are waiting and, with
+/// [`idle_timeout`](Self::idle_timeout), shrinks back to `size` runners when
+/// they are no longer needed.
+///
+/// ```
+/// use deno_runner::{Builder, RunnerPool};
+/// use std::collections::HashMap;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let pool = RunnerPool::new(4, || Builder::new().build());
+///
+/// let vars = HashMap::from([("name", "duyet")]);
+/// let result = pool.run("`Hello ${name}`", Some(vars)).await.unwrap();
+/// assert_eq!(result, "Hello duyet");
+/// # }
+/// ```
+pub struct RunnerPool {
+    jobs: Mutex<Option<mpsc::Sender<Job>>>,
+    workers: Mutex<Vec<Worker>>,
+    shared: Arc<Shared>,
+}
+
+/// State of the pool its runner threads read.
+struct Shared {
+    factory: Factory,
+    queue: Mutex<mpsc::Receiver<Job>>,
+    recycle_after: AtomicUsize,
+    leak_check: Mutex<Option<LeakCheck>>,
+    min_size: usize,
+    max_size: AtomicUsize,
+    /// `None` keeps the runners above `min_size` for the life of the pool
+    idle_timeout: Mutex<Option<Duration>>,
+    /// Runner threads started and not evicted
+    live: AtomicUsize,
+    /// Runners not on a run, including the ones still being built
+    idle: AtomicUsize,
+    /// Runs sent and not yet taken by a runner
+    queued: AtomicUsize,
+    /// Runner threads started so far, to name the next one
+    spawned: AtomicUsize,
+}
+
+impl Shared {
+    /// Evict the runner calling this if the pool has more than its minimum.
+    fn try_evict(&self) -> bool {
+        self.live
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |live| {
+                (live > self.min_size).then(|| live - 1)
+            })
+            .is_ok()
+    }
+}
+
+/// How a runner of the pool stopped, see [`RunnerPool::shutdown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShutdownOutcome {
+    /// Finished its runs and stopped within the graceful timeout
+    Drained,
+    /// Was still busy at the timeout, its run was terminated and the runs
+    /// it had yet to take failed
+    Terminated,
+    /// Its thread panicked
+    Panicked,
+}
+
+/// Thread of a runner, with what is needed to stop it.
+struct Worker {
+    thread: JoinHandle<()>,
+    stop: Arc<Stop>,
+}
+
+/// Set by [`RunnerPool::shutdown`] to stop a runner thread mid-run.
+#[derive(Default)]
+struct Stop {
+    stopping: AtomicBool,
+    /// Set when the runner stopped after [`RunnerPool::idle_timeout`]
+    evicted: AtomicBool,
+    /// The runner's isolate and the run it is on, if any
+    current: Mutex<Option<(v8::IsolateHandle, AbortHandle)>>,
+}
+
+impl Stop {
+    fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::SeqCst)
+    }
+
+    /// Stop taking runs, and end the current one: terminate busy JS and
+    /// drop it at its next await.
+    fn force(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        if let Some((isolate, abort)) = self.current.lock().unwrap().take() {
+            abort.abort();
+            isolate.terminate_execution();
+        }
+    }
+}
+
+impl RunnerPool {
+    pub fn new<F>(size: usize, factory: F) -> Self
+    where
+        F: Fn() -> DenoRunner + Send + Sync + 'static,
+    {
+        assert!(size > 0, "a runner pool needs at least one runner");
+
+        let (jobs, queue) = mpsc::channel();
+        let shared = Arc::new(Shared {
+            factory: Box::new(factory),
+            queue: Mutex::new(queue),
+            recycle_after: AtomicUsize::new(0),
+            leak_check: Mutex::new(None),
+            min_size: size,
+            max_size: AtomicUsize::new(size),
+            idle_timeout: Mutex::new(None),
+            live: AtomicUsize::new(size),
+            idle: AtomicUsize::new(0),
+            queued: AtomicUsize::new(0),
+            spawned: AtomicUsize::new(0),
+        });
+
+        let workers = (0..size).map(|_| spawn(&shared)).collect();
+
+        Self {
+            jobs: Mutex::new(Some(jobs)),
+            workers: Mutex::new(workers),
+            shared,
+        }
+    }
+
+    /// Start more runners while runs are waiting for one, up to `max`
+    /// runners in total. By default the pool keeps the `size` it was
+    /// created with.
+    ///
+    /// ```
+    /// use deno_runner::{Builder, RunnerPool};
+    /// use std::time::Duration;
+    ///
+    /// // One warm runner, up to 8 under load, back to one after a minute idle
+    /// let pool = RunnerPool::new(1, || Builder::new().build())
+    ///     .max_size(8)
+    ///     .idle_timeout(Duration::from_secs(60));
+    /// assert_eq!(pool.size(), 1);
+    /// ```
+    pub fn max_size(self, max: usize) -> Self {
+        assert!(
+            max >= self.shared.min_size,
+            "the maximum size of a runner pool can't be below its size"
+        );
+        self.shared.max_size.store(max, Ordering::SeqCst);
+        self
+    }
+
+    /// Stop a runner that had no run for `timeout`, while the pool has more
+    /// than the `size` it was created with, so the runners started under
+    /// load don't hold on to their memory. By default they are kept for the
+    /// life of the pool.
+    pub fn idle_timeout(self, timeout: Duration) -> Self {
+        *self.shared.idle_timeout.lock().unwrap() = Some(timeout);
+        self
+    }
+
+    /// Replace a runner with a freshly built one after it ran `runs`
+    /// scripts, so state scripts leave on `globalThis` doesn't build up.
+    /// `0`, the default, keeps runners for the life of the pool.
+    pub fn recycle_after(self, runs: usize) -> Self {
+        self.shared.recycle_after.store(runs, Ordering::SeqCst);
+        self
+    }
+
+    /// Check what each run leaves behind on its runner, and call `hook`
+    /// when the heap still in use after a full collection grew by at least
+    /// `min_growth` bytes, to find scripts filling up the pool's memory.
+    ///
+    /// Every run is followed by a full collection, which takes some
+    /// milliseconds, so this is meant for investigating rather than always
+    /// on.
+    ///
+    /// ```
+    /// use deno_runner::{Builder, RunnerPool};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let pool = RunnerPool::new(1, || Builder::new().build()).on_heap_leak(1 << 20, |leak| {
+    ///     eprintln!("{} keeps {} more bytes in {:?}", leak.script_name, leak.growth(), leak.new_globals);
+    /// });
+    /// pool.run::<String, String>("globalThis.cache = new Array(1 << 20).fill(0)", None)
+    ///     .await
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub fn on_heap_leak<F>(self, min_growth: usize, hook: F) -> Self
+    where
+        F: Fn(&HeapLeak) + Send + Sync + 'static,
+    {
+        *self.shared.leak_check.lock().unwrap() = Some(LeakCheck {
+            min_growth,
+            hook: Arc::new(hook),
+        });
+        self
+    }
+
+    /// Number of runners in the pool, which changes under load when the
+    /// pool has a [`max_size`](Self::max_size).
+    pub fn size(&self) -> usize {
+        self.shared.live.load(Ordering::SeqCst)
+    }
+
+    /// Stop the pool for a redeploy: refuse new runs, give the queued and
+    /// running ones up to `graceful_timeout` to finish, then terminate the
+    /// runners still busy. Runs that didn't finish fail. Returns how each
+    /// runner stopped, in order, and nothing when the pool was already shut
+    /// down.
+    ///
+    /// ```
+    /// use deno_runner::{Builder, RunnerPool, ShutdownOutcome};
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let pool = RunnerPool::new(2, || Builder::new().build());
+    /// pool.run::<String, String>("1 + 1", None).await.unwrap();
+    ///
+    /// let outcomes = pool.shutdown(Duration::from_secs(5)).await;
+    /// assert_eq!(outcomes, [ShutdownOutcome::Drained, ShutdownOutcome::Drained]);
+    /// assert!(pool.run::<String, String>("1 + 1", None).await.is_err());
+    /// # }
+    /// ```
+    pub async fn shutdown(&self, graceful_timeout: Duration) -> Vec<ShutdownOutcome> {
+        self.jobs.lock().unwrap().take();
+        let workers: Vec<_> = self
+            .workers
+            .lock()
+            .unwrap()
+            .drain(..)
+            .filter(|worker| !worker.is_evicted())
+            .collect();
+
+        let deadline = Instant::now().checked_add(graceful_timeout);
+        while deadline.map_or(true, |deadline| Instant::now() < deadline)
+            && workers.iter().any(|worker| !worker.thread.is_finished())
+        {
+            executor::sleep(SHUTDOWN_POLL_INTERVAL).await;
+        }
+
+        let forced: Vec<_> = workers
+            .iter()
+            .map(|worker| !worker.thread.is_finished())
+            .collect();
+        for (worker, forced) in workers.iter().zip(&forced) {
+            if *forced {
+                worker.stop.force();
+            }
+        }
+        // Terminated runners stop once their run unwinds
+        while workers.iter().any(|worker| !worker.thread.is_finished()) {
+            executor::sleep(SHUTDOWN_POLL_INTERVAL).await;
+        }
+        // Runs no runner took fail, instead of waiting forever for a report
+        let queue = self.shared.queue.lock().unwrap();
+        while let Ok(job) = queue.try_recv() {
+            drop(job);
+        }
+        drop(queue);
+
+        workers
+            .into_iter()
+            .zip(forced)
+            .map(|(worker, forced)| match worker.thread.join() {
+                Err(_) => ShutdownOutcome::Panicked,
+                Ok(()) if forced => ShutdownOutcome::Terminated,
+                Ok(()) => ShutdownOutcome::Drained,
+            })
+            .collect()
+    }
+
+    /// Same as [`DenoRunner::run`], on the next free runner. Variables are
+    /// bound as their JSON value.
+    pub async fn run<K, V>(
+        &self,
+        custom_code: impl ToString,
+        vars: Option<HashMap<K, V>>,
+    ) -> Result<String>
+    where
+        K: Display,
+        V: Serialize,
+    {
+        let report = self
+            .run_with_options(custom_code, vars, RunOptions::default())
+            .await?;
+
+        Ok(report.result)
+    }
+
+    /// Same as [`DenoRunner::run_with_options`], on the next free runner.
+    pub async fn run_with_options<K, V>(
+        &self,
+        custom_code: impl ToString,
+        vars: Option<HashMap<K, V>>,
+        options: RunOptions,
+    ) -> Result<RunReport>
+    where
+        K: Display,
+        V: Serialize,
+    {
+        let (job, report) = Job::new(custom_code, vars, options)?;
+        // Counted before sending, so the runner taking it can't count it first
+        self.shared.queued.fetch_add(1, Ordering::SeqCst);
+        let sent = self
+            .jobs
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|jobs| jobs.send(job).ok());
+        if sent.is_none() {
+            self.shared.queued.fetch_sub(1, Ordering::SeqCst);
+            return Err(anyhow!("Runner pool is shut down"));
+        }
+        self.grow();
+
+        self::report(report).await
+    }
+
+    /// Start a runner if more runs are waiting than there are idle runners
+    /// to take them, and the pool is below its maximum size.
+    fn grow(&self) {
+        let shared = &self.shared;
+        if shared.queued.load(Ordering::SeqCst) <= shared.idle.load(Ordering::SeqCst) {
+            return;
+        }
+        let max = shared.max_size.load(Ordering::SeqCst);
+        let started = shared
+            .live
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |live| {
+                (live < max).then(|| live + 1)
+            })
+            .is_ok();
+        if started {
+            let mut workers = self.workers.lock().unwrap();
+            workers.retain(|worker| !worker.is_evicted());
+            workers.push(spawn(shared));
+        }
+    }
+}
+
+impl Worker {
+    /// Whether the runner stopped after the idle timeout, so it no longer
+    /// counts as one of the pool's runners.
+    fn is_evicted(&self) -> bool {
+        self.stop.evicted.load(Ordering::SeqCst) && self.thread.is_finished()
+    }
+}
+
+impl Drop for RunnerPool {
+    /// Let queued runs finish, then stop the threads.
+    fn drop(&mut self) {
+        self.jobs.lock().unwrap().take();
+        for worker in self.workers.get_mut().unwrap().drain(..) {
+            let _ = worker.thread.join();
+        }
+    }
+}
+
+/// Start a runner thread, counted in `live` by the caller.
+fn spawn(shared: &Arc<Shared>) -> Worker {
+    let i = shared.spawned.fetch_add(1, Ordering::SeqCst);
+    shared.idle.fetch_add(1, Ordering::SeqCst);
+    let stop = Arc::new(Stop::default());
+    let thread = thread::Builder::new()
+        .name(format!("deno-runner-{}", i))
+        .spawn({
+            let shared = shared.clone();
+            let stop = stop.clone();
+            move || work(shared, stop)
+        })
+        .expect("failed to spawn runner pool thread");
+    Worker { thread, stop }
+}
+
+/// Next run for the runner, or `None` once the pool is shut down or the
+/// runner was evicted.
+fn next_job(shared: &Shared, stop: &Stop) -> Option<Job> {
+    let mut idle_since = Instant::now();
+    loop {
+        // The lock is only held while waiting, not while running
+        let queue = shared.queue.lock().unwrap();
+        let timeout = match *shared.idle_timeout.lock().unwrap() {
+            Some(timeout) => timeout,
+            None => return queue.recv().ok(),
+        };
+
+        // Runners waiting for the lock time out as soon as they get it
+        let left = timeout.saturating_sub(idle_since.elapsed());
+        let job = if left.is_zero() {
+            queue.try_recv().map_err(|err| match err {
+                mpsc::TryRecvError::Empty => mpsc::RecvTimeoutError::Timeout,
+                mpsc::TryRecvError::Disconnected => mpsc::RecvTimeoutError::Disconnected,
+            })
+        } else {
+            queue.recv_timeout(left)
+        };
+        match job {
+            Ok(job) => return Some(job),
+            Err(mpsc::RecvTimeoutError::Disconnected) => return None,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                drop(queue);
+                if shared.try_evict() {
+                    stop.evicted.store(true, Ordering::SeqCst);
+                    shared.idle.fetch_sub(1, Ordering::SeqCst);
+                    return None;
+                }
+                // One of the pool's `size` runners, which are kept
+                idle_since = Instant::now();
+            }
+        }
+    }
+}
+
+fn work(shared: Arc<Shared>, stop: Arc<Stop>) {
+    let mut runner = (shared.factory)();
+    let mut runs = 0;
+    // Measured after the last run, to compare the next one with
+    let mut retained = None;
+
+    loop {
+        let job = match next_job(&shared, &stop) {
+            Some(job) => job,
+            None => return,
+        };
+        shared.queued.fetch_sub(1, Ordering::SeqCst);
+
+        if job.reply.is_canceled() {
+            continue;
+        }
+        shared.idle.fetch_sub(1, Ordering::SeqCst);
+
+        let (abort, registration) = AbortHandle::new_pair();
+        let isolate = runner.runtime.v8_isolate().thread_safe_handle();
+        *stop.current.lock().unwrap() = Some((isolate, abort));
+        // Checked after publishing the run, so `Stop::force` can't miss it
+        if stop.is_stopping() {
+            return;
+        }
+
+        let check = shared.leak_check.lock().unwrap().clone();
+        let before = match (&check, retained.take()) {
+            (Some(_), Some(before)) => Some(before),
+            (Some(_), None) => Retained::measure(&mut runner.runtime).ok(),
+            (None, _) => None,
+        };
+        let script_name = job.options.script_name_or_default().to_string();
+        let tags = job.options.tags.clone();
+
+        job.run(&mut runner, Some(registration));
+        stop.current.lock().unwrap().take();
+        if stop.is_stopping() {
+            return;
+        }
+
+        if let (Some(check), Some(before)) = (check, before) {
+            if let Ok(after) = Retained::measure(&mut runner.runtime) {
+                let leak = after.leak(&before, script_name, tags);
+                if leak.growth() >= check.min_growth {
+                    (check.hook)(&leak);
+                }
+                retained = Some(after);
+            }
+        }
+
+        runs += 1;
+        let limit = shared.recycle_after.load(Ordering::SeqCst);
+        if limit > 0 && runs >= limit {
+            runner = (shared.factory)();
+            runs = 0;
+            retained = None;
+        }
+        shared.idle.fetch_add(1, Ordering::SeqCst);
+    }
+}
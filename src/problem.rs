@@ -0,0 +1,163 @@
+use crate::RunnerError;
+use serde::Serialize;
+
+/// RFC 7807 problem details for a failed run, for embedders returning
+/// script errors from HTTP APIs.
+///
+/// The `detail` only says what the script author needs to know: the
+/// exception class and message of a thrown error, never stack traces, host
+/// paths or the values a result failed to deserialize from. Errors that
+/// aren't a [`RunnerError`] get a generic `500`.
+///
+/// | Error | `code` | `status` |
+/// |---|---|---|
+/// | [`RunnerError::Execution`] | `execution` | 422 |
+/// | [`RunnerError::Timeout`] | `timeout` | 408 |
+/// | [`RunnerError::HeapLimitExceeded`] | `heap_limit_exceeded` | 422 |
+/// | [`RunnerError::ResultDeserialization`] | `invalid_result` | 422 |
+/// | [`RunnerError::BindingConflict`] | `binding_conflict` | 400 |
+/// | [`RunnerError::ResultRejected`] | `result_rejected` | 422 |
+/// | anything else | `internal` | 500 |
+///
+/// ```
+/// use deno_runner::{Builder, ProblemDetails};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut runner = Builder::new().build();
+/// let err = runner
+///     .run::<_, String, String>("throw new RangeError('qty must be positive')", None)
+///     .await
+///     .unwrap_err();
+///
+/// let problem = ProblemDetails::from_error(&err);
+/// assert_eq!(problem.status, 422);
+/// assert_eq!(problem.detail, "RangeError: qty must be positive");
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProblemDetails {
+    /// `urn:deno-runner:problem:<code>`, replace it to point at your docs
+    #[serde(rename = "type")]
+    pub type_uri: String,
+    pub title: &'static str,
+    /// Suggested HTTP status code
+    pub status: u16,
+    pub detail: String,
+    /// Stable name of the error kind
+    pub code: &'static str,
+    /// Line of the throw in the script, for execution errors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<i64>,
+    /// Column of the throw in the script, for execution errors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<i64>,
+}
+
+impl ProblemDetails {
+    /// Media type to send problem details with.
+    pub const CONTENT_TYPE: &'static str = "application/problem+json";
+
+    pub fn from_error(err: &anyhow::Error) -> Self {
+        let runner_error = err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<RunnerError>());
+
+        match runner_error {
+            Some(RunnerError::Execution(error)) => {
+                let detail = match &error.class {
+                    Some(class) => format!("{}: {}", class, error.message),
+                    None => error.message.clone(),
+                };
+                Self {
+                    line: error.line,
+                    column: error.column,
+                    ..Self::new("execution", "Script threw an error", 422, detail)
+                }
+            }
+            Some(RunnerError::Timeout(limit)) => Self::new(
+                "timeout",
+                "Script timed out",
+                408,
+                format!("The script did not finish within {:?}", limit),
+            ),
+            Some(RunnerError::HeapLimitExceeded(bytes)) => Self::new(
+                "heap_limit_exceeded",
+                "Script used too much memory",
+                422,
+                format!("The script exceeded its memory limit of {} bytes", bytes),
+            ),
+            Some(RunnerError::ResultDeserialization { .. }) => Self::new(
+                "invalid_result",
+                "Script returned an invalid result",
+                422,
+                "The script's result does not have the expected shape".to_string(),
+            ),
+            Some(RunnerError::BindingConflict { name, .. }) => Self::new(
+                "binding_conflict",
+                "Script variable conflicts with a global",
+                400,
+                format!("The variable `{}` has the name of a built-in global", name),
+            ),
+            Some(RunnerError::ResultRejected(reason)) => Self::new(
+                "result_rejected",
+                "Script result was rejected",
+                422,
+                reason.to_string(),
+            ),
+            None => Self::new(
+                "internal",
+                "Script could not be run",
+                500,
+                "The script could not be run because of an internal error".to_string(),
+            ),
+        }
+    }
+
+    fn new(code: &'static str, title: &'static str, status: u16, detail: String) -> Self {
+        Self {
+            type_uri: format!("urn:deno-runner:problem:{}", code),
+            title,
+            status,
+            detail,
+            code,
+            line: None,
+            column: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deno_core::serde_json::{self, json};
+    use std::time::Duration;
+
+    #[test]
+    fn test_timeout() {
+        let err = anyhow::Error::from(RunnerError::Timeout(Duration::from_secs(2)))
+            .context("run 1 failed");
+        let problem = ProblemDetails::from_error(&err);
+
+        assert_eq!(
+            serde_json::to_value(&problem).unwrap(),
+            json!({
+                "type": "urn:deno-runner:problem:timeout",
+                "title": "Script timed out",
+                "status": 408,
+                "detail": "The script did not finish within 2s",
+                "code": "timeout",
+            })
+        );
+    }
+
+    #[test]
+    fn test_internal_errors_are_not_exposed() {
+        let err = anyhow::anyhow!("failed to open /etc/deno_runner/secret.key");
+        let problem = ProblemDetails::from_error(&err);
+
+        assert_eq!(problem.status, 500);
+        assert_eq!(problem.code, "internal");
+        assert!(!problem.detail.contains("secret.key"));
+    }
+}
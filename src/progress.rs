@@ -0,0 +1,113 @@
+use deno_core::{op, OpDecl, OpState};
+use serde::Serialize;
+use std::{
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// Minimum time between two updates of the same stage by default
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A `progress.report(stage, pct)` call of a script, see
+/// [`Builder::on_progress`](crate::Builder::on_progress).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Progress {
+    pub stage: String,
+    /// From 0 to 100
+    pub percent: f64,
+}
+
+/// Hooks receiving a runner's progress updates, and the last update
+/// delivered to throttle the next ones.
+#[derive(Clone)]
+pub(crate) struct ProgressReporter {
+    pub(crate) hooks: Vec<Rc<dyn Fn(&Progress)>>,
+    pub(crate) interval: Duration,
+    last: Option<(String, Instant)>,
+}
+
+impl Default for ProgressReporter {
+    fn default() -> Self {
+        Self {
+            hooks: vec![],
+            interval: DEFAULT_INTERVAL,
+            last: None,
+        }
+    }
+}
+
+impl ProgressReporter {
+    /// Forget the last update, so the first one of a run is never dropped.
+    pub(crate) fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// Deliver `progress` unless an update of the same stage was delivered
+    /// less than `interval` ago. Stage changes and completion always go
+    /// through.
+    fn report(&mut self, progress: Progress) {
+        let now = Instant::now();
+        let throttled = match &self.last {
+            Some((stage, at)) => {
+                *stage == progress.stage
+                    && progress.percent < 100.0
+                    && now.duration_since(*at) < self.interval
+            }
+            None => false,
+        };
+        if throttled || self.hooks.is_empty() {
+            return;
+        }
+
+        for hook in &self.hooks {
+            hook(&progress);
+        }
+        self.last = Some((progress.stage, now));
+    }
+}
+
+pub(crate) fn decls() -> Vec<OpDecl> {
+    vec![op_progress_report::decl()]
+}
+
+#[op]
+fn op_progress_report(state: &mut OpState, stage: String, percent: f64) {
+    if let Some(reporter) = state.try_borrow_mut::<ProgressReporter>() {
+        reporter.report(Progress { stage, percent });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_throttle() {
+        let delivered = Rc::new(RefCell::new(vec![]));
+        let sink = delivered.clone();
+        let mut reporter = ProgressReporter {
+            hooks: vec![Rc::new(move |progress: &Progress| {
+                sink.borrow_mut().push(progress.percent)
+            })],
+            interval: Duration::from_secs(60),
+            last: None,
+        };
+        let report = |reporter: &mut ProgressReporter, stage: &str, percent| {
+            reporter.report(Progress {
+                stage: stage.to_string(),
+                percent,
+            })
+        };
+
+        report(&mut reporter, "load", 0.0);
+        report(&mut reporter, "load", 50.0);
+        report(&mut reporter, "load", 100.0);
+        report(&mut reporter, "transform", 10.0);
+        report(&mut reporter, "transform", 20.0);
+        reporter.reset();
+        report(&mut reporter, "transform", 30.0);
+
+        assert_eq!(*delivered.borrow(), [0.0, 100.0, 10.0, 30.0]);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::{
+    pool::{self, Job},
+    DenoRunner, RunOptions, RunReport,
+};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+type Factory = Arc<dyn Fn(&str) -> DenoRunner + Send + Sync>;
+
+/// Long-lived runners looked up by name, e.g. one per tenant of a web
+/// server, so each keeps the globals its scripts leave behind between
+/// requests.
+///
+/// A runner is built with `factory` the first time its name is asked for,
+/// on a dedicated thread like the runners of a
+/// [`RunnerPool`](crate::RunnerPool). The registry is `Sync`: keep one for
+/// the whole process in a `static` or an `Arc`.
+///
+/// ```
+/// use deno_runner::{Builder, RunnerRegistry};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let registry = RunnerRegistry::new(|_tenant| Builder::new().build())
+///     .ttl(Duration::from_secs(15 * 60));
+///
+/// let runner = registry.get_or_create("tenant-42");
+/// runner
+///     .run::<String, String>("globalThis.visits = (globalThis.visits ?? 0) + 1", None)
+///     .await
+///     .unwrap();
+///
+/// let runner = registry.get_or_create("tenant-42");
+/// let visits = runner.run::<String, String>("visits", None).await.unwrap();
+/// assert_eq!(visits, "1");
+/// # }
+/// ```
+pub struct RunnerRegistry {
+    factory: Factory,
+    ttl: Option<Duration>,
+    runners: Mutex<HashMap<String, Arc<NamedRunner>>>,
+}
+
+impl RunnerRegistry {
+    /// `factory` builds the runner for a name.
+    pub fn new<F>(factory: F) -> Self
+    where
+        F: Fn(&str) -> DenoRunner + Send + Sync + 'static,
+    {
+        Self {
+            factory: Arc::new(factory),
+            ttl: None,
+            runners: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Evict runners that weren't used for `ttl`, the next time the
+    /// registry is looked up or [`evict_expired`](Self::evict_expired) is
+    /// called. By default runners are kept until they are evicted by name.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Runner registered as `name`, building it if there is none.
+    pub fn get_or_create(&self, name: &str) -> Arc<NamedRunner> {
+        let mut runners = self.runners.lock().unwrap();
+        let expired = self.take_expired(&mut runners);
+        let runner = runners
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(NamedRunner::spawn(name, self.factory.clone())))
+            .clone();
+        // Stopping a runner waits for its thread, without holding up
+        // lookups of the other ones
+        drop(runners);
+        drop(expired);
+        runner
+    }
+
+    /// Runner registered as `name`, if there is one.
+    pub fn get(&self, name: &str) -> Option<Arc<NamedRunner>> {
+        let mut runners = self.runners.lock().unwrap();
+        let expired = self.take_expired(&mut runners);
+        let runner = runners.get(name).cloned();
+        drop(runners);
+        drop(expired);
+        runner
+    }
+
+    /// Remove the runner registered as `name`, returning whether there was
+    /// one. Handles taken earlier keep working, the runner stops once the
+    /// last of them is dropped.
+    pub fn evict(&self, name: &str) -> bool {
+        let removed = self.runners.lock().unwrap().remove(name);
+        removed.is_some()
+    }
+
+    /// Remove the runners idle for longer than the [`ttl`](Self::ttl),
+    /// returning how many there were.
+    pub fn evict_expired(&self) -> usize {
+        let expired = self.take_expired(&mut self.runners.lock().unwrap());
+        expired.len()
+    }
+
+    /// Names of the registered runners, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.runners.lock().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Number of registered runners.
+    pub fn len(&self) -> usize {
+        self.runners.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Take the expired runners out of `runners`, to be dropped once the
+    /// lock is released.
+    fn take_expired(
+        &self,
+        runners: &mut HashMap<String, Arc<NamedRunner>>,
+    ) -> Vec<Arc<NamedRunner>> {
+        let ttl = match self.ttl {
+            Some(ttl) => ttl,
+            None => return vec![],
+        };
+        let names: Vec<_> = runners
+            .iter()
+            .filter(|(_, runner)| runner.idle() >= ttl)
+            .map(|(name, _)| name.clone())
+            .collect();
+        names
+            .iter()
+            .filter_map(|name| runners.remove(name))
+            .collect()
+    }
+}
+
+/// A runner of a [`RunnerRegistry`], running one script at a time in the
+/// order they were sent.
+pub struct NamedRunner {
+    name: String,
+    jobs: Mutex<Option<mpsc::Sender<Job>>>,
+    worker: Option<JoinHandle<()>>,
+    last_used: Mutex<Instant>,
+    /// Runs sent and not reported yet, the runner isn't idle while there
+    /// are any
+    pending: AtomicUsize,
+}
+
+/// A run of a [`NamedRunner`] waiting for its report.
+struct Pending<'a>(&'a NamedRunner);
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        // Before the count, so the runner is never idle since the send
+        *self.0.last_used.lock().unwrap() = Instant::now();
+        self.0.pending.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl NamedRunner {
+    fn spawn(name: &str, factory: Factory) -> Self {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let runner_name = name.to_string();
+        let worker = thread::Builder::new()
+            .name(format!("deno-runner-{}", name))
+            .spawn(move || {
+                let mut runner = factory(&runner_name);
+                for job in queue {
+                    job.run(&mut runner, None);
+                }
+            })
+            .expect("failed to spawn registry runner thread");
+
+        Self {
+            name: name.to_string(),
+            jobs: Mutex::new(Some(jobs)),
+            worker: Some(worker),
+            last_used: Mutex::new(Instant::now()),
+            pending: AtomicUsize::new(0),
+        }
+    }
+
+    /// Name the runner is registered as.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Time since the last run of this runner finished, or since it was
+    /// built. Zero while a run is pending.
+    pub fn idle(&self) -> Duration {
+        if self.pending.load(Ordering::SeqCst) > 0 {
+            return Duration::ZERO;
+        }
+        self.last_used.lock().unwrap().elapsed()
+    }
+
+    /// Same as [`DenoRunner::run`], variables are bound as their JSON value.
+    pub async fn run<K, V>(
+        &self,
+        custom_code: impl ToString,
+        vars: Option<HashMap<K, V>>,
+    ) -> Result<String>
+    where
+        K: Display,
+        V: Serialize,
+    {
+        let report = self
+            .run_with_options(custom_code, vars, RunOptions::default())
+            .await?;
+
+        Ok(report.result)
+    }
+
+    /// Same as [`DenoRunner::run_with_options`].
+    pub async fn run_with_options<K, V>(
+        &self,
+        custom_code: impl ToString,
+        vars: Option<HashMap<K, V>>,
+        options: RunOptions,
+    ) -> Result<RunReport>
+    where
+        K: Display,
+        V: Serialize,
+    {
+        let (job, report) = Job::new(custom_code, vars, options)?;
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        let _pending = Pending(self);
+        self.jobs
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|jobs| jobs.send(job).ok())
+            .ok_or_else(|| anyhow!("Runner {} is stopped", self.name))?;
+
+        pool::report(report).await
+    }
+}
+
+impl Drop for NamedRunner {
+    /// Let queued runs finish, then stop the thread.
+    fn drop(&mut self) {
+        self.jobs.lock().unwrap().take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl std::fmt::Debug for NamedRunner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NamedRunner")
+            .field("name", &self.name)
+            .field("idle", &self.idle())
+            .finish()
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{testing::ConsoleStream, Change};
+use deno_core::serde_json::Value;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::RandomState, BTreeMap},
+    hash::{BuildHasher, Hash, Hasher},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// Outcome of [`DenoRunner::run_with_options`](crate::DenoRunner::run_with_options).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunReport {
+    /// Result of the script, formatted like [`DenoRunner::run`](crate::DenoRunner::run)
+    pub result: String,
+    /// Code passed to `exit(code)` if the script ended itself early
+    pub exit_code: Option<i32>,
+    /// Random identifier of this run, also used in error messages
+    pub run_id: String,
+    /// Tags set with [`RunOptions::tag`](crate::RunOptions::tag)
+    pub tags: BTreeMap<String, String>,
+    /// Ops the script called, only recorded in a
+    /// [`dry_run`](crate::RunOptions::dry_run)
+    pub op_calls: Vec<OpCall>,
+    /// Whether the result came from a [`MemoCache`](crate::MemoCache)
+    /// instead of running the script
+    pub cached: bool,
+    /// Lines written with `console.log` (and `info`/`debug`), only captured
+    /// with [`capture_console`](crate::RunOptions::capture_console)
+    pub stdout: Vec<String>,
+    /// Lines written with `console.error` (and `warn`), only captured with
+    /// [`capture_console`](crate::RunOptions::capture_console)
+    pub stderr: Vec<String>,
+    /// Console calls in the order they were made, only captured with
+    /// [`capture_console`](crate::RunOptions::capture_console)
+    pub console: Vec<ConsoleEntry>,
+}
+
+/// A console call captured with
+/// [`capture_console`](crate::RunOptions::capture_console).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsoleEntry {
+    pub level: ConsoleLevel,
+    /// Printed text, several lines for a table
+    pub message: String,
+    /// Number of `console.group()` calls the entry is nested in
+    pub depth: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsoleLevel {
+    Log,
+    Info,
+    Debug,
+    Warn,
+    Error,
+    /// `console.table()` of an object or array
+    Table,
+    /// Label of a `console.group()`
+    Group,
+    /// `console.timeLog()` and `console.timeEnd()`
+    Time,
+}
+
+impl ConsoleEntry {
+    /// Stream the entry is printed to when not captured.
+    pub fn stream(&self) -> ConsoleStream {
+        match self.level {
+            ConsoleLevel::Warn | ConsoleLevel::Error => ConsoleStream::Stderr,
+            _ => ConsoleStream::Stdout,
+        }
+    }
+
+    /// Printed lines, indented by the group depth.
+    pub fn lines(&self) -> impl Iterator<Item = String> + '_ {
+        let indent = "  ".repeat(self.depth);
+        self.message
+            .split('\n')
+            .map(move |line| format!("{}{}", indent, line))
+    }
+}
+
+/// An op call recorded during a dry run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpCall {
+    pub op: String,
+    /// Arguments as JSON, values JSON can't represent become `null`
+    pub args: Vec<Value>,
+}
+
+/// New random run identifier, 16 hex digits.
+pub(crate) fn new_run_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let mut hasher = RandomState::new().build_hasher();
+    COUNTER.fetch_add(1, Ordering::Relaxed).hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Where the time went while building a runner, see
+/// [`DenoRunner::build_report`](crate::DenoRunner::build_report).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildReport {
+    /// Wall time of the whole `Builder::build()` call
+    pub total: Duration,
+    /// Creating the isolate and initializing extensions (`JsRuntime::new`)
+    pub runtime_init: Duration,
+    /// Deserializing a startup snapshot, `None` when built without one
+    pub snapshot_load: Option<Duration>,
+    /// Evaluating `runtime.js` and the builder's own setup scripts
+    pub prelude: Duration,
+    /// Running the [`Builder::warmup`](crate::Builder::warmup) scripts
+    pub warmup: Duration,
+    /// Number of extensions the runtime was created with
+    pub extensions: usize,
+    /// Number of user registered ops
+    pub ops: usize,
+}
+
+/// Outcome of [`DenoRunner::shadow_run`](crate::DenoRunner::shadow_run).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadowReport {
+    /// Result of the old script, the one callers should use
+    pub result: Value,
+    /// Result of the new script, or its error message
+    pub shadow: Result<Value, String>,
+    /// Differences from `result` to `shadow`, empty if the new script failed
+    pub changes: Vec<Change>,
+}
+
+impl ShadowReport {
+    /// Whether the new script failed or produced a different result.
+    pub fn diverged(&self) -> bool {
+        self.shadow.is_err() || !self.changes.is_empty()
+    }
+}
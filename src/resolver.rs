@@ -0,0 +1,67 @@
+use crate::{eval::JsonLiteral, Builder, DenoRunner, RunOptions};
+use anyhow::{anyhow, bail, Result};
+use deno_core::serde_json::Value;
+use std::collections::HashMap;
+
+/// Scripts acting as GraphQL-style field resolvers, run on one warm runner.
+///
+/// Each script sees the `parent` object and the field `args` as variables
+/// and evaluates to the field value:
+///
+/// ```no_run
+/// # async fn example() -> deno_runner::anyhow::Result<()> {
+/// use deno_runner::{serde_json::json, Builder, Resolvers};
+///
+/// let mut resolvers = Resolvers::new(Builder::new())
+///     .resolver("User.displayName", "`${parent.first} ${parent.last}`")?;
+///
+/// let name = resolvers
+///     .resolve("User.displayName", &json!({ "first": "Duyet", "last": "Le" }), &json!({}))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Resolvers {
+    runner: DenoRunner,
+    scripts: HashMap<String, String>,
+}
+
+impl Resolvers {
+    pub fn new(builder: Builder) -> Self {
+        Self {
+            runner: builder.build(),
+            scripts: HashMap::new(),
+        }
+    }
+
+    /// Register the script resolving `field`, named `Type.field`.
+    pub fn resolver(mut self, field: impl ToString, script: impl ToString) -> Result<Self> {
+        let field = field.to_string();
+        match field.split_once('.') {
+            Some((ty, name)) if !ty.is_empty() && !name.is_empty() && !name.contains('.') => {}
+            _ => bail!("Invalid resolver field `{}`, expected `Type.field`", field),
+        }
+
+        self.scripts.insert(field, script.to_string());
+        Ok(self)
+    }
+
+    /// Resolve `field` for `parent` with the field's `args`.
+    pub async fn resolve(&mut self, field: &str, parent: &Value, args: &Value) -> Result<Value> {
+        let script = self
+            .scripts
+            .get(field)
+            .ok_or_else(|| anyhow!("No resolver registered for `{}`", field))?;
+
+        let vars = HashMap::from([
+            ("parent", JsonLiteral(parent.clone())),
+            ("args", JsonLiteral(args.clone())),
+        ]);
+        let options = RunOptions::new().script_name(format!("{}.js", field));
+
+        self.runner
+            .run_json_with_options(script, Some(vars), options)
+            .await
+            .map_err(|err| err.context(format!("Resolver `{}` failed", field)))
+    }
+}
@@ -1,28 +1,1185 @@
 ;((globalThis) => {
   const core = Deno.core
 
+  // Intrinsics captured before any user code runs. Helpers below only use
+  // these, so a script patching `Array.prototype.map` or `Object.prototype`
+  // can't change how the host-provided globals behave.
+  const uncurryThis = Function.prototype.bind.bind(Function.prototype.call)
+  const primordials = Object.freeze({
+    ArrayBufferIsView: ArrayBuffer.isView,
+    ArrayIsArray: Array.isArray,
+    ArrayPrototypeFilter: uncurryThis(Array.prototype.filter),
+    ArrayPrototypeFlatMap: uncurryThis(Array.prototype.flatMap),
+    ArrayPrototypeIncludes: uncurryThis(Array.prototype.includes),
+    ArrayPrototypeJoin: uncurryThis(Array.prototype.join),
+    ArrayPrototypeMap: uncurryThis(Array.prototype.map),
+    ArrayPrototypePop: uncurryThis(Array.prototype.pop),
+    ArrayPrototypePush: uncurryThis(Array.prototype.push),
+    ArrayPrototypeSome: uncurryThis(Array.prototype.some),
+    ArrayPrototypeSplice: uncurryThis(Array.prototype.splice),
+    DataViewPrototypeGetByteLength: uncurryThis(
+      Object.getOwnPropertyDescriptor(DataView.prototype, 'byteLength').get,
+    ),
+    DateNow: Date.now,
+    JSONParse: JSON.parse,
+    JSONStringify: JSON.stringify,
+    MapPrototypeDelete: uncurryThis(Map.prototype.delete),
+    MapPrototypeForEach: uncurryThis(Map.prototype.forEach),
+    MapPrototypeGet: uncurryThis(Map.prototype.get),
+    MapPrototypeHas: uncurryThis(Map.prototype.has),
+    MapPrototypeSet: uncurryThis(Map.prototype.set),
+    MathFloor: Math.floor,
+    MathMax: Math.max,
+    MathMin: Math.min,
+    NumberIsFinite: Number.isFinite,
+    NumberIsInteger: Number.isInteger,
+    NumberParseInt: Number.parseInt,
+    ObjectCreate: Object.create,
+    ObjectDefineProperty: Object.defineProperty,
+    ObjectEntries: Object.entries,
+    ObjectFreeze: Object.freeze,
+    ObjectGetOwnPropertyDescriptor: Object.getOwnPropertyDescriptor,
+    ObjectGetOwnPropertyNames: Object.getOwnPropertyNames,
+    ObjectIsFrozen: Object.isFrozen,
+    ObjectHasOwn: uncurryThis(Object.prototype.hasOwnProperty),
+    ObjectKeys: Object.keys,
+    PromiseAll: Promise.all.bind(Promise),
+    PromisePrototypeThen: uncurryThis(Promise.prototype.then),
+    PromiseResolve: Promise.resolve.bind(Promise),
+    ReflectApply: Reflect.apply,
+    ReflectConstruct: Reflect.construct,
+    ReflectDeleteProperty: Reflect.deleteProperty,
+    RegExpPrototypeExec: uncurryThis(RegExp.prototype.exec),
+    RegExpPrototypeTest: uncurryThis(RegExp.prototype.test),
+    SafeMap: Map,
+    SafeSet: Set,
+    SetPrototypeAdd: uncurryThis(Set.prototype.add),
+    SetPrototypeHas: uncurryThis(Set.prototype.has),
+    StringPrototypeCodePointAt: uncurryThis(String.prototype.codePointAt),
+    StringPrototypeEndsWith: uncurryThis(String.prototype.endsWith),
+    StringPrototypeIncludes: uncurryThis(String.prototype.includes),
+    StringPrototypeToLowerCase: uncurryThis(String.prototype.toLowerCase),
+    StringPrototypeTrim: uncurryThis(String.prototype.trim),
+    StringPrototypeRepeat: uncurryThis(String.prototype.repeat),
+    StringPrototypeSlice: uncurryThis(String.prototype.slice),
+    StringPrototypeSplit: uncurryThis(String.prototype.split),
+    SymbolAsyncIterator: Symbol.asyncIterator,
+    SymbolIterator: Symbol.iterator,
+    TypedArrayPrototypeGetByteLength: uncurryThis(
+      Object.getOwnPropertyDescriptor(Object.getPrototypeOf(Uint8Array.prototype), 'byteLength').get,
+    ),
+    TypedArrayPrototypeSet: uncurryThis(Object.getPrototypeOf(Uint8Array.prototype).set),
+    Uint8Array,
+  })
+  const {
+    ArrayBufferIsView,
+    ArrayIsArray,
+    ArrayPrototypeFilter,
+    ArrayPrototypeFlatMap,
+    ArrayPrototypeIncludes,
+    ArrayPrototypeJoin,
+    ArrayPrototypeMap,
+    ArrayPrototypePop,
+    ArrayPrototypePush,
+    ArrayPrototypeSome,
+    ArrayPrototypeSplice,
+    DataViewPrototypeGetByteLength,
+    DateNow,
+    JSONParse,
+    JSONStringify,
+    MapPrototypeDelete,
+    MapPrototypeForEach,
+    MapPrototypeGet,
+    MapPrototypeHas,
+    MapPrototypeSet,
+    MathFloor,
+    MathMax,
+    MathMin,
+    NumberIsFinite,
+    NumberIsInteger,
+    NumberParseInt,
+    ObjectCreate,
+    ObjectDefineProperty,
+    ObjectEntries,
+    ObjectFreeze,
+    ObjectGetOwnPropertyDescriptor,
+    ObjectGetOwnPropertyNames,
+    ObjectIsFrozen,
+    ObjectHasOwn,
+    ObjectKeys,
+    PromiseAll,
+    PromisePrototypeThen,
+    PromiseResolve,
+    ReflectApply,
+    ReflectConstruct,
+    ReflectDeleteProperty,
+    RegExpPrototypeExec,
+    RegExpPrototypeTest,
+    SafeMap,
+    SafeSet,
+    SetPrototypeAdd,
+    SetPrototypeHas,
+    StringPrototypeCodePointAt,
+    StringPrototypeEndsWith,
+    StringPrototypeIncludes,
+    StringPrototypeToLowerCase,
+    StringPrototypeTrim,
+    StringPrototypeRepeat,
+    StringPrototypeSlice,
+    StringPrototypeSplit,
+    SymbolAsyncIterator,
+    SymbolIterator,
+    TypedArrayPrototypeGetByteLength,
+    TypedArrayPrototypeSet,
+    Uint8Array,
+  } = primordials
+
+  // Hooks the host calls, this script evaluates to them. Only the host
+  // holds the object, so scripts can neither call nor replace them.
+  const hooks = ObjectCreate(null)
+
+  function defineHook(name, fn) {
+    hooks[name] = fn
+  }
+
+  defineHook('primordials', primordials)
+
+  // Raw ops skip every check of `callOp`, scripts only get the wrappers
+  const ops = core.ops
+
+  // Strings are printed as they are, like Deno's console, other values as JSON
   function argsToMessage(...args) {
-    return args.map((arg) => JSON.stringify(arg)).join(' ')
+    return ArrayPrototypeJoin(
+      ArrayPrototypeMap(args, (arg) => (typeof arg === 'string' ? arg : JSONStringify(arg))),
+      ' ',
+    )
+  }
+
+  let groupIndent = ''
+  const timers = new SafeMap()
+
+  // Console calls kept for the host instead of printed, once enabled, as
+  // `ConsoleEntry` objects
+  let captured = null
+
+  defineHook('captureConsole', () => {
+    captured = []
+  })
+
+  defineHook('takeConsole', () => JSONStringify(captured === null ? [] : ArrayPrototypeSplice(captured, 0)))
+
+  // `level` is the name of a `ConsoleLevel`, warnings and errors go to stderr
+  function print(message, level) {
+    if (captured !== null) {
+      ArrayPrototypePush(captured, { level, message, depth: groupIndent.length / 2 })
+      return
+    }
+
+    const isErr = level === 'warn' || level === 'error'
+    const prefix = isErr ? '[err]: ' : '[out]: '
+    const lines = ArrayPrototypeMap(StringPrototypeSplit(message, '\n'), (line) => `${prefix}${groupIndent}${line}\n`)
+    core.print(ArrayPrototypeJoin(lines, ''), isErr)
+  }
+
+  function renderTable(data, columns) {
+    const isObject = (value) => value !== null && typeof value === 'object'
+    const rows = ObjectEntries(data)
+    const keys = columns ?? [...new SafeSet(ArrayPrototypeFlatMap(rows, ([, row]) => (isObject(row) ? ObjectKeys(row) : [])))]
+    const hasValues = ArrayPrototypeSome(rows, ([, row]) => !isObject(row))
+    const header = ['(index)', ...keys]
+    if (hasValues) ArrayPrototypePush(header, 'Values')
+
+    const body = ArrayPrototypeMap(rows, ([index, row]) => {
+      const cells = [index]
+      for (const key of keys) {
+        ArrayPrototypePush(cells, isObject(row) && ObjectHasOwn(row, key) ? JSONStringify(row[key]) : '')
+      }
+      if (hasValues) ArrayPrototypePush(cells, isObject(row) ? '' : JSONStringify(row))
+      return cells
+    })
+
+    const widths = ArrayPrototypeMap(header, (_, i) => {
+      let width = 0
+      for (const row of [header, ...body]) width = MathMax(width, `${row[i]}`.length)
+      return width + 2
+    })
+    const pad = (cell, width) => {
+      const left = MathFloor((width - cell.length) / 2)
+      return StringPrototypeRepeat(' ', left) + cell + StringPrototypeRepeat(' ', width - cell.length - left)
+    }
+    const line = (l, m, r) => l + ArrayPrototypeJoin(ArrayPrototypeMap(widths, (w) => StringPrototypeRepeat('─', w)), m) + r
+    const row = (cells) => '│' + ArrayPrototypeJoin(ArrayPrototypeMap(cells, (cell, i) => pad(`${cell}`, widths[i])), '│') + '│'
+
+    return ArrayPrototypeJoin(
+      [line('┌', '┬', '┐'), row(header), line('├', '┼', '┤'), ...ArrayPrototypeMap(body, row), line('└', '┴', '┘')],
+      '\n',
+    )
+  }
+
+  function timeLog(label = 'default', ...args) {
+    if (!MapPrototypeHas(timers, label)) return
+    const elapsed = DateNow() - MapPrototypeGet(timers, label)
+    print(ArrayPrototypeJoin([`${label}: ${elapsed}ms`, ...ArrayPrototypeMap(args, (arg) => JSONStringify(arg))], ' '), 'time')
   }
 
   globalThis.console = {
     log: (...args) => {
-      core.print(`[out]: ${argsToMessage(...args)}\n`, false)
+      print(argsToMessage(...args), 'log')
+    },
+    info: (...args) => {
+      print(argsToMessage(...args), 'info')
+    },
+    debug: (...args) => {
+      print(argsToMessage(...args), 'debug')
+    },
+    warn: (...args) => {
+      print(argsToMessage(...args), 'warn')
     },
     error: (...args) => {
-      core.print(`[err]: ${argsToMessage(...args)}\n`, true)
+      print(argsToMessage(...args), 'error')
+    },
+    table: (data, columns) => {
+      if (data === null || typeof data !== 'object') {
+        print(argsToMessage(data), 'log')
+        return
+      }
+      print(renderTable(data, columns), 'table')
+    },
+    group: (...label) => {
+      if (label.length > 0) print(argsToMessage(...label), 'group')
+      groupIndent += '  '
+    },
+    groupEnd: () => {
+      groupIndent = StringPrototypeSlice(groupIndent, 2)
+    },
+    time: (label = 'default') => {
+      MapPrototypeSet(timers, label, DateNow())
     },
+    timeLog,
+    timeEnd: (label = 'default') => {
+      timeLog(label)
+      MapPrototypeDelete(timers, label)
+    },
+  }
+
+  // Expected argument types per op, set with `Builder::op_signature`
+  const opSync = core.opSync
+  const opAsync = core.opAsync
+  const opSignatures = new SafeMap()
+
+  defineHook('setOpSignatures', (signatures) => {
+    for (const [name, params] of ObjectEntries(signatures)) {
+      MapPrototypeSet(opSignatures, name, params)
+    }
+  })
+
+  function checkOpArgs(name, args) {
+    const params = MapPrototypeGet(opSignatures, name)
+    if (params === undefined) return
+
+    for (let i = 0; i < params.length; i++) {
+      const actual = typeOf(args[i])
+      if (params[i] !== 'any' && actual !== params[i]) {
+        throw new TypeError(`${name}: argument ${i + 1} expected ${params[i]}, received ${actual}`)
+      }
+    }
+  }
+
+  // serde_v8 only reports the position of an argument it couldn't read,
+  // name the op so the error is actionable from the script side
+  function opArgError(name, error) {
+    const match = error instanceof TypeError && RegExpPrototypeExec(/parsing args at position (\d+)/, error.message)
+    if (!match) return error
+    return new TypeError(`${name}: argument ${NumberParseInt(match[1]) + 1} has the wrong type (${error.message})`)
+  }
+
+  // Name the op instead of deno_core's generic error when a script calls
+  // one the runner doesn't have
+  function checkOpRegistered(name) {
+    if (!ObjectHasOwn(ops, name)) {
+      throw new ReferenceError(`op ${name} is not registered with this runner`)
+    }
+  }
+
+  // Most bytes a single op call may send or return, see
+  // `Builder::op_payload_limit`. `null` when unlimited.
+  let opPayloadLimit = null
+
+  defineHook('setOpPayloadLimit', (limit) => {
+    opPayloadLimit = limit
+  })
+
+  function byteLength(view) {
+    try {
+      return TypedArrayPrototypeGetByteLength(view)
+    } catch {
+      return DataViewPrototypeGetByteLength(view)
+    }
+  }
+
+  // Estimated size of `value` once handed to Rust, counting stops as soon
+  // as it passes `limit` so huge payloads aren't walked to the end
+  function payloadSize(value, limit) {
+    const seen = new SafeSet()
+    const pending = [value]
+    let size = 0
+    while (pending.length > 0 && size <= limit) {
+      const item = ArrayPrototypePop(pending)
+      if (typeof item === 'string') {
+        size += item.length + 2
+      } else if (typeof item !== 'object' || item === null) {
+        size += 8
+      } else if (!SetPrototypeHas(seen, item)) {
+        SetPrototypeAdd(seen, item)
+        if (ArrayBufferIsView(item)) {
+          size += byteLength(item)
+        } else {
+          const keys = ObjectKeys(item)
+          for (let i = 0; i < keys.length && size <= limit; i++) {
+            size += keys[i].length + 4
+            ArrayPrototypePush(pending, item[keys[i]])
+          }
+        }
+      }
+    }
+    return size
+  }
+
+  function checkPayload(name, what, value) {
+    if (opPayloadLimit === null || payloadSize(value, opPayloadLimit) <= opPayloadLimit) return
+    const error = new RangeError(`${name}: ${what} larger than the op payload limit of ${opPayloadLimit} bytes`)
+    error.code = 'ERR_OP_PAYLOAD_TOO_LARGE'
+    error.op = name
+    error.limit = opPayloadLimit
+    throw error
+  }
+
+  function sendOp(name, args) {
+    checkPayload(name, 'arguments are', args)
+    const result = ReflectApply(opSync, core, [name, ...args])
+    checkPayload(name, 'result is', result)
+    return result
+  }
+
+  function sendOpAsync(name, args) {
+    checkPayload(name, 'arguments are', args)
+    const promise = ReflectApply(opAsync, core, [name, ...args])
+    if (opPayloadLimit === null) return promise
+    return promise.then((result) => {
+      checkPayload(name, 'result is', result)
+      return result
+    })
+  }
+
+  // Same checks as the global op functions, so pure runs, dry runs and
+  // `restrictOps` apply to these too
+  core.opSync = (name, ...args) => callOp(name, args)
+  core.opAsync = (name, ...args) => callOpAsync(name, args)
+
+  // Dry run, see `RunOptions::dry_run`: ops are not called, each call is
+  // recorded and answered with its stubbed value (undefined by default)
+  let dryRun = null
+  const opCalls = []
+  // Names passed to `bindFastOps`, walked by index so runs reusing the
+  // runner can't change the iteration through `Array.prototype`
+  const fastOps = []
+
+  function forEachFastOp(fn) {
+    for (let i = 0; i < fastOps.length; i++) fn(fastOps[i])
+  }
+
+  defineHook('setDryRun', (stubs) => {
+    dryRun = stubs
+    forEachFastOp((name) => {
+      globalThis[name] = (...args) => callOp(name, args)
+    })
+  })
+
+  defineHook('takeOpCalls', () => JSONStringify(ArrayPrototypeSplice(opCalls, 0)))
+
+  function recordOpCall(name, args) {
+    ArrayPrototypePush(opCalls, { op: name, args })
+    return ObjectHasOwn(dryRun, name) ? dryRun[name] : undefined
+  }
+
+  // UTF-8 only `TextEncoder` and `TextDecoder`, backed by `core.encode`
+  // and `core.decode`
+  const { encode: encodeUtf8, decode: decodeUtf8 } = core
+  const UTF8_LABELS = ['utf-8', 'utf8', 'unicode-1-1-utf-8']
+
+  function toBytes(input) {
+    if (input === undefined) return new Uint8Array(0)
+    if (ArrayBufferIsView(input)) return new Uint8Array(input.buffer, input.byteOffset, input.byteLength)
+    if (input instanceof ArrayBuffer || input instanceof SharedArrayBuffer) return new Uint8Array(input)
+    throw new TypeError('The input must be an ArrayBuffer or ArrayBufferView')
+  }
+
+  class TextEncoder {
+    get encoding() {
+      return 'utf-8'
+    }
+
+    encode(input = '') {
+      return encodeUtf8(`${input}`)
+    }
+
+    // Encode as many whole characters of `source` as fit into `destination`
+    encodeInto(source, destination) {
+      source = `${source}`
+      let read = 0
+      let written = 0
+      while (read < source.length) {
+        const codePoint = StringPrototypeCodePointAt(source, read)
+        const units = codePoint > 0xffff ? 2 : 1
+        const size = codePoint < 0x80 ? 1 : codePoint < 0x800 ? 2 : codePoint < 0x10000 ? 3 : 4
+        if (written + size > destination.length) break
+        TypedArrayPrototypeSet(destination, encodeUtf8(StringPrototypeSlice(source, read, read + units)), written)
+        read += units
+        written += size
+      }
+      return { read, written }
+    }
+  }
+
+  class TextDecoder {
+    #fatal
+    #ignoreBOM
+
+    constructor(label = 'utf-8', options = {}) {
+      if (!ArrayPrototypeIncludes(UTF8_LABELS, StringPrototypeToLowerCase(StringPrototypeTrim(`${label}`)))) {
+        throw new RangeError(`The encoding "${label}" is not supported, only utf-8 is`)
+      }
+      this.#fatal = !!options.fatal
+      this.#ignoreBOM = !!options.ignoreBOM
+    }
+
+    get encoding() {
+      return 'utf-8'
+    }
+
+    get fatal() {
+      return this.#fatal
+    }
+
+    get ignoreBOM() {
+      return this.#ignoreBOM
+    }
+
+    decode(input, options = {}) {
+      if (options.stream) throw new TypeError('Streaming decode is not supported')
+      const bytes = toBytes(input)
+      let text = decodeUtf8(bytes)
+      // `core.decode` drops a leading byte order mark
+      const hasBOM = bytes[0] === 0xef && bytes[1] === 0xbb && bytes[2] === 0xbf
+      if (hasBOM && this.#ignoreBOM) text = `\ufeff${text}`
+      if (this.#fatal && StringPrototypeIncludes(text, '\ufffd')) {
+        const encoded = encodeUtf8(text)
+        const offset = hasBOM && !this.#ignoreBOM ? 3 : 0
+        let valid = encoded.length === bytes.length - offset
+        for (let i = 0; valid && i < encoded.length; i++) valid = encoded[i] === bytes[i + offset]
+        if (!valid) throw new TypeError('The encoded data is not valid utf-8')
+      }
+      return text
+    }
+  }
+
+  globalThis.TextEncoder = TextEncoder
+  globalThis.TextDecoder = TextDecoder
+
+  // Timers, see `timers.rs`. Callbacks run from the event loop, which a
+  // run drains before it returns
+  let activeTimers = new SafeMap()
+  let nextTimerId = 1
+  // Largest delay in ms browsers and Node.js keep, 2^31 - 1
+  const MAX_TIMER_DELAY = 0x7fffffff
+
+  function startTimer(callback, delay, args, repeat) {
+    if (typeof callback !== 'function') {
+      throw new TypeError('The timer callback must be a function')
+    }
+    // Saturated at the largest delay timers take, instead of firing at once
+    delay = NumberIsFinite(+delay) ? MathMin(MAX_TIMER_DELAY, MathMax(0, MathFloor(+delay))) : 0
+    const id = nextTimerId++
+    const rid = opSync('op_timer_start')
+    MapPrototypeSet(activeTimers, id, rid)
+
+    const wait = () => {
+      PromisePrototypeThen(opAsync('op_timer_sleep', rid, delay), (fired) => {
+        if (!fired || !MapPrototypeHas(activeTimers, id)) return
+        if (!repeat) clearTimer(id)
+        ReflectApply(callback, globalThis, args)
+        if (repeat && MapPrototypeHas(activeTimers, id)) wait()
+      })
+    }
+    wait()
+    return id
+  }
+
+  function clearTimer(id) {
+    const rid = MapPrototypeGet(activeTimers, id)
+    if (rid === undefined) return
+    MapPrototypeDelete(activeTimers, id)
+    opSync('op_timer_clear', rid)
+  }
+
+  globalThis.setTimeout = (callback, delay = 0, ...args) => startTimer(callback, delay, args, false)
+  globalThis.setInterval = (callback, delay = 0, ...args) => startTimer(callback, delay, args, true)
+  globalThis.clearTimeout = (id) => clearTimer(id)
+  globalThis.clearInterval = (id) => clearTimer(id)
+
+  // Pure run, see `RunOptions::pure`: anything that reaches the host or
+  // depends on when or where the script runs throws instead
+  let pure = false
+
+  function impure(what) {
+    throw new Error(`${what} is not allowed in a pure run`)
+  }
+
+  let beforePure = null
+
+  defineHook('setPure', () => {
+    pure = true
+    beforePure = {
+      random: Math.random,
+      Date: globalThis.Date,
+      setTimeout: globalThis.setTimeout,
+      setInterval: globalThis.setInterval,
+    }
+    forEachFastOp((name) => {
+      globalThis[name] = () => impure(`op ${name}`)
+    })
+
+    Math.random = () => impure('Math.random()')
+
+    const RealDate = Date
+    function PureDate(...args) {
+      if (new.target === undefined || args.length === 0) impure('Reading the current time')
+      return ReflectConstruct(RealDate, args, new.target)
+    }
+    // Dates made in the run only lead back to `PureDate`, even through the
+    // prototype they inherit from
+    PureDate.prototype = ObjectCreate(RealDate.prototype, {
+      constructor: { value: PureDate, writable: true, configurable: true },
+    })
+    ObjectDefineProperty(RealDate.prototype, 'constructor', {
+      value: PureDate,
+      writable: true,
+      configurable: true,
+    })
+    PureDate.now = () => impure('Date.now()')
+    PureDate.parse = RealDate.parse
+    PureDate.UTC = RealDate.UTC
+    globalThis.Date = PureDate
+
+    for (const timer of ['setTimeout', 'setInterval', 'setImmediate']) {
+      if (ObjectHasOwn(globalThis, timer)) globalThis[timer] = () => impure(`${timer}()`)
+    }
+  })
+
+  // Ops declared in the script's `// requires:` header, see
+  // `RunOptions::approve_capabilities`. `null` when not restricted.
+  let allowedOps = null
+
+  function checkOpAllowed(name) {
+    if (allowedOps !== null && !SetPrototypeHas(allowedOps, name)) {
+      throw new Error(`op ${name} is not declared in the script's // requires: header`)
+    }
+  }
+
+  defineHook('restrictOps', (names) => {
+    allowedOps = new SafeSet(names)
+    forEachFastOp((name) => {
+      if (!SetPrototypeHas(allowedOps, name)) globalThis[name] = () => checkOpAllowed(name)
+    })
+  })
+
+  // Failures and delays injected per op, see `FaultPlan`
+  let faultPlans = new SafeMap()
+  let faultCallCounts = new SafeMap()
+
+  defineHook('setFaultPlan', (plan) => {
+    for (const [name, faults] of ObjectEntries(plan)) {
+      MapPrototypeSet(faultPlans, name, faults)
+      if (ArrayPrototypeIncludes(fastOps, name)) globalThis[name] = (...args) => callOp(name, args)
+    }
+  })
+
+  // `{ delay, error }` for this call of `name`, or undefined if it has no faults
+  function nextFault(name) {
+    const faults = MapPrototypeGet(faultPlans, name)
+    if (faults === undefined) return undefined
+
+    const call = (MapPrototypeGet(faultCallCounts, name) ?? 0) + 1
+    MapPrototypeSet(faultCallCounts, name, call)
+
+    let delay = 0
+    let error = null
+    for (const fault of faults) {
+      if (fault.kind === 'delay') delay += fault.ms
+      if (fault.kind === 'failAlways' || (fault.kind === 'failNth' && fault.nth === call)) {
+        error = new Error(`${name}: injected failure on call ${call}`)
+      }
+    }
+    return { delay, error }
+  }
+
+  function callOp(name, args) {
+    checkOpRegistered(name)
+    if (pure) impure(`op ${name}`)
+    checkOpAllowed(name)
+    checkOpArgs(name, args)
+    const fault = nextFault(name)
+    if (fault !== undefined) {
+      if (fault.delay > 0) opSync('op_fault_sleep', fault.delay)
+      if (fault.error !== null) throw fault.error
+    }
+    if (dryRun !== null) return recordOpCall(name, args)
+    try {
+      return sendOp(name, args)
+    } catch (error) {
+      throw opArgError(name, error)
+    }
+  }
+
+  function callOpAsync(name, args) {
+    checkOpRegistered(name)
+    if (pure) impure(`op ${name}`)
+    checkOpAllowed(name)
+    checkOpArgs(name, args)
+    const fault = nextFault(name)
+    if (fault !== undefined) {
+      return (async () => {
+        if (fault.delay > 0) await opAsync('op_fault_delay', fault.delay)
+        if (fault.error !== null) throw fault.error
+        return invokeOpAsync(name, args)
+      })()
+    }
+    return invokeOpAsync(name, args)
+  }
+
+  function invokeOpAsync(name, args) {
+    if (dryRun !== null) return PromiseResolve(recordOpCall(name, args))
+    return sendOpAsync(name, args).catch((error) => {
+      throw opArgError(name, error)
+    })
   }
 
   // Re-export op to `globalThis`
-  for (let op of Object.keys(core.ops)) {
+  for (let op of ObjectKeys(ops)) {
     globalThis[op] = (...args) => {
-      return core.opSync(op, ...args)
+      return callOp(op, args)
     }
   }
 
+  // Fast ops are called directly, any JS wrapper would stop V8 from taking
+  // the fast call path
+  defineHook('bindFastOps', (names) => {
+    for (const name of names) {
+      ArrayPrototypePush(fastOps, name)
+      globalThis[name] = ops[name]
+    }
+  })
+
   // Re-export opSync and opAsync to `globalThis`
   // Usage: rust("op_name", arg1, arg2, ...)
-  globalThis.rust = core.opSync
-  globalThis.rustAsync = core.opAsync
+  globalThis.rust = (name, ...args) => callOp(name, args)
+  globalThis.rustAsync = (name, ...args) => callOpAsync(name, args)
+
+  // Language features turned off with `Builder::disable_language_feature`,
+  // the rest are enforced from Rust
+  defineHook('disableLanguageFeatures', (names) => {
+    if (ArrayPrototypeIncludes(names, 'weak_refs')) {
+      ReflectDeleteProperty(globalThis, 'WeakRef')
+      ReflectDeleteProperty(globalThis, 'FinalizationRegistry')
+    }
+    if (ArrayPrototypeIncludes(names, 'regexp_lookbehind')) {
+      const checkPattern = (pattern) => {
+        if (typeof pattern === 'string' && RegExpPrototypeTest(/\(\?<[=!]/, pattern)) {
+          throw new SyntaxError('RegExp lookbehind is disabled for this runner')
+        }
+      }
+      const GatedRegExp = new Proxy(RegExp, {
+        apply(target, thisArg, args) {
+          checkPattern(args[0])
+          return ReflectApply(target, thisArg, args)
+        },
+        construct(target, args, newTarget) {
+          checkPattern(args[0])
+          return ReflectConstruct(target, args, newTarget === GatedRegExp ? target : newTarget)
+        },
+      })
+      ObjectDefineProperty(RegExp.prototype, 'constructor', { value: GatedRegExp, writable: true, enumerable: false, configurable: true })
+      globalThis.RegExp = GatedRegExp
+    }
+  })
+
+  // Pull items from a Rust stream registered with `Builder::add_stream` one
+  // at a time, the stream is only opened once iteration starts.
+  // Usage: for await (const user of stream("users", { pageSize: 100 })) { ... }
+
+  function stream(name, args = null) {
+    if (pure) impure('stream()')
+    return {
+      [SymbolAsyncIterator]() {
+        let rid = null
+        let done = false
+
+        return {
+          async next() {
+            if (done) return { done: true, value: undefined }
+            if (rid === null) rid = opSync('op_stream_open', name, args)
+
+            let chunk
+            try {
+              chunk = await opAsync('op_stream_next', rid)
+            } catch (error) {
+              // The host closed the stream
+              done = true
+              throw error
+            }
+            if (chunk === null) {
+              done = true
+              return { done: true, value: undefined }
+            }
+            return { done: false, value: chunk.value }
+          },
+          async return(value) {
+            if (rid !== null && !done) opSync('op_stream_close', rid)
+            done = true
+            return { done: true, value }
+          },
+        }
+      },
+    }
+  }
+
+  globalThis.stream = stream
+
+  // Items of a `DenoRunner::run_stream` result, plain iterables work too
+  defineHook('asyncIterator', (iterable) => {
+    const method = iterable?.[SymbolAsyncIterator] ?? iterable?.[SymbolIterator]
+    if (typeof method !== 'function') throw new TypeError('run_stream script must evaluate to an async iterable')
+    return ReflectApply(method, iterable, [])
+  })
+
+  defineHook('iteratorNext', async (iterator) => {
+    const { done, value } = await iterator.next()
+    return { done: !!done, value }
+  })
+
+  // Run async tasks with at most `limit` of them in flight, so scripts calling
+  // async ops in bulk don't flood the host.
+  // Usage: await parallel(ids.map((id) => () => rustAsync("fetch", id)), { limit: 4 })
+  async function parallel(tasks, options = undefined) {
+    // Only own properties, so a polluted `Object.prototype.limit` is ignored
+    const limit = options != null && ObjectHasOwn(options, 'limit') ? options.limit : parallel.defaultLimit
+    if (!NumberIsInteger(limit) || limit < 1) {
+      throw new RangeError(`parallel: limit must be a positive integer, got ${limit}`)
+    }
+
+    const results = new Array(tasks.length)
+    let next = 0
+
+    async function worker() {
+      while (next < tasks.length) {
+        const index = next++
+        results[index] = await tasks[index]()
+      }
+    }
+
+    const workers = []
+    for (let i = 0; i < MathMin(limit, tasks.length); i++) {
+      ArrayPrototypePush(workers, worker())
+    }
+    await PromiseAll(workers)
+
+    return results
+  }
+  parallel.defaultLimit = 8
+
+  globalThis.parallel = parallel
+
+  // Check the variables a script relies on before doing any work, reporting
+  // every problem at once. A trailing `?` marks a variable as optional.
+  // Usage: expects({ a: 'number', 'b?': 'string' })
+  const reservedWords = new SafeSet([
+    'await', 'break', 'case', 'catch', 'class', 'const', 'continue', 'debugger', 'default',
+    'delete', 'do', 'else', 'enum', 'export', 'extends', 'false', 'finally', 'for', 'function',
+    'if', 'implements', 'import', 'in', 'instanceof', 'interface', 'let', 'new', 'null',
+    'package', 'private', 'protected', 'public', 'return', 'static', 'super', 'switch', 'this',
+    'throw', 'true', 'try', 'typeof', 'var', 'void', 'while', 'with', 'yield',
+  ])
+
+  function typeOf(value) {
+    if (value === null) return 'null'
+    if (ArrayIsArray(value)) return 'array'
+    return typeof value
+  }
+
+  function expects(spec) {
+    const problems = []
+
+    for (const [key, expected] of ObjectEntries(spec)) {
+      const optional = StringPrototypeEndsWith(key, '?')
+      const name = optional ? StringPrototypeSlice(key, 0, -1) : key
+
+      if (!RegExpPrototypeTest(/^[A-Za-z_$][\w$]*$/, name) || SetPrototypeHas(reservedWords, name)) {
+        throw new TypeError(`expects: invalid variable name ${JSONStringify(name)}`)
+      }
+
+      // Variables are bound as properties of the global, see `bind`
+      const value = name in globalThis ? globalThis[name] : undefined
+      if (value === undefined) {
+        if (!optional) ArrayPrototypePush(problems, `missing variable ${name} (${expected})`)
+        continue
+      }
+
+      const actual = typeOf(value)
+      if (expected !== 'any' && actual !== expected) {
+        ArrayPrototypePush(problems, `variable ${name} should be ${expected}, got ${actual}`)
+      }
+    }
+
+    if (problems.length > 0) {
+      throw new TypeError(`Unexpected script variables:\n  ${ArrayPrototypeJoin(problems, '\n  ')}`)
+    }
+  }
+
+  globalThis.expects = expects
+
+  // Progress updates for the host, see `Builder::on_progress`
+  // Usage: progress.report("transform", (i / rows.length) * 100)
+  globalThis.progress = ObjectFreeze({
+    __proto__: null,
+    report: (stage, pct) => {
+      pct = +pct
+      if (!(pct >= 0 && pct <= 100)) throw new RangeError(`Progress must be between 0 and 100, got ${pct}`)
+      opSync('op_progress_report', `${stage}`, pct)
+    },
+  })
+
+  // Sandboxed file access backed by the host's `VirtualFs`, see `Builder::virtual_fs`
+  // Usage: const config = JSON.parse(fs.readTextFile("/config.json"))
+  defineHook('defineFs', () => {
+    const { encode, decode } = core
+    // Files outlive the run, so a pure run can't touch them at all
+    const vfs = (what, name, ...args) => {
+      if (pure) impure(what)
+      return opSync(name, ...args)
+    }
+
+    globalThis.fs = ObjectFreeze({
+      __proto__: null,
+      readFile: (path) => vfs('Reading files', 'op_vfs_read', path),
+      readTextFile: (path) => decode(vfs('Reading files', 'op_vfs_read', path)),
+      writeFile: (path, bytes) => vfs('Writing files', 'op_vfs_write', path, bytes),
+      writeTextFile: (path, text) => vfs('Writing files', 'op_vfs_write', path, encode(`${text}`)),
+      remove: (path) => vfs('Removing files', 'op_vfs_remove', path),
+      exists: (path) => vfs('Reading files', 'op_vfs_exists', path),
+      readDir: (path) => vfs('Reading files', 'op_vfs_read_dir', path),
+    })
+  })
+
+  // Strings exchanged with ops by index, see `Builder::string_table`
+  // Usage: set_status(strings.id("active")); strings.get(get_status())
+  defineHook('setStringTable', (table) => {
+    const ids = new SafeMap()
+    for (let id = 0; id < table.length; id++) {
+      if (!MapPrototypeHas(ids, table[id])) MapPrototypeSet(ids, table[id], id)
+    }
+
+    globalThis.strings = ObjectFreeze({
+      __proto__: null,
+      id: (string) => {
+        const id = MapPrototypeGet(ids, string)
+        if (id === undefined) throw new RangeError(`${JSONStringify(string)} is not in the string table`)
+        return id
+      },
+      get: (id) => {
+        if (!NumberIsInteger(id) || id < 0 || id >= table.length) throw new RangeError(`${id} is not a string table id`)
+        return table[id]
+      },
+    })
+  })
+
+  // Globals resolved by the host on first read, see `Builder::lazy_binding`.
+  // A value is shared within a run and resolved again by the next one.
+  let lazyValues = new SafeMap()
+
+  function lazyValue(name) {
+    if (pure) impure(`Lazy binding ${name}`)
+    if (!MapPrototypeHas(lazyValues, name)) {
+      MapPrototypeSet(lazyValues, name, opAsync('op_lazy_binding', name))
+    }
+    return MapPrototypeGet(lazyValues, name)
+  }
+
+  defineHook('defineLazyBindings', (names) => {
+    for (const name of names) {
+      ObjectDefineProperty(globalThis, name, {
+        get: () => lazyValue(name),
+        enumerable: true,
+        configurable: true,
+      })
+    }
+  })
+
+  // Per-run feature flags set by the host, read-only for the script.
+  // Usage: if (features.isEnabled("new-pricing")) { ... }
+  function setFeatures(flags) {
+    const features = ObjectCreate(null)
+    for (const [name, enabled] of ObjectEntries(flags)) {
+      features[name] = enabled === true
+    }
+    features.isEnabled = (name) => ObjectHasOwn(flags, name) && flags[name] === true
+
+    ObjectDefineProperty(globalThis, 'features', {
+      value: ObjectFreeze(features),
+      writable: false,
+      enumerable: false,
+      configurable: true,
+    })
+  }
+
+  setFeatures(ObjectCreate(null))
+  defineHook('setFeatures', setFeatures)
+
+  // End the run early, like `process.exit()` in Node. The run succeeds with
+  // `value` as its result and `code` reported as the exit code.
+  // Usage: if (!input) exit(1, 'no input')
+  const exitSignal = ObjectFreeze({ toString: () => 'exit() called' })
+  let exitStatus = null
+  // Whether the signal reached the host, a script catching it keeps running
+  // and doesn't exit
+  let exitPropagated = false
+
+  globalThis.exit = (code = 0, value = undefined) => {
+    exitStatus = ObjectFreeze({ __proto__: null, code: code | 0, value })
+    throw exitSignal
+  }
+
+  // Called with every exception handed to the host
+  opSync('op_set_format_exception_callback', (error) => {
+    if (error === exitSignal) exitPropagated = true
+    return null
+  })
+
+  defineHook('takeExitStatus', () => {
+    const status = exitPropagated ? exitStatus : null
+    exitStatus = null
+    exitPropagated = false
+    return status
+  })
+
+  // Bind a host variable for the next script, see `DenoRunner::run`
+  let boundNames = []
+
+  // `mode` is the name of a `BindingMode`
+  function bind(name, value, mode = 'mutable') {
+    if (mode === 'const') {
+      const set = () => {
+        throw new TypeError(`Assignment to constant variable ${name}`)
+      }
+      ObjectDefineProperty(globalThis, name, { get: () => value, set, enumerable: false, configurable: true })
+    } else {
+      ObjectDefineProperty(globalThis, name, { value, writable: true, enumerable: mode === 'global', configurable: true })
+    }
+    if (mode !== 'global') ArrayPrototypePush(boundNames, name)
+  }
+
+  defineHook('bind', bind)
+
+  // Freeze `value` and everything reachable from it, for values several
+  // runs share
+  function deepFreeze(value) {
+    if (value === null || typeof value !== 'object' || ObjectIsFrozen(value)) return value
+    ObjectFreeze(value)
+    const keys = ObjectKeys(value)
+    for (let i = 0; i < keys.length; i++) deepFreeze(value[keys[i]])
+    return value
+  }
+
+  defineHook('deepFreeze', deepFreeze)
+
+  // Names of the globals, other than the bindings of the last run, see
+  // `RunnerPool::on_heap_leak`
+  defineHook('globalNames', () => {
+    const names = ObjectGetOwnPropertyNames(globalThis)
+    return ArrayPrototypeFilter(names, (name) => !ArrayPrototypeIncludes(boundNames, name))
+  })
+
+  // Globals of the runtime, there before any script ran
+  let runtimeGlobals = new SafeSet()
+
+  defineHook('markRuntimeGlobals', () => {
+    runtimeGlobals = new SafeSet(ObjectGetOwnPropertyNames(globalThis))
+  })
+
+  function scriptGlobals() {
+    return ArrayPrototypeFilter(
+      ObjectGetOwnPropertyNames(globalThis),
+      (name) => !SetPrototypeHas(runtimeGlobals, name) && !ArrayPrototypeIncludes(boundNames, name),
+    )
+  }
+
+  // Whether scripts left globals behind, which memoized runs could read
+  defineHook('hasScriptGlobals', () => scriptGlobals().length > 0)
+
+  // JSON text of each global scripts set, see `DenoRunner::checkpoint`.
+  // Functions and values without a JSON representation are left out.
+  defineHook('checkpoint', () => {
+    const globals = ObjectCreate(null)
+    const names = scriptGlobals()
+    for (let i = 0; i < names.length; i++) {
+      const name = names[i]
+      const descriptor = ObjectGetOwnPropertyDescriptor(globalThis, name)
+      if (ObjectHasOwn(descriptor, 'get') || ObjectHasOwn(descriptor, 'set')) continue
+      let json
+      try {
+        json = JSONStringify(descriptor.value)
+      } catch (error) {
+        throw new TypeError(`Global ${name} can't be saved: ${error.message}`)
+      }
+      if (json !== undefined) globals[name] = json
+    }
+    return globals
+  })
+
+  // Put back the globals of a checkpoint, see `DenoRunner::restore`
+  defineHook('restore', (json) => {
+    const globals = JSONParse(json)
+    const names = scriptGlobals()
+    for (let i = 0; i < names.length; i++) {
+      const name = names[i]
+      if (!ObjectHasOwn(globals, name) && typeof globalThis[name] !== 'function') {
+        ReflectDeleteProperty(globalThis, name)
+      }
+    }
+    const saved = ObjectKeys(globals)
+    for (let i = 0; i < saved.length; i++) {
+      const name = saved[i]
+      if (ObjectHasOwn(globalThis, name)) {
+        globalThis[name] = globals[name]
+      } else {
+        ObjectDefineProperty(globalThis, name, { value: globals[name], writable: true, enumerable: true, configurable: true })
+      }
+    }
+  })
+
+  // Type of the global a binding named `name` would replace, `undefined`
+  // when there is none. Getters aren't called.
+  defineHook('existingGlobal', (name) => {
+    const descriptor = ObjectGetOwnPropertyDescriptor(globalThis, name)
+    if (descriptor === undefined) return undefined
+    return ObjectHasOwn(descriptor, 'get') ? 'accessor' : typeof descriptor.value
+  })
+
+  // Binary codecs for `DenoRunner::run_encoded`, more are registered by
+  // optional preludes (e.g. msgpack.js)
+  const codecs = new SafeMap([
+    ['json', { encode: (value) => encodeUtf8(JSONStringify(value) ?? 'null'), decode: (bytes) => JSONParse(decodeUtf8(bytes)) }],
+  ])
+
+  defineHook('registerCodec', (name, codec) => MapPrototypeSet(codecs, name, codec))
+
+  function codecFor(name) {
+    const codec = MapPrototypeGet(codecs, name)
+    if (codec === undefined) throw new TypeError(`Unknown codec ${name}`)
+    return codec
+  }
+
+  defineHook('bindEncoded', (codec) => {
+    const bindings = codecFor(codec).decode(opSync('op_encoded_bindings'))
+    if (bindings === null || typeof bindings !== 'object' || ArrayIsArray(bindings)) {
+      throw new TypeError('Encoded bindings must be a map of variable names to values')
+    }
+    for (const [name, value] of ObjectEntries(bindings)) bind(name, value)
+  })
+
+  defineHook('encodeResult', (codec, value) => codecFor(codec).encode(value))
+
+  // JSON text of a result for the host, `undefined` when it has no JSON
+  // representation. See `RunOptions::non_finite` for `nonFinite` and
+  // `RunOptions::cycles` for `cycles`.
+  defineHook('resultJson', (value, nonFinite, cycles) => {
+    // Objects from the root to the value being written
+    const ancestors = []
+    function replacer(key, item) {
+      if (typeof item === 'number' && !NumberIsFinite(item) && nonFinite !== 'null') {
+        if (nonFinite === 'string') return `${item}`
+        throw new RangeError(key === '' ? `Result is ${item}` : `Result contains ${item} at key "${key}"`)
+      }
+      if (cycles !== 'mark' || item === null || typeof item !== 'object') return item
+
+      // `this` holds `item`, its siblings' descendants are done
+      while (ancestors.length > 0 && ancestors[ancestors.length - 1] !== this) {
+        ArrayPrototypePop(ancestors)
+      }
+      if (ArrayPrototypeIncludes(ancestors, item)) return '[Circular]'
+      ArrayPrototypePush(ancestors, item)
+      return item
+    }
+    return JSONStringify(value, nonFinite === 'null' && cycles !== 'mark' ? undefined : replacer)
+  })
+
+  // Function mapping a JSON array of items with `map`, see
+  // `DenoRunner::map_stream`. Promises are only awaited when returned.
+  defineHook('batchMapper', (map) => {
+    if (typeof map !== 'function') throw new TypeError('map_stream script must evaluate to a function')
+
+    return async (batch) => {
+      const items = JSONParse(batch)
+      const mapped = []
+      let pending = false
+      for (let i = 0; i < items.length; i++) {
+        const value = map(items[i], i)
+        if (value !== null && typeof value === 'object' && typeof value.then === 'function') pending = true
+        ArrayPrototypePush(mapped, value)
+      }
+      if (pending) {
+        for (let i = 0; i < mapped.length; i++) mapped[i] = await mapped[i]
+      }
+      return JSONStringify(mapped)
+    }
+  })
+
+  // Undo per-run settings before a runner executes its next script
+  defineHook('resetRun', () => {
+    for (let i = 0; i < boundNames.length; i++) {
+      ReflectDeleteProperty(globalThis, boundNames[i])
+    }
+    boundNames = []
+    allowedOps = null
+    if (pure) {
+      Math.random = beforePure.random
+      globalThis.Date = beforePure.Date
+      ObjectDefineProperty(beforePure.Date.prototype, 'constructor', {
+        value: beforePure.Date,
+        writable: true,
+        configurable: true,
+      })
+      globalThis.setTimeout = beforePure.setTimeout
+      globalThis.setInterval = beforePure.setInterval
+      pure = false
+    }
+    forEachFastOp((name) => {
+      globalThis[name] = ops[name]
+    })
+    dryRun = null
+    ArrayPrototypeSplice(opCalls, 0)
+    faultPlans = new SafeMap()
+    faultCallCounts = new SafeMap()
+    exitStatus = null
+    exitPropagated = false
+    captured = null
+    // Left over when the last run failed or timed out
+    MapPrototypeForEach(activeTimers, (rid) => opSync('op_timer_clear', rid))
+    activeTimers = new SafeMap()
+    lazyValues = new SafeMap()
+    groupIndent = ''
+    setFeatures(ObjectCreate(null))
+  })
+
+  ReflectDeleteProperty(core, 'ops')
+  return hooks
 })(globalThis)
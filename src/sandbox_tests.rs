@@ -0,0 +1,370 @@
+//! Known sandbox escape and abuse scenarios, for embedders to run against
+//! their own [`Builder`] in CI:
+//!
+//! ```ignore
+//! #[tokio::test]
+//! async fn test_sandbox() {
+//!     let report = deno_runner::sandbox_tests::run_escape_suite(&my_builder()).await;
+//!     assert!(report.is_contained(), "{}", report);
+//! }
+//! ```
+//!
+//! Every scenario gets new runners built from the builder. Besides the
+//! scripts below, each registered op is called with hostile arguments
+//! (wrong types, huge strings, `NaN`, objects without a prototype), so run
+//! the suite against a configuration whose ops are safe to call, e.g. with
+//! test doubles for the ones writing to real systems.
+
+use crate::{hooks, Builder, RunnerError};
+use deno_core::{futures::FutureExt, serde_json};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    net::TcpListener,
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Outcomes of [`run_escape_suite`], in the order the scenarios ran.
+#[derive(Debug, Clone, Default)]
+pub struct EscapeReport {
+    pub scenarios: Vec<ScenarioOutcome>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScenarioOutcome {
+    pub name: String,
+    /// Whether the runner stopped the script, `false` is a hole
+    pub contained: bool,
+    pub detail: String,
+}
+
+impl EscapeReport {
+    /// Whether every scenario was contained.
+    pub fn is_contained(&self) -> bool {
+        self.scenarios.iter().all(|scenario| scenario.contained)
+    }
+
+    /// Scenarios the runner didn't contain.
+    pub fn breaches(&self) -> impl Iterator<Item = &ScenarioOutcome> {
+        self.scenarios.iter().filter(|scenario| !scenario.contained)
+    }
+
+    /// Outcome of the scenario `name`, e.g. `infinite_loop` or
+    /// `op:<name>` for the calls to an op.
+    pub fn scenario(&self, name: &str) -> Option<&ScenarioOutcome> {
+        self.scenarios.iter().find(|scenario| scenario.name == name)
+    }
+
+    fn push(&mut self, name: &str, contained: Contained) {
+        let (contained, detail) = match contained {
+            Ok(detail) => (true, detail),
+            Err(detail) => (false, detail),
+        };
+        self.scenarios.push(ScenarioOutcome {
+            name: name.to_string(),
+            contained,
+            detail,
+        });
+    }
+}
+
+impl fmt::Display for EscapeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for scenario in &self.scenarios {
+            let status = if scenario.contained { "ok" } else { "BREACH" };
+            writeln!(f, "{:<6} {}: {}", status, scenario.name, scenario.detail)?;
+        }
+        Ok(())
+    }
+}
+
+/// `Ok` with what stopped the script, `Err` with what it got away with.
+type Contained = Result<String, String>;
+
+/// Run every scenario on runners built from `builder`.
+///
+/// Scripts the builder doesn't bound, like an infinite loop without a
+/// [`timeout`](Builder::timeout), are reported as breaches without being
+/// run. Panics are breaches too, but ops panicking inside V8 still abort
+/// the process.
+pub async fn run_escape_suite(builder: &Builder) -> EscapeReport {
+    let mut report = EscapeReport::default();
+
+    report.push("host_env", host_env(builder).await);
+    report.push("file_import", file_import(builder).await);
+    report.push("fetch_loopback", fetch_loopback(builder).await);
+    report.push("binding_leak", binding_leak(builder).await);
+    report.push("runtime_hooks", runtime_hooks(builder).await);
+    report.push("raw_ops", raw_ops(builder).await);
+    report.push("infinite_loop", infinite_loop(builder).await);
+    report.push("memory_exhaustion", memory_exhaustion(builder).await);
+    for op in &builder.ops {
+        report.push(
+            &format!("op:{}", op.name),
+            hostile_op_calls(builder, op.name).await,
+        );
+    }
+    report
+}
+
+/// Run `code` on a new runner, with panics as errors.
+async fn run(builder: &Builder, code: &str) -> anyhow::Result<String> {
+    let run = async {
+        let mut runner = builder.clone().build();
+        runner.run::<_, String, String>(code, None).await
+    };
+
+    match AssertUnwindSafe(run).catch_unwind().await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow::anyhow!("deno_runner panicked")),
+    }
+}
+
+fn runner_error(err: &anyhow::Error) -> Option<&RunnerError> {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<RunnerError>())
+}
+
+/// Host environment variables reaching scripts through `process.env`,
+/// besides the ones allowed with
+/// [`NodeCompat::allow_env`](crate::NodeCompat::allow_env).
+async fn host_env(builder: &Builder) -> Contained {
+    let env = match run(builder, "JSON.stringify(globalThis.process?.env ?? {})").await {
+        Ok(env) => env,
+        Err(err) => return Ok(format!("process.env failed: {}", err)),
+    };
+    let env: BTreeMap<String, serde_json::Value> = serde_json::from_str(&env).unwrap_or_default();
+    let allowed = |key: &str| {
+        builder
+            .node_compat
+            .as_ref()
+            .map_or(false, |compat| compat.host_env().contains(key))
+    };
+
+    let leaked: Vec<String> = std::env::vars()
+        .filter(|(key, value)| env.get(key).and_then(|v| v.as_str()) == Some(value.as_str()))
+        .map(|(key, _)| key)
+        .filter(|key| !allowed(key))
+        .collect();
+    if leaked.is_empty() {
+        Ok("no host variables in process.env but the allowed ones".to_string())
+    } else {
+        Err(format!(
+            "process.env has host variables {}",
+            leaked.join(", ")
+        ))
+    }
+}
+
+/// Importing a module from the host's filesystem.
+async fn file_import(builder: &Builder) -> Contained {
+    let path = std::env::temp_dir().join(format!("deno_runner_escape_{}.js", std::process::id()));
+    if let Err(err) = std::fs::write(&path, "export default 'escaped'") {
+        return Ok(format!("can't write the probe module: {}", err));
+    }
+    let url = deno_core::ModuleSpecifier::from_file_path(&path).unwrap();
+
+    let code = format!(
+        "import({:?}).then((module) => module.default)",
+        url.as_str()
+    );
+    let result = run(builder, &code).await;
+    let _ = std::fs::remove_file(&path);
+    match result {
+        Ok(result) if result == "escaped" => Err(format!("imported {}", path.display())),
+        Ok(result) => Ok(format!("import gave {}", result)),
+        Err(err) => Ok(format!("import failed: {}", err)),
+    }
+}
+
+/// `fetch` requests to a server on the host's loopback interface.
+async fn fetch_loopback(builder: &Builder) -> Contained {
+    let listener = match TcpListener::bind("127.0.0.1:0") {
+        Ok(listener) => listener,
+        Err(err) => return Ok(format!("can't listen on loopback: {}", err)),
+    };
+    let port = listener.local_addr().unwrap().port();
+    listener.set_nonblocking(true).unwrap();
+
+    // Closes connections right away, so a request that gets through fails
+    // instead of waiting for a response
+    let connected = Arc::new(AtomicBool::new(false));
+    let done = Arc::new(AtomicBool::new(false));
+    let server = {
+        let (connected, done) = (connected.clone(), done.clone());
+        thread::spawn(move || {
+            while !done.load(Ordering::SeqCst) {
+                match listener.accept() {
+                    Ok(_) => connected.store(true, Ordering::SeqCst),
+                    Err(_) => thread::sleep(Duration::from_millis(5)),
+                }
+            }
+        })
+    };
+
+    let code = format!(
+        r#"
+        typeof fetch !== 'function'
+            ? 'no fetch'
+            : fetch('http://127.0.0.1:{}/').then(() => 'fetched', (err) => `${{err}}`)
+        "#,
+        port
+    );
+    let result = run(builder, &code).await;
+    done.store(true, Ordering::SeqCst);
+    let _ = server.join();
+
+    if connected.load(Ordering::SeqCst) {
+        return Err(format!("connected to 127.0.0.1:{}", port));
+    }
+    match result {
+        Ok(result) => Ok(result),
+        Err(err) => Ok(format!("fetch failed: {}", err)),
+    }
+}
+
+/// Variables of a run staying visible to the next one.
+async fn binding_leak(builder: &Builder) -> Contained {
+    let leaked = async {
+        let mut runner = builder.clone().build();
+        let vars = HashMap::from([("escape_probe_secret", "s3cret")]);
+        runner.run("escape_probe_secret", Some(vars)).await?;
+        runner
+            .run::<_, String, String>("typeof escape_probe_secret", None)
+            .await
+    };
+
+    match AssertUnwindSafe(leaked).catch_unwind().await {
+        Ok(Ok(kind)) if kind == "undefined" => Ok("cleared after the run".to_string()),
+        Ok(Ok(kind)) => Err(format!("a variable of the last run is still a {}", kind)),
+        Ok(Err(err)) => Ok(format!("runs failed: {}", err)),
+        Err(_) => Err("deno_runner panicked".to_string()),
+    }
+}
+
+/// Host hooks of `runtime.js` reachable from scripts, e.g. a
+/// `Deno.core.resetRun()` lifting the restrictions of the run.
+async fn runtime_hooks(builder: &Builder) -> Contained {
+    let mut runner = builder.clone().build();
+    let names = match hooks::names(&mut runner.runtime) {
+        Ok(names) => names,
+        Err(err) => return Err(format!("can't list the hooks: {}", err)),
+    };
+
+    let code = format!(
+        r#"
+        JSON.stringify({}.filter((name) => {{
+            const core = globalThis.Deno?.core
+            return core != null && name in core
+        }}))
+        "#,
+        serde_json::to_string(&names).unwrap()
+    );
+    let reachable = match runner.run::<_, String, String>(code, None).await {
+        Ok(reachable) => reachable,
+        Err(err) => return Ok(format!("looking up the hooks failed: {}", err)),
+    };
+    match serde_json::from_str::<Vec<String>>(&reachable) {
+        Ok(reachable) if reachable.is_empty() => {
+            Ok(format!("none of the {} hooks is on Deno.core", names.len()))
+        }
+        Ok(reachable) => Err(format!(
+            "scripts can call Deno.core.{}",
+            reachable.join(", Deno.core.")
+        )),
+        Err(_) => Err(format!("unexpected lookup result {}", reachable)),
+    }
+}
+
+/// Ops called through `Deno.core.ops`, skipping the checks of the
+/// wrappers scripts get (restricted ops, pure mode, dry runs).
+async fn raw_ops(builder: &Builder) -> Contained {
+    match run(builder, "typeof globalThis.Deno?.core?.ops").await {
+        Ok(kind) if kind == "undefined" => Ok("Deno.core.ops is not defined".to_string()),
+        Ok(kind) => Err(format!("Deno.core.ops is a {}", kind)),
+        Err(err) => Ok(format!("looking up Deno.core.ops failed: {}", err)),
+    }
+}
+
+/// A script that never returns.
+async fn infinite_loop(builder: &Builder) -> Contained {
+    if builder.timeout.is_none() {
+        return Err("no Builder::timeout, a script can run forever".to_string());
+    }
+
+    match run(builder, "while (true) {}").await {
+        Err(err) => match runner_error(&err) {
+            Some(RunnerError::Timeout(limit)) => Ok(format!("timed out after {:?}", limit)),
+            _ => Err(format!("failed with {} instead of timing out", err)),
+        },
+        Ok(_) => Err("an infinite loop returned".to_string()),
+    }
+}
+
+/// A script allocating until the process runs out of memory.
+async fn memory_exhaustion(builder: &Builder) -> Contained {
+    if builder.max_heap_size.is_none() {
+        return Err("no Builder::max_heap_size, a script can use all memory".to_string());
+    }
+
+    let code = "const hoard = []; while (true) hoard.push(new Array(1e6).fill(hoard.length))";
+    match run(builder, code).await {
+        Err(err) => match runner_error(&err) {
+            Some(RunnerError::HeapLimitExceeded(bytes)) => {
+                Ok(format!("stopped at the {} bytes heap limit", bytes))
+            }
+            Some(RunnerError::Timeout(limit)) => Ok(format!("timed out after {:?}", limit)),
+            _ => Err(format!(
+                "failed with {} instead of hitting the heap limit",
+                err
+            )),
+        },
+        Ok(_) => Err("an endless allocation returned".to_string()),
+    }
+}
+
+/// Calls of the op `name` with arguments it doesn't expect.
+async fn hostile_op_calls(builder: &Builder, name: &str) -> Contained {
+    let code = format!(
+        r#"
+        (async () => {{
+        const op = globalThis[{:?}]
+        const calls = [
+            [],
+            [undefined, null],
+            ['x'.repeat(1 << 20)],
+            [-1, NaN, Infinity, -0],
+            [{{ __proto__: null, toString: null, valueOf: null }}],
+            [[[[[[[]]]]]]],
+            [new Uint8Array(16), Symbol('probe')],
+        ]
+        let failed = 0
+        for (const args of calls) {{
+            try {{
+                await op(...args)
+            }} catch {{
+                failed++
+            }}
+        }}
+        return `${{calls.length - failed}} of ${{calls.length}} calls returned`
+        }})()
+        "#,
+        name
+    );
+
+    match run(builder, &code).await {
+        Ok(detail) => Ok(detail),
+        Err(err) => match runner_error(&err) {
+            Some(RunnerError::Timeout(limit)) => {
+                Err(format!("calls hung until the {:?} timeout", limit))
+            }
+            _ => Err(format!("calls failed the run: {}", err)),
+        },
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{DenoRunner, VarName};
+use anyhow::Result;
+use deno_core::{futures::future::poll_fn, serde_json};
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Wake, Waker},
+    time::{Duration, Instant},
+};
+
+type RunFuture = Pin<Box<dyn Future<Output = (DenoRunner, Result<String>)>>>;
+
+/// Handle of a session added to a [`Scheduler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SessionId(usize);
+
+/// How a session was served by a [`Scheduler`], see [`Scheduler::metrics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionMetrics {
+    /// Time slices the session was given
+    pub slices: u64,
+    /// Time spent running the session's scripts
+    pub busy: Duration,
+    /// Longest time the session was ready to continue but waited for
+    /// other sessions
+    pub max_wait: Duration,
+    /// Slices that ran past the slice length, because the script ran that
+    /// long without awaiting anything
+    pub overruns: u64,
+}
+
+/// Many long-lived runners ("sessions") multiplexed on the calling task.
+///
+/// Each session runs its submitted scripts one after another. Sessions
+/// waiting on async ops don't hold anything up: the scheduler rotates over
+/// the sessions that can make progress and polls each one's event loop for
+/// at most `slice` before moving to the next. A script running
+/// synchronously can't be interrupted mid-slice, it shows up in
+/// [`SessionMetrics::overruns`].
+///
+/// ```
+/// use deno_runner::{Builder, Scheduler};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut scheduler = Scheduler::new(Duration::from_millis(5));
+/// let alice = scheduler.add_session(Builder::new().build());
+/// let bob = scheduler.add_session(Builder::new().build());
+///
+/// scheduler.submit(alice, "globalThis.total = 1; total");
+/// scheduler.submit(bob, "40 + 2");
+/// scheduler.submit(alice, "total + 1");
+///
+/// let finished = scheduler.run_until_idle().await;
+/// assert_eq!(finished.len(), 3);
+/// assert!(scheduler.metrics(alice).unwrap().slices >= 2);
+/// # }
+/// ```
+pub struct Scheduler {
+    slice: Duration,
+    sessions: Vec<Option<Session>>,
+    next: usize,
+}
+
+impl Scheduler {
+    pub fn new(slice: Duration) -> Self {
+        Self {
+            slice,
+            sessions: vec![],
+            next: 0,
+        }
+    }
+
+    pub fn add_session(&mut self, runner: DenoRunner) -> SessionId {
+        self.sessions.push(Some(Session {
+            runner: Some(runner),
+            queue: VecDeque::new(),
+            running: None,
+            waker: Arc::new(SessionWaker::default()),
+            metrics: SessionMetrics::default(),
+        }));
+        SessionId(self.sessions.len() - 1)
+    }
+
+    /// Take the runner of a session back. `None` if the session is unknown
+    /// or still has scripts to run, in which case it is kept.
+    pub fn remove_session(&mut self, id: SessionId) -> Option<DenoRunner> {
+        let slot = self.sessions.get_mut(id.0)?;
+        match slot {
+            Some(session) if session.running.is_none() && session.queue.is_empty() => {
+                slot.take().and_then(|session| session.runner)
+            }
+            _ => None,
+        }
+    }
+
+    /// Queue `code` to run on the session's runner after the scripts
+    /// submitted before it. Panics if the session was removed.
+    pub fn submit(&mut self, id: SessionId, code: impl ToString) {
+        self.session(id).queue.push_back(Job::Run(code.to_string()));
+    }
+
+    /// Set the global `name` of the session's runner to `value` once the
+    /// scripts submitted before it ran, for feeding new data to a live
+    /// session. Unlike variables passed to a run, it stays set for the
+    /// later scripts. Fails for a name that isn't a valid identifier or a
+    /// value that doesn't serialize to JSON. Panics if the session was
+    /// removed.
+    pub fn bind<T: Serialize + ?Sized>(
+        &mut self,
+        id: SessionId,
+        name: &str,
+        value: &T,
+    ) -> Result<()> {
+        let name = VarName::parse(name)?.as_str().to_string();
+        let value = serde_json::to_value(value)?;
+        self.session(id).queue.push_back(Job::Bind(name, value));
+        Ok(())
+    }
+
+    pub fn metrics(&self, id: SessionId) -> Option<SessionMetrics> {
+        let session = self.sessions.get(id.0)?.as_ref()?;
+        Some(session.metrics)
+    }
+
+    /// Whether no session has scripts left to run.
+    pub fn is_idle(&self) -> bool {
+        self.sessions
+            .iter()
+            .flatten()
+            .all(|session| session.running.is_none() && session.queue.is_empty())
+    }
+
+    /// Run every submitted script, returning each result with its session
+    /// in the order they finished. A [`bind`](Self::bind) that failed is
+    /// returned as an error in its place.
+    pub async fn run_until_idle(&mut self) -> Vec<(SessionId, Result<String>)> {
+        let mut finished = vec![];
+        poll_fn(|cx| self.turn(cx, &mut finished)).await;
+        finished
+    }
+
+    /// Give every session that can make progress one slice, starting one
+    /// session further each turn so none is always served last.
+    fn turn(
+        &mut self,
+        cx: &mut Context<'_>,
+        finished: &mut Vec<(SessionId, Result<String>)>,
+    ) -> Poll<()> {
+        let count = self.sessions.len();
+        for offset in 0..count {
+            let index = (self.next + offset) % count;
+            if let Some(session) = &mut self.sessions[index] {
+                *session.waker.scheduler.lock().unwrap() = Some(cx.waker().clone());
+                if let Some(result) = session.serve(self.slice) {
+                    finished.push((SessionId(index), result));
+                }
+            }
+        }
+        self.next = (self.next + 1) % count.max(1);
+
+        if self.is_idle() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn session(&mut self, id: SessionId) -> &mut Session {
+        self.sessions
+            .get_mut(id.0)
+            .and_then(Option::as_mut)
+            .expect("unknown session")
+    }
+}
+
+/// Work submitted to a session, in order.
+enum Job {
+    Run(String),
+    Bind(String, serde_json::Value),
+}
+
+struct Session {
+    /// `None` while a script runs, the run future owns the runner then
+    runner: Option<DenoRunner>,
+    queue: VecDeque<Job>,
+    running: Option<RunFuture>,
+    waker: Arc<SessionWaker>,
+    metrics: SessionMetrics,
+}
+
+impl Session {
+    /// Poll the running script until it finishes, has to wait, or used up
+    /// `slice`. Returns the result of a finished script.
+    fn serve(&mut self, slice: Duration) -> Option<Result<String>> {
+        if self.running.is_none() {
+            let code = loop {
+                match self.queue.pop_front()? {
+                    Job::Run(code) => break code,
+                    Job::Bind(name, value) => {
+                        let runner = self.runner.as_mut().expect("idle session has a runner");
+                        if let Err(err) = runner.set_global(&name, &value) {
+                            if !self.queue.is_empty() {
+                                self.waker.wake_by_ref();
+                            }
+                            return Some(Err(err));
+                        }
+                    }
+                }
+            };
+            let mut runner = self.runner.take().expect("idle session has a runner");
+            self.running = Some(Box::pin(async move {
+                let result = runner.run::<_, String, String>(code, None).await;
+                (runner, result)
+            }));
+            self.waker.wake_by_ref();
+        }
+
+        let woken_at = self.waker.woken_at.lock().unwrap().take()?;
+        let started = Instant::now();
+        self.metrics.slices = self.metrics.slices.saturating_add(1);
+        self.metrics.max_wait = self
+            .metrics
+            .max_wait
+            .max(started.saturating_duration_since(woken_at));
+
+        let waker = Waker::from(self.waker.clone());
+        let mut cx = Context::from_waker(&waker);
+        let running = self.running.as_mut().unwrap();
+        let result = loop {
+            if let Poll::Ready((runner, result)) = running.as_mut().poll(&mut cx) {
+                break Some((runner, result));
+            }
+            // Keep going while the script is ready again and the slice lasts
+            if started.elapsed() >= slice || self.waker.woken_at.lock().unwrap().take().is_none() {
+                break None;
+            }
+        };
+
+        let elapsed = started.elapsed();
+        self.metrics.busy = self.metrics.busy.saturating_add(elapsed);
+        if elapsed > slice {
+            self.metrics.overruns = self.metrics.overruns.saturating_add(1);
+        }
+
+        let (runner, result) = result?;
+        self.running = None;
+        self.runner = Some(runner);
+        if !self.queue.is_empty() {
+            self.waker.wake_by_ref();
+        }
+        Some(result)
+    }
+}
+
+/// Marks its session ready to continue and wakes the task running the
+/// [`Scheduler`].
+#[derive(Default)]
+struct SessionWaker {
+    woken_at: Mutex<Option<Instant>>,
+    scheduler: Mutex<Option<Waker>>,
+}
+
+impl Wake for SessionWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken_at
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Instant::now);
+        if let Some(waker) = &*self.scheduler.lock().unwrap() {
+            waker.wake_by_ref();
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use deno_core::serde_json::{self, Value};
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Metadata, ObjectValidation, RootSchema, Schema, SchemaObject},
+    JsonSchema,
+};
+use std::collections::BTreeMap;
+
+/// JSON schemas of the variables declared with
+/// [`Builder::declare_binding`](crate::Builder::declare_binding).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BindingSchemas {
+    generator: SchemaGenerator,
+    properties: BTreeMap<String, Schema>,
+}
+
+impl BindingSchemas {
+    pub(crate) fn add<T: JsonSchema>(&mut self, name: String) {
+        let schema = self.generator.subschema_for::<T>();
+        self.properties.insert(name, schema);
+    }
+
+    /// A single object schema with one property per binding, with shared
+    /// type definitions hoisted into `definitions`.
+    pub(crate) fn document(&self) -> Value {
+        let root = RootSchema {
+            meta_schema: self.generator.settings().meta_schema.clone(),
+            schema: SchemaObject {
+                metadata: Some(Box::new(Metadata {
+                    title: Some("Script bindings".to_string()),
+                    description: Some("Variables available to the script".to_string()),
+                    ..Default::default()
+                })),
+                instance_type: Some(InstanceType::Object.into()),
+                object: Some(Box::new(ObjectValidation {
+                    required: self.properties.keys().cloned().collect(),
+                    properties: self.properties.clone().into_iter().collect(),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            },
+            definitions: self.generator.definitions().clone(),
+        };
+
+        serde_json::to_value(root).unwrap()
+    }
+}
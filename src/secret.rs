@@ -0,0 +1,119 @@
+use crate::Codec;
+use anyhow::anyhow;
+use deno_core::serde_json::Value;
+use std::fmt;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Value of a binding set with [`RunOptions::secret`](crate::RunOptions::secret),
+/// never shown by `Debug`.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Secret(pub(crate) String);
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// Replaces every secret value in what a run hands back to the host.
+pub(crate) struct Redactor<'a>(Vec<&'a str>);
+
+impl<'a> Redactor<'a> {
+    pub(crate) fn new(secrets: impl Iterator<Item = &'a Secret>) -> Self {
+        let mut values: Vec<_> = secrets
+            .map(|secret| secret.0.as_str())
+            .filter(|value| !value.is_empty())
+            .collect();
+        // Longest first, so a secret containing another is fully replaced
+        values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+        Self(values)
+    }
+
+    pub(crate) fn text(&self, text: String) -> String {
+        self.0.iter().fold(text, |text, secret| {
+            if text.contains(secret) {
+                text.replace(secret, REDACTED)
+            } else {
+                text
+            }
+        })
+    }
+
+    /// Redact the string values and keys of a JSON text.
+    pub(crate) fn json(&self, json: String) -> String {
+        match deno_core::serde_json::from_str(&json) {
+            Ok(value) => self.value(value).to_string(),
+            Err(_) => self.text(json),
+        }
+    }
+
+    pub(crate) fn value(&self, value: Value) -> Value {
+        match value {
+            Value::String(text) => Value::String(self.text(text)),
+            Value::Array(items) => Value::Array(items.into_iter().map(|v| self.value(v)).collect()),
+            Value::Object(entries) => Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, v)| (self.text(key), self.value(v)))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    /// Redact a result encoded with `codec`. Only JSON text can be
+    /// rewritten, other encodings containing a secret are refused.
+    pub(crate) fn encoded(&self, codec: Codec, bytes: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        let contains = |secret: &&str| {
+            bytes
+                .windows(secret.len())
+                .any(|window| window == secret.as_bytes())
+        };
+        if !self.0.iter().any(contains) {
+            return Ok(bytes);
+        }
+
+        match codec {
+            Codec::Json => Ok(self.json(String::from_utf8(bytes)?).into_bytes()),
+            #[cfg(feature = "msgpack")]
+            Codec::MsgPack => Err(anyhow!("The msgpack result contains a secret value")),
+        }
+    }
+
+    /// The error itself if it doesn't mention a secret, otherwise a plain
+    /// error with the redacted message chain.
+    pub(crate) fn error(&self, err: anyhow::Error) -> anyhow::Error {
+        let message = format!("{:#}", err);
+        if self.0.iter().any(|secret| message.contains(secret)) {
+            anyhow!(self.text(message))
+        } else {
+            err
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deno_core::serde_json::json;
+
+    #[test]
+    fn test_redact() {
+        let secrets = [
+            Secret("sk_live".to_string()),
+            Secret("sk_live_123".to_string()),
+        ];
+        let redactor = Redactor::new(secrets.iter());
+
+        assert_eq!(
+            redactor.text("key sk_live_123 and sk_live".to_string()),
+            "key [REDACTED] and [REDACTED]"
+        );
+        assert_eq!(
+            redactor.value(json!({ "auth": ["Bearer sk_live_123", 1] })),
+            json!({ "auth": ["Bearer [REDACTED]", 1] })
+        );
+        assert_eq!(format!("{:?}", secrets[0]), "[REDACTED]");
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::{bail, Context, Result};
+use deno_core::serde_json::{self, Value};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path, sync::Arc};
+
+const MAGIC: &[u8; 8] = b"DRSESS01";
+
+/// Saved state of a runner built with [`Builder::persistent`](crate::Builder::persistent),
+/// taken with [`DenoRunner::suspend`](crate::DenoRunner::suspend) and
+/// restored with [`Builder::resume`](crate::Builder::resume).
+///
+/// Holds a V8 snapshot of the runner's heap, so globals, functions and
+/// anything else scripts left behind come back, along with the ops and run
+/// count the runner needs to continue. Snapshots only load with the same
+/// version of this crate that saved them.
+#[derive(Clone)]
+pub struct SessionSnapshot {
+    pub(crate) header: Header,
+    pub(crate) bytes: Arc<[u8]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Header {
+    version: String,
+    pub(crate) ops: Vec<String>,
+    pub(crate) runs: usize,
+}
+
+impl SessionSnapshot {
+    pub(crate) fn new(bytes: &[u8], ops: Vec<String>, runs: usize) -> Self {
+        Self {
+            header: Header {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                ops,
+                runs,
+            },
+            bytes: Arc::from(bytes),
+        }
+    }
+
+    /// Size of the heap snapshot in bytes.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Scripts the runner ran before it was suspended.
+    pub fn runs(&self) -> usize {
+        self.header.runs
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let header = serde_json::to_vec(&self.header).unwrap();
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 4 + header.len() + self.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&header);
+        bytes.extend_from_slice(&self.bytes);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let rest = match bytes.strip_prefix(MAGIC.as_slice()) {
+            Some(rest) if rest.len() >= 4 => rest,
+            _ => bail!("Not a deno_runner session snapshot"),
+        };
+        let (len, rest) = rest.split_at(4);
+        let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+        if rest.len() < len {
+            bail!("Session snapshot is truncated");
+        }
+        let (header, snapshot) = rest.split_at(len);
+        let header: Header =
+            serde_json::from_slice(header).context("Invalid session snapshot header")?;
+
+        if header.version != env!("CARGO_PKG_VERSION") {
+            bail!(
+                "Session snapshot was saved by deno_runner {}, this is {}",
+                header.version,
+                env!("CARGO_PKG_VERSION")
+            );
+        }
+
+        Ok(Self {
+            header,
+            bytes: Arc::from(snapshot),
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, self.to_bytes())
+            .with_context(|| format!("Failed to save session to {}", path.display()))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)
+            .with_context(|| format!("Failed to load session from {}", path.display()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub(crate) fn to_deno(&self) -> deno_core::Snapshot {
+        deno_core::Snapshot::Boxed(self.bytes.to_vec().into_boxed_slice())
+    }
+}
+
+impl std::fmt::Debug for SessionSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionSnapshot")
+            .field("len", &self.len())
+            .field("ops", &self.header.ops)
+            .field("runs", &self.header.runs)
+            .finish()
+    }
+}
+
+/// Globals scripts set on a runner, taken with
+/// [`DenoRunner::checkpoint`](crate::DenoRunner::checkpoint) and put back
+/// with [`DenoRunner::restore`](crate::DenoRunner::restore).
+///
+/// Unlike a [`SessionSnapshot`] it only holds JSON values, so it is cheap
+/// to take after every action, and can be stored or sent anywhere.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Checkpoint {
+    globals: BTreeMap<String, Value>,
+}
+
+impl Checkpoint {
+    /// From the JSON text of each global.
+    pub(crate) fn new(globals: BTreeMap<String, String>) -> Result<Self> {
+        let globals = globals
+            .into_iter()
+            .map(|(name, json)| Ok((name, serde_json::from_str(&json)?)))
+            .collect::<Result<_>>()?;
+        Ok(Self { globals })
+    }
+
+    /// Value of each saved global.
+    pub fn globals(&self) -> &BTreeMap<String, Value> {
+        &self.globals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let session = SessionSnapshot::new(b"heap", vec!["add".to_string()], 3);
+        let restored = SessionSnapshot::from_bytes(&session.to_bytes()).unwrap();
+
+        assert_eq!(restored.header, session.header);
+        assert_eq!(&*restored.bytes, b"heap");
+    }
+
+    #[test]
+    fn test_invalid_bytes() {
+        assert!(SessionSnapshot::from_bytes(b"nope").is_err());
+
+        let mut bytes = SessionSnapshot::new(b"heap", vec![], 0).to_bytes();
+        bytes.truncate(14);
+        let err = SessionSnapshot::from_bytes(&bytes).unwrap_err();
+        assert_eq!(err.to_string(), "Session snapshot is truncated");
+    }
+}
@@ -0,0 +1,61 @@
+use deno_core::v8;
+use std::collections::BTreeMap;
+
+/// Rust-owned bytes exposed to scripts as a `SharedArrayBuffer`, see
+/// [`Builder::shared_buffer`](crate::Builder::shared_buffer).
+///
+/// Clones point at the same memory, so one lookup table can be handed to
+/// every runner (and thread) instead of copying it into each isolate. The
+/// memory is freed when the last clone and the last isolate using it are
+/// dropped.
+///
+/// # Safety constraints
+///
+/// Scripts can write to the buffer at any time and from several isolates at
+/// once. Plain reads and writes of the same bytes from different runners are
+/// a data race in the JS memory model, so scripts sharing writable data must
+/// go through `Atomics`. On the Rust side [`to_vec`](Self::to_vec) only gives
+/// a snapshot; treat the buffer as read-only once runners are using it, or
+/// coordinate writers yourself.
+#[derive(Clone)]
+pub struct SharedBuffer(v8::SharedRef<v8::BackingStore>);
+
+impl SharedBuffer {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        let store =
+            v8::SharedArrayBuffer::new_backing_store_from_boxed_slice(bytes.into_boxed_slice());
+        Self(store.make_shared())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.byte_length()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copy of the current contents.
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.0.iter().map(|byte| byte.get()).collect()
+    }
+}
+
+impl std::fmt::Debug for SharedBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedBuffer")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Define each buffer as a global `SharedArrayBuffer` with its name.
+pub(crate) fn bind(scope: &mut v8::HandleScope, buffers: &BTreeMap<String, SharedBuffer>) {
+    let global = scope.get_current_context().global(scope);
+
+    for (name, buffer) in buffers {
+        let value = v8::SharedArrayBuffer::with_backing_store(scope, &buffer.0);
+        let key = v8::String::new(scope, name).unwrap();
+        global.set(scope, key.into(), value.into());
+    }
+}
@@ -0,0 +1,106 @@
+use crate::Builder;
+use anyhow::{anyhow, Result};
+use deno_core::{JsRuntime, RuntimeOptions};
+use std::sync::Arc;
+
+/// V8 startup snapshot of a runtime with `runtime.js` evaluated and the
+/// builder's extensions registered, see [`Builder::snapshot`].
+///
+/// Runners built with [`Builder::from_snapshot`] deserialize it instead of
+/// initializing the runtime from scratch. Clones share the same bytes.
+#[derive(Clone)]
+pub struct Snapshot {
+    pub(crate) startup: StartupSnapshot,
+    pub(crate) config: Builder,
+    /// Scripts evaluated on top of the builder's snapshot with
+    /// [`layer`](Self::layer)
+    pub(crate) layers: usize,
+}
+
+impl Snapshot {
+    /// Size of the snapshot in bytes.
+    pub fn len(&self) -> usize {
+        self.startup.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Snapshot of this one with `code` evaluated on top, e.g. a tenant's
+    /// helper library over a base snapshot shared by every tenant. Runners
+    /// built from it start with the globals `code` defined, without
+    /// evaluating it again like a [`warmup`](Builder::warmup) script.
+    ///
+    /// ```
+    /// use deno_runner::Builder;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let base = Builder::new().snapshot();
+    /// let tenant = base
+    ///     .layer("function discount(price) { return price * 0.9 }")
+    ///     .unwrap();
+    ///
+    /// let mut runner = Builder::from_snapshot(&tenant).build();
+    /// let result = runner
+    ///     .run::<_, String, String>("discount(100)", None)
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(result, "90");
+    /// # }
+    /// ```
+    ///
+    /// Fails when `code` throws.
+    pub fn layer(&self, code: impl ToString) -> Result<Snapshot> {
+        let mut runtime = JsRuntime::new(RuntimeOptions {
+            extensions: self.config.extensions(),
+            startup_snapshot: Some(self.startup.to_deno()),
+            will_snapshot: true,
+            ..Default::default()
+        });
+        runtime
+            .execute_script("[runner:layer]", &code.to_string())
+            .map_err(|err| anyhow!("snapshot layer failed: {}", err))?;
+
+        let bytes = runtime.snapshot();
+        Ok(Snapshot {
+            startup: StartupSnapshot {
+                bytes: Arc::from(&*bytes),
+                ops: self.startup.ops.clone(),
+            },
+            config: self.config.clone(),
+            layers: self.layers + 1,
+        })
+    }
+
+    /// Scripts evaluated on top of the builder's snapshot, see
+    /// [`layer`](Self::layer).
+    pub fn layers(&self) -> usize {
+        self.layers
+    }
+}
+
+impl std::fmt::Debug for Snapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("len", &self.len())
+            .field("ops", &self.startup.ops)
+            .field("layers", &self.layers)
+            .finish()
+    }
+}
+
+/// Snapshot bytes and the ops they were taken with, which a runtime
+/// loading them must register again in the same order.
+#[derive(Clone)]
+pub(crate) struct StartupSnapshot {
+    pub(crate) bytes: Arc<[u8]>,
+    pub(crate) ops: Vec<&'static str>,
+}
+
+impl StartupSnapshot {
+    pub(crate) fn to_deno(&self) -> deno_core::Snapshot {
+        deno_core::Snapshot::Boxed(self.bytes.to_vec().into_boxed_slice())
+    }
+}
@@ -0,0 +1,119 @@
+use anyhow::{anyhow, Result};
+use deno_core::{
+    futures::stream::{LocalBoxStream, Stream, StreamExt},
+    op,
+    serde_json::{self, Value},
+    AsyncRefCell, OpDecl, OpState, RcRef, Resource, ResourceId,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{borrow::Cow, cell::RefCell, collections::HashMap, rc::Rc};
+
+type HostStream = LocalBoxStream<'static, Result<Value>>;
+type StreamFactory = Rc<dyn Fn(Value) -> Result<HostStream>>;
+
+/// Streams registered with [`Builder::add_stream`](crate::Builder::add_stream), by name.
+#[derive(Clone, Default)]
+pub(crate) struct StreamFactories(HashMap<String, StreamFactory>);
+
+impl StreamFactories {
+    pub(crate) fn insert<F, A, S, T>(&mut self, name: String, factory: F)
+    where
+        F: Fn(A) -> S + 'static,
+        A: DeserializeOwned,
+        S: Stream<Item = Result<T>> + 'static,
+        T: Serialize,
+    {
+        let factory: StreamFactory = Rc::new(move |args| {
+            let args = serde_json::from_value(args)?;
+            let stream = factory(args).map(|item| Ok(serde_json::to_value(item?)?));
+            Ok(stream.boxed_local())
+        });
+
+        self.0.insert(name, factory);
+    }
+
+    pub(crate) fn names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.0.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    pub(crate) fn retain(&mut self, keep: impl Fn(&str) -> bool) {
+        self.0.retain(|name, _| keep(name));
+    }
+}
+
+struct StreamResource(AsyncRefCell<HostStream>);
+
+impl Resource for StreamResource {
+    fn name(&self) -> Cow<str> {
+        "hostStream".into()
+    }
+}
+
+/// Wrapper so a `null` item can be told apart from the end of the stream.
+#[derive(Serialize)]
+struct StreamChunk {
+    value: Value,
+}
+
+pub(crate) fn decls() -> Vec<OpDecl> {
+    vec![
+        op_stream_open::decl(),
+        op_stream_next::decl(),
+        op_stream_close::decl(),
+    ]
+}
+
+#[op]
+fn op_stream_open(state: &mut OpState, name: String, args: Value) -> Result<ResourceId> {
+    let factory = state
+        .borrow::<StreamFactories>()
+        .0
+        .get(&name)
+        .cloned()
+        .ok_or_else(|| anyhow!("Stream '{}' is not registered with this runner", name))?;
+
+    let stream = factory(args)?;
+
+    Ok(state
+        .resource_table
+        .add(StreamResource(AsyncRefCell::new(stream))))
+}
+
+#[op]
+async fn op_stream_next(
+    state: Rc<RefCell<OpState>>,
+    rid: ResourceId,
+) -> Result<Option<StreamChunk>> {
+    let resource = state.borrow().resource_table.get::<StreamResource>(rid)?;
+
+    let item = {
+        let mut stream = RcRef::map(&resource, |r| &r.0).borrow_mut().await;
+        stream.next().await
+    };
+
+    match item {
+        Some(Ok(value)) => Ok(Some(StreamChunk { value })),
+        // The iteration ends with the error, nothing calls `op_stream_close`
+        Some(Err(err)) => {
+            let _ = state.borrow_mut().resource_table.close(rid);
+            Err(err)
+        }
+        None => {
+            state.borrow_mut().resource_table.close(rid)?;
+            Ok(None)
+        }
+    }
+}
+
+#[op]
+fn op_stream_close(state: &mut OpState, rid: ResourceId) -> Result<()> {
+    // Already closed when the stream was drained
+    let _ = state.resource_table.close(rid);
+    Ok(())
+}
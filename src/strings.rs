@@ -0,0 +1,94 @@
+use crate::hooks::HookCall;
+use deno_core::serde_json;
+use std::{collections::HashMap, sync::Arc};
+
+/// Strings that ops and scripts pass around by index instead of by value,
+/// see [`Builder::string_table`](crate::Builder::string_table).
+///
+/// For enum-like values an op returns or takes over and over (statuses,
+/// kinds, currency codes), sending a `u32` avoids allocating a new string on
+/// every call on both sides. Ops read the table from their `OpState`:
+///
+/// ```no_run
+/// use deno_runner::{op, OpState, StringTable};
+///
+/// #[op]
+/// fn next_status(state: &mut OpState, status: u32) -> u32 {
+///     let strings = state.borrow::<StringTable>();
+///     match strings.get(status) {
+///         Some("pending") => strings.id("active").unwrap(),
+///         _ => strings.id("done").unwrap(),
+///     }
+/// }
+/// ```
+///
+/// and scripts convert with `strings.id("active")` and `strings.get(id)`.
+#[derive(Debug, Clone, Default)]
+pub struct StringTable {
+    strings: Arc<[String]>,
+    ids: Arc<HashMap<String, u32>>,
+}
+
+impl StringTable {
+    /// Table of `strings`, ids are their positions. Duplicates keep the
+    /// id of their first occurrence.
+    pub fn new<I, S>(strings: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let strings: Vec<String> = strings.into_iter().map(Into::into).collect();
+        let mut ids = HashMap::with_capacity(strings.len());
+        for (id, string) in strings.iter().enumerate() {
+            ids.entry(string.clone()).or_insert(id as u32);
+        }
+
+        Self {
+            strings: strings.into(),
+            ids: Arc::new(ids),
+        }
+    }
+
+    pub fn id(&self, string: &str) -> Option<u32> {
+        self.ids.get(string).copied()
+    }
+
+    pub fn get(&self, id: u32) -> Option<&str> {
+        self.strings.get(id as usize).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    pub(crate) fn init_call(&self) -> Option<HookCall> {
+        if self.is_empty() {
+            return None;
+        }
+
+        Some(HookCall::new(
+            "setStringTable",
+            serde_json::to_string(&self.strings).unwrap(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let table = StringTable::new(["pending", "active", "pending", "done"]);
+
+        assert_eq!(table.id("active"), Some(1));
+        assert_eq!(table.id("pending"), Some(0));
+        assert_eq!(table.id("unknown"), None);
+        assert_eq!(table.get(3), Some("done"));
+        assert_eq!(table.get(4), None);
+    }
+}
@@ -0,0 +1,124 @@
+use std::{collections::BTreeMap, time::Duration};
+
+/// Receives run lifecycle events, set with
+/// [`Builder::telemetry`](crate::Builder::telemetry) to feed execution
+/// telemetry into logs, metrics or traces. Every method defaults to doing
+/// nothing.
+pub trait TelemetryExporter {
+    fn run_started(&self, _run: &RunInfo) {}
+
+    fn run_finished(&self, _run: &RunInfo, _stats: &RunStats) {}
+
+    fn run_failed(&self, _run: &RunInfo, _stats: &RunStats, _error: &anyhow::Error) {}
+}
+
+/// Which run an event is about.
+#[derive(Debug, Clone, Copy)]
+pub struct RunInfo<'a> {
+    pub run_id: &'a str,
+    /// Tags set with [`RunOptions::tag`](crate::RunOptions::tag)
+    pub tags: &'a BTreeMap<String, String>,
+}
+
+/// Measurements of a finished or failed run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunStats {
+    /// Wall time from start of the run to its result or error
+    pub duration: Duration,
+    /// Code passed to `exit(code)`, if the script called it
+    pub exit_code: Option<i32>,
+    /// What the run started from, to attribute its duration
+    pub startup_kind: StartupKind,
+}
+
+/// What a run started from, cheapest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StartupKind {
+    /// The result came from the [`MemoCache`](crate::MemoCache), the
+    /// script didn't run
+    Cached,
+    /// The runner ran scripts before, or was resumed from a session
+    Warm,
+    /// First run on a runner built with
+    /// [`Builder::from_snapshot`](crate::Builder::from_snapshot)
+    Snapshot,
+    /// First run on a runner that evaluated its prelude and warmup scripts
+    /// when built
+    Cold,
+}
+
+/// Drops every event, the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopExporter;
+
+impl TelemetryExporter for NoopExporter {}
+
+/// Writes events with the `log` crate: `debug` when a run starts, `info`
+/// when it finishes and `warn` when it fails.
+#[cfg(feature = "log")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogExporter;
+
+#[cfg(feature = "log")]
+impl TelemetryExporter for LogExporter {
+    fn run_started(&self, run: &RunInfo) {
+        log::debug!("run {} started {:?}", run.run_id, run.tags);
+    }
+
+    fn run_finished(&self, run: &RunInfo, stats: &RunStats) {
+        log::info!(
+            "run {} finished in {:?} ({:?} start, exit code {:?}) {:?}",
+            run.run_id,
+            stats.duration,
+            stats.startup_kind,
+            stats.exit_code,
+            run.tags
+        );
+    }
+
+    fn run_failed(&self, run: &RunInfo, stats: &RunStats, error: &anyhow::Error) {
+        log::warn!(
+            "run {} failed after {:?} ({:?} start): {:#} {:?}",
+            run.run_id,
+            stats.duration,
+            stats.startup_kind,
+            error,
+            run.tags
+        );
+    }
+}
+
+/// Emits events with the `tracing` crate, with the run id, tags and stats
+/// as fields.
+#[cfg(feature = "tracing")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingExporter;
+
+#[cfg(feature = "tracing")]
+impl TelemetryExporter for TracingExporter {
+    fn run_started(&self, run: &RunInfo) {
+        tracing::debug!(run_id = run.run_id, tags = ?run.tags, "run started");
+    }
+
+    fn run_finished(&self, run: &RunInfo, stats: &RunStats) {
+        tracing::info!(
+            run_id = run.run_id,
+            tags = ?run.tags,
+            duration_ms = u64::try_from(stats.duration.as_millis()).unwrap_or(u64::MAX),
+            exit_code = ?stats.exit_code,
+            startup_kind = ?stats.startup_kind,
+            "run finished"
+        );
+    }
+
+    fn run_failed(&self, run: &RunInfo, stats: &RunStats, error: &anyhow::Error) {
+        tracing::warn!(
+            run_id = run.run_id,
+            tags = ?run.tags,
+            duration_ms = u64::try_from(stats.duration.as_millis()).unwrap_or(u64::MAX),
+            startup_kind = ?stats.startup_kind,
+            error = %format!("{:#}", error),
+            "run failed"
+        );
+    }
+}
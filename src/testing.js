@@ -0,0 +1,25 @@
+;((hooks) => {
+  hooks.initTesting = ({ now, seed }) => {
+    // Virtual clock, time stands still at `now` for the whole run
+    const RealDate = Date
+    function VirtualDate(...args) {
+      if (new.target === undefined) return new RealDate(now).toString()
+      return args.length === 0 ? new RealDate(now) : new RealDate(...args)
+    }
+    VirtualDate.prototype = RealDate.prototype
+    VirtualDate.now = () => now
+    VirtualDate.parse = RealDate.parse
+    VirtualDate.UTC = RealDate.UTC
+    globalThis.Date = VirtualDate
+
+    // Seeded `Math.random` (mulberry32), same sequence for the same seed
+    let state = seed >>> 0
+    Math.random = () => {
+      state = (state + 0x6d2b79f5) >>> 0
+      let t = state
+      t = Math.imul(t ^ (t >>> 15), t | 1)
+      t ^= t + Math.imul(t ^ (t >>> 7), t | 61)
+      return ((t ^ (t >>> 14)) >>> 0) / 4294967296
+    }
+  }
+})
@@ -0,0 +1,221 @@
+//! Deterministic harness for testing scripts from downstream crates.
+//!
+//! ```no_run
+//! use deno_runner::testing::TestRunner;
+//! use std::collections::HashMap;
+//!
+//! # async fn example() -> deno_runner::anyhow::Result<()> {
+//! let outcome = TestRunner::new()
+//!     .now(1_700_000_000_000)
+//!     .seed(42)
+//!     .run("console.log(Date.now()); Math.random() < 1", None::<HashMap<String, String>>)
+//!     .await?;
+//!
+//! outcome
+//!     .assert_result("true")
+//!     .assert_stdout(&["1700000000000"])
+//!     .assert_no_stderr();
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    hooks::{self, HookCall},
+    secret::{Redactor, Secret},
+    Builder, RunOptions,
+};
+use anyhow::Result;
+use deno_core::serde_json;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Display};
+
+/// Runs scripts with a virtual clock, a seeded `Math.random` and captured
+/// console output, so the same script and inputs always give the same
+/// [`TestOutcome`].
+///
+/// Each [`run`](Self::run) gets a fresh runner built from the same
+/// [`Builder`], nothing leaks from one run to the next.
+#[derive(Clone)]
+pub struct TestRunner {
+    builder: Builder,
+    now: u64,
+    seed: u32,
+}
+
+impl TestRunner {
+    pub fn new() -> Self {
+        Self::with_builder(Builder::new())
+    }
+
+    /// Test scripts against a configured builder, e.g. one with ops.
+    pub fn with_builder(builder: Builder) -> Self {
+        Self {
+            builder,
+            // 2000-01-01T00:00:00Z
+            now: 946_684_800_000,
+            seed: 0,
+        }
+    }
+
+    /// Milliseconds since the Unix epoch returned by `Date.now()` and used
+    /// by `new Date()`; the clock doesn't move during a run.
+    pub fn now(mut self, millis: u64) -> Self {
+        self.now = millis;
+        self
+    }
+
+    /// Seed for `Math.random()`.
+    pub fn seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub async fn run<C, K, V>(
+        &self,
+        custom_code: C,
+        vars: Option<HashMap<K, V>>,
+    ) -> Result<TestOutcome>
+    where
+        C: ToString,
+        K: Display,
+        V: Display + std::fmt::Debug,
+    {
+        self.run_with_options(custom_code, vars, RunOptions::default())
+            .await
+    }
+
+    pub async fn run_with_options<C, K, V>(
+        &self,
+        custom_code: C,
+        vars: Option<HashMap<K, V>>,
+        options: RunOptions,
+    ) -> Result<TestOutcome>
+    where
+        C: ToString,
+        K: Display,
+        V: Display + std::fmt::Debug,
+    {
+        let mut runner = self.builder.clone().build();
+        hooks::load(
+            &mut runner.runtime,
+            "[deno:testing.js]",
+            include_str!("./testing.js"),
+        )?;
+        HookCall::new("captureConsole", "").run(&mut runner.runtime)?;
+        HookCall::new(
+            "initTesting",
+            serde_json::json!({ "now": self.now, "seed": self.seed }).to_string(),
+        )
+        .run(&mut runner.runtime)?;
+
+        // Console output is already captured for the whole run
+        let options = options.capture_console(false);
+        let secrets: Vec<Secret> = options.secrets.values().cloned().collect();
+        let report = runner
+            .run_report(&custom_code.to_string(), vars, options)
+            .await?;
+
+        let redactor = Redactor::new(secrets.iter());
+        let console = runner
+            .take_console()?
+            .iter()
+            .flat_map(|entry| {
+                let stream = entry.stream();
+                entry.lines().map(move |line| ConsoleLine {
+                    stream,
+                    line: redactor.text(line),
+                })
+            })
+            .collect();
+
+        Ok(TestOutcome {
+            result: report.result,
+            exit_code: report.exit_code,
+            console,
+        })
+    }
+}
+
+impl Default for TestRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A console line captured by [`TestRunner`], without the `[out]: ` or
+/// `[err]: ` prefix but with `console.group()` indentation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsoleLine {
+    pub stream: ConsoleStream,
+    pub line: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsoleStream {
+    Stdout,
+    Stderr,
+}
+
+/// Result and console output of a [`TestRunner`] run, with chainable
+/// assertions that panic with a readable message on mismatch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestOutcome {
+    pub result: String,
+    pub exit_code: Option<i32>,
+    pub console: Vec<ConsoleLine>,
+}
+
+impl TestOutcome {
+    /// Lines written with `console.log` (and `info`/`debug`).
+    pub fn stdout(&self) -> Vec<&str> {
+        self.lines(ConsoleStream::Stdout)
+    }
+
+    /// Lines written with `console.error` (and `warn`).
+    pub fn stderr(&self) -> Vec<&str> {
+        self.lines(ConsoleStream::Stderr)
+    }
+
+    pub fn assert_result(&self, expected: &str) -> &Self {
+        assert_eq!(self.result, expected, "unexpected script result");
+        self
+    }
+
+    pub fn assert_exit_code(&self, expected: Option<i32>) -> &Self {
+        assert_eq!(self.exit_code, expected, "unexpected exit code");
+        self
+    }
+
+    pub fn assert_stdout(&self, expected: &[&str]) -> &Self {
+        assert_eq!(self.stdout(), expected, "unexpected stdout");
+        self
+    }
+
+    pub fn assert_stdout_contains(&self, text: &str) -> &Self {
+        assert!(
+            self.stdout().iter().any(|line| line.contains(text)),
+            "stdout does not contain {:?}: {:#?}",
+            text,
+            self.stdout()
+        );
+        self
+    }
+
+    pub fn assert_no_stderr(&self) -> &Self {
+        assert!(
+            self.stderr().is_empty(),
+            "unexpected stderr: {:#?}",
+            self.stderr()
+        );
+        self
+    }
+
+    fn lines(&self, stream: ConsoleStream) -> Vec<&str> {
+        self.console
+            .iter()
+            .filter(|line| line.stream == stream)
+            .map(|line| line.line.as_str())
+            .collect()
+    }
+}
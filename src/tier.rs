@@ -0,0 +1,53 @@
+use anyhow::{bail, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set once the first runner is built, V8 ignores flag changes after that.
+static V8_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Which V8 execution tiers scripts may use, see [`set_execution_tier`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecutionTier {
+    /// Interpreter, baseline and optimizing compilers, V8's default
+    #[default]
+    Default,
+    /// Bytecode interpreter only (`--no-opt --no-sparkplug`), functions are
+    /// compiled lazily and never optimized. Uses less memory and has no
+    /// JIT warmup jitter, best for tiny rules that finish in microseconds.
+    InterpreterOnly,
+    /// No runtime code generation at all (`--jitless`), also disables
+    /// WebAssembly. For hosts where executable memory isn't allowed.
+    Jitless,
+}
+
+/// Choose the execution tier for every runner in the process.
+///
+/// V8 flags are process wide and read when the first isolate is created, so
+/// this must be called before the first [`Builder::build`](crate::Builder::build)
+/// and fails afterwards.
+pub fn set_execution_tier(tier: ExecutionTier) -> Result<()> {
+    if V8_STARTED.load(Ordering::SeqCst) {
+        bail!("The execution tier must be set before the first runner is built");
+    }
+
+    let flags: &[&str] = match tier {
+        ExecutionTier::Default => return Ok(()),
+        ExecutionTier::InterpreterOnly => &["--no-opt", "--no-sparkplug", "--lazy"],
+        ExecutionTier::Jitless => &["--jitless"],
+    };
+
+    // The first argument stands in for the program name, like in `argv`
+    let args = std::iter::once("")
+        .chain(flags.iter().copied())
+        .map(String::from)
+        .collect();
+    let unrecognized = deno_core::v8_set_flags(args);
+    if unrecognized.len() > 1 {
+        bail!("V8 did not recognize flags {:?}", &unrecognized[1..]);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn mark_started() {
+    V8_STARTED.store(true, Ordering::SeqCst);
+}
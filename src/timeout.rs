@@ -0,0 +1,48 @@
+use deno_core::v8;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// Thread terminating JS execution in an isolate once a time limit passes,
+/// unless stopped first.
+pub(crate) struct Watchdog {
+    stop: mpsc::Sender<()>,
+    fired: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl Watchdog {
+    pub(crate) fn start(isolate: v8::IsolateHandle, limit: Duration) -> Self {
+        let (stop, stopped) = mpsc::channel();
+        let fired = Arc::new(AtomicBool::new(false));
+
+        let thread = thread::spawn({
+            let fired = fired.clone();
+            move || {
+                if let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(limit) {
+                    fired.store(true, Ordering::SeqCst);
+                    isolate.terminate_execution();
+                }
+            }
+        });
+
+        Self {
+            stop,
+            fired,
+            thread,
+        }
+    }
+
+    /// Stop watching, returns whether execution was terminated.
+    pub(crate) fn stop(self) -> bool {
+        let _ = self.stop.send(());
+        let _ = self.thread.join();
+        self.fired.load(Ordering::SeqCst)
+    }
+}
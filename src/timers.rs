@@ -0,0 +1,51 @@
+use anyhow::Result;
+use deno_core::{op, CancelFuture, CancelHandle, OpDecl, OpState, RcRef, Resource, ResourceId};
+use std::{borrow::Cow, cell::RefCell, rc::Rc, time::Duration};
+
+/// A pending `setTimeout`/`setInterval`, closing it cancels the wait.
+struct TimerResource(CancelHandle);
+
+impl Resource for TimerResource {
+    fn name(&self) -> Cow<str> {
+        "timer".into()
+    }
+
+    fn close(self: Rc<Self>) {
+        self.0.cancel();
+    }
+}
+
+pub(crate) fn decls() -> Vec<OpDecl> {
+    vec![
+        op_timer_start::decl(),
+        op_timer_sleep::decl(),
+        op_timer_clear::decl(),
+    ]
+}
+
+#[op]
+fn op_timer_start(state: &mut OpState) -> ResourceId {
+    state.resource_table.add(TimerResource(CancelHandle::new()))
+}
+
+/// Wait `ms`, `false` if the timer was cleared first.
+#[op]
+async fn op_timer_sleep(state: Rc<RefCell<OpState>>, rid: ResourceId, ms: u64) -> Result<bool> {
+    let timer = match state.borrow().resource_table.get::<TimerResource>(rid) {
+        Ok(timer) => timer,
+        Err(_) => return Ok(false),
+    };
+
+    let cancel = RcRef::map(&timer, |r| &r.0);
+    let fired = crate::executor::sleep(Duration::from_millis(ms))
+        .or_cancel(cancel)
+        .await
+        .is_ok();
+    Ok(fired)
+}
+
+#[op]
+fn op_timer_clear(state: &mut OpState, rid: ResourceId) {
+    // Already closed when a timeout fired
+    let _ = state.resource_table.close(rid);
+}
@@ -0,0 +1,13 @@
+;((globalThis) => {
+  // deno_url only registers its classes on `__bootstrap`, expose them the
+  // way browsers do
+  const { url } = globalThis.__bootstrap
+  const globals = {
+    URL: url.URL,
+    URLSearchParams: url.URLSearchParams,
+  }
+
+  for (const [name, value] of Object.entries(globals)) {
+    Object.defineProperty(globalThis, name, { value, writable: true, enumerable: false, configurable: true })
+  }
+})(globalThis)
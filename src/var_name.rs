@@ -0,0 +1,199 @@
+use anyhow::{bail, Result};
+use std::{borrow::Cow, fmt};
+
+/// Words that parse as identifiers but can't be used as a `let` binding.
+const RESERVED: &[&str] = &[
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+/// A variable name known to be a valid JS identifier, usable anywhere a
+/// binding key is expected (it implements `Display`).
+///
+/// Use the [`var_name!`](crate::var_name) macro to have literal names checked
+/// at compile time, or [`VarName::parse`] for names only known at runtime.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VarName(Cow<'static, str>);
+
+impl VarName {
+    /// Panics if `name` is not a valid identifier; in a `const` context,
+    /// as done by [`var_name!`](crate::var_name), that is a compile error.
+    pub const fn new(name: &'static str) -> Self {
+        if !is_valid(name) {
+            panic!("invalid variable name");
+        }
+        Self(Cow::Borrowed(name))
+    }
+
+    pub fn parse(name: impl Into<String>) -> Result<Self> {
+        let name = name.into();
+        if !is_valid(&name) {
+            bail!("'{}' is not a valid variable name", name);
+        }
+        Ok(Self(Cow::Owned(name)))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for VarName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for VarName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Build a [`VarName`](crate::VarName) from a literal, failing to compile if
+/// it is not a valid identifier:
+///
+/// ```
+/// use deno_runner::{var_name, VarName};
+///
+/// const PRICE: VarName = var_name!("price");
+/// assert_eq!(PRICE.as_str(), "price");
+/// ```
+///
+/// ```compile_fail
+/// let name = deno_runner::var_name!("unit price");
+/// ```
+#[macro_export]
+macro_rules! var_name {
+    ($name:literal) => {{
+        const NAME: $crate::VarName = $crate::VarName::new($name);
+        NAME
+    }};
+}
+
+/// ASCII identifier (`[A-Za-z_$][A-Za-z0-9_$]*`) that isn't a reserved word.
+const fn is_valid(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    if bytes.is_empty() || bytes[0].is_ascii_digit() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        if !(byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'$') {
+            return false;
+        }
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < RESERVED.len() {
+        if bytes_eq(bytes, RESERVED[i].as_bytes()) {
+            return false;
+        }
+        i += 1;
+    }
+
+    true
+}
+
+const fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_names() {
+        for name in ["a", "price", "_private", "$el", "value2", "camelCase"] {
+            assert!(VarName::parse(name).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_invalid_names() {
+        for name in [
+            "",
+            "2fast",
+            "unit price",
+            "a-b",
+            "let",
+            "class",
+            "x;alert(1)",
+        ] {
+            assert!(VarName::parse(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_const_name() {
+        const NAME: VarName = var_name!("total");
+        assert_eq!(NAME.to_string(), "total");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid variable name")]
+    fn test_new_panics_at_runtime() {
+        let name = String::from("not valid");
+        VarName::new(Box::leak(name.into_boxed_str()));
+    }
+}
@@ -0,0 +1,230 @@
+use anyhow::{anyhow, bail, Result};
+use deno_core::{
+    futures::future::{self, FutureExt},
+    op, ModuleLoader, ModuleSource, ModuleSourceFuture, ModuleSpecifier, ModuleType, OpDecl,
+    OpState, ZeroCopyBuf,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+/// In-memory file tree the host fills, given to scripts instead of the real
+/// filesystem, see [`Builder::virtual_fs`](crate::Builder::virtual_fs).
+///
+/// Paths are absolute and `/` separated; relative paths, `.` and `..` are
+/// resolved from the root, and nothing can reach above it. Clones share the
+/// same files, so the host can read what a script wrote after the run.
+#[derive(Debug, Clone, Default)]
+pub struct VirtualFs(Arc<Mutex<BTreeMap<String, Vec<u8>>>>);
+
+impl VirtualFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file while setting up the tree.
+    pub fn with_file(self, path: &str, contents: impl Into<Vec<u8>>) -> Self {
+        self.write(path, contents);
+        self
+    }
+
+    pub fn read(&self, path: &str) -> Option<Vec<u8>> {
+        self.0.lock().unwrap().get(&normalize(path)).cloned()
+    }
+
+    pub fn write(&self, path: &str, contents: impl Into<Vec<u8>>) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(normalize(path), contents.into());
+    }
+
+    /// Remove a file, returns whether it existed.
+    pub fn remove(&self, path: &str) -> bool {
+        self.0.lock().unwrap().remove(&normalize(path)).is_some()
+    }
+
+    pub fn exists(&self, path: &str) -> bool {
+        let path = normalize(path);
+        let prefix = dir_prefix(&path);
+        let files = self.0.lock().unwrap();
+        files.contains_key(&path) || files.keys().any(|file| file.starts_with(&prefix))
+    }
+
+    /// Names of the files and directories directly inside `dir`.
+    pub fn read_dir(&self, dir: &str) -> Vec<String> {
+        let prefix = dir_prefix(&normalize(dir));
+        let files = self.0.lock().unwrap();
+        let entries: BTreeSet<_> = files
+            .keys()
+            .filter_map(|file| file.strip_prefix(&prefix))
+            .map(|rest| rest.split('/').next().unwrap().to_string())
+            .collect();
+        entries.into_iter().collect()
+    }
+}
+
+/// Absolute path with `.`, `..` and empty segments resolved.
+fn normalize(path: &str) -> String {
+    let mut parts: Vec<&str> = vec![];
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    format!("/{}", parts.join("/"))
+}
+
+fn dir_prefix(dir: &str) -> String {
+    if dir == "/" {
+        dir.to_string()
+    } else {
+        format!("{}/", dir)
+    }
+}
+
+/// Loads `import`ed modules from a [`VirtualFs`], used as the module loader
+/// of runners built with one.
+pub(crate) struct VirtualFsModuleLoader(pub(crate) VirtualFs);
+
+impl ModuleLoader for VirtualFsModuleLoader {
+    fn resolve(&self, specifier: &str, referrer: &str, _is_main: bool) -> Result<ModuleSpecifier> {
+        let base = match ModuleSpecifier::parse(referrer) {
+            Ok(base) if base.scheme() == "file" => base,
+            _ => ModuleSpecifier::parse("file:///").unwrap(),
+        };
+        let resolved = base.join(specifier)?;
+        if resolved.scheme() != "file" {
+            bail!(
+                "Only modules from the virtual filesystem can be imported, got {}",
+                specifier
+            );
+        }
+        Ok(resolved)
+    }
+
+    fn load(
+        &self,
+        module_specifier: &ModuleSpecifier,
+        _maybe_referrer: Option<ModuleSpecifier>,
+        _is_dyn_import: bool,
+    ) -> Pin<Box<ModuleSourceFuture>> {
+        let specifier = module_specifier.to_string();
+        let source = self
+            .0
+            .read(module_specifier.path())
+            .ok_or_else(|| anyhow!("Module not found in the virtual filesystem: {}", specifier))
+            .map(|code| ModuleSource {
+                code: code.into_boxed_slice(),
+                module_type: ModuleType::JavaScript,
+                module_url_specified: specifier.clone(),
+                module_url_found: specifier,
+            });
+
+        future::ready(source).boxed_local()
+    }
+}
+
+pub(crate) fn decls() -> Vec<OpDecl> {
+    vec![
+        op_vfs_read::decl(),
+        op_vfs_write::decl(),
+        op_vfs_remove::decl(),
+        op_vfs_exists::decl(),
+        op_vfs_read_dir::decl(),
+    ]
+}
+
+fn vfs(state: &OpState) -> Result<&VirtualFs> {
+    state
+        .try_borrow::<VirtualFs>()
+        .ok_or_else(|| anyhow!("This runner has no virtual filesystem"))
+}
+
+#[op]
+fn op_vfs_read(state: &mut OpState, path: String) -> Result<ZeroCopyBuf> {
+    let contents = vfs(state)?
+        .read(&path)
+        .ok_or_else(|| anyhow!("No such file: {}", normalize(&path)))?;
+    Ok(contents.into())
+}
+
+#[op]
+fn op_vfs_write(state: &mut OpState, path: String, contents: ZeroCopyBuf) -> Result<()> {
+    vfs(state)?.write(&path, contents.to_vec());
+    Ok(())
+}
+
+#[op]
+fn op_vfs_remove(state: &mut OpState, path: String) -> Result<bool> {
+    Ok(vfs(state)?.remove(&path))
+}
+
+#[op]
+fn op_vfs_exists(state: &mut OpState, path: String) -> Result<bool> {
+    Ok(vfs(state)?.exists(&path))
+}
+
+#[op]
+fn op_vfs_read_dir(state: &mut OpState, path: String) -> Result<Vec<String>> {
+    Ok(vfs(state)?.read_dir(&path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deno_core::futures::executor::block_on;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("a/b.txt"), "/a/b.txt");
+        assert_eq!(normalize("/a/./b/../c.txt"), "/a/c.txt");
+        assert_eq!(normalize("../../etc/passwd"), "/etc/passwd");
+        assert_eq!(normalize("/"), "/");
+    }
+
+    #[test]
+    fn test_tree() {
+        let fs = VirtualFs::new()
+            .with_file("/data/a.json", "{}")
+            .with_file("/data/nested/b.txt", "b")
+            .with_file("/readme.md", "hi");
+
+        assert!(fs.exists("/data"));
+        assert!(fs.exists("data/nested/b.txt"));
+        assert!(!fs.exists("/dat"));
+        assert_eq!(fs.read_dir("/data"), vec!["a.json", "nested"]);
+        assert_eq!(fs.read_dir("/"), vec!["data", "readme.md"]);
+        assert!(fs.remove("/readme.md"));
+        assert_eq!(fs.read("/readme.md"), None);
+    }
+
+    #[test]
+    fn test_module_loader() {
+        let loader = VirtualFsModuleLoader(
+            VirtualFs::new().with_file("/lib/math.js", "export const two = 2"),
+        );
+
+        let specifier = loader
+            .resolve("./math.js", "file:///lib/main.js", false)
+            .unwrap();
+        assert_eq!(specifier.as_str(), "file:///lib/math.js");
+
+        let source = block_on(loader.load(&specifier, None, false)).unwrap();
+        assert_eq!(&*source.code, b"export const two = 2");
+
+        assert!(loader
+            .resolve("https://example.com/x.js", "file:///main.js", false)
+            .is_err());
+        let missing = loader
+            .resolve("/nope.js", "file:///main.js", false)
+            .unwrap();
+        assert!(block_on(loader.load(&missing, None, false)).is_err());
+    }
+}
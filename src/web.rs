@@ -0,0 +1,117 @@
+use crate::Builder;
+use deno_core::Extension;
+
+/// Web platform APIs a builder enables on top of `URL`, they share the
+/// `deno_web` extension.
+struct Apis {
+    fetch: bool,
+    crypto: bool,
+}
+
+impl Apis {
+    #[cfg_attr(
+        not(any(feature = "fetch", feature = "crypto")),
+        allow(unused_variables)
+    )]
+    fn of(builder: &Builder) -> Self {
+        Self {
+            #[cfg(feature = "fetch")]
+            fetch: builder.fetch.is_some(),
+            #[cfg(not(feature = "fetch"))]
+            fetch: false,
+            #[cfg(feature = "crypto")]
+            crypto: builder.crypto.is_some(),
+            #[cfg(not(feature = "crypto"))]
+            crypto: false,
+        }
+    }
+
+    fn needs_web(&self) -> bool {
+        self.fetch || self.crypto
+    }
+}
+
+/// What `deno_web` asks the host for, kept in the `OpState`.
+#[cfg(any(feature = "fetch", feature = "crypto"))]
+struct WebPermissions;
+
+#[cfg(any(feature = "fetch", feature = "crypto"))]
+impl deno_web::TimersPermission for WebPermissions {
+    fn allow_hrtime(&mut self) -> bool {
+        false
+    }
+
+    fn check_unstable(&self, _state: &deno_core::OpState, _api_name: &'static str) {}
+}
+
+/// Extensions of the enabled APIs, registered after `deno_console` in
+/// dependency order.
+pub(crate) fn extensions(builder: &Builder) -> Vec<Extension> {
+    let mut extensions = vec![deno_webidl::init(), deno_url::init()];
+    #[cfg(any(feature = "fetch", feature = "crypto"))]
+    if Apis::of(builder).needs_web() {
+        extensions.extend(web_extensions(builder));
+    }
+    #[cfg(not(any(feature = "fetch", feature = "crypto")))]
+    let _ = builder;
+    extensions
+}
+
+/// `deno_web` and the APIs built on it.
+#[cfg(any(feature = "fetch", feature = "crypto"))]
+fn web_extensions(builder: &Builder) -> Vec<Extension> {
+    let mut extensions = vec![deno_web::init::<WebPermissions>(
+        deno_web::BlobStore::default(),
+        None,
+    )];
+    #[cfg(feature = "fetch")]
+    if let Some(options) = &builder.fetch {
+        extensions.extend(crate::fetch::extensions(
+            options,
+            builder.permissions.as_ref(),
+        ));
+    }
+    #[cfg(feature = "crypto")]
+    if let Some(seed) = builder.crypto {
+        extensions.push(deno_crypto::init(seed));
+    }
+    extensions.push(
+        Extension::builder()
+            .state(|state| {
+                state.put(WebPermissions);
+                Ok(())
+            })
+            .build(),
+    );
+    extensions
+}
+
+/// Names of the extensions added by [`extensions`], for `describe()`.
+pub(crate) fn extension_names(builder: &Builder) -> Vec<&'static str> {
+    let apis = Apis::of(builder);
+    let mut names = vec!["deno_webidl", "deno_url"];
+    if apis.needs_web() {
+        names.push("deno_web");
+    }
+    if apis.fetch {
+        names.push("deno_fetch");
+    }
+    if apis.crypto {
+        names.push("deno_crypto");
+    }
+    names
+}
+
+/// Scripts exposing the enabled APIs as globals, the extensions only
+/// register them on `__bootstrap`.
+pub(crate) fn scripts(builder: &Builder) -> Vec<(&'static str, &'static str)> {
+    let apis = Apis::of(builder);
+    let mut scripts = vec![("[deno:url.js]", include_str!("./url.js"))];
+    if apis.fetch {
+        scripts.push(("[deno:fetch.js]", include_str!("./fetch.js")));
+    }
+    if apis.crypto {
+        scripts.push(("[deno:crypto.js]", include_str!("./crypto.js")));
+    }
+    scripts
+}
@@ -0,0 +1,62 @@
+use deno_runner::{op, Builder};
+use std::collections::HashMap;
+
+#[op]
+async fn double_async(value: i32) -> i32 {
+    value * 2
+}
+
+#[tokio::test]
+async fn test_promise_result_is_resolved() {
+    let mut runner = Builder::new().build();
+    let result = runner
+        .run::<_, String, String>("Promise.resolve(21).then((n) => n * 2)", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "42");
+}
+
+#[tokio::test]
+async fn test_top_level_await() {
+    let custom_code = r#"
+        const doubled = await Deno.core.opAsync('double_async', value);
+        return doubled + 1;
+    "#;
+
+    let mut runner = Builder::new().add_op(double_async::decl()).build();
+    let vars = HashMap::from([("value", 20)]);
+    let result = runner.run(custom_code, Some(vars)).await.unwrap();
+
+    assert_eq!(result, "41");
+}
+
+#[tokio::test]
+async fn test_rejected_promise_is_an_error() {
+    let mut runner = Builder::new().build();
+    let err = runner
+        .run::<_, String, String>("await null; throw new Error('boom')", None)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("boom"));
+}
+
+#[tokio::test]
+async fn test_async_json_result() {
+    let custom_code = r#"
+        const items = await Promise.all([1, 2, 3].map(async (n) => n * n));
+        return { items };
+    "#;
+
+    let mut runner = Builder::new().build();
+    let value = runner
+        .run_json::<String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        value,
+        deno_runner::serde_json::json!({ "items": [1, 4, 9] })
+    );
+}
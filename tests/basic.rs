@@ -1,4 +1,4 @@
-use deno_runner::Builder;
+use deno_runner::{var_name, Builder, VarName};
 use std::collections::HashMap;
 
 #[tokio::test]
@@ -8,7 +8,7 @@ async fn test_function() {
         add(a, b)
     "#;
 
-    let runner = Builder::new().build();
+    let mut runner = Builder::new().build();
     let vars = HashMap::from([("a", 1), ("b", 2)]);
     let result = runner.run(custom_code, Some(vars)).await.unwrap();
 
@@ -22,9 +22,20 @@ async fn test_console_log() {
         console.error(value);
     "#;
 
-    let runner = Builder::new().build();
+    let mut runner = Builder::new().build();
     let vars = HashMap::from([("value", "hello")]);
     let result = runner.run(custom_code, Some(vars)).await;
 
     assert!(result.is_ok());
 }
+
+#[tokio::test]
+async fn test_var_name_keys() {
+    const A: VarName = var_name!("a");
+
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([(A, 1), (VarName::parse("b").unwrap(), 2)]);
+    let result = runner.run("a + b", Some(vars)).await.unwrap();
+
+    assert_eq!(result, "3");
+}
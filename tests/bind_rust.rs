@@ -6,6 +6,11 @@ fn add(a: i32, b: i32) -> i32 {
     a + b
 }
 
+#[op(fast)]
+fn add_fast(a: i32, b: i32) -> i32 {
+    a + b
+}
+
 #[op]
 fn string_concat(a: String, b: String) -> Result<String> {
     Ok(format!("{}{}", a, b))
@@ -15,7 +20,7 @@ fn string_concat(a: String, b: String) -> Result<String> {
 async fn test_bind_fn_add() {
     let custom_code = "add(a, b)";
 
-    let runner = Builder::new().add_op(add::decl()).build();
+    let mut runner = Builder::new().add_op(add::decl()).build();
     let vars = HashMap::from([("a", 1), ("b", 2)]);
 
     let result = runner.run(custom_code, Some(vars)).await.unwrap();
@@ -27,7 +32,7 @@ async fn test_bind_fn_add() {
 async fn test_trigger_via_rust_helper() {
     let custom_code = "rust('add', a, b)";
 
-    let runner = Builder::new().add_op(add::decl()).build();
+    let mut runner = Builder::new().add_op(add::decl()).build();
     let vars = HashMap::from([("a", 1), ("b", 2)]);
 
     let result = runner.run(custom_code, Some(vars)).await.unwrap();
@@ -39,10 +44,92 @@ async fn test_trigger_via_rust_helper() {
 async fn test_bind_fn_string_concat() {
     let custom_code = r#"string_concat(a, b)"#;
 
-    let runner = Builder::new().add_op(string_concat::decl()).build();
+    let mut runner = Builder::new().add_op(string_concat::decl()).build();
     let vars = HashMap::from([("a", "a"), ("b", "hihi")]);
 
     let result = runner.run(custom_code, Some(vars)).await.unwrap();
 
     assert_eq!(result, "ahihi");
 }
+
+#[tokio::test]
+async fn test_bind_fast_fn() {
+    let custom_code = r#"
+        let sum = 0;
+        for (let i = 0; i < 100000; i++) {
+            sum = add_fast(sum, 1);
+        }
+        sum
+    "#;
+
+    let mut runner = Builder::new().add_fast_op(add_fast::decl()).build();
+    let result = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "100000");
+}
+
+#[tokio::test]
+async fn test_fast_fn_is_not_wrapped() {
+    let custom_code = "String(add_fast).includes('[native code]')";
+
+    let mut runner = Builder::new().add_fast_op(add_fast::decl()).build();
+    let result = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "true");
+}
+
+#[tokio::test]
+async fn test_op_signature_type_error() {
+    let custom_code = r#"
+        try {
+            add(1, "2")
+        } catch (e) {
+            `${e.name}: ${e.message}`
+        }
+    "#;
+
+    let mut runner = Builder::new()
+        .add_op(add::decl())
+        .op_signature("add", &["number", "number"])
+        .build();
+    let result = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result,
+        "TypeError: add: argument 2 expected number, received string"
+    );
+}
+
+#[tokio::test]
+async fn test_op_signature_via_rust_helper() {
+    let custom_code = r#"
+        try {
+            rust("string_concat", "a", null)
+        } catch (e) {
+            e.message
+        }
+    "#;
+
+    let mut runner = Builder::new()
+        .add_op(string_concat::decl())
+        .op_signature("string_concat", &["string", "string"])
+        .build();
+    let result = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result,
+        "string_concat: argument 2 expected string, received null"
+    );
+}
@@ -0,0 +1,60 @@
+use deno_runner::{BindingMode, Builder, ConflictPolicy, RunOptions, RunnerError};
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn test_conflict_with_runtime_global() {
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("console", 1)]);
+    let err = runner.run("console", Some(vars)).await.unwrap_err();
+
+    match err.downcast_ref::<RunnerError>() {
+        Some(RunnerError::BindingConflict { name, existing }) => {
+            assert_eq!(name, "console");
+            assert_eq!(existing, "object");
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_conflict_with_earlier_run() {
+    let mut runner = Builder::new().build();
+    runner
+        .run::<_, String, String>("globalThis.total = 10", None)
+        .await
+        .unwrap();
+
+    let vars = HashMap::from([("total", 1)]);
+    let err = runner.run("total", Some(vars)).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Variable `total` would replace the existing global `total` (number)"
+    );
+}
+
+#[tokio::test]
+async fn test_override_policy() {
+    let mut runner = Builder::new().build();
+    let options = RunOptions::new()
+        .binding_mode(BindingMode::Global)
+        .binding_conflicts(ConflictPolicy::Override);
+
+    for rate in [2, 3] {
+        let vars = HashMap::from([("rate", rate)]);
+        let report = runner
+            .run_with_options("rate * 10", Some(vars), options.clone())
+            .await
+            .unwrap();
+        assert_eq!(report.result, (rate * 10).to_string());
+    }
+}
+
+#[tokio::test]
+async fn test_no_conflict_between_runs() {
+    let mut runner = Builder::new().build();
+    for qty in [1, 2] {
+        let vars = HashMap::from([("qty", qty)]);
+        let result = runner.run("qty", Some(vars)).await.unwrap();
+        assert_eq!(result, qty.to_string());
+    }
+}
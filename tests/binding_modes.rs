@@ -0,0 +1,53 @@
+use deno_runner::{BindingMode, Builder, RunOptions};
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn test_mutable_binding() {
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("qty", 2)]);
+    let result = runner
+        .run_with_options("qty += 1; qty", Some(vars), RunOptions::new())
+        .await
+        .unwrap();
+
+    assert_eq!(result.result, "3");
+}
+
+#[tokio::test]
+async fn test_const_binding() {
+    let mut runner = Builder::new().build();
+    let options = RunOptions::new().binding_mode(BindingMode::Const);
+
+    let vars = HashMap::from([("qty", 2)]);
+    let err = runner
+        .run_with_options("qty = 5", Some(vars), options.clone())
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("Assignment to constant variable qty"));
+
+    // Rebinding the same name on the next run works
+    let vars = HashMap::from([("qty", 4)]);
+    let result = runner
+        .run_with_options("qty * 2", Some(vars), options)
+        .await
+        .unwrap();
+    assert_eq!(result.result, "8");
+}
+
+#[tokio::test]
+async fn test_global_binding_outlives_run() {
+    let mut runner = Builder::new().build();
+    let options = RunOptions::new().binding_mode(BindingMode::Global);
+
+    let vars = HashMap::from([("rate", 3)]);
+    runner
+        .run_with_options("rate", Some(vars), options)
+        .await
+        .unwrap();
+
+    let result = runner
+        .run::<_, String, String>("rate * 2", None)
+        .await
+        .unwrap();
+    assert_eq!(result, "6");
+}
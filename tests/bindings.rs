@@ -0,0 +1,40 @@
+use deno_runner::{serde_json::json, Builder};
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn test_strings_bound_verbatim() {
+    let text = "quote \" backslash \\ newline \n separator \u{2028} end */ ${x} `tick`";
+    let vars = HashMap::from([("text", text)]);
+
+    let mut runner = Builder::new().build();
+    let result = runner.run("text", Some(vars)).await.unwrap();
+
+    assert_eq!(result, text);
+}
+
+#[tokio::test]
+async fn test_json_values() {
+    let rows: Vec<_> = (0..10_000)
+        .map(|i| json!({ "id": i, "tags": ["a", "b"] }))
+        .collect();
+    let result = deno_runner::eval_with(
+        "rows.length + ':' + rows[9999].id + ':' + typeof nested.deep.flag",
+        json!({ "rows": rows, "nested": { "deep": { "flag": true } } }),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(result, "10000:9999:boolean");
+}
+
+#[tokio::test]
+async fn test_numbers_and_booleans() {
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("a", 1.5f64), ("b", -2.0)]);
+    let result = runner.run("a * b", Some(vars)).await.unwrap();
+    assert_eq!(result, "-3");
+
+    let vars = HashMap::from([("flag", true)]);
+    let result = runner.run("typeof flag", Some(vars)).await.unwrap();
+    assert_eq!(result, "boolean");
+}
@@ -0,0 +1,15 @@
+use deno_runner::{op, Builder};
+
+#[op]
+fn noop() {}
+
+#[test]
+fn test_build_report() {
+    let runner = Builder::new().add_op(noop::decl()).build();
+    let report = runner.build_report();
+
+    assert_eq!(report.ops, 1);
+    assert!(report.extensions >= 2);
+    assert_eq!(report.snapshot_load, None);
+    assert!(report.total >= report.runtime_init + report.prelude);
+}
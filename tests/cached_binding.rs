@@ -0,0 +1,42 @@
+use deno_runner::{serde_json::json, Builder, RunOptions};
+
+#[tokio::test]
+async fn test_cached_binding_is_parsed_once() {
+    let config = json!({ "rates": { "vip": 0.2 }, "tiers": ["gold"] });
+    let mut runner = Builder::new().build();
+
+    let mut results = vec![];
+    for code in [
+        "globalThis.first = config; config.rates.vip",
+        "first === config",
+        "config.rates.vip = 1; config.tiers.push('x') ?? 0",
+    ] {
+        let options = RunOptions::new().cached_binding("config", &config).unwrap();
+        let report = runner
+            .run_with_options::<String, String>(code, None, options)
+            .await;
+        results.push(
+            report
+                .map(|report| report.result)
+                .map_err(|err| err.to_string()),
+        );
+    }
+
+    assert_eq!(results[0].as_deref(), Ok("0.2"));
+    assert_eq!(results[1].as_deref(), Ok("true"));
+    // Shared by every run, so frozen
+    assert!(results[2].as_ref().unwrap_err().contains("not extensible"));
+
+    let options = RunOptions::new()
+        .cached_binding("config", json!({ "rates": { "vip": 0.3 } }))
+        .unwrap();
+    let report = runner
+        .run_with_options::<String, String>(
+            "`${first === config} ${config.rates.vip}`",
+            None,
+            options,
+        )
+        .await
+        .unwrap();
+    assert_eq!(report.result, "false 0.3");
+}
@@ -0,0 +1,94 @@
+use deno_runner::{serde_json::json, Builder, RunnerError};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_call_handler_repeatedly() {
+    let mut runner = Builder::new().build();
+    runner
+        .run::<_, String, String>(
+            "function handler(input) { return { doubled: input.value * 2, label: `#${input.value}` } }",
+            None,
+        )
+        .await
+        .unwrap();
+
+    for value in [1, 2, 3] {
+        let result = runner
+            .call_function("handler", &[json!({ "value": value })])
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            json!({ "doubled": value * 2, "label": format!("#{}", value) })
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_call_async_function() {
+    let mut runner = Builder::new().build();
+    runner
+        .run::<_, String, String>(
+            "globalThis.sum = async (a, b) => { await null; return a + b }",
+            None,
+        )
+        .await
+        .unwrap();
+
+    let result = runner
+        .call_function("sum", &[json!(2), json!(3)])
+        .await
+        .unwrap();
+    assert_eq!(result, json!(5));
+}
+
+#[tokio::test]
+async fn test_call_errors() {
+    let mut runner = Builder::new().build();
+    runner
+        .run::<_, String, String>(
+            "globalThis.fail = (message) => { throw new TypeError(message) }; globalThis.notAFunction = 1",
+            None,
+        )
+        .await
+        .unwrap();
+
+    let err = runner.call_function("missing", &[]).await.unwrap_err();
+    assert_eq!(err.to_string(), "`missing` is not a global function");
+    let err = runner.call_function("notAFunction", &[]).await.unwrap_err();
+    assert_eq!(err.to_string(), "`notAFunction` is not a global function");
+
+    let err = runner
+        .call_function("fail", &[json!("bad input")])
+        .await
+        .unwrap_err();
+    match err.downcast_ref::<RunnerError>() {
+        Some(RunnerError::Execution(error)) => {
+            assert_eq!(error.class.as_deref(), Some("TypeError"));
+            assert_eq!(error.message, "bad input");
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_call_times_out() {
+    let limit = Duration::from_millis(100);
+    let mut runner = Builder::new().timeout(limit).build();
+    runner
+        .run::<_, String, String>("globalThis.spin = () => { while (true) {} }", None)
+        .await
+        .unwrap();
+
+    let err = runner.call_function("spin", &[]).await.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<RunnerError>(),
+        Some(RunnerError::Timeout(_))
+    ));
+
+    let result = runner
+        .run::<_, String, String>("1 + 1", None)
+        .await
+        .unwrap();
+    assert_eq!(result, "2");
+}
@@ -0,0 +1,143 @@
+use deno_runner::{op, Builder, RunOptions};
+
+#[op]
+fn fetch_data(id: u32) -> u32 {
+    id * 10
+}
+
+#[op]
+fn delete_all() -> bool {
+    true
+}
+
+#[op(fast)]
+fn count_fast(a: i32) -> i32 {
+    a + 1
+}
+
+fn runner() -> deno_runner::DenoRunner {
+    Builder::new()
+        .add_op(fetch_data::decl())
+        .add_op(delete_all::decl())
+        .add_fast_op(count_fast::decl())
+        .build()
+}
+
+fn options() -> RunOptions {
+    RunOptions::new().approve_capabilities([
+        "op:fetch_data",
+        "op:delete_all",
+        "net:api.example.com",
+    ])
+}
+
+#[tokio::test]
+async fn test_declared_op() {
+    let custom_code = r#"
+        // requires: net:api.example.com, op:fetch_data
+        fetch_data(4)
+    "#;
+
+    let report = runner()
+        .run_with_options::<_, String, String>(custom_code, None, options())
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, "40");
+}
+
+#[tokio::test]
+async fn test_undeclared_op() {
+    let custom_code = r#"
+        // requires: op:fetch_data
+        delete_all()
+    "#;
+
+    let direct = custom_code.replace("delete_all()", "Deno.core.opSync('delete_all')");
+    for code in [custom_code, direct.as_str()] {
+        let err = runner()
+            .run_with_options::<_, String, String>(code, None, options())
+            .await
+            .unwrap_err();
+
+        assert!(format!("{:#}", err).contains("op delete_all is not declared"));
+    }
+}
+
+#[tokio::test]
+async fn test_undeclared_fast_op() {
+    let custom_code = r#"
+        // requires: op:fetch_data
+        count_fast(1)
+    "#;
+
+    let err = runner()
+        .run_with_options::<_, String, String>(
+            custom_code,
+            None,
+            options().approve_capabilities(["op:count_fast"]),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(format!("{:#}", err).contains("op count_fast is not declared"));
+}
+
+#[tokio::test]
+async fn test_no_header_gets_no_capabilities() {
+    let err = runner()
+        .run_with_options::<_, String, String>("delete_all()", None, options())
+        .await
+        .unwrap_err();
+
+    assert!(format!("{:#}", err).contains("op delete_all is not declared"));
+}
+
+#[tokio::test]
+async fn test_hooks_and_raw_ops_are_hidden() {
+    for call in [
+        "Deno.core.resetRun(); delete_all()",
+        "Deno.core.ops.delete_all()",
+    ] {
+        let code = format!("// requires: op:fetch_data\n{}", call);
+        let err = runner()
+            .run_with_options::<_, String, String>(&code, None, options())
+            .await
+            .unwrap_err();
+
+        assert!(
+            format!("{:#}", err).contains("TypeError"),
+            "{}: {:#}",
+            call,
+            err
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_unapproved_capability() {
+    let custom_code = r#"
+        // requires: op:fetch_data
+        fetch_data(1)
+    "#;
+
+    let err = runner()
+        .run_with_options::<_, String, String>(custom_code, None, RunOptions::new())
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "Script requires capabilities the host has not approved: op:fetch_data"
+    );
+}
+
+#[tokio::test]
+async fn test_no_header_is_unrestricted() {
+    let result = runner()
+        .run::<_, String, String>("delete_all()", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "true");
+}
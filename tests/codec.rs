@@ -0,0 +1,40 @@
+use deno_runner::{anyhow::Result, v8, Builder, ValueCodec};
+use std::collections::HashMap;
+
+/// Binds every value as a BigInt and prints BigInt results with an `n` suffix
+struct BigIntCodec;
+
+impl ValueCodec for BigIntCodec {
+    fn encode(&self, value: &dyn std::fmt::Debug) -> String {
+        format!("BigInt({:?})", value)
+    }
+
+    fn decode(
+        &self,
+        scope: &mut v8::HandleScope,
+        value: v8::Local<v8::Value>,
+    ) -> Result<Option<String>> {
+        if value.is_big_int() {
+            return Ok(Some(format!("{}n", value.to_rust_string_lossy(scope))));
+        }
+        Ok(None)
+    }
+}
+
+#[tokio::test]
+async fn test_custom_codec() {
+    let mut runner = Builder::new().codec(BigIntCodec).build();
+    let vars = HashMap::from([("a", "9007199254740993"), ("b", "2")]);
+    let result = runner.run("a * b", Some(vars)).await.unwrap();
+
+    assert_eq!(result, "18014398509481986n");
+}
+
+#[tokio::test]
+async fn test_custom_codec_falls_back_to_default_decode() {
+    let mut runner = Builder::new().codec(BigIntCodec).build();
+    let vars = HashMap::from([("a", 1)]);
+    let result = runner.run("typeof a", Some(vars)).await.unwrap();
+
+    assert_eq!(result, "bigint");
+}
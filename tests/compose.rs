@@ -0,0 +1,37 @@
+use deno_runner::{compose, serde_json::json, Builder};
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn test_compose_isolates_snippets() {
+    let code = compose(&[
+        (
+            "total",
+            "var sum = 0; for (let i = 1; i <= n; i++) sum += i; return sum",
+        ),
+        ("leaked", "return typeof sum"),
+        ("later", "await null; return n * 2"),
+    ])
+    .unwrap();
+
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("n", 3)]);
+    let result = runner.run_json(&code, Some(vars)).await.unwrap();
+
+    assert_eq!(
+        result,
+        json!({ "total": 6, "leaked": "undefined", "later": 3 })
+    );
+}
+
+#[tokio::test]
+async fn test_compose_failing_snippet() {
+    let code = compose(&[("ok", "return 1"), ("broken", "throw new Error('boom')")]).unwrap();
+
+    let mut runner = Builder::new().build();
+    let err = runner
+        .run_json::<String, String>(&code, None)
+        .await
+        .unwrap_err();
+
+    assert!(format!("{:#}", err).contains("boom"));
+}
@@ -0,0 +1,66 @@
+use deno_runner::{Builder, ConsoleEntry, ConsoleLevel, RunOptions};
+
+fn entry(level: ConsoleLevel, message: &str, depth: usize) -> ConsoleEntry {
+    ConsoleEntry {
+        level,
+        message: message.to_string(),
+        depth,
+    }
+}
+
+#[tokio::test]
+async fn test_rich_console_methods() {
+    let custom_code = r#"
+        console.group("outer");
+        console.info("inside");
+        console.groupEnd();
+        console.time("load");
+        console.timeEnd("load");
+        console.table([{ a: 1, b: "x" }, { a: 2 }]);
+        console.table([1, 2], ["a"]);
+        console.warn("careful");
+        "done"
+    "#;
+
+    let mut runner = Builder::new().build();
+    let report = runner
+        .run_with_options::<_, String, String>(
+            custom_code,
+            None,
+            RunOptions::new().capture_console(true),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, "done");
+    let console = &report.console;
+    assert_eq!(console.len(), 6);
+    assert_eq!(console[0], entry(ConsoleLevel::Group, "outer", 0));
+    assert_eq!(console[1], entry(ConsoleLevel::Info, "inside", 1));
+    assert_eq!(console[2].level, ConsoleLevel::Time);
+    assert!(console[2].message.starts_with("load: "));
+    assert!(console[2].message.ends_with("ms"));
+    assert_eq!(
+        console[3],
+        entry(
+            ConsoleLevel::Table,
+            [
+                "┌─────────┬───┬─────┐",
+                "│ (index) │ a │  b  │",
+                "├─────────┼───┼─────┤",
+                "│    0    │ 1 │ \"x\" │",
+                "│    1    │ 2 │     │",
+                "└─────────┴───┴─────┘",
+            ]
+            .join("\n")
+            .as_str(),
+            0
+        )
+    );
+    assert_eq!(console[4].level, ConsoleLevel::Table);
+    assert!(console[4].message.contains("Values"));
+    assert_eq!(console[5], entry(ConsoleLevel::Warn, "careful", 0));
+
+    assert_eq!(report.stdout[..2], ["outer", "  inside"]);
+    assert_eq!(report.stderr, ["careful"]);
+}
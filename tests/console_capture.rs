@@ -0,0 +1,66 @@
+use deno_runner::{Builder, RunOptions};
+
+#[tokio::test]
+async fn test_capture_console() {
+    let custom_code = r#"
+        console.log("loading", 2, "rows");
+        console.warn("row 2 is empty");
+        console.info("done");
+        "ok"
+    "#;
+
+    let mut runner = Builder::new().build();
+    let report = runner
+        .run_with_options::<_, String, String>(
+            custom_code,
+            None,
+            RunOptions::new().capture_console(true),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, "ok");
+    assert_eq!(report.stdout, vec!["loading 2 rows", "done"]);
+    assert_eq!(report.stderr, vec!["row 2 is empty"]);
+
+    // Each run only reports its own output
+    let report = runner
+        .run_with_options::<_, String, String>(
+            "console.log('second'); 1",
+            None,
+            RunOptions::new().capture_console(true),
+        )
+        .await
+        .unwrap();
+    assert_eq!(report.stdout, vec!["second"]);
+    assert!(report.stderr.is_empty());
+}
+
+#[tokio::test]
+async fn test_not_captured_by_default() {
+    let mut runner = Builder::new().build();
+    let report = runner
+        .run_with_options::<_, String, String>("console.log('hi'); 1", None, RunOptions::new())
+        .await
+        .unwrap();
+
+    assert!(report.stdout.is_empty());
+    assert!(report.stderr.is_empty());
+}
+
+#[tokio::test]
+async fn test_captured_secrets_are_redacted() {
+    let mut runner = Builder::new().build();
+    let report = runner
+        .run_with_options::<_, String, String>(
+            "console.log(`token=${TOKEN}`); 1",
+            None,
+            RunOptions::new()
+                .secret("TOKEN", "s3cr3t")
+                .capture_console(true),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(report.stdout, vec!["token=[REDACTED]"]);
+}
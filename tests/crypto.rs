@@ -0,0 +1,48 @@
+#![cfg(feature = "crypto")]
+
+use deno_runner::Builder;
+
+#[tokio::test]
+async fn test_digest() {
+    let custom_code = r#"
+        const data = new Uint8Array([104, 101, 108, 108, 111]); // "hello"
+        const digest = await crypto.subtle.digest("SHA-256", data);
+        Array.from(new Uint8Array(digest), (b) => b.toString(16).padStart(2, "0")).join("")
+    "#;
+
+    let mut runner = Builder::new().enable_crypto(None).build();
+    let result: String = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result,
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+    );
+}
+
+#[tokio::test]
+async fn test_random_uuid() {
+    let mut runner = Builder::new().enable_crypto(None).build();
+    let result: String = runner
+        .run::<_, String, String>("crypto.randomUUID()", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result.len(), 36);
+    assert_eq!(&result[14..15], "4");
+}
+
+#[tokio::test]
+async fn test_seeded_random_values() {
+    let code = "crypto.getRandomValues(new Uint32Array(4)).join(',')";
+
+    let mut first = Builder::new().enable_crypto(Some(42)).build();
+    let mut second = Builder::new().enable_crypto(Some(42)).build();
+
+    assert_eq!(
+        first.run::<_, String, String>(code, None).await.unwrap(),
+        second.run::<_, String, String>(code, None).await.unwrap()
+    );
+}
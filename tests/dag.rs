@@ -0,0 +1,78 @@
+use deno_runner::{op, serde_json::json, Builder, Dag};
+use std::{cell::Cell, collections::HashMap, time::Duration};
+
+#[tokio::test]
+async fn test_dag_passes_results_downstream() {
+    let dag = Dag::new()
+        .node("user", "({ name: name.toUpperCase(), tags })", &[])
+        .node("greeting", "`Hello ${user.name}`", &["user"])
+        .node(
+            "summary",
+            "`${greeting} (${user.tags.length} tags)`",
+            &["user", "greeting"],
+        );
+
+    let inputs = HashMap::from([
+        ("name".to_string(), json!("duyet")),
+        ("tags".to_string(), json!(["a", "b"])),
+    ]);
+    let outputs = dag
+        .execute(|| Builder::new().build(), inputs)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        outputs["user"],
+        json!({"name": "DUYET", "tags": ["a", "b"]})
+    );
+    assert_eq!(outputs["summary"], json!("Hello DUYET (2 tags)"));
+}
+
+#[tokio::test]
+async fn test_dag_node_failure() {
+    let dag = Dag::new()
+        .node("a", "1", &[])
+        .node("b", "missing + a", &["a"]);
+
+    let err = dag
+        .execute(|| Builder::new().build(), HashMap::new())
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), "DAG node 'b' failed");
+}
+
+thread_local! {
+    static ARRIVED: Cell<u32> = Cell::new(0);
+}
+
+/// Number of nodes that reached this op, waiting a little for the others
+#[op]
+async fn rendezvous() -> u32 {
+    ARRIVED.with(|arrived| arrived.set(arrived.get() + 1));
+    for _ in 0..50 {
+        if ARRIVED.with(Cell::get) >= 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    ARRIVED.with(Cell::get)
+}
+
+#[tokio::test]
+async fn test_dag_level_runs_concurrently() {
+    let dag = Dag::new()
+        .node("a", "rendezvous()", &[])
+        .node("b", "rendezvous()", &[])
+        .node("both", "[a, b]", &["a", "b"]);
+
+    let outputs = dag
+        .execute(
+            || Builder::new().add_op(rendezvous::decl()).build(),
+            HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(outputs["both"], json!([2, 2]));
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "polars")]
+
+use deno_runner::{Builder, RunOptions};
+use polars::prelude::{ChunkAgg, DataType};
+
+#[tokio::test]
+async fn test_run_dataframe() {
+    let custom_code = r#"
+        [
+            { region: "north", total: 10.5, orders: 3, paid: true },
+            { region: "south", total: 2, orders: 1 },
+            { region: null, total: 4.25, orders: 2, paid: false, note: "late" },
+        ]
+    "#;
+
+    let mut runner = Builder::new().build();
+    let df = runner
+        .run_dataframe::<String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(df.shape(), (3, 5));
+    assert_eq!(
+        df.get_column_names(),
+        ["region", "total", "orders", "paid", "note"]
+    );
+    assert_eq!(df.column("total").unwrap().dtype(), &DataType::Float64);
+    assert_eq!(df.column("orders").unwrap().dtype(), &DataType::Int64);
+    assert_eq!(df.column("orders").unwrap().i64().unwrap().sum(), Some(6));
+    assert_eq!(df.column("region").unwrap().null_count(), 1);
+    assert_eq!(df.column("paid").unwrap().null_count(), 1);
+    assert_eq!(df.column("note").unwrap().null_count(), 2);
+}
+
+#[tokio::test]
+async fn test_run_dataframe_invalid_rows() {
+    let mut runner = Builder::new().build();
+
+    let err = runner
+        .run_dataframe::<String, String>("({ rows: [] })", None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("must return an array of objects"));
+
+    let err = runner
+        .run_dataframe::<String, String>("[{ qty: 1 }, { qty: '2' }]", None)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Row 1 has a string in the number column `qty`"
+    );
+}
+
+#[tokio::test]
+async fn test_run_dataframe_redacts_secrets() {
+    let mut runner = Builder::new().build();
+    runner
+        .run_with_options::<_, String, String>(
+            "globalThis.leaked = token",
+            None,
+            RunOptions::new().secret("token", "sk_live_123"),
+        )
+        .await
+        .unwrap();
+
+    let df = runner
+        .run_dataframe::<String, String>("[{ key: `key ${leaked}` }, { key: 'none' }]", None)
+        .await
+        .unwrap();
+    let keys: Vec<_> = df
+        .column("key")
+        .unwrap()
+        .str()
+        .unwrap()
+        .into_iter()
+        .collect();
+    assert_eq!(keys, [Some("key [REDACTED]"), Some("none")]);
+
+    let err = runner
+        .run_dataframe::<String, String>("throw new Error(`bad ${leaked}`)", None)
+        .await
+        .unwrap_err();
+    assert!(!format!("{:#}", err).contains("sk_live_123"), "{:#}", err);
+}
@@ -0,0 +1,47 @@
+use deno_runner::{op, serde_json, Builder, SharedBuffer, VirtualFs};
+use std::time::Duration;
+
+#[op]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[op(fast)]
+fn square(x: i32) -> i32 {
+    x * x
+}
+
+#[test]
+fn test_describe_builder() {
+    let builder = Builder::new()
+        .add_op(add::decl())
+        .add_fast_op(square::decl())
+        .timeout(Duration::from_secs(2))
+        .max_heap_size(64 * 1024 * 1024)
+        .shared_buffer("table", &SharedBuffer::new(vec![0; 16]))
+        .virtual_fs(&VirtualFs::new());
+
+    let description = builder.describe();
+
+    assert_eq!(description.ops, ["add", "square"]);
+    assert_eq!(description.fast_ops, ["square"]);
+    assert_eq!(description.limits.timeout_ms, Some(2000));
+    assert_eq!(description.limits.max_heap_size, Some(64 * 1024 * 1024));
+    assert_eq!(description.limits.parallel_limit, None);
+    assert_eq!(description.module_loader, "virtual_fs");
+    assert_eq!(description.shared_buffers, [("table".to_string(), 16)]);
+    assert!(!description.snapshot);
+}
+
+#[test]
+fn test_describe_runner_matches_builder() {
+    let builder = Builder::new().add_op(add::decl()).parallel_limit(2);
+    let expected = builder.describe();
+    let runner = builder.build();
+
+    assert_eq!(runner.describe(), expected);
+
+    let json = serde_json::to_value(runner.describe()).unwrap();
+    assert_eq!(json["module_loader"], "none");
+    assert_eq!(json["limits"]["parallel_limit"], 2);
+}
@@ -0,0 +1,91 @@
+use deno_runner::{op, serde_json::json, Builder, OpCall, RunOptions};
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static CHARGES: AtomicUsize = AtomicUsize::new(0);
+
+#[op]
+fn charge(_customer: String, amount: u32) -> u32 {
+    CHARGES.fetch_add(1, Ordering::SeqCst);
+    amount
+}
+
+#[op(fast)]
+fn double(value: u32) -> u32 {
+    value * 2
+}
+
+#[tokio::test]
+async fn test_dry_run_records_calls() {
+    let custom_code = r#"
+        const receipt = charge(customer, 100);
+        rust("charge", customer, double(25));
+        receipt.id
+    "#;
+
+    let mut runner = Builder::new()
+        .add_op(charge::decl())
+        .add_fast_op(double::decl())
+        .build();
+    let vars = HashMap::from([("customer", "acme")]);
+    let options = RunOptions::new()
+        .dry_run(true)
+        .stub("charge", json!({ "id": "ch_1" }))
+        .stub("double", 50);
+    let report = runner
+        .run_with_options(custom_code, Some(vars), options)
+        .await
+        .unwrap();
+
+    assert_eq!(CHARGES.load(Ordering::SeqCst), 0);
+    assert_eq!(report.result, "ch_1");
+    assert_eq!(
+        report.op_calls,
+        vec![
+            OpCall {
+                op: "charge".to_string(),
+                args: vec![json!("acme"), json!(100)],
+            },
+            OpCall {
+                op: "double".to_string(),
+                args: vec![json!(25)],
+            },
+            OpCall {
+                op: "charge".to_string(),
+                args: vec![json!("acme"), json!(50)],
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_dry_run_async_op_without_stub() {
+    let mut runner = Builder::new().add_op(charge::decl()).build();
+    let options = RunOptions::new().dry_run(true);
+    let report = runner
+        .run_with_options::<_, String, String>(
+            "rustAsync('charge', 'acme', 1); 'done'",
+            None,
+            options,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, "done");
+    assert_eq!(report.op_calls.len(), 1);
+    assert_eq!(report.op_calls[0].op, "charge");
+}
+
+#[tokio::test]
+async fn test_no_recording_without_dry_run() {
+    let mut runner = Builder::new().add_op(double::decl()).build();
+    let report = runner
+        .run_with_options::<_, String, String>("double(2)", None, RunOptions::new())
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, "4");
+    assert!(report.op_calls.is_empty());
+}
@@ -0,0 +1,93 @@
+use deno_runner::{serde_json, Builder, Codec, RunOptions};
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn test_json_codec() {
+    let mut runner = Builder::new().build();
+    let bindings = serde_json::to_vec(&HashMap::from([("values", vec![1.5, 2.5, 3.0])])).unwrap();
+
+    let result = runner
+        .run_encoded(
+            "({ sum: values.reduce((a, b) => a + b, 0) })",
+            &bindings,
+            RunOptions::new().codec(Codec::Json),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result, br#"{"sum":7}"#);
+}
+
+#[tokio::test]
+async fn test_bindings_must_be_a_map() {
+    let mut runner = Builder::new().build();
+
+    let err = runner
+        .run_encoded("1", b"[1, 2]", RunOptions::new())
+        .await
+        .unwrap_err();
+
+    assert!(err
+        .to_string()
+        .contains("Encoded bindings must be a map of variable names to values"));
+}
+
+#[cfg(feature = "msgpack")]
+#[tokio::test]
+async fn test_msgpack_codec() {
+    let mut runner = Builder::new().build();
+    let samples: Vec<f64> = (0..10_000).map(|i| i as f64 * 0.5).collect();
+    let bindings = rmp_serde::to_vec_named(&HashMap::from([("samples", &samples)])).unwrap();
+
+    let result = runner
+        .run_encoded(
+            "samples.map((x) => x * 2)",
+            &bindings,
+            RunOptions::new().codec(Codec::MsgPack),
+        )
+        .await
+        .unwrap();
+
+    let doubled: Vec<f64> = rmp_serde::from_slice(&result).unwrap();
+    assert_eq!(doubled.len(), samples.len());
+    assert_eq!(doubled[9_999], 9_999.0);
+}
+
+#[cfg(feature = "msgpack")]
+#[tokio::test]
+async fn test_msgpack_binary_and_structs() {
+    #[derive(serde::Serialize)]
+    struct Input {
+        name: String,
+        #[serde(with = "serde_bytes_compat")]
+        payload: Vec<u8>,
+    }
+
+    mod serde_bytes_compat {
+        pub fn serialize<S: serde::Serializer>(bytes: &[u8], s: S) -> Result<S::Ok, S::Error> {
+            s.serialize_bytes(bytes)
+        }
+    }
+
+    let mut runner = Builder::new().build();
+    let input = Input {
+        name: "blob".to_string(),
+        payload: vec![1, 2, 3],
+    };
+    let bindings = rmp_serde::to_vec_named(&HashMap::from([("input", input)])).unwrap();
+
+    let result = runner
+        .run_encoded(
+            "({ name: input.name, binary: input.payload instanceof Uint8Array, total: input.payload.reduce((a, b) => a + b) })",
+            &bindings,
+            RunOptions::new().codec(Codec::MsgPack),
+        )
+        .await
+        .unwrap();
+
+    let output: serde_json::Value = rmp_serde::from_slice(&result).unwrap();
+    assert_eq!(
+        output,
+        serde_json::json!({"name": "blob", "binary": true, "total": 6})
+    );
+}
@@ -0,0 +1,111 @@
+use deno_runner::{from_union, serde_json::json, Builder, Json, RunnerError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum External {
+    Circle { radius: f64 },
+    Label(String),
+    Empty,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "t", content = "c")]
+enum Adjacent {
+    Point { x: i32, y: i32 },
+    Id(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Internal {
+    Square { side: f64 },
+    Nothing,
+}
+
+/// Bind `value`, let the script return it as is and read it back
+async fn round_trip<T>(value: T) -> T
+where
+    T: Serialize + for<'de> Deserialize<'de>,
+{
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("value", Json(value))]);
+    runner.run_as("value", Some(vars)).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_enums_round_trip() {
+    for value in [
+        External::Circle { radius: 1.5 },
+        External::Label("x".to_string()),
+        External::Empty,
+    ] {
+        assert_eq!(round_trip(value.clone()).await, value);
+    }
+    for value in [Adjacent::Point { x: 1, y: -2 }, Adjacent::Id(7)] {
+        assert_eq!(round_trip(value.clone()).await, value);
+    }
+    for value in [Internal::Square { side: 2.0 }, Internal::Nothing] {
+        assert_eq!(round_trip(value.clone()).await, value);
+    }
+}
+
+#[tokio::test]
+async fn test_scripts_see_the_serde_representation() {
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([
+        ("external", Json(json!(External::Circle { radius: 1.0 }))),
+        ("adjacent", Json(json!(Adjacent::Id(3)))),
+        ("internal", Json(json!(Internal::Square { side: 2.0 }))),
+    ]);
+    let result = runner
+        .run_json(
+            "[external.Circle.radius, adjacent.t + adjacent.c, internal.kind]",
+            Some(vars),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result, json!([1, "Id3", "square"]));
+}
+
+#[tokio::test]
+async fn test_from_union() {
+    let mut runner = Builder::new().build();
+    let shapes = runner
+        .run_json::<String, String>("[{ shape: 'Circle', radius: 2 }, { shape: 'Empty' }]", None)
+        .await
+        .unwrap();
+
+    let shapes: Vec<External> = shapes
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|shape| from_union(shape.clone(), "shape").unwrap())
+        .collect();
+    assert_eq!(shapes, [External::Circle { radius: 2.0 }, External::Empty]);
+}
+
+#[test]
+fn test_from_union_errors() {
+    let missing = from_union::<External>(json!({ "radius": 1 }), "shape").unwrap_err();
+    assert!(missing.to_string().contains("Missing the `shape` field"));
+
+    let not_object = from_union::<External>(json!("Circle"), "shape").unwrap_err();
+    assert!(not_object.to_string().contains("Expected an object"));
+
+    let unknown = from_union::<External>(json!({ "shape": "Square" }), "shape").unwrap_err();
+    match unknown.downcast_ref::<RunnerError>() {
+        Some(RunnerError::ResultDeserialization { type_name, source }) => {
+            assert!(type_name.ends_with("External"));
+            assert!(source.to_string().contains("unknown variant `Square`"));
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+
+    let bad_field =
+        from_union::<External>(json!({ "shape": "Circle", "radius": "big" }), "shape").unwrap_err();
+    assert!(bad_field
+        .to_string()
+        .contains("invalid type: string \"big\""));
+}
@@ -0,0 +1,47 @@
+use deno_runner::{Builder, RunOptions};
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn test_error_context() {
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("qty", "3"), ("api_key", "secret-value")]);
+    let options = RunOptions::new()
+        .script_name("pricing.js")
+        .error_context(true);
+    let err = runner
+        .run_with_options("qty * missing", Some(vars), options)
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "script 'pricing.js' failed (bindings: api_key, qty)"
+    );
+    let full = format!("{:#}", err);
+    assert!(full.contains("missing is not defined"));
+    assert!(!full.contains("secret-value"));
+}
+
+#[tokio::test]
+async fn test_error_context_without_bindings() {
+    let mut runner = Builder::new().build();
+    let options = RunOptions::new().error_context(true);
+    let err = runner
+        .run_with_options::<_, String, String>("throw new Error('boom')", None, options)
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), "script 'code.js' failed (bindings: none)");
+}
+
+#[tokio::test]
+async fn test_script_name_in_stack() {
+    let mut runner = Builder::new().build();
+    let options = RunOptions::new().script_name("rules/discount.js");
+    let err = runner
+        .run_with_options::<_, String, String>("null.x", None, options)
+        .await
+        .unwrap_err();
+
+    assert!(format!("{:#}", err).contains("rules/discount.js"));
+}
@@ -0,0 +1,39 @@
+use deno_runner::{eval, eval_with, serde_json::json};
+
+#[tokio::test]
+async fn test_eval() {
+    let result = eval("1 + 1").await.unwrap();
+
+    assert_eq!(result, "2");
+}
+
+#[tokio::test]
+async fn test_eval_with() {
+    let result = eval_with("a + b", json!({"a": 1, "b": 2})).await.unwrap();
+    assert_eq!(result, "3");
+
+    let result = eval_with(
+        "user.name + tags.length",
+        json!({"user": {"name": "duyet"}, "tags": [1, 2]}),
+    )
+    .await
+    .unwrap();
+    assert_eq!(result, "duyet2");
+}
+
+#[tokio::test]
+async fn test_eval_with_rejects_non_object() {
+    let result = eval_with("1", json!([1, 2])).await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_eval_reuses_the_thread_runner() {
+    let count = "globalThis.count = (globalThis.count ?? 0) + 1";
+    assert_eq!(eval(count).await.unwrap(), "1");
+    assert_eq!(eval(count).await.unwrap(), "2");
+
+    eval_with("a", json!({"a": 1})).await.unwrap();
+    assert_eq!(eval("typeof a").await.unwrap(), "undefined");
+}
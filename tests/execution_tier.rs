@@ -0,0 +1,21 @@
+use deno_runner::{set_execution_tier, Builder, ExecutionTier};
+use std::collections::HashMap;
+
+// V8 flags are process wide, so this file holds a single test
+#[tokio::test]
+async fn test_interpreter_only() {
+    set_execution_tier(ExecutionTier::InterpreterOnly).unwrap();
+
+    let custom_code = r#"
+        let total = 0;
+        for (let i = 0; i < 10000; i++) total += i * rate;
+        total
+    "#;
+
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("rate", 2)]);
+    let result = runner.run(custom_code, Some(vars)).await.unwrap();
+
+    assert_eq!(result, "99990000");
+    assert!(set_execution_tier(ExecutionTier::Jitless).is_err());
+}
@@ -0,0 +1,40 @@
+use deno_runner::{executor, op, Builder, RunnerPool};
+use std::time::{Duration, Instant};
+
+#[op]
+async fn wait_ms(ms: u64) -> u64 {
+    executor::sleep(Duration::from_millis(ms)).await;
+    ms
+}
+
+#[test]
+fn test_block_on_runner() {
+    let result = executor::block_on(async {
+        let mut runner = Builder::new().add_op(wait_ms::decl()).build();
+        runner
+            .run::<_, String, String>("await rustAsync('wait_ms', 20)", None)
+            .await
+    });
+
+    assert_eq!(result.unwrap(), "20");
+}
+
+#[test]
+fn test_timeout() {
+    let started = Instant::now();
+    let timed_out = executor::block_on(executor::timeout(
+        Duration::from_millis(10),
+        executor::sleep(Duration::from_secs(5)),
+    ));
+
+    assert_eq!(timed_out, None);
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn test_pool_without_async_caller() {
+    let pool = RunnerPool::new(1, || Builder::new().build());
+    let result = executor::block_on(pool.run::<String, String>("1 + 1", None));
+
+    assert_eq!(result.unwrap(), "2");
+}
@@ -0,0 +1,86 @@
+use deno_runner::{Builder, RunOptions};
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn test_exit_with_value() {
+    let custom_code = r#"
+        if (!value) exit(2, "empty input");
+        "unreachable"
+    "#;
+
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("value", "")]);
+    let report = runner
+        .run_with_options(custom_code, Some(vars), RunOptions::new())
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, "empty input");
+    assert_eq!(report.exit_code, Some(2));
+}
+
+#[tokio::test]
+async fn test_exit_without_arguments() {
+    let custom_code = r#"
+        exit();
+        throw new Error("unreachable");
+    "#;
+
+    let mut runner = Builder::new().build();
+    let result = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "undefined");
+}
+
+#[tokio::test]
+async fn test_no_exit_code_when_completed() {
+    let mut runner = Builder::new().build();
+    let report = runner
+        .run_with_options::<_, String, String>("1 + 1", None, RunOptions::new())
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, "2");
+    assert_eq!(report.exit_code, None);
+}
+
+#[tokio::test]
+async fn test_errors_are_not_exits() {
+    let mut runner = Builder::new().build();
+    let result = runner
+        .run::<_, String, String>("throw new Error('boom')", None)
+        .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_caught_exit_does_not_end_the_run() {
+    let custom_code = r#"
+        try {
+            exit(3, "caught");
+        } catch {}
+        throw new Error("boom");
+    "#;
+
+    let mut runner = Builder::new().build();
+    let err = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("boom"));
+
+    let report = runner
+        .run_with_options::<_, String, String>(
+            "try { exit(4) } catch {} 'done'",
+            None,
+            RunOptions::new(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(report.result, "done");
+    assert_eq!(report.exit_code, None);
+}
@@ -0,0 +1,89 @@
+use deno_runner::{Builder, LanguageFeature};
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn test_expects_comment() {
+    let custom_code = r#"
+        // @expects a: number, b: number
+        a + b
+    "#;
+
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("a", 1), ("b", 2)]);
+    let result = runner.run(custom_code, Some(vars)).await.unwrap();
+
+    assert_eq!(result, "3");
+}
+
+#[tokio::test]
+async fn test_expects_comment_reports_every_problem() {
+    let custom_code = r#"
+        // @expects a: number, b: number, c?: string
+        a + b
+    "#;
+
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("a", "1")]);
+    let err = runner.run(custom_code, Some(vars)).await.unwrap_err();
+    let message = err.to_string();
+
+    assert!(message.contains("variable a should be number, got string"));
+    assert!(message.contains("missing variable b (number)"));
+    assert!(!message.contains("variable c"));
+}
+
+#[tokio::test]
+async fn test_expects_call() {
+    let custom_code = r#"
+        expects({ value: 'string', 'count?': 'number' });
+        value
+    "#;
+
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("value", "hello")]);
+    let result = runner.run(custom_code, Some(vars)).await.unwrap();
+
+    assert_eq!(result, "hello");
+}
+
+#[tokio::test]
+#[should_panic(expected = "missing variable count (number)")]
+async fn test_expects_call_missing_variable() {
+    let custom_code = r#"
+        expects({ count: 'number' });
+        count
+    "#;
+
+    let mut runner = Builder::new().build();
+    let _ = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_expects_without_eval() {
+    let custom_code = r#"
+        // @expects value: string
+        value
+    "#;
+
+    let mut runner = Builder::new()
+        .disable_language_feature(LanguageFeature::Eval)
+        .build();
+    let vars = HashMap::from([("value", "hello")]);
+    let result = runner.run(custom_code, Some(vars)).await.unwrap();
+
+    assert_eq!(result, "hello");
+}
+
+#[tokio::test]
+async fn test_expects_rejects_reserved_words() {
+    let mut runner = Builder::new().build();
+    let err = runner
+        .run::<_, String, String>("expects({ class: 'number' })", None)
+        .await
+        .unwrap_err();
+
+    assert!(format!("{:#}", err).contains("expects: invalid variable name \"class\""));
+}
@@ -0,0 +1,78 @@
+use deno_runner::{Builder, ConflictPolicy, RunOptions, RunnerError};
+use std::collections::HashMap;
+
+/// The fast path must give exactly what V8 gives
+#[tokio::test]
+async fn test_fast_path_matches_v8() {
+    let expressions = [
+        "a + b",
+        "a * 2 > b && !flag",
+        "(a - b) / 3",
+        "a % 0",
+        "flag || a",
+        "b === 2.5",
+        "ratio * 100",
+        "a + name",
+        "Math.max(a, b)",
+    ];
+
+    for code in expressions {
+        let mut results = vec![];
+
+        for fast_path in [true, false] {
+            let mut runner = Builder::new().build();
+            let vars = HashMap::from([
+                ("a", "7"),
+                ("b", "2.5"),
+                ("flag", "false"),
+                ("ratio", "0.07"),
+                ("name", "\"x\""),
+            ]);
+            let options = RunOptions::new().fast_path(fast_path);
+            // Bind the literals above as-is, not as JS strings
+            let vars: HashMap<_, _> = vars.into_iter().map(|(k, v)| (k, Literal(v))).collect();
+
+            let report = runner
+                .run_with_options(code, Some(vars), options)
+                .await
+                .unwrap();
+            results.push(report.result);
+        }
+
+        assert_eq!(results[0], results[1], "mismatch for `{}`", code);
+    }
+}
+
+struct Literal(&'static str);
+
+impl std::fmt::Display for Literal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::fmt::Debug for Literal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// Variables the normal path refuses aren't let through by the fast path
+#[tokio::test]
+async fn test_fast_path_checks_bindings() {
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("console", "1")]);
+    let options = RunOptions::new()
+        .fast_path(true)
+        .binding_conflicts(ConflictPolicy::Error);
+
+    let err = runner
+        .run_with_options("console + 1", Some(vars), options)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<RunnerError>(),
+        Some(RunnerError::BindingConflict { name, .. }) if name == "console"
+    ));
+}
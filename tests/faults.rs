@@ -0,0 +1,86 @@
+use deno_runner::{op, Builder, FaultPlan, RunOptions};
+use std::time::{Duration, Instant};
+
+#[op]
+fn fetch_data(id: u32) -> u32 {
+    id * 10
+}
+
+#[tokio::test]
+async fn test_fail_nth() {
+    let custom_code = r#"
+        const results = [];
+        for (const id of [1, 2, 3]) {
+            try {
+                results.push(fetch_data(id));
+            } catch (error) {
+                results.push(error.message);
+            }
+        }
+        results.join(",")
+    "#;
+
+    let mut runner = Builder::new().add_op(fetch_data::decl()).build();
+    let options = RunOptions::new().faults(FaultPlan::fail_nth("fetch_data", 2));
+    let report = runner
+        .run_with_options::<_, String, String>(custom_code, None, options)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        report.result,
+        "10,fetch_data: injected failure on call 2,30"
+    );
+}
+
+#[tokio::test]
+async fn test_retry_logic_under_failure() {
+    let custom_code = r#"
+        function withRetry(fn, attempts) {
+            for (let i = 1; ; i++) {
+                try {
+                    return fn();
+                } catch (error) {
+                    if (i === attempts) throw error;
+                }
+            }
+        }
+        withRetry(() => rust("fetch_data", 4), 3)
+    "#;
+
+    let plan = FaultPlan::fail_nth("fetch_data", 1).and(FaultPlan::fail_nth("fetch_data", 2));
+    let mut runner = Builder::new().add_op(fetch_data::decl()).build();
+    let report = runner
+        .run_with_options::<_, String, String>(custom_code, None, RunOptions::new().faults(plan))
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, "40");
+}
+
+#[tokio::test]
+async fn test_fail_always() {
+    let mut runner = Builder::new().add_op(fetch_data::decl()).build();
+    let options = RunOptions::new().faults(FaultPlan::fail_always("fetch_data"));
+    let result = runner
+        .run_with_options::<_, String, String>("fetch_data(1)", None, options)
+        .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_delay() {
+    let mut runner = Builder::new().add_op(fetch_data::decl()).build();
+    let options =
+        RunOptions::new().faults(FaultPlan::delay("fetch_data", Duration::from_millis(100)));
+
+    let started = Instant::now();
+    let report = runner
+        .run_with_options::<_, String, String>("fetch_data(1)", None, options)
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, "10");
+    assert!(started.elapsed() >= Duration::from_millis(100));
+}
@@ -0,0 +1,44 @@
+use deno_runner::{Builder, RunOptions};
+
+async fn run_with_flags(code: &str, options: RunOptions) -> String {
+    let mut runner = Builder::new().build();
+
+    runner
+        .run_with_options::<_, String, String>(code, None, options)
+        .await
+        .unwrap()
+        .result
+}
+
+#[tokio::test]
+async fn test_feature_flags() {
+    let options = RunOptions::new().feature_flags([("new-pricing", true), ("beta", false)]);
+    let custom_code = r#"
+        [features.isEnabled("new-pricing"), features.isEnabled("beta"), features.isEnabled("unknown")].join()
+    "#;
+
+    assert_eq!(
+        run_with_flags(custom_code, options).await,
+        "true,false,false"
+    );
+}
+
+#[tokio::test]
+async fn test_feature_flags_are_frozen() {
+    let options = RunOptions::new().feature_flags([("beta", false)]);
+    let custom_code = r#"
+        features.beta = true;
+        try { features = { isEnabled: () => true } } catch (e) {}
+        features.isEnabled("beta")
+    "#;
+
+    assert_eq!(run_with_flags(custom_code, options).await, "false");
+}
+
+#[tokio::test]
+async fn test_features_defined_without_flags() {
+    assert_eq!(
+        run_with_flags("features.isEnabled('beta')", RunOptions::new()).await,
+        "false"
+    );
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "fetch")]
+
+use deno_runner::{Builder, FetchOptions, RunOptions};
+
+#[tokio::test]
+async fn test_fetch_disabled_by_default() {
+    let mut runner = Builder::new().build();
+    let result: String = runner
+        .run::<_, String, _>("typeof fetch", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "undefined");
+}
+
+#[tokio::test]
+async fn test_fetch_disallowed_host() {
+    let mut runner = Builder::new()
+        .enable_fetch(FetchOptions::new().allow_host("api.example.com"))
+        .build();
+
+    let code = r#"
+        const response = await fetch("https://example.org/data.json")
+            .then(() => "fetched", (err) => err.message)
+        response
+    "#;
+    let result: String = runner.run::<_, String, _>(code, None).await.unwrap();
+
+    assert!(result.contains("fetch from example.org is not allowed"));
+}
+
+#[tokio::test]
+async fn test_fetch_undeclared_host() {
+    let mut runner = Builder::new().enable_fetch(FetchOptions::new()).build();
+
+    let code = r#"
+        // requires: net:api.example.com
+        fetch("https://example.org/data.json").then(() => "fetched", (err) => err.message)
+    "#;
+    let options = RunOptions::new().approve_capabilities(["net:api.example.com"]);
+    let report = runner
+        .run_with_options::<_, String, String>(code, None, options)
+        .await
+        .unwrap();
+
+    assert!(report
+        .result
+        .contains("fetch from example.org is not declared in the script's // requires: header"));
+}
+
+#[test]
+fn test_describe_fetch() {
+    let description = Builder::new().enable_fetch(FetchOptions::new()).describe();
+
+    assert_eq!(
+        description.extensions,
+        [
+            "deno_console",
+            "deno_webidl",
+            "deno_url",
+            "deno_web",
+            "deno_fetch",
+            "ops",
+            "deno_runner"
+        ]
+    );
+}
@@ -0,0 +1,30 @@
+#![cfg(feature = "fuzz")]
+
+use deno_runner::{fuzz, Builder, RunnerError};
+
+#[test]
+fn test_run_untrusted_bytes() {
+    assert_eq!(fuzz::run_untrusted_bytes(b"6 * 7").unwrap(), "42");
+    assert!(fuzz::run_untrusted_bytes(b"\xff\xfe)").is_err());
+    assert!(fuzz::run_untrusted_bytes(b"eval('1')").is_err());
+}
+
+#[test]
+fn test_limits_are_enforced() {
+    let err = fuzz::run_untrusted_bytes(b"while (true) {}").unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<RunnerError>(),
+        Some(RunnerError::Timeout(limit)) if *limit == fuzz::TIMEOUT
+    ));
+}
+
+#[test]
+fn test_panics_become_errors() {
+    let builder = Builder::new().warmup("throw new Error('bad warmup')");
+    let err = fuzz::run_untrusted_bytes_with(builder, b"1").unwrap_err();
+
+    assert!(err
+        .to_string()
+        .starts_with("deno_runner panicked: warmup script failed"));
+}
@@ -0,0 +1,49 @@
+use deno_runner::{serde_json::json, Builder};
+use serde::Deserialize;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Stats {
+    count: u32,
+    names: Vec<String>,
+}
+
+#[tokio::test]
+async fn test_globals_across_runs() {
+    let mut runner = Builder::new().build();
+    runner
+        .set_global("stats", &json!({ "count": 0, "names": [] }))
+        .unwrap();
+
+    for name in ["ada", "linus"] {
+        runner
+            .run(
+                "stats.count += 1; stats.names.push(name)",
+                Some([("name", name)].into_iter().collect()),
+            )
+            .await
+            .unwrap();
+    }
+
+    let stats: Stats = runner.get_global("stats").unwrap();
+    assert_eq!(
+        stats,
+        Stats {
+            count: 2,
+            names: vec!["ada".to_string(), "linus".to_string()],
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_missing_and_mismatched_globals() {
+    let mut runner = Builder::new().build();
+
+    let missing: Option<String> = runner.get_global("nothing").unwrap();
+    assert_eq!(missing, None);
+
+    runner.set_global("label", "total").unwrap();
+    let err = runner.get_global::<u32>("label").unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("Global `label` is not a valid u32"));
+}
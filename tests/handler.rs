@@ -0,0 +1,69 @@
+use deno_runner::{
+    handler::{Handler, Request, Response},
+    Builder, RunOptions,
+};
+use std::collections::BTreeMap;
+
+const MODULE: &str = r#"
+    let hits = 0
+
+    export function handle(request) {
+        hits += 1
+        switch (request.url.slice("https://example.com".length)) {
+            case "/text": return "plain"
+            case "/json": return { items: [request.method, request.headers["x-user"]] }
+            case "/created": return { status: 201, headers: { Location: "/items/1" } }
+            case "/hits": return { body: hits }
+            default: throw new Error(`no route for ${request.url}`)
+        }
+    }
+"#;
+
+#[tokio::test]
+async fn test_handler_responses() {
+    let handler = Handler::new(1, MODULE, Builder::new);
+    let get = |path: &str| Request::new("GET", format!("https://example.com{}", path));
+
+    let response = handler.handle(get("/text")).await.unwrap();
+    assert_eq!(
+        response,
+        Response {
+            status: 200,
+            headers: BTreeMap::new(),
+            body: "plain".to_string()
+        }
+    );
+
+    let response = handler
+        .handle(get("/json").header("X-User", "duyet"))
+        .await
+        .unwrap();
+    assert_eq!(response.body, r#"{"items":["GET","duyet"]}"#);
+    assert_eq!(response.headers["content-type"], "application/json");
+
+    let response = handler.handle(get("/created")).await.unwrap();
+    assert_eq!((response.status, response.body.as_str()), (201, ""));
+    assert_eq!(response.headers["location"], "/items/1");
+
+    // The module is evaluated once, its state lives with the runner
+    let response = handler.handle(get("/hits")).await.unwrap();
+    assert_eq!(response.body, "4");
+
+    let err = handler
+        .handle_with_options(get("/missing"), RunOptions::new().tag("route", "missing"))
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("no route for https://example.com/missing"));
+}
+
+#[tokio::test]
+async fn test_handler_without_handle_export() {
+    let handler = Handler::new(1, "export const version = 1", Builder::new);
+    let err = handler
+        .handle(Request::new("GET", "https://example.com/"))
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("handler:///main.js doesn't export a handle function"));
+}
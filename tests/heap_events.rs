@@ -0,0 +1,48 @@
+use deno_runner::{Builder, PressureLevel};
+use std::{cell::RefCell, rc::Rc};
+
+const LIMIT: usize = 32 * 1024 * 1024;
+
+#[tokio::test]
+async fn test_on_gc() {
+    let events = Rc::new(RefCell::new(vec![]));
+    let recorded = events.clone();
+    let mut runner = Builder::new()
+        .on_gc(move |event| recorded.borrow_mut().push(*event))
+        .build();
+
+    let custom_code = r#"
+        let total = 0;
+        for (let i = 0; i < 200; i++) total += new Array(10_000).fill(i).length;
+        total
+    "#;
+    runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert!(!events.borrow().is_empty());
+}
+
+#[tokio::test]
+async fn test_on_heap_pressure() {
+    let levels = Rc::new(RefCell::new(vec![]));
+    let recorded = levels.clone();
+    let mut runner = Builder::new()
+        .max_heap_size(LIMIT)
+        .on_heap_pressure(move |pressure| recorded.borrow_mut().push(pressure.level))
+        .build();
+
+    let custom_code = r#"
+        const chunks = [];
+        while (true) chunks.push(new Array(100_000).fill('x'));
+    "#;
+    assert!(runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .is_err());
+
+    let levels = levels.borrow();
+    assert_eq!(levels.first(), Some(&PressureLevel::Elevated));
+    assert_eq!(levels.last(), Some(&PressureLevel::Critical));
+}
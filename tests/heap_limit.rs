@@ -0,0 +1,40 @@
+use deno_runner::{Builder, RunnerError};
+
+const LIMIT: usize = 32 * 1024 * 1024;
+
+#[tokio::test]
+async fn test_heap_limit_exceeded() {
+    let custom_code = r#"
+        const chunks = [];
+        while (true) chunks.push(new Array(100_000).fill('x'));
+    "#;
+
+    let mut runner = Builder::new().max_heap_size(LIMIT).build();
+    let err = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap_err();
+
+    match err.downcast_ref::<RunnerError>() {
+        Some(RunnerError::HeapLimitExceeded(bytes)) => assert_eq!(*bytes, LIMIT),
+        other => panic!("unexpected error: {:?}", other),
+    }
+
+    // The isolate is usable again
+    let result = runner
+        .run::<_, String, String>("new Array(1000).fill(1).length", None)
+        .await
+        .unwrap();
+    assert_eq!(result, "1000");
+}
+
+#[tokio::test]
+async fn test_within_heap_limit() {
+    let mut runner = Builder::new().max_heap_size(LIMIT).build();
+    let result = runner
+        .run::<_, String, String>("new Array(10_000).fill('x').join('').length", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "10000");
+}
@@ -0,0 +1,68 @@
+use deno_runner::{anyhow::Result, op, Builder, MemoCache, RunOptions};
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+static CHARGES: AtomicUsize = AtomicUsize::new(0);
+
+#[op]
+fn charge(amount: f64) -> String {
+    let n = CHARGES.fetch_add(1, Ordering::SeqCst) + 1;
+    format!("ch_{}_{}", n, amount)
+}
+
+async fn run_charge(cache: &MemoCache, key: &str, amount: &str) -> Result<(String, bool)> {
+    let mut runner = Builder::new()
+        .add_op(charge::decl())
+        .memoize(cache, Duration::from_secs(60))
+        .build();
+    let vars = HashMap::from([("amount", amount)]);
+    let report = runner
+        .run_with_options(
+            "charge(Number(amount))",
+            Some(vars),
+            RunOptions::new().idempotency_key(key),
+        )
+        .await?;
+
+    Ok((report.result, report.cached))
+}
+
+#[tokio::test]
+async fn test_retry_returns_recorded_outcome() {
+    let cache = MemoCache::new();
+
+    let first = run_charge(&cache, "order-1", "25").await.unwrap();
+    let retry = run_charge(&cache, "order-1", "25").await.unwrap();
+    assert_eq!(retry.0, first.0);
+    assert!(!first.1);
+    assert!(retry.1);
+
+    let other = run_charge(&cache, "order-2", "25").await.unwrap();
+    assert_ne!(other.0, first.0);
+    assert_eq!(CHARGES.load(Ordering::SeqCst), 2);
+
+    let err = run_charge(&cache, "order-1", "30").await.unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Idempotency key was already used by a run with another script or bindings"));
+}
+
+#[tokio::test]
+async fn test_idempotency_key_needs_cache() {
+    let mut runner = Builder::new().build();
+    let err = runner
+        .run_with_options::<_, String, String>(
+            "1 + 1",
+            None,
+            RunOptions::new().idempotency_key("k"),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(err
+        .to_string()
+        .contains("Idempotency keys need a MemoCache"));
+}
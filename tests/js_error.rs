@@ -0,0 +1,67 @@
+use deno_runner::{Builder, RunOptions, RunnerError};
+
+#[tokio::test]
+async fn test_structured_error() {
+    let custom_code = "const a = 1\nfunction check(value) {\n  throw new TypeError(`bad value ${value}`)\n}\ncheck(a)";
+
+    let mut runner = Builder::new().build();
+    let err = runner
+        .run_with_options::<_, String, String>(
+            custom_code,
+            None,
+            RunOptions::new().script_name("rules/check.js"),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(err
+        .to_string()
+        .starts_with("Uncaught TypeError: bad value 1"));
+    match err.downcast_ref::<RunnerError>() {
+        Some(RunnerError::Execution(error)) => {
+            assert_eq!(error.message, "bad value 1");
+            assert_eq!(error.class.as_deref(), Some("TypeError"));
+            assert_eq!(error.resource_name.as_deref(), Some("rules/check.js"));
+            assert_eq!(error.line, Some(3));
+            assert_eq!(error.column, Some(9));
+            assert_eq!(error.frames[0].function_name.as_deref(), Some("check"));
+            assert_eq!(error.frames[1].function_name, None);
+            assert_eq!(error.frames[1].line, Some(5));
+        }
+        other => panic!("expected an execution error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_thrown_value() {
+    let mut runner = Builder::new().build();
+    let err = runner
+        .run::<_, String, String>("throw 'plain'", None)
+        .await
+        .unwrap_err();
+
+    match err.downcast_ref::<RunnerError>() {
+        Some(RunnerError::Execution(error)) => {
+            assert_eq!(error.class, None);
+            assert!(error.message.contains("plain"));
+        }
+        other => panic!("expected an execution error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_rejected_promise() {
+    let mut runner = Builder::new().build();
+    let err = runner
+        .run::<_, String, String>("await Promise.reject(new RangeError('late'))", None)
+        .await
+        .unwrap_err();
+
+    match err.downcast_ref::<RunnerError>() {
+        Some(RunnerError::Execution(error)) => {
+            assert_eq!(error.class.as_deref(), Some("RangeError"));
+            assert_eq!(error.message, "late");
+        }
+        other => panic!("expected an execution error, got {:?}", other),
+    }
+}
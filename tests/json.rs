@@ -8,7 +8,7 @@ async fn test_parse() {
         json['a'] + json['b']['c']
     "#;
 
-    let runner = Builder::new().build();
+    let mut runner = Builder::new().build();
 
     let vars = HashMap::from([("value", r#"{"a": 1, "b": {"c": 2}}"#)]);
     let expected = "3".to_string();
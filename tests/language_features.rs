@@ -0,0 +1,94 @@
+use deno_runner::{Builder, LanguageFeature};
+
+async fn run_err(builder: Builder, code: &str) -> String {
+    let mut runner = builder.build();
+    let err = runner
+        .run::<_, String, String>(code, None)
+        .await
+        .unwrap_err();
+    format!("{:#}", err)
+}
+
+#[tokio::test]
+async fn test_eval_disabled() {
+    let builder = || Builder::new().disable_language_feature(LanguageFeature::Eval);
+
+    for code in [
+        "eval('1 + 1')",
+        "new Function('return 1')()",
+        "(async function () {}).constructor('return 1')",
+    ] {
+        let err = run_err(builder(), code).await;
+        assert!(err.contains("EvalError"), "{}: {}", code, err);
+    }
+
+    // Scripts themselves still run
+    let result = builder()
+        .build()
+        .run::<_, String, String>("[1, 2].map((x) => x * 2).join()", None)
+        .await
+        .unwrap();
+    assert_eq!(result, "2,4");
+}
+
+#[tokio::test]
+async fn test_dynamic_import_disabled() {
+    let builder = Builder::new().disable_language_feature(LanguageFeature::DynamicImport);
+    let err = run_err(builder, "await import('./lib.js')").await;
+
+    assert!(
+        err.contains("Dynamic import is disabled for this runner"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_regexp_lookbehind_disabled() {
+    let builder = || Builder::new().disable_language_feature(LanguageFeature::RegExpLookbehind);
+
+    let err = run_err(builder(), r"/(?<=\$)\d+/.exec('$42')[0]").await;
+    assert!(err.contains("RegExp lookbehind is disabled for this runner"));
+
+    let err = run_err(builder(), r"new RegExp('(?<' + '=a)b')").await;
+    assert!(err.contains("SyntaxError: RegExp lookbehind is disabled"));
+
+    let err = run_err(builder(), r"/x/.constructor('(?<' + '!a)b')").await;
+    assert!(err.contains("SyntaxError: RegExp lookbehind is disabled"));
+
+    let result = builder()
+        .build()
+        .run::<_, String, String>(
+            r"[new RegExp('\\d+').exec('a1')[0], /b/ instanceof RegExp].join()",
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result, "1,true");
+}
+
+#[tokio::test]
+async fn test_weak_refs_disabled() {
+    let mut runner = Builder::new()
+        .disable_language_feature(LanguageFeature::WeakRefs)
+        .build();
+    let result = runner
+        .run::<_, String, String>("typeof WeakRef + ',' + typeof FinalizationRegistry", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "undefined,undefined");
+}
+
+#[test]
+fn test_describe_lists_disabled_features() {
+    let description = Builder::new()
+        .disable_language_feature(LanguageFeature::WeakRefs)
+        .disable_language_feature(LanguageFeature::Eval)
+        .describe();
+
+    assert_eq!(
+        description.disabled_language_features,
+        vec!["eval", "weak_refs"]
+    );
+}
@@ -0,0 +1,62 @@
+use deno_runner::{anyhow::Result, Builder};
+use std::{cell::Cell, rc::Rc};
+
+#[tokio::test]
+async fn test_lazy_binding_is_a_shared_promise() {
+    let custom_code = r#"
+        (async () => profile instanceof Promise && profile === profile && (await profile))()
+    "#;
+
+    let mut runner = Builder::new()
+        .lazy_binding("profile", || async { Result::<_>::Ok("duyet") })
+        .build();
+    let result = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "duyet");
+}
+
+#[tokio::test]
+async fn test_lazy_binding_not_resolved_when_unused() {
+    let calls = Rc::new(Cell::new(0));
+    let counter = calls.clone();
+
+    let mut runner = Builder::new()
+        .lazy_binding("profile", move || {
+            counter.set(counter.get() + 1);
+            async { Result::<_>::Ok(42) }
+        })
+        .build();
+    let result = runner
+        .run::<_, String, String>("1 + 1", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "2");
+    assert_eq!(calls.get(), 0);
+}
+
+#[tokio::test]
+async fn test_lazy_binding_resolved_again_next_run() {
+    let calls = Rc::new(Cell::new(0));
+    let counter = calls.clone();
+
+    let mut runner = Builder::new()
+        .lazy_binding("profile", move || {
+            counter.set(counter.get() + 1);
+            let call = counter.get();
+            async move { Result::<_>::Ok(format!("duyet #{}", call)) }
+        })
+        .build();
+
+    for expected in ["duyet #1", "duyet #2"] {
+        let result = runner
+            .run::<_, String, String>("(async () => (await profile) + '')()", None)
+            .await
+            .unwrap();
+        assert_eq!(result, expected);
+    }
+    assert_eq!(calls.get(), 2);
+}
@@ -0,0 +1,75 @@
+use deno_runner::{serde_json::Value, Builder};
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+struct Order {
+    price: f64,
+    qty: u32,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Line {
+    total: f64,
+}
+
+#[tokio::test]
+async fn test_map_stream() {
+    let orders = (1..=1000).map(|qty| Order { price: 0.5, qty });
+
+    let mut runner = Builder::new().build();
+    let lines: Vec<Line> = runner
+        .map_stream(
+            "(order) => ({ total: order.price * order.qty })",
+            stream::iter(orders),
+        )
+        .map(Result::unwrap)
+        .collect()
+        .await;
+
+    assert_eq!(lines.len(), 1000);
+    assert_eq!(lines[0], Line { total: 0.5 });
+    assert_eq!(lines[999], Line { total: 500.0 });
+}
+
+#[tokio::test]
+async fn test_map_stream_async_function() {
+    let mut runner = Builder::new().build();
+    let items: Vec<u32> = runner
+        .map_stream("async (n) => n * 2", stream::iter([1, 2, 3]))
+        .map(Result::unwrap)
+        .collect()
+        .await;
+
+    assert_eq!(items, vec![2, 4, 6]);
+}
+
+#[tokio::test]
+async fn test_map_stream_not_a_function() {
+    let mut runner = Builder::new().build();
+    let items: Vec<_> = runner
+        .map_stream::<_, _, Value>("42", stream::iter([1]))
+        .collect()
+        .await;
+
+    assert_eq!(items.len(), 1);
+    let err = items[0].as_ref().unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("map_stream script must evaluate to a function"));
+}
+
+#[tokio::test]
+async fn test_map_stream_error_item() {
+    let mut runner = Builder::new().build();
+    let items: Vec<_> = runner
+        .map_stream::<_, _, Value>(
+            "(n) => { if (n === 2) throw new Error('bad item'); return n }",
+            stream::iter([1, 2, 3]),
+        )
+        .collect()
+        .await;
+
+    assert_eq!(items.len(), 1);
+    assert!(matches!(&items[0], Err(err) if err.to_string().contains("bad item")));
+}
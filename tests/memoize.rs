@@ -0,0 +1,145 @@
+use deno_runner::{op, BindingMode, Builder, MemoCache, RunOptions};
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+static LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+
+#[op]
+fn lookup_rate(country: String) -> f64 {
+    LOOKUPS.fetch_add(1, Ordering::SeqCst);
+    if country == "VN" {
+        0.1
+    } else {
+        0.2
+    }
+}
+
+async fn evaluate(cache: &MemoCache, country: &str, options: RunOptions) -> (String, bool) {
+    let mut runner = Builder::new()
+        .add_op(lookup_rate::decl())
+        .memoize(cache, Duration::from_secs(60))
+        .build();
+    let vars = HashMap::from([("country", country), ("amount", "100")]);
+    let report = runner
+        .run_with_options(
+            "Number(amount) * (country === 'VN' ? 0.1 : 0.2)",
+            Some(vars),
+            options,
+        )
+        .await
+        .unwrap();
+
+    (report.result, report.cached)
+}
+
+#[tokio::test]
+async fn test_memoized_result() {
+    let cache = MemoCache::new();
+    let pure = || RunOptions::new().pure(true);
+
+    assert_eq!(
+        evaluate(&cache, "VN", pure()).await,
+        ("10".to_string(), false)
+    );
+    assert_eq!(
+        evaluate(&cache, "VN", pure()).await,
+        ("10".to_string(), true)
+    );
+    assert_eq!(
+        evaluate(&cache, "US", pure()).await,
+        ("20".to_string(), false)
+    );
+    assert_eq!(cache.len(), 2);
+
+    // Same script and bindings, but a result-affecting option differs
+    let options = pure().binding_mode(BindingMode::Const);
+    assert_eq!(
+        evaluate(&cache, "VN", options).await,
+        ("10".to_string(), false)
+    );
+}
+
+#[tokio::test]
+async fn test_impure_runs_are_not_cached() {
+    let cache = MemoCache::new();
+    let run = || async {
+        let mut runner = Builder::new()
+            .add_op(lookup_rate::decl())
+            .memoize(&cache, Duration::from_secs(60))
+            .build();
+        let vars = HashMap::from([("country", "VN")]);
+        runner
+            .run_with_options("lookup_rate(country)", Some(vars), RunOptions::new())
+            .await
+            .unwrap()
+    };
+
+    assert!(!run().await.cached);
+    assert!(!run().await.cached);
+    assert_eq!(LOOKUPS.load(Ordering::SeqCst), 2);
+    assert!(cache.is_empty());
+}
+
+#[tokio::test]
+async fn test_expired_result_runs_again() {
+    let cache = MemoCache::new();
+    let run = || async {
+        Builder::new()
+            .memoize(&cache, Duration::ZERO)
+            .build()
+            .run_with_options::<_, String, String>("1 + 1", None, RunOptions::new())
+            .await
+            .unwrap()
+    };
+
+    assert!(!run().await.cached);
+    assert!(!run().await.cached);
+}
+
+#[tokio::test]
+async fn test_errors_are_not_cached() {
+    let cache = MemoCache::new();
+    let result = Builder::new()
+        .memoize(&cache, Duration::from_secs(60))
+        .build()
+        .run_with_options::<_, String, String>("missing", None, RunOptions::new().pure(true))
+        .await;
+
+    assert!(result.is_err());
+    assert!(cache.is_empty());
+}
+
+#[tokio::test]
+async fn test_runs_reading_leftover_globals_are_not_cached() {
+    let cache = MemoCache::new();
+    let mut runner = Builder::new()
+        .memoize(&cache, Duration::from_secs(60))
+        .build();
+    let pure = || RunOptions::new().pure(true);
+
+    runner
+        .run::<_, String, String>("globalThis.rate = 0.1", None)
+        .await
+        .unwrap();
+    let report = runner
+        .run_with_options::<_, String, String>("100 * rate", None, pure())
+        .await
+        .unwrap();
+    assert_eq!(report.result, "10");
+    assert!(cache.is_empty());
+
+    // The same script over different leftovers gives its own result
+    runner
+        .run::<_, String, String>("globalThis.rate = 0.2", None)
+        .await
+        .unwrap();
+    let report = runner
+        .run_with_options::<_, String, String>("100 * rate", None, pure())
+        .await
+        .unwrap();
+    assert_eq!(report.result, "20");
+    assert!(!report.cached);
+}
@@ -0,0 +1,47 @@
+use deno_runner::{serde_json::json, Builder, MemoryModuleLoader, Module};
+
+#[tokio::test]
+async fn test_import_embedded_modules() {
+    let loader: MemoryModuleLoader = [
+        ("app:utils", "export default (n) => n * 2;"),
+        (
+            "app:pricing",
+            "import double from 'app:utils'; export const total = (n) => double(n) + 1;",
+        ),
+    ]
+    .into_iter()
+    .collect();
+
+    let mut runner = Builder::new().module_loader(loader).build();
+    let result = runner
+        .run_module::<String, String>(
+            Module::Source(
+                "import helper from 'app:utils'; import { total } from 'app:pricing'; export default [helper(4), total(4)];",
+            ),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result, json!([8, 9]));
+}
+
+#[tokio::test]
+async fn test_unregistered_module() {
+    let loader = MemoryModuleLoader::new().with_module("app:utils", "export default 1;");
+    let mut runner = Builder::new().module_loader(loader).build();
+
+    let err = runner
+        .run_module::<String, String>(
+            Module::Source("import secret from 'file:///etc/passwd'; export default secret;"),
+            None,
+        )
+        .await
+        .unwrap_err();
+
+    assert!(
+        format!("{:#}", err).contains("Module not registered with the loader: file:///etc/passwd"),
+        "{:#}",
+        err
+    );
+}
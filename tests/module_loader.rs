@@ -0,0 +1,47 @@
+use deno_runner::{serde_json::json, Builder, FsModuleLoader, Module};
+use std::fs;
+
+#[tokio::test]
+async fn test_imports_disabled_by_default() {
+    let mut runner = Builder::new().build();
+
+    let err = runner
+        .run_module::<String, String>(
+            Module::Source("import { answer } from '/etc/answer.js'; export default answer;"),
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("Imports are disabled for this runner"),
+        "{:#}",
+        err
+    );
+
+    // Modules without imports still run
+    let result = runner
+        .run_module::<String, String>(Module::Source("export default 6 * 7;"), None)
+        .await
+        .unwrap();
+    assert_eq!(result, json!(42));
+}
+
+#[tokio::test]
+async fn test_fs_module_loader() {
+    let dir = std::env::temp_dir().join(format!("deno_runner_loader_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("answer.js"), "export const answer = 42;").unwrap();
+
+    let mut runner = Builder::new().module_loader(FsModuleLoader).build();
+    let source = format!(
+        "import {{ answer }} from {:?}; export default answer;",
+        dir.join("answer.js").display().to_string()
+    );
+    let result = runner
+        .run_module::<String, String>(Module::Source(&source), None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, json!(42));
+    assert_eq!(runner.describe().module_loader, "custom");
+}
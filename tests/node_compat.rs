@@ -0,0 +1,78 @@
+use deno_runner::{Builder, NodeCompat};
+
+async fn run_node(code: &str) -> String {
+    let compat = NodeCompat::new().env("NODE_ENV", "test");
+    let mut runner = Builder::new().node_compat(compat).build();
+
+    runner.run::<_, String, String>(code, None).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_buffer() {
+    assert_eq!(
+        run_node("Buffer.from('hello').toString('base64')").await,
+        "aGVsbG8="
+    );
+    assert_eq!(
+        run_node("Buffer.from('aGVsbG8=', 'base64').toString()").await,
+        "hello"
+    );
+    assert_eq!(run_node("Buffer.from('hi').toString('hex')").await, "6869");
+    assert_eq!(
+        run_node("Buffer.from(new Uint8Array([104, 105]).buffer).toString()").await,
+        "hi"
+    );
+}
+
+#[tokio::test]
+async fn test_process_env_is_filtered() {
+    assert_eq!(run_node("process.env.NODE_ENV").await, "test");
+    assert_eq!(run_node("process.env.PATH").await, "undefined");
+}
+
+#[tokio::test]
+async fn test_require_bundled_modules() {
+    assert_eq!(
+        run_node("const path = require('path'); path.join('/a/b', '../c', 'd.js')").await,
+        "/a/c/d.js"
+    );
+    assert_eq!(
+        run_node("require('node:path').extname('index.test.js')").await,
+        ".js"
+    );
+    assert_eq!(
+        run_node("require('querystring').parse('a=1&b=x%20y&a=2').a.join(',')").await,
+        "1,2"
+    );
+    assert_eq!(
+        run_node("require('querystring').stringify({ q: 'a b', n: 1 })").await,
+        "q=a%20b&n=1"
+    );
+    assert_eq!(
+        run_node("require('querystring').parse('a=100%&b=x+y').a").await,
+        "100%"
+    );
+    assert_eq!(
+        run_node("require('querystring').unescape('%E0%A4%A')").await,
+        "%E0%A4%A"
+    );
+}
+
+#[tokio::test]
+async fn test_require_unknown_module() {
+    assert_eq!(
+        run_node("try { require('fs') } catch (e) { e.code }").await,
+        "MODULE_NOT_FOUND"
+    );
+}
+
+#[tokio::test]
+async fn test_node_compat_is_opt_in() {
+    let mut runner = Builder::new().build();
+    let result = runner
+        .run::<_, String, String>("typeof Buffer + typeof require", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "undefinedundefined");
+}
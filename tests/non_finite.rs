@@ -0,0 +1,95 @@
+use deno_runner::{serde_json::json, Builder, NonFinite, RunOptions};
+
+const CODE: &str = "({ ratio: 0 / 0, max: 1 / 0, min: -1 / 0, ok: 1.5 })";
+
+#[tokio::test]
+async fn test_json_default_is_null() {
+    let mut runner = Builder::new().build();
+    let value = runner.run_json::<String, String>(CODE, None).await.unwrap();
+
+    assert_eq!(
+        value,
+        json!({ "ratio": null, "max": null, "min": null, "ok": 1.5 })
+    );
+}
+
+#[tokio::test]
+async fn test_run_default_is_unchanged() {
+    let mut runner = Builder::new().build();
+    let result = runner
+        .run::<_, String, String>("0 / 0", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "NaN");
+}
+
+#[tokio::test]
+async fn test_string_policy() {
+    let mut runner = Builder::new().build();
+    let options = RunOptions::new().non_finite(NonFinite::String);
+
+    let json = runner
+        .run_json_with_options::<String, String>(CODE, None, options.clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        json,
+        json!({ "ratio": "NaN", "max": "Infinity", "min": "-Infinity", "ok": 1.5 })
+    );
+
+    let report = runner
+        .run_with_options::<_, String, String>("-1 / 0", None, options)
+        .await
+        .unwrap();
+    assert_eq!(report.result, "-Infinity");
+}
+
+#[tokio::test]
+async fn test_null_policy_for_run() {
+    let mut runner = Builder::new().build();
+    let report = runner
+        .run_with_options::<_, String, String>(
+            "0 / 0",
+            None,
+            RunOptions::new().non_finite(NonFinite::Null),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, "null");
+}
+
+#[tokio::test]
+async fn test_error_policy() {
+    let mut runner = Builder::new().build();
+    let options = RunOptions::new().non_finite(NonFinite::Error);
+
+    let err = runner
+        .run_json_with_options::<String, String>(CODE, None, options.clone())
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Result contains NaN at key \"ratio\""));
+
+    let err = runner
+        .run_with_options::<_, String, String>("1 / 0", None, options)
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Result is Infinity");
+}
+
+#[tokio::test]
+async fn test_fast_path_follows_policy() {
+    let mut runner = Builder::new().build();
+    let options = RunOptions::new()
+        .fast_path(true)
+        .non_finite(NonFinite::Null);
+
+    let report = runner
+        .run_with_options::<_, String, String>("1 / 0", None, options)
+        .await
+        .unwrap();
+    assert_eq!(report.result, "null");
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "npm")]
+
+use deno_runner::{serde_json::json, Builder, Module, NpmModuleLoader, Permissions};
+use std::{fs, path::PathBuf};
+
+/// Cache dir with two versions of a fake `lodash-es` and a scoped package.
+fn cache_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("deno_runner_npm_{}", std::process::id()));
+    for (version, size) in [("4.17.20", 1), ("4.17.21", 2), ("3.10.1", 3)] {
+        let package = dir.join("lodash-es").join(version);
+        fs::create_dir_all(&package).unwrap();
+        fs::write(
+            package.join("package.json"),
+            r#"{ "name": "lodash-es", "module": "lodash.js" }"#,
+        )
+        .unwrap();
+        fs::write(
+            package.join("lodash.js"),
+            "export { chunk } from './chunk.js';",
+        )
+        .unwrap();
+        fs::write(
+            package.join("chunk.js"),
+            format!(
+                "export const chunk = (xs) => xs.length ? [xs.slice(0, {0}), ...chunk(xs.slice({0}))] : [];",
+                size
+            ),
+        )
+        .unwrap();
+    }
+
+    let scoped = dir.join("@acme").join("tax").join("1.0.0");
+    fs::create_dir_all(&scoped).unwrap();
+    fs::write(
+        scoped.join("package.json"),
+        r#"{ "exports": { ".": { "import": "./index.mjs" } } }"#,
+    )
+    .unwrap();
+    fs::write(scoped.join("index.mjs"), "export default (n) => n * 1.1;").unwrap();
+    dir
+}
+
+async fn run(source: &str) -> deno_runner::anyhow::Result<deno_runner::serde_json::Value> {
+    let mut runner = Builder::new()
+        .module_loader(NpmModuleLoader::new(cache_dir()))
+        .build();
+    runner
+        .run_module::<String, String>(Module::Source(source), None)
+        .await
+}
+
+#[tokio::test]
+async fn test_npm_imports_from_cache() {
+    let result = run(r#"
+        import { chunk } from "npm:lodash-es@4";
+        import { chunk as oldChunk } from "npm:lodash-es@3/chunk.js";
+        import tax from "npm:@acme/tax";
+        export default [chunk([1, 2, 3]), oldChunk([1, 2, 3]), tax(10)];
+    "#)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        result,
+        json!([[[1, 2], [3]], [[1, 2, 3]], 11.000000000000002])
+    );
+}
+
+#[tokio::test]
+async fn test_npm_package_not_cached() {
+    let err = run("import left from 'npm:left-pad@1'; export default left;")
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("npm package left-pad@1 is not in the cache"),
+        "{:#}",
+        err
+    );
+
+    let err = run("import x from 'npm:lodash-es@4/../../../etc/passwd'; export default x;")
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("Invalid npm"), "{:#}", err);
+}
+
+#[tokio::test]
+async fn test_npm_downloads_need_net_permission() {
+    let cache = cache_dir();
+    let mut runner = Builder::new()
+        .module_loader(NpmModuleLoader::new(&cache).registry("https://registry.npmjs.org"))
+        .permissions(Permissions::new().allow_read([&cache]))
+        .build();
+
+    let err = runner
+        .run_module::<String, String>(
+            Module::Source("import left from 'npm:left-pad@1'; export default left;"),
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("Requires net access to \"registry.npmjs.org\""),
+        "{:#}",
+        err
+    );
+
+    // Cached packages still load
+    let result = runner
+        .run_module::<String, String>(
+            Module::Source("import { chunk } from 'npm:lodash-es@4'; export default chunk([1]);"),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result, json!([[1]]));
+}
@@ -0,0 +1,61 @@
+use deno_runner::{Builder, NumberFormat, RunOptions};
+
+async fn run_formatted(code: &str, format: NumberFormat) -> String {
+    let mut runner = Builder::new().build();
+    let options = RunOptions::new().number_format(format);
+
+    runner
+        .run_with_options::<_, String, String>(code, None, options)
+        .await
+        .unwrap()
+        .result
+}
+
+#[tokio::test]
+async fn test_default_number_format() {
+    assert_eq!(
+        run_formatted("0.1 + 0.2", NumberFormat::Default).await,
+        "0.30000000000000004"
+    );
+}
+
+#[tokio::test]
+async fn test_fixed_number_format() {
+    assert_eq!(
+        run_formatted("0.1 + 0.2", NumberFormat::Fixed(2)).await,
+        "0.30"
+    );
+    assert_eq!(run_formatted("2.5", NumberFormat::Fixed(0)).await, "3");
+}
+
+#[tokio::test]
+async fn test_precision_number_format() {
+    assert_eq!(
+        run_formatted("1234.5", NumberFormat::Precision(3)).await,
+        "1.23e+3"
+    );
+}
+
+#[tokio::test]
+async fn test_locale_number_format() {
+    assert_eq!(
+        run_formatted("1234.5", NumberFormat::Locale("en-US".to_string())).await,
+        "1,234.5"
+    );
+}
+
+#[tokio::test]
+async fn test_number_format_ignores_non_numbers() {
+    assert_eq!(run_formatted("'0.1'", NumberFormat::Fixed(3)).await, "0.1");
+}
+
+#[tokio::test]
+async fn test_invalid_number_format() {
+    let mut runner = Builder::new().build();
+    let options = RunOptions::new().number_format(NumberFormat::Fixed(1000));
+    let result = runner
+        .run_with_options::<_, String, String>("1", None, options)
+        .await;
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,99 @@
+use deno_runner::{op, Builder};
+
+#[op]
+fn echo(text: String) -> String {
+    text
+}
+
+#[op]
+fn repeat(text: String, times: usize) -> String {
+    text.repeat(times)
+}
+
+fn runner() -> deno_runner::DenoRunner {
+    Builder::new()
+        .add_op(echo::decl())
+        .add_op(repeat::decl())
+        .op_payload_limit(1024)
+        .build()
+}
+
+#[tokio::test]
+async fn test_within_limit() {
+    let result = runner()
+        .run::<_, String, String>("echo('x'.repeat(100)).length", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "100");
+}
+
+#[tokio::test]
+async fn test_oversized_arguments() {
+    for code in [
+        "echo('x'.repeat(2048))",
+        "Deno.core.opSync('echo', 'x'.repeat(2048))",
+        "await rustAsync('echo', 'x'.repeat(2048))",
+        "echo(new Uint8Array(4096))",
+        "echo(Array.from({ length: 500 }, (_, i) => ({ i })))",
+    ] {
+        let err = runner()
+            .run::<_, String, String>(code, None)
+            .await
+            .unwrap_err();
+
+        assert!(
+            err.to_string()
+                .contains("arguments are larger than the op payload limit of 1024 bytes"),
+            "{}: {}",
+            code,
+            err
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_oversized_result() {
+    let err = runner()
+        .run::<_, String, String>("repeat('x', 4096)", None)
+        .await
+        .unwrap_err();
+
+    assert!(err
+        .to_string()
+        .contains("repeat: result is larger than the op payload limit of 1024 bytes"));
+}
+
+#[tokio::test]
+async fn test_structured_exception() {
+    let custom_code = r#"
+        try {
+            echo("x".repeat(2048))
+        } catch (error) {
+            [error.name, error.code, error.op, error.limit].join(",")
+        }
+    "#;
+
+    let result = runner()
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "RangeError,ERR_OP_PAYLOAD_TOO_LARGE,echo,1024");
+}
+
+#[tokio::test]
+async fn test_spoofed_byte_length() {
+    let custom_code = r#"
+        const bytes = new Uint8Array(4096)
+        Object.defineProperty(bytes, "byteLength", { value: 1 })
+        echo(bytes)
+    "#;
+
+    let err = runner()
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("op payload limit"));
+}
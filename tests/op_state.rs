@@ -0,0 +1,64 @@
+use deno_runner::{op, Builder, OpState};
+use std::{cell::Cell, rc::Rc};
+
+struct AppConfig {
+    currency: String,
+}
+
+struct Counter(Rc<Cell<u32>>);
+
+#[op]
+fn currency(state: &mut OpState) -> String {
+    state.borrow::<AppConfig>().currency.clone()
+}
+
+#[op]
+fn next_id(state: &mut OpState) -> u32 {
+    let counter = state.borrow::<Counter>();
+    counter.0.set(counter.0.get() + 1);
+    counter.0.get()
+}
+
+#[tokio::test]
+async fn test_state() {
+    let counter = Rc::new(Cell::new(0));
+    let shared = counter.clone();
+
+    let mut runner = Builder::new()
+        .add_op(currency::decl())
+        .add_op(next_id::decl())
+        .state(|state| {
+            state.put(AppConfig {
+                currency: "EUR".to_string(),
+            })
+        })
+        .state(move |state| state.put(Counter(shared.clone())))
+        .build();
+
+    let result = runner
+        .run::<_, String, String>("`${currency()}:${next_id()}:${next_id()}`", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "EUR:1:2");
+    assert_eq!(counter.get(), 2);
+}
+
+#[tokio::test]
+async fn test_state_per_runner() {
+    let builder = Builder::new().add_op(currency::decl()).state(|state| {
+        state.put(AppConfig {
+            currency: "USD".to_string(),
+        })
+    });
+
+    for _ in 0..2 {
+        let result = builder
+            .clone()
+            .build()
+            .run::<_, String, String>("currency()", None)
+            .await
+            .unwrap();
+        assert_eq!(result, "USD");
+    }
+}
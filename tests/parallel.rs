@@ -0,0 +1,37 @@
+use deno_runner::Builder;
+
+// Every task returns a promise that never settles, so the number of started
+// tasks is exactly the number of workers `parallel()` spawned.
+const PENDING_TASKS: &str = r#"
+    let started = 0;
+    const tasks = [1, 2, 3, 4].map(() => () => {
+        started++;
+        return new Promise(() => {});
+    });
+"#;
+
+#[tokio::test]
+async fn test_parallel_respects_limit() {
+    let custom_code = format!("{} parallel(tasks, {{ limit: 2 }}); started", PENDING_TASKS);
+
+    let mut runner = Builder::new().build();
+    let result = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "2");
+}
+
+#[tokio::test]
+async fn test_parallel_builder_default_limit() {
+    let custom_code = format!("{} parallel(tasks); started", PENDING_TASKS);
+
+    let mut runner = Builder::new().parallel_limit(3).build();
+    let result = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "3");
+}
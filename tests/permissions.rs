@@ -0,0 +1,83 @@
+use deno_runner::{
+    anyhow::Result, op, serde_json::json, Builder, FsModuleLoader, Module, NodeCompat, OpState,
+    Permissions,
+};
+use std::{fs, path::PathBuf};
+
+fn module_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("deno_runner_{}_{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("answer.js"), "export const answer = 42;").unwrap();
+    dir
+}
+
+#[tokio::test]
+async fn test_imports_need_read_access() {
+    let allowed = module_dir("allowed");
+    let denied = module_dir("denied");
+    let mut runner = Builder::new()
+        .module_loader(FsModuleLoader)
+        .permissions(Permissions::new().allow_read([&allowed]))
+        .build();
+
+    let source = format!(
+        "import {{ answer }} from {:?}; export default answer;",
+        allowed.join("answer.js").display().to_string()
+    );
+    let result = runner
+        .run_module::<String, String>(Module::Source(&source), None)
+        .await
+        .unwrap();
+    assert_eq!(result, json!(42));
+
+    let source = format!(
+        "import {{ answer }} from {:?}; export default answer;",
+        denied.join("answer.js").display().to_string()
+    );
+    let err = runner
+        .run_module::<String, String>(Module::Source(&source), None)
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("Requires read access"));
+}
+
+#[tokio::test]
+async fn test_env_needs_env_access() {
+    std::env::set_var("DENO_RUNNER_PERMISSIONS_TOKEN", "s3cr3t");
+    std::env::set_var("DENO_RUNNER_PERMISSIONS_REGION", "eu");
+    let compat = NodeCompat::new()
+        .allow_env("DENO_RUNNER_PERMISSIONS_TOKEN")
+        .allow_env("DENO_RUNNER_PERMISSIONS_REGION")
+        .env("MODE", "test");
+
+    let mut runner = Builder::new()
+        .node_compat(compat)
+        .permissions(Permissions::new().allow_env(["DENO_RUNNER_PERMISSIONS_REGION"]))
+        .build();
+    let result = runner
+        .run_json::<String, String>("Object.keys(process.env)", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, json!(["DENO_RUNNER_PERMISSIONS_REGION", "MODE"]));
+}
+
+#[op]
+fn read_text(state: &mut OpState, path: String) -> Result<String> {
+    state.borrow::<Permissions>().check_read(path.as_ref())?;
+    Ok(fs::read_to_string(path)?)
+}
+
+#[tokio::test]
+async fn test_host_ops_check_permissions() {
+    let mut runner = Builder::new()
+        .add_op(read_text::decl())
+        .permissions(Permissions::new())
+        .build();
+
+    let err = runner
+        .run::<_, String, String>("rust('read_text', '/etc/hostname')", None)
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("Requires read access to \"/etc/hostname\""));
+}
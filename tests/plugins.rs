@@ -0,0 +1,10 @@
+#![cfg(feature = "plugins")]
+
+use deno_runner::Builder;
+
+#[test]
+fn test_missing_plugin() {
+    let result = unsafe { Builder::new().load_plugin("/nonexistent/libplugin.so") };
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,185 @@
+use deno_runner::{serde_json::json, Builder, HeapLeak, RunOptions, RunnerPool, ShutdownOutcome};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_pool_runs_concurrently() {
+    let pool = Arc::new(RunnerPool::new(4, || Builder::new().build()));
+    assert_eq!(pool.size(), 4);
+
+    let runs = (0..32).map(|i| {
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            let vars = HashMap::from([("value", i)]);
+            pool.run("value * 2", Some(vars)).await.unwrap()
+        })
+    });
+
+    let results = futures::future::join_all(runs).await;
+    for (i, result) in results.into_iter().enumerate() {
+        assert_eq!(result.unwrap(), (i * 2).to_string());
+    }
+}
+
+#[tokio::test]
+async fn test_pool_binds_json_values() {
+    let pool = RunnerPool::new(1, || Builder::new().build());
+    let vars = HashMap::from([("user", json!({ "name": "duyet", "tags": ["a"] }))]);
+
+    let report = pool
+        .run_with_options(
+            "`${user.name} ${user.tags.length}`",
+            Some(vars),
+            RunOptions::new().tag("tenant", "acme"),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, "duyet 1");
+    assert_eq!(report.tags["tenant"], "acme");
+}
+
+#[tokio::test]
+async fn test_pool_error() {
+    let pool = RunnerPool::new(1, || Builder::new().build());
+    let result = pool.run::<String, String>("missing", None).await;
+
+    assert!(result.is_err());
+    // The runner is still usable
+    assert_eq!(
+        pool.run::<String, String>("1 + 1", None).await.unwrap(),
+        "2"
+    );
+}
+
+#[tokio::test]
+async fn test_pool_recycles_runners() {
+    let pool = RunnerPool::new(1, || Builder::new().build()).recycle_after(2);
+    let code = "globalThis.count = (globalThis.count ?? 0) + 1";
+
+    let mut counts = vec![];
+    for _ in 0..4 {
+        counts.push(pool.run::<String, String>(code, None).await.unwrap());
+    }
+
+    assert_eq!(counts, ["1", "2", "1", "2"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_pool_shutdown_terminates_busy_runners() {
+    let pool = Arc::new(RunnerPool::new(3, || Builder::new().build()));
+    let run = |code: &'static str| {
+        let pool = pool.clone();
+        tokio::spawn(async move { pool.run::<String, String>(code, None).await })
+    };
+    let sleeping = run("await new Promise((resolve) => setTimeout(resolve, 60_000))");
+    let spinning = run("while (true) {}");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let mut outcomes = pool.shutdown(Duration::from_millis(50)).await;
+    outcomes.sort_by_key(|outcome| *outcome != ShutdownOutcome::Drained);
+    assert_eq!(
+        outcomes,
+        [
+            ShutdownOutcome::Drained,
+            ShutdownOutcome::Terminated,
+            ShutdownOutcome::Terminated
+        ]
+    );
+
+    assert!(sleeping.await.unwrap().is_err());
+    assert!(spinning.await.unwrap().is_err());
+    let refused = pool.run::<String, String>("1", None).await.unwrap_err();
+    assert_eq!(refused.to_string(), "Runner pool is shut down");
+    assert!(pool.shutdown(Duration::ZERO).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_pool_reports_heap_leaks() {
+    let leaks = Arc::new(Mutex::new(Vec::<HeapLeak>::new()));
+    let pool = RunnerPool::new(1, || Builder::new().build()).on_heap_leak(1 << 20, {
+        let leaks = leaks.clone();
+        move |leak| leaks.lock().unwrap().push(leak.clone())
+    });
+
+    let vars = HashMap::from([("value", 1)]);
+    for code in [
+        "1 + 1",
+        "globalThis.cache = new Array(1 << 20).fill(value); cache.length",
+        "globalThis.cache = null",
+    ] {
+        let options = RunOptions::new()
+            .script_name("cache.js")
+            .tag("tenant", "acme");
+        pool.run_with_options(code, Some(vars.clone()), options)
+            .await
+            .unwrap();
+    }
+    // Leaks are reported once the result is sent, wait for the runner
+    pool.shutdown(Duration::from_secs(5)).await;
+
+    let leaks = leaks.lock().unwrap();
+    assert_eq!(leaks.len(), 1, "{:?}", leaks);
+    assert_eq!(leaks[0].script_name, "cache.js");
+    assert_eq!(leaks[0].tags["tenant"], "acme");
+    assert_eq!(leaks[0].new_globals, ["cache"]);
+    assert!(leaks[0].growth() >= 1 << 20);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_pool_grows_under_load_and_shrinks_when_idle() {
+    let pool = Arc::new(
+        RunnerPool::new(1, || Builder::new().build())
+            .max_size(3)
+            .idle_timeout(Duration::from_millis(100)),
+    );
+    assert_eq!(pool.size(), 1);
+
+    let runs: Vec<_> = (0..3)
+        .map(|_| {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                let code = "const end = Date.now() + 300; while (Date.now() < end) {}; 1";
+                pool.run::<String, String>(code, None).await.unwrap()
+            })
+        })
+        .collect();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(pool.size(), 3);
+    for result in futures::future::join_all(runs).await {
+        assert_eq!(result.unwrap(), "1");
+    }
+
+    // The runners started for the load stop, down to the pool's size
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(pool.size(), 1);
+    assert_eq!(
+        pool.run::<String, String>("1 + 1", None).await.unwrap(),
+        "2"
+    );
+    assert_eq!(pool.size(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_pool_shutdown_fails_queued_runs() {
+    let pool = Arc::new(RunnerPool::new(1, || Builder::new().build()));
+    let run = |code: &'static str| {
+        let pool = pool.clone();
+        tokio::spawn(async move { pool.run::<String, String>(code, None).await })
+    };
+    let spinning = run("while (true) {}");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    // More runs than runners, left waiting behind the busy one
+    let queued: Vec<_> = ["1", "2", "3"].into_iter().map(run).collect();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let outcomes = pool.shutdown(Duration::from_millis(50)).await;
+    assert_eq!(outcomes, [ShutdownOutcome::Terminated]);
+    assert!(spinning.await.unwrap().is_err());
+    for run in queued {
+        assert!(run.await.unwrap().is_err());
+    }
+}
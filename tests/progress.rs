@@ -0,0 +1,68 @@
+use deno_runner::{Builder, Progress};
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+fn recording_builder() -> (Builder, Rc<RefCell<Vec<Progress>>>) {
+    let updates = Rc::new(RefCell::new(vec![]));
+    let sink = updates.clone();
+    let builder = Builder::new()
+        .progress_interval(Duration::from_secs(60))
+        .on_progress(move |progress| sink.borrow_mut().push(progress.clone()));
+    (builder, updates)
+}
+
+#[tokio::test]
+async fn test_progress_updates() {
+    let custom_code = r#"
+        for (let i = 0; i <= 4; i++) progress.report("load", i * 25)
+        progress.report("transform", 0)
+        progress.report("transform", 50)
+        "done"
+    "#;
+
+    let (builder, updates) = recording_builder();
+    let mut runner = builder.build();
+    runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    let updates: Vec<(String, f64)> = updates
+        .borrow()
+        .iter()
+        .map(|progress| (progress.stage.clone(), progress.percent))
+        .collect();
+    assert_eq!(
+        updates,
+        [
+            ("load".to_string(), 0.0),
+            ("load".to_string(), 100.0),
+            ("transform".to_string(), 0.0),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_progress_out_of_range() {
+    let (builder, updates) = recording_builder();
+    let mut runner = builder.build();
+
+    let err = runner
+        .run::<_, String, String>("progress.report('load', 120)", None)
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("RangeError: Progress must be between 0 and 100, got 120"));
+    assert!(updates.borrow().is_empty());
+}
+
+#[tokio::test]
+async fn test_progress_without_hooks() {
+    let mut runner = Builder::new().build();
+    let result = runner
+        .run::<_, String, String>("progress.report('load', 10); 'ok'", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "ok");
+}
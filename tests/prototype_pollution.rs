@@ -0,0 +1,88 @@
+use deno_runner::{Builder, NodeCompat};
+
+async fn run(builder: Builder, code: &str) -> String {
+    builder
+        .build()
+        .run::<_, String, String>(code, None)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_console_survives_patched_prototypes() {
+    let custom_code = r#"
+        Array.prototype.map = () => { throw new Error("hijacked map") };
+        Array.prototype.join = () => { throw new Error("hijacked join") };
+        String.prototype.split = () => { throw new Error("hijacked split") };
+        Map.prototype.set = () => { throw new Error("hijacked set") };
+        JSON.stringify = () => { throw new Error("hijacked stringify") };
+
+        console.log("still", "works");
+        console.time("t");
+        console.timeEnd("t");
+        console.table([{ a: 1 }]);
+        "ok"
+    "#;
+
+    assert_eq!(run(Builder::new(), custom_code).await, "ok");
+}
+
+#[tokio::test]
+async fn test_parallel_ignores_polluted_options() {
+    let custom_code = r#"
+        Object.prototype.limit = 100;
+        let started = 0;
+        const tasks = [1, 2, 3, 4].map(() => () => {
+            started++;
+            return new Promise(() => {});
+        });
+        parallel(tasks, {});
+        started
+    "#;
+
+    assert_eq!(
+        run(Builder::new().parallel_limit(2), custom_code).await,
+        "2"
+    );
+}
+
+#[tokio::test]
+async fn test_expects_survives_patched_prototypes() {
+    let custom_code = r#"
+        String.prototype.endsWith = () => true;
+        RegExp.prototype.test = () => false;
+        expects({ value: 'string' });
+        value
+    "#;
+
+    let mut runner = Builder::new().build();
+    let vars = std::collections::HashMap::from([("value", "hello")]);
+    let result = runner.run(custom_code, Some(vars)).await.unwrap();
+
+    assert_eq!(result, "hello");
+}
+
+#[tokio::test]
+async fn test_host_hooks_cannot_be_replaced() {
+    let custom_code = r#"
+        Deno.core.takeExitStatus = () => ({ code: 0, value: "forged" });
+        throw new Error("boom");
+    "#;
+
+    let mut runner = Builder::new().build();
+    let result = runner.run::<_, String, String>(custom_code, None).await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_require_ignores_prototype_keys() {
+    let custom_code = r#"
+        try { require("constructor"); "resolved" } catch (e) { e.code }
+    "#;
+
+    assert_eq!(
+        run(Builder::new().node_compat(NodeCompat::new()), custom_code).await,
+        "MODULE_NOT_FOUND"
+    );
+}
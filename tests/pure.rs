@@ -0,0 +1,154 @@
+use deno_runner::{op, Builder, RunOptions, VirtualFs};
+use std::collections::HashMap;
+
+#[op]
+fn save(value: u32) -> u32 {
+    value
+}
+
+async fn run_pure(code: &str) -> deno_runner::anyhow::Result<String> {
+    let mut runner = Builder::new().add_op(save::decl()).build();
+    let vars = HashMap::from([("price", 10)]);
+    let report = runner
+        .run_with_options(code, Some(vars), RunOptions::new().pure(true))
+        .await?;
+
+    Ok(report.result)
+}
+
+#[tokio::test]
+async fn test_pure_computation() {
+    let result = run_pure("[price * 2, new Date(0).toISOString()].join(' ')")
+        .await
+        .unwrap();
+
+    assert_eq!(result, "20 1970-01-01T00:00:00.000Z");
+}
+
+#[tokio::test]
+async fn test_pure_denies_impure_calls() {
+    for (code, message) in [
+        ("save(price)", "op save is not allowed in a pure run"),
+        (
+            "rust('save', price)",
+            "op save is not allowed in a pure run",
+        ),
+        (
+            "Deno.core.opSync('save', price)",
+            "op save is not allowed in a pure run",
+        ),
+        (
+            "Math.random()",
+            "Math.random() is not allowed in a pure run",
+        ),
+        ("Date.now()", "Date.now() is not allowed in a pure run"),
+        (
+            "new Date()",
+            "Reading the current time is not allowed in a pure run",
+        ),
+        ("stream('users')", "stream() is not allowed in a pure run"),
+    ] {
+        let err = run_pure(code).await.unwrap_err();
+        assert!(
+            format!("{:#}", err).contains(message),
+            "{}: {:#}",
+            code,
+            err
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_pure_dates_cannot_reach_the_real_date() {
+    for code in [
+        "new (new Date(0).constructor)()",
+        "new (Object.getPrototypeOf(Object.getPrototypeOf(new Date(0))).constructor)()",
+        "new Date(0).constructor.now()",
+    ] {
+        let err = run_pure(code).await.unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("is not allowed in a pure run"),
+            "{}: {:#}",
+            code,
+            err
+        );
+    }
+
+    // The real Date is back after the pure run
+    let mut runner = Builder::new().build();
+    runner
+        .run_with_options::<_, String, String>("1", None, RunOptions::new().pure(true))
+        .await
+        .unwrap();
+    let result = runner
+        .run::<_, String, String>("new Date(0).constructor === Date && Date.now() > 0", None)
+        .await
+        .unwrap();
+    assert_eq!(result, "true");
+}
+
+#[tokio::test]
+async fn test_pure_denies_files() {
+    let fs = VirtualFs::new().with_file("/config.json", "{}");
+    let mut runner = Builder::new().virtual_fs(&fs).build();
+
+    for code in [
+        "fs.readFile('/config.json')",
+        "fs.readTextFile('/config.json')",
+        "fs.exists('/config.json')",
+        "fs.readDir('/')",
+        "fs.writeTextFile('/out.txt', 'x')",
+        "fs.remove('/config.json')",
+    ] {
+        let err = runner
+            .run_with_options::<_, String, String>(code, None, RunOptions::new().pure(true))
+            .await
+            .unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("files is not allowed in a pure run"),
+            "{}: {:#}",
+            code,
+            err
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_pure_cannot_reach_hooks_or_raw_ops() {
+    for code in [
+        "Deno.core.resetRun(); save(price)",
+        "Deno.core.ops.save(price)",
+    ] {
+        let err = run_pure(code).await.unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("TypeError"),
+            "{}: {:#}",
+            code,
+            err
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_pure_comment() {
+    let custom_code = r#"
+        // @pure
+        Math.random()
+    "#;
+
+    let mut runner = Builder::new().build();
+    let result = runner.run::<_, String, String>(custom_code, None).await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_not_pure_by_default() {
+    let mut runner = Builder::new().add_op(save::decl()).build();
+    let result = runner
+        .run::<_, String, String>("save(1) + Math.floor(Math.random())", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "1");
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "arrow")]
+
+use arrow::{
+    array::{ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray},
+    record_batch::RecordBatch,
+};
+use deno_runner::Builder;
+use std::sync::Arc;
+
+fn sales() -> RecordBatch {
+    RecordBatch::try_from_iter([
+        (
+            "region",
+            Arc::new(StringArray::from(vec![Some("north"), None, Some("south")])) as ArrayRef,
+        ),
+        (
+            "amount",
+            Arc::new(Float64Array::from(vec![Some(10.5), Some(2.0), None])) as ArrayRef,
+        ),
+        (
+            "units",
+            Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef,
+        ),
+        (
+            "paid",
+            Arc::new(BooleanArray::from(vec![true, false, true])) as ArrayRef,
+        ),
+    ])
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_record_batch_columns() {
+    let custom_code = r#"
+        const { numRows, columns } = sales;
+        [
+            numRows,
+            columns.amount instanceof Float64Array,
+            columns.units instanceof BigInt64Array,
+            columns.units.reduce((a, b) => a + b, 0n),
+            columns.paid.join(''),
+            columns.region.join('|'),
+        ].join(',')
+    "#;
+
+    let mut runner = Builder::new().record_batch("sales", sales()).build();
+    let result = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "3,true,true,6,101,north||south");
+}
+
+#[tokio::test]
+async fn test_record_batch_nulls_and_schema() {
+    let custom_code = r#"
+        const { columns, nulls, schema } = sales;
+        let total = 0;
+        for (let i = 0; i < sales.numRows; i++) {
+            if (!nulls.amount[i]) total += columns.amount[i];
+        }
+        JSON.stringify({ total, nullable: Object.keys(nulls), types: schema.map((f) => f.type) })
+    "#;
+
+    let mut runner = Builder::new().record_batch("sales", sales()).build();
+    let result = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result,
+        r#"{"total":12.5,"nullable":["region","amount"],"types":["Utf8","Float64","Int64","Boolean"]}"#
+    );
+}
+
+#[tokio::test]
+async fn test_record_batch_is_frozen() {
+    let custom_code = r#"
+        sales.columns.units = [];
+        sales.columns.units instanceof BigInt64Array && Object.isFrozen(sales)
+    "#;
+
+    let mut runner = Builder::new().record_batch("sales", sales()).build();
+    let result = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "true");
+}
@@ -0,0 +1,81 @@
+use deno_runner::{Builder, RunnerRegistry};
+use std::{collections::HashMap, time::Duration};
+
+const VISIT: &str = "globalThis.visits = (globalThis.visits ?? 0) + 1";
+
+#[tokio::test]
+async fn test_registry_keeps_runners_by_name() {
+    let registry = RunnerRegistry::new(|tenant| {
+        Builder::new()
+            .warmup(format!("globalThis.tenant = '{}'", tenant))
+            .build()
+    });
+
+    for _ in 0..2 {
+        let runner = registry.get_or_create("acme");
+        runner.run::<String, String>(VISIT, None).await.unwrap();
+    }
+    let runner = registry.get_or_create("globex");
+    assert_eq!(runner.name(), "globex");
+    assert_eq!(
+        runner.run::<String, String>(VISIT, None).await.unwrap(),
+        "1"
+    );
+
+    let acme = registry.get("acme").unwrap();
+    let vars = HashMap::from([("suffix", "!")]);
+    let result = acme
+        .run("`${tenant} ${visits}${suffix}`", Some(vars))
+        .await
+        .unwrap();
+    assert_eq!(result, "acme 2!");
+    assert_eq!(registry.names(), ["acme", "globex"]);
+}
+
+#[tokio::test]
+async fn test_registry_evicts() {
+    let registry = RunnerRegistry::new(|_| Builder::new().build()).ttl(Duration::from_millis(50));
+    let runner = registry.get_or_create("acme");
+    runner.run::<String, String>(VISIT, None).await.unwrap();
+
+    assert!(registry.evict("acme"));
+    assert!(!registry.evict("acme"));
+    assert!(registry.get("acme").is_none());
+    // The evicted runner still serves handles taken before
+    assert_eq!(
+        runner.run::<String, String>(VISIT, None).await.unwrap(),
+        "2"
+    );
+    drop(runner);
+
+    registry.get_or_create("acme");
+    std::thread::sleep(Duration::from_millis(100));
+    registry.get_or_create("globex");
+    assert_eq!(registry.names(), ["globex"]);
+
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(registry.evict_expired(), 1);
+    assert!(registry.is_empty());
+}
+
+#[tokio::test]
+async fn test_registry_keeps_busy_runners() {
+    let registry = RunnerRegistry::new(|_| Builder::new().build()).ttl(Duration::from_millis(50));
+    let runner = registry.get_or_create("acme");
+
+    let slow = "const end = Date.now() + 300; while (Date.now() < end) {}; 1";
+    let (result, evicted) = tokio::join!(runner.run::<String, String>(slow, None), async {
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        registry.evict_expired()
+    });
+    assert_eq!(result.unwrap(), "1");
+    // Still working past the TTL, so not idle
+    assert_eq!(evicted, 0);
+    // Idle from when the run finished
+    assert!(runner.idle() < Duration::from_millis(50));
+    assert_eq!(registry.evict_expired(), 0);
+
+    drop(runner);
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(registry.evict_expired(), 1);
+}
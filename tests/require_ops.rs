@@ -0,0 +1,47 @@
+use deno_runner::{op, Builder};
+
+#[op]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[test]
+fn test_required_ops_registered() {
+    Builder::new()
+        .add_op(add::decl())
+        .require_ops(&["add"])
+        .build();
+}
+
+#[test]
+#[should_panic(expected = "required op `greet` is not registered")]
+fn test_required_op_missing() {
+    Builder::new()
+        .add_op(add::decl())
+        .require_ops(&["add", "greet"])
+        .build();
+}
+
+#[tokio::test]
+async fn test_calling_missing_op() {
+    let mut runner = Builder::new().add_op(add::decl()).build();
+
+    for code in [
+        "rust('greet', 'duyet')",
+        "Deno.core.opSync('greet', 'duyet')",
+        "await rustAsync('greet')",
+    ] {
+        let err = runner
+            .run::<_, String, String>(code, None)
+            .await
+            .unwrap_err();
+
+        assert!(
+            err.to_string()
+                .contains("ReferenceError: op greet is not registered with this runner"),
+            "{}: {}",
+            code,
+            err
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use deno_runner::{op, serde_json::json, Builder, Resolvers};
+
+#[op]
+fn avatar_url(id: u32, size: u32) -> String {
+    format!("https://cdn.example.com/{}?s={}", id, size)
+}
+
+fn resolvers() -> Resolvers {
+    Resolvers::new(Builder::new().add_op(avatar_url::decl()))
+        .resolver("User.displayName", "`${parent.first} ${parent.last}`")
+        .unwrap()
+        .resolver("User.avatar", "avatar_url(parent.id, args.size ?? 64)")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_resolve_fields() {
+    let mut resolvers = resolvers();
+    let user = json!({ "id": 7, "first": "Duyet", "last": "Le" });
+
+    for _ in 0..3 {
+        let name = resolvers
+            .resolve("User.displayName", &user, &json!({}))
+            .await
+            .unwrap();
+        assert_eq!(name, json!("Duyet Le"));
+    }
+
+    let avatar = resolvers
+        .resolve("User.avatar", &user, &json!({ "size": 128 }))
+        .await
+        .unwrap();
+    assert_eq!(avatar, json!("https://cdn.example.com/7?s=128"));
+}
+
+#[tokio::test]
+async fn test_unknown_field() {
+    let err = resolvers()
+        .resolve("User.email", &json!({}), &json!({}))
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), "No resolver registered for `User.email`");
+}
+
+#[tokio::test]
+async fn test_resolver_error() {
+    let err = resolvers()
+        .resolve("User.displayName", &json!(null), &json!({}))
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), "Resolver `User.displayName` failed");
+}
+
+#[test]
+fn test_invalid_field_name() {
+    assert!(Resolvers::new(Builder::new())
+        .resolver("displayName", "1")
+        .is_err());
+}
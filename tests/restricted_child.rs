@@ -0,0 +1,96 @@
+use deno_runner::{op, Builder, MemoCache, RunOptions, VirtualFs};
+use std::time::Duration;
+
+#[op]
+fn read_report() -> String {
+    "report".to_string()
+}
+
+#[op]
+fn drop_tables() -> bool {
+    true
+}
+
+fn admin() -> deno_runner::DenoRunner {
+    Builder::new()
+        .add_op(read_report::decl())
+        .add_op(drop_tables::decl())
+        .virtual_fs(&VirtualFs::new().with_file("/notes.txt", "hi"))
+        .build()
+}
+
+#[tokio::test]
+async fn test_child_keeps_granted_ops() {
+    let mut child = admin().restricted_child(["op:read_report"]).unwrap();
+    let result = child
+        .run::<_, String, String>("read_report()", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "report");
+}
+
+#[tokio::test]
+async fn test_child_loses_other_ops() {
+    let mut child = admin().restricted_child(["op:read_report"]).unwrap();
+
+    for code in [
+        "typeof drop_tables",
+        "typeof fs",
+        "try { Deno.core.opSync('drop_tables') } catch (e) { 'denied' }",
+    ] {
+        let result = child.run::<_, String, String>(code, None).await.unwrap();
+        assert!(result == "undefined" || result == "denied", "{}", code);
+    }
+}
+
+#[tokio::test]
+async fn test_child_does_not_share_the_memo() {
+    let cache = MemoCache::new();
+    let mut parent = Builder::new()
+        .add_op(read_report::decl())
+        .memoize(&cache, Duration::from_secs(60))
+        .build();
+    let pure = || RunOptions::new().pure(true);
+
+    parent
+        .run_with_options::<_, String, String>("typeof read_report", None, pure())
+        .await
+        .unwrap();
+    let mut child = parent.restricted_child(Vec::<String>::new()).unwrap();
+    let report = child
+        .run_with_options::<_, String, String>("typeof read_report", None, pure())
+        .await
+        .unwrap();
+
+    assert!(!report.cached);
+    assert_eq!(report.result, "undefined");
+}
+
+#[tokio::test]
+async fn test_child_fs() {
+    let mut child = admin().restricted_child(["fs"]).unwrap();
+    let result = child
+        .run::<_, String, String>("fs.readTextFile('/notes.txt')", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "hi");
+}
+
+#[test]
+fn test_child_cannot_escalate() {
+    let parent = Builder::new().add_op(read_report::decl()).build();
+
+    let err = parent
+        .restricted_child(["op:read_report", "op:drop_tables"])
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "Parent runner has no capability `op:drop_tables` to grant"
+    );
+
+    assert!(parent.restricted_child(["fs"]).is_err());
+    assert!(parent.restricted_child(["net:example.com"]).is_err());
+}
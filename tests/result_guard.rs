@@ -0,0 +1,67 @@
+use deno_runner::{
+    serde_json::{json, Value},
+    Builder, Module, ProblemDetails, RejectReason, RunnerError,
+};
+
+fn no_emails(result: &Value) -> Result<(), RejectReason> {
+    match result.to_string().contains('@') {
+        true => Err(RejectReason::new("results can't contain email addresses")),
+        false => Ok(()),
+    }
+}
+
+#[tokio::test]
+async fn test_result_guard_rejects() {
+    let mut runner = Builder::new().result_guard(no_emails).build();
+
+    let err = runner
+        .run_json::<String, String>("({ owner: 'me@duyet.net' })", None)
+        .await
+        .unwrap_err();
+    match err.downcast_ref::<RunnerError>() {
+        Some(RunnerError::ResultRejected(reason)) => {
+            assert_eq!(reason.to_string(), "results can't contain email addresses")
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+
+    let problem = ProblemDetails::from_error(&err);
+    assert_eq!(problem.code, "result_rejected");
+    assert_eq!(problem.detail, "results can't contain email addresses");
+
+    let result = runner
+        .run_json::<String, String>("({ owner: 'duyet' })", None)
+        .await
+        .unwrap();
+    assert_eq!(result, json!({ "owner": "duyet" }));
+}
+
+#[tokio::test]
+async fn test_result_guard_every_run_kind() {
+    let max_len = |result: &Value| match result.to_string().len() > 16 {
+        true => Err(RejectReason::new("result too large")),
+        false => Ok(()),
+    };
+    let mut runner = Builder::new().result_guard(max_len).build();
+
+    let err = runner
+        .run::<_, String, String>("'x'.repeat(100)", None)
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Script result was rejected: result too large"));
+
+    let err = runner
+        .run_module::<String, String>(Module::Source("export default 'x'.repeat(100)"), None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("result too large"));
+
+    runner
+        .run::<_, String, String>("globalThis.big = () => 'x'.repeat(100)", None)
+        .await
+        .unwrap();
+    let err = runner.call_function("big", &[]).await.unwrap_err();
+    assert!(err.to_string().contains("result too large"));
+}
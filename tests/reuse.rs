@@ -0,0 +1,87 @@
+use deno_runner::{op, Builder, RunOptions};
+use std::collections::HashMap;
+
+#[op]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[tokio::test]
+async fn test_run_many_scripts() {
+    let custom_code = r#"
+        const total = add(a, b);
+        total
+    "#;
+
+    let mut runner = Builder::new().add_op(add::decl()).build();
+    for (a, b) in [(1, 2), (3, 4), (5, 6)] {
+        let vars = HashMap::from([("a", a), ("b", b)]);
+        let result = runner.run(custom_code, Some(vars)).await.unwrap();
+
+        assert_eq!(result, (a + b).to_string());
+    }
+}
+
+#[tokio::test]
+async fn test_globals_persist() {
+    let mut runner = Builder::new().build();
+    runner
+        .run::<_, String, String>("globalThis.counter = 1", None)
+        .await
+        .unwrap();
+    let result = runner
+        .run::<_, String, String>("++globalThis.counter", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "2");
+}
+
+#[tokio::test]
+async fn test_options_reset_between_runs() {
+    let mut runner = Builder::new().add_op(add::decl()).build();
+
+    let options = RunOptions::new().pure(true).feature_flags([("beta", true)]);
+    let report = runner
+        .run_with_options::<_, String, String>("features.isEnabled('beta')", None, options)
+        .await
+        .unwrap();
+    assert_eq!(report.result, "true");
+
+    let result = runner
+        .run::<_, String, String>(
+            "[add(1, 1), features.isEnabled('beta'), typeof Math.random()].join()",
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result, "2,false,number");
+}
+
+#[tokio::test]
+async fn test_failed_run_does_not_poison_runner() {
+    let mut runner = Builder::new().build();
+    assert!(runner
+        .run::<_, String, String>("const x = 1; throw new Error('boom')", None)
+        .await
+        .is_err());
+
+    let result = runner
+        .run::<_, String, String>("const x = 2; x", None)
+        .await
+        .unwrap();
+    assert_eq!(result, "2");
+}
+
+#[tokio::test]
+async fn test_bindings_do_not_leak() {
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("secret", 1)]);
+    runner.run("secret", Some(vars)).await.unwrap();
+
+    let result = runner
+        .run::<_, String, String>("typeof secret", None)
+        .await
+        .unwrap();
+    assert_eq!(result, "undefined");
+}
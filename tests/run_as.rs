@@ -0,0 +1,57 @@
+use deno_runner::{Builder, RunnerError};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Invoice {
+    total: f64,
+    lines: Vec<String>,
+    note: Option<String>,
+}
+
+#[tokio::test]
+async fn test_run_as() {
+    let custom_code = r#"
+        ({ total: price * qty, lines: [`${qty} x ${price}`] })
+    "#;
+
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("price", 2.5), ("qty", 4.0)]);
+    let invoice: Invoice = runner.run_as(custom_code, Some(vars)).await.unwrap();
+
+    assert_eq!(
+        invoice,
+        Invoice {
+            total: 10.0,
+            lines: vec!["4 x 2.5".to_string()],
+            note: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_run_as_shape_mismatch() {
+    let mut runner = Builder::new().build();
+    let err = runner
+        .run_as::<Invoice, String, String>("({ total: 'ten' })", None)
+        .await
+        .unwrap_err();
+
+    match err.downcast_ref::<RunnerError>() {
+        Some(RunnerError::ResultDeserialization { type_name, .. }) => {
+            assert!(type_name.ends_with("Invoice"))
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_run_as_script_error_is_not_deserialization() {
+    let mut runner = Builder::new().build();
+    let err = runner
+        .run_as::<Invoice, String, String>("missing", None)
+        .await
+        .unwrap_err();
+
+    assert!(err.downcast_ref::<RunnerError>().is_none());
+}
@@ -0,0 +1,106 @@
+use deno_runner::{op, serde_json::json, Builder, Cycles, RunOptions};
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn test_run_json() {
+    let custom_code = r#"
+        ({
+            total: price * qty,
+            items: [1, 2.5, "three", null, true],
+            nested: { when: new Date(0) },
+        })
+    "#;
+
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("price", 2), ("qty", 3)]);
+    let value = runner.run_json(custom_code, Some(vars)).await.unwrap();
+
+    assert_eq!(
+        value,
+        json!({
+            "total": 6,
+            "items": [1, 2.5, "three", null, true],
+            "nested": { "when": "1970-01-01T00:00:00.000Z" },
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_run_json_without_representation() {
+    let mut runner = Builder::new().build();
+
+    for code in ["undefined", "() => 1", "Symbol('x')"] {
+        let value = runner.run_json::<String, String>(code, None).await.unwrap();
+        assert_eq!(value, json!(null), "{}", code);
+    }
+}
+
+#[tokio::test]
+async fn test_run_json_error() {
+    let mut runner = Builder::new().build();
+    let result = runner
+        .run_json::<String, String>("JSON.stringify(1n) && 1", None)
+        .await;
+
+    assert!(result.is_err());
+}
+
+#[op]
+fn charge(amount: u32) -> u32 {
+    amount
+}
+
+#[tokio::test]
+async fn test_run_json_with_options() {
+    let mut runner = Builder::new().add_op(charge::decl()).build();
+    let options = RunOptions::new()
+        .secret("TOKEN", "s3cr3t")
+        .dry_run(true)
+        .stub("charge", 0);
+
+    let value = runner
+        .run_json_with_options::<String, String>(
+            "({ auth: `Bearer ${TOKEN}`, charged: charge(100) })",
+            None,
+            options,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(value, json!({ "auth": "Bearer [REDACTED]", "charged": 0 }));
+}
+
+#[tokio::test]
+async fn test_run_json_marks_cycles() {
+    let custom_code = r#"
+        const shared = { id: 1 }
+        const root = { name: 'root', children: [], pair: [shared, shared] }
+        root.children.push({ parent: root, self: null })
+        root.children[0].self = root.children[0]
+        root
+    "#;
+
+    let mut runner = Builder::new().build();
+    let value = runner
+        .run_json::<String, String>(custom_code, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "name": "root",
+            "children": [{ "parent": "[Circular]", "self": "[Circular]" }],
+            "pair": [{ "id": 1 }, { "id": 1 }],
+        })
+    );
+
+    let err = runner
+        .run_json_with_options::<String, String>(
+            custom_code,
+            None,
+            RunOptions::new().cycles(Cycles::Error),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("circular structure"), "{:#}", err);
+}
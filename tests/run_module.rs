@@ -0,0 +1,72 @@
+use deno_runner::{serde_json::json, Builder, Module, VirtualFs};
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn test_module_default_export() {
+    let source = r#"
+        const double = (n) => n * 2;
+        export default { total: double(price) };
+    "#;
+
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("price", 21)]);
+    let result = runner
+        .run_module(Module::Source(source), Some(vars))
+        .await
+        .unwrap();
+
+    assert_eq!(result, json!({ "total": 42 }));
+}
+
+#[tokio::test]
+async fn test_module_namespace() {
+    let source = r#"
+        export const name = "duyet";
+        export const tags = await Promise.resolve(["a", "b"]);
+    "#;
+
+    let mut runner = Builder::new().build();
+    let result = runner
+        .run_module::<String, String>(Module::Source(source), None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, json!({ "name": "duyet", "tags": ["a", "b"] }));
+}
+
+#[tokio::test]
+async fn test_module_imports() {
+    let fs = VirtualFs::new()
+        .with_file("/lib/math.js", "export const square = (n) => n * n;")
+        .with_file(
+            "/main.js",
+            "import { square } from './lib/math.js'; export default square(7);",
+        );
+
+    let mut runner = Builder::new().virtual_fs(&fs).build();
+    let from_specifier = runner
+        .run_module::<String, String>(Module::Specifier("file:///main.js"), None)
+        .await
+        .unwrap();
+    let from_source = runner
+        .run_module::<String, String>(
+            Module::Source("import { square } from './lib/math.js'; export default square(3);"),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(from_specifier, json!(49));
+    assert_eq!(from_source, json!(9));
+}
+
+#[tokio::test]
+async fn test_module_error() {
+    let mut runner = Builder::new().build();
+    let err = runner
+        .run_module::<String, String>(Module::Source("throw new Error('boom')"), None)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("boom"));
+}
@@ -0,0 +1,89 @@
+use deno_runner::{op, serde_json::json, Builder};
+use futures::StreamExt;
+use std::{collections::HashMap, time::Duration};
+
+#[op]
+async fn sleep_ms(millis: u64) {
+    tokio::time::sleep(Duration::from_millis(millis)).await;
+}
+
+#[tokio::test]
+async fn test_async_generator() {
+    let custom_code = r#"
+        (async function* () {
+            for (let page = 1; page <= pages; page++) {
+                await rustAsync("sleep_ms", 1);
+                yield { page };
+            }
+        })()
+    "#;
+
+    let mut runner = Builder::new().add_op(sleep_ms::decl()).build();
+    let vars = HashMap::from([("pages", 3)]);
+    let items: Vec<_> = runner
+        .run_stream(custom_code, Some(vars))
+        .map(Result::unwrap)
+        .collect()
+        .await;
+
+    assert_eq!(
+        items,
+        vec![
+            json!({ "page": 1 }),
+            json!({ "page": 2 }),
+            json!({ "page": 3 })
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_sync_iterable() {
+    let mut runner = Builder::new().build();
+    let items: Vec<_> = runner
+        .run_stream::<String, String>("['a', 'b']", None)
+        .map(Result::unwrap)
+        .collect()
+        .await;
+
+    assert_eq!(items, vec![json!("a"), json!("b")]);
+}
+
+#[tokio::test]
+async fn test_stream_stops_after_error() {
+    let custom_code = r#"
+        (async function* () {
+            yield 1;
+            throw new Error("page 2 failed");
+        })()
+    "#;
+
+    let mut runner = Builder::new().build();
+    let items: Vec<_> = runner
+        .run_stream::<String, String>(custom_code, None)
+        .collect()
+        .await;
+
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].as_ref().unwrap(), &json!(1));
+    assert!(items[1]
+        .as_ref()
+        .unwrap_err()
+        .to_string()
+        .contains("page 2 failed"));
+}
+
+#[tokio::test]
+async fn test_not_iterable() {
+    let mut runner = Builder::new().build();
+    let items: Vec<_> = runner
+        .run_stream::<String, String>("42", None)
+        .collect()
+        .await;
+
+    assert_eq!(items.len(), 1);
+    assert!(items[0]
+        .as_ref()
+        .unwrap_err()
+        .to_string()
+        .contains("run_stream script must evaluate to an async iterable"));
+}
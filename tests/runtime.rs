@@ -11,7 +11,7 @@ fn test_tokio_runtime() {
             add(a, b)
         "#;
 
-        let runner = Builder::new().build();
+        let mut runner = Builder::new().build();
         let vars = HashMap::from([("a", 1), ("b", 2)]);
         let result = runner.run(custom_code, Some(vars)).await.unwrap();
 
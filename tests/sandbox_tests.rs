@@ -0,0 +1,53 @@
+#![cfg(feature = "sandbox-tests")]
+
+use deno_runner::{op, sandbox_tests::run_escape_suite, Builder, NodeCompat};
+use std::time::Duration;
+
+#[op]
+fn lookup_price(sku: String) -> u32 {
+    sku.len() as u32 * 100
+}
+
+fn hardened() -> Builder {
+    Builder::new()
+        .add_op(lookup_price::decl())
+        .timeout(Duration::from_millis(500))
+        .max_heap_size(32 * 1024 * 1024)
+}
+
+#[tokio::test]
+async fn test_hardened_builder_is_contained() {
+    let report = run_escape_suite(&hardened()).await;
+
+    assert!(report.is_contained(), "{}", report);
+    assert!(report.scenario("op:lookup_price").is_some());
+}
+
+#[tokio::test]
+async fn test_breaches_are_reported() {
+    let report = run_escape_suite(&Builder::new()).await;
+
+    let breaches: Vec<&str> = report.breaches().map(|b| b.name.as_str()).collect();
+    assert_eq!(breaches, ["infinite_loop", "memory_exhaustion"]);
+}
+
+#[tokio::test]
+async fn test_hooks_and_raw_ops_are_contained() {
+    let report = run_escape_suite(&hardened()).await;
+
+    for name in ["runtime_hooks", "raw_ops"] {
+        let scenario = report.scenario(name).unwrap();
+        assert!(scenario.contained, "{}: {}", name, scenario.detail);
+    }
+}
+
+/// Variables the embedder exposed aren't a breach, `PATH` is read as is so
+/// the test doesn't have to change the environment of the other tests
+#[tokio::test]
+async fn test_allowed_env_is_not_a_breach() {
+    let builder = hardened().node_compat(NodeCompat::new().allow_env("PATH"));
+    let report = run_escape_suite(&builder).await;
+
+    let host_env = report.scenario("host_env").unwrap();
+    assert!(host_env.contained, "{}", host_env.detail);
+}
@@ -0,0 +1,107 @@
+use deno_runner::{op, serde_json, Builder, Scheduler};
+use std::time::Duration;
+
+#[op]
+async fn sleep_ms(millis: u64) {
+    tokio::time::sleep(Duration::from_millis(millis)).await;
+}
+
+fn runner() -> deno_runner::DenoRunner {
+    Builder::new().add_op(sleep_ms::decl()).build()
+}
+
+#[tokio::test]
+async fn test_waiting_session_does_not_block_others() {
+    let mut scheduler = Scheduler::new(Duration::from_millis(5));
+    let slow = scheduler.add_session(runner());
+    let fast = scheduler.add_session(runner());
+
+    scheduler.submit(slow, "await rustAsync('sleep_ms', 200); 'slow'");
+    scheduler.submit(fast, "await rustAsync('sleep_ms', 10); 'fast'");
+    scheduler.submit(fast, "'fast again'");
+
+    let finished: Vec<_> = scheduler
+        .run_until_idle()
+        .await
+        .into_iter()
+        .map(|(id, result)| (id, result.unwrap()))
+        .collect();
+
+    assert_eq!(
+        finished,
+        vec![
+            (fast, "fast".to_string()),
+            (fast, "fast again".to_string()),
+            (slow, "slow".to_string()),
+        ]
+    );
+    assert!(scheduler.is_idle());
+}
+
+#[tokio::test]
+async fn test_sessions_keep_state() {
+    let mut scheduler = Scheduler::new(Duration::from_millis(5));
+    let session = scheduler.add_session(runner());
+
+    scheduler.submit(session, "globalThis.count = 1; count");
+    scheduler.run_until_idle().await;
+    scheduler.submit(session, "count += 1; count");
+    let finished = scheduler.run_until_idle().await;
+
+    assert_eq!(finished[0].1.as_ref().unwrap(), "2");
+
+    let mut runner = scheduler.remove_session(session).unwrap();
+    let result = runner
+        .run::<_, String, String>("count", None)
+        .await
+        .unwrap();
+    assert_eq!(result, "2");
+    assert!(scheduler.metrics(session).is_none());
+}
+
+#[tokio::test]
+async fn test_metrics() {
+    let mut scheduler = Scheduler::new(Duration::from_millis(5));
+    let busy = scheduler.add_session(runner());
+    let other = scheduler.add_session(runner());
+
+    scheduler.submit(
+        busy,
+        "const end = Date.now() + 50; while (Date.now() < end) {} 1",
+    );
+    scheduler.submit(other, "await rustAsync('sleep_ms', 1); 2");
+    let finished = scheduler.run_until_idle().await;
+    assert!(finished.iter().all(|(_, result)| result.is_ok()));
+
+    let busy = scheduler.metrics(busy).unwrap();
+    assert!(busy.slices >= 1);
+    assert!(busy.overruns >= 1);
+    assert!(busy.busy >= Duration::from_millis(50));
+
+    let other = scheduler.metrics(other).unwrap();
+    assert!(other.slices >= 2);
+    assert!(other.max_wait > Duration::ZERO);
+}
+
+#[tokio::test]
+async fn test_bind_between_scripts() {
+    let mut scheduler = Scheduler::new(Duration::from_millis(5));
+    let session = scheduler.add_session(runner());
+
+    scheduler.submit(session, "typeof order");
+    scheduler
+        .bind(session, "order", &serde_json::json!({ "qty": 3 }))
+        .unwrap();
+    scheduler.submit(session, "order.qty * 2");
+    scheduler.submit(session, "order.qty + 1");
+
+    let results: Vec<_> = scheduler
+        .run_until_idle()
+        .await
+        .into_iter()
+        .map(|(_, result)| result.unwrap())
+        .collect();
+
+    assert_eq!(results, ["undefined", "6", "4"]);
+    assert!(scheduler.bind(session, "not a name", &1).is_err());
+}
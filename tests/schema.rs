@@ -0,0 +1,34 @@
+#![cfg(feature = "schemars")]
+
+use deno_runner::Builder;
+use schemars::JsonSchema;
+
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+struct User {
+    /// Display name
+    name: String,
+    age: Option<u32>,
+}
+
+#[test]
+fn test_describe_bindings() {
+    let runner = Builder::new()
+        .declare_binding::<User>("user")
+        .declare_binding::<Vec<String>>("tags")
+        .build();
+
+    let doc = runner.describe_bindings();
+
+    assert_eq!(doc["title"], "Script bindings");
+    assert_eq!(
+        doc["required"],
+        deno_runner::serde_json::json!(["tags", "user"])
+    );
+    assert_eq!(doc["properties"]["user"]["$ref"], "#/definitions/User");
+    assert_eq!(doc["properties"]["tags"]["type"], "array");
+    assert_eq!(
+        doc["definitions"]["User"]["properties"]["name"]["description"],
+        "Display name"
+    );
+}
@@ -0,0 +1,96 @@
+use deno_runner::{op, serde_json::json, testing::TestRunner, Builder, RunOptions};
+
+#[op]
+fn call_api(auth: String) -> String {
+    format!("ok {}", auth.len())
+}
+
+#[tokio::test]
+async fn test_secret_is_bound() {
+    let mut runner = Builder::new().add_op(call_api::decl()).build();
+    let options = RunOptions::new().secret("apiKey", "sk_live_123");
+    let report = runner
+        .run_with_options::<_, String, String>("call_api(apiKey)", None, options)
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, "ok 11");
+}
+
+#[tokio::test]
+async fn test_secret_redacted_from_result_and_error() {
+    let options = RunOptions::new().secret("apiKey", "sk_live_123");
+    let report = Builder::new()
+        .build()
+        .run_with_options::<_, String, String>("`key=${apiKey}`", None, options.clone())
+        .await
+        .unwrap();
+    assert_eq!(report.result, "key=[REDACTED]");
+
+    let err = Builder::new()
+        .build()
+        .run_with_options::<_, String, String>(
+            "throw new Error('bad key ' + apiKey)",
+            None,
+            options.clone(),
+        )
+        .await
+        .unwrap_err();
+    let message = format!("{:#}", err);
+    assert!(message.contains("bad key [REDACTED]"));
+    assert!(!message.contains("sk_live_123"));
+    assert!(!format!("{:?}", options).contains("sk_live_123"));
+}
+
+#[tokio::test]
+async fn test_secret_redacted_from_dry_run() {
+    let mut runner = Builder::new().add_op(call_api::decl()).build();
+    let options = RunOptions::new()
+        .secret("apiKey", "sk_live_123")
+        .dry_run(true);
+    let report = runner
+        .run_with_options::<_, String, String>("call_api(`Bearer ${apiKey}`)", None, options)
+        .await
+        .unwrap();
+
+    assert_eq!(report.op_calls[0].args, vec![json!("Bearer [REDACTED]")]);
+}
+
+#[tokio::test]
+async fn test_secret_redacted_from_console() {
+    let outcome = TestRunner::new()
+        .run_with_options::<_, String, String>(
+            "console.log(apiKey); 1",
+            None,
+            RunOptions::new().secret("apiKey", "sk_live_123"),
+        )
+        .await
+        .unwrap();
+
+    outcome.assert_stdout(&["[REDACTED]"]);
+}
+
+#[tokio::test]
+async fn test_secret_redacted_from_later_calls() {
+    let mut runner = Builder::new().build();
+    let options = RunOptions::new().secret("apiKey", "sk_live_123");
+    runner
+        .run_with_options::<_, String, String>(
+            "globalThis.stash = { auth: `Bearer ${apiKey}` }; globalThis.leak = () => { throw new Error(stash.auth) }",
+            None,
+            options,
+        )
+        .await
+        .unwrap();
+
+    let result = runner
+        .run_json::<String, String>("stash", None)
+        .await
+        .unwrap();
+    assert_eq!(result, json!({ "auth": "Bearer [REDACTED]" }));
+
+    let err = runner.call_function("leak", &[]).await.unwrap_err();
+    let message = format!("{:#}", err);
+    assert!(message.contains("Bearer [REDACTED]"));
+    assert!(!message.contains("sk_live_123"));
+}
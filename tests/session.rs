@@ -0,0 +1,131 @@
+use deno_runner::{
+    op,
+    serde_json::{self, json},
+    Builder, Checkpoint, SessionSnapshot,
+};
+use std::collections::HashMap;
+
+#[op]
+fn double(value: i32) -> i32 {
+    value * 2
+}
+
+fn builder() -> Builder {
+    Builder::new().add_op(double::decl()).persistent()
+}
+
+#[tokio::test]
+async fn test_suspend_and_resume_from_disk() {
+    let mut runner = builder().build();
+    runner
+        .run::<_, String, String>(
+            "globalThis.cart = []; globalThis.addItem = (item) => cart.push(item)",
+            None,
+        )
+        .await
+        .unwrap();
+    runner
+        .run::<_, String, String>("addItem('apple')", None)
+        .await
+        .unwrap();
+
+    let path = std::env::temp_dir().join(format!("deno_runner_session_{}.bin", std::process::id()));
+    let session = runner.suspend().unwrap();
+    assert_eq!(session.runs(), 2);
+    session.save(&path).unwrap();
+
+    let session = SessionSnapshot::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut runner = builder().resume(&session).unwrap();
+    let result = runner
+        .run::<_, String, String>(
+            "addItem('pear'); `${cart.join(',')}:${double(cart.length)}`",
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result, "apple,pear:4");
+
+    // A resumed runner can be suspended again
+    let session = runner.suspend().unwrap();
+    assert_eq!(session.runs(), 3);
+}
+
+#[tokio::test]
+async fn test_resume_with_other_ops() {
+    let session = builder().build().suspend().unwrap();
+
+    let err = Builder::new().resume(&session).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Session was saved with ops [double], the builder registers []"
+    );
+}
+
+#[test]
+fn test_suspend_requires_persistent() {
+    let err = Builder::new().build().suspend().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Only runners built with Builder::persistent can be suspended"
+    );
+}
+
+#[tokio::test]
+async fn test_checkpoint_and_restore() {
+    let mut runner = Builder::new().warmup("globalThis.version = 1").build();
+    let vars = HashMap::from([("item", "'apple'")]);
+    runner
+        .run(
+            "globalThis.cart = [item]; globalThis.total = () => cart.length",
+            Some(vars),
+        )
+        .await
+        .unwrap();
+
+    let checkpoint = runner.checkpoint().unwrap();
+    // Bindings and functions aren't saved
+    assert_eq!(
+        checkpoint.globals(),
+        &[
+            ("cart".to_string(), json!(["apple"])),
+            ("version".to_string(), json!(1))
+        ]
+        .into()
+    );
+
+    let bytes = serde_json::to_vec(&checkpoint).unwrap();
+    let checkpoint: Checkpoint = serde_json::from_slice(&bytes).unwrap();
+
+    runner
+        .run::<_, String, String>(
+            "cart.push('pear'); globalThis.coupon = 'X'; globalThis.version = 2",
+            None,
+        )
+        .await
+        .unwrap();
+    runner.restore(&checkpoint).unwrap();
+
+    let result = runner
+        .run::<_, String, String>("`${cart} ${total()} ${typeof coupon} ${version}`", None)
+        .await
+        .unwrap();
+    assert_eq!(result, "apple 1 undefined 1");
+}
+
+#[tokio::test]
+async fn test_checkpoint_cycle() {
+    let mut runner = Builder::new().build();
+    runner
+        .run::<_, String, String>("globalThis.node = {}; node.self = node", None)
+        .await
+        .unwrap();
+
+    let err = runner.checkpoint().unwrap_err();
+    assert!(
+        err.to_string().contains("Global node can't be saved"),
+        "{}",
+        err
+    );
+}
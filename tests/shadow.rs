@@ -0,0 +1,81 @@
+use deno_runner::{op, serde_json::json, Builder, ChangeKind};
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+#[tokio::test]
+async fn test_shadow_run_same_result() {
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("price", 10)]);
+    let report = runner
+        .shadow_run("price * 2", "price + price", Some(vars))
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, json!(20));
+    assert!(!report.diverged());
+}
+
+#[tokio::test]
+async fn test_shadow_run_divergence() {
+    let mut runner = Builder::new().build();
+    let vars = HashMap::from([("price", 10)]);
+    let report = runner
+        .shadow_run(
+            "({ total: price * 1.1 })",
+            "({ total: price * 1.2, currency: 'EUR' })",
+            Some(vars),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, json!({"total": 11.000000000000002}));
+    assert!(report.diverged());
+    assert_eq!(report.changes.len(), 2);
+    assert_eq!(report.changes[0].path, "/currency");
+    assert!(matches!(report.changes[1].kind, ChangeKind::Changed { .. }));
+}
+
+#[tokio::test]
+async fn test_shadow_failure_does_not_affect_result() {
+    let mut runner = Builder::new().build();
+    let report = runner
+        .shadow_run::<String, String>("1", "throw new Error('boom')", None)
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, json!(1));
+    assert!(report.shadow.unwrap_err().contains("boom"));
+}
+
+#[tokio::test]
+async fn test_primary_failure_is_returned() {
+    let mut runner = Builder::new().build();
+    let result = runner
+        .shadow_run::<String, String>("throw new Error('boom')", "1", None)
+        .await;
+
+    assert!(result.is_err());
+}
+
+static CHARGES: AtomicUsize = AtomicUsize::new(0);
+
+#[op]
+fn charge(amount: u32) -> u32 {
+    CHARGES.fetch_add(1, Ordering::SeqCst);
+    amount
+}
+
+#[tokio::test]
+async fn test_shadow_does_not_call_ops() {
+    let mut runner = Builder::new().add_op(charge::decl()).build();
+    let report = runner
+        .shadow_run::<String, String>("charge(5)", "charge(6)", None)
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, json!(5));
+    assert_eq!(report.shadow.unwrap(), json!(null));
+    assert_eq!(CHARGES.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,38 @@
+use deno_runner::{Builder, SharedBuffer};
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn test_shared_buffer_read() {
+    let table = SharedBuffer::new(vec![10, 20, 30]);
+    let mut runner = Builder::new().shared_buffer("table", &table).build();
+    let vars = HashMap::from([("index", 1)]);
+    let result = runner
+        .run("new Uint8Array(table)[index]", Some(vars))
+        .await
+        .unwrap();
+
+    assert_eq!(result, "20");
+}
+
+#[tokio::test]
+async fn test_shared_buffer_is_shared() {
+    let table = SharedBuffer::new(vec![0; 4]);
+
+    let mut writer = Builder::new().shared_buffer("table", &table).build();
+    writer
+        .run::<_, String, String>("Atomics.store(new Int32Array(table), 0, 42); true", None)
+        .await
+        .unwrap();
+
+    let mut reader = Builder::new().shared_buffer("table", &table).build();
+    let result = reader
+        .run::<_, String, String>(
+            "table instanceof SharedArrayBuffer && Atomics.load(new Int32Array(table), 0)",
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result, "42");
+    assert_eq!(table.to_vec(), 42i32.to_ne_bytes());
+}
@@ -0,0 +1,73 @@
+use deno_runner::{op, Builder};
+use std::collections::HashMap;
+
+#[op]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[tokio::test]
+async fn test_runner_from_snapshot() {
+    let snapshot = Builder::new().add_op(add::decl()).snapshot();
+    assert!(!snapshot.is_empty());
+
+    for value in [1, 2] {
+        let mut runner = Builder::from_snapshot(&snapshot).build();
+        assert!(runner.build_report().snapshot_load.is_some());
+
+        let vars = HashMap::from([("value", value)]);
+        let result = runner
+            .run("add(value, 40) + ' ' + typeof parallel", Some(vars))
+            .await
+            .unwrap();
+        assert_eq!(result, format!("{} function", value + 40));
+    }
+}
+
+#[tokio::test]
+async fn test_snapshot_keeps_per_build_settings() {
+    let snapshot = Builder::new().snapshot();
+    let mut runner = Builder::from_snapshot(&snapshot).parallel_limit(3).build();
+
+    let result = runner
+        .run::<_, String, String>("parallel.defaultLimit", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "3");
+}
+
+#[test]
+#[should_panic(expected = "ops must be registered before taking the snapshot")]
+fn test_snapshot_ops_mismatch() {
+    let snapshot = Builder::new().snapshot();
+    Builder::from_snapshot(&snapshot)
+        .add_op(add::decl())
+        .build();
+}
+
+#[tokio::test]
+async fn test_layered_snapshots() {
+    let base = Builder::new().add_op(add::decl()).snapshot();
+    let acme = base
+        .layer("function price(n) { return add(n, 1) }")
+        .unwrap()
+        .layer("globalThis.tenant = 'acme'")
+        .unwrap();
+    let globex = base
+        .layer("function price(n) { return add(n, 2) }")
+        .unwrap();
+    assert_eq!((base.layers(), acme.layers()), (0, 2));
+
+    for (snapshot, expected) in [(&acme, "11 acme"), (&globex, "12 undefined")] {
+        let mut runner = Builder::from_snapshot(snapshot).build();
+        let result = runner
+            .run::<_, String, String>("`${price(10)} ${globalThis.tenant}`", None)
+            .await
+            .unwrap();
+        assert_eq!(result, expected);
+    }
+
+    let err = base.layer("throw new Error('broken')").unwrap_err();
+    assert!(err.to_string().contains("broken"));
+}
@@ -0,0 +1,51 @@
+use deno_runner::{Builder, RunOptions, RunnerError};
+
+// `input.ts` was `const x = 1;\nthrow new Error("boom")`, the host added a
+// line on top
+const CODE: &str = "'use strict';\nconst x = 1;\nthrow new Error(\"boom\")";
+const MAP: &str = r#"{"version":3,"sources":["input.ts"],"names":[],"mappings":";AAAA;AACA"}"#;
+
+#[tokio::test]
+async fn test_source_mapped_stack() {
+    let mut runner = Builder::new().build();
+    let options = RunOptions::new()
+        .script_name("bundle.js")
+        .source_map(MAP)
+        .unwrap();
+    let err = runner
+        .run_with_options::<_, String, String>(CODE, None, options)
+        .await
+        .unwrap_err();
+
+    match err.downcast_ref::<RunnerError>() {
+        Some(RunnerError::Execution(error)) => {
+            assert_eq!(error.resource_name.as_deref(), Some("input.ts"));
+            assert_eq!(error.line, Some(2));
+            assert_eq!(error.column, Some(1));
+            assert!(error.to_string().contains("at input.ts:2:1"), "{}", error);
+            assert!(!error.to_string().contains("bundle.js"), "{}", error);
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_without_source_map() {
+    let mut runner = Builder::new().build();
+    let options = RunOptions::new().script_name("bundle.js");
+    let err = runner
+        .run_with_options::<_, String, String>(CODE, None, options)
+        .await
+        .unwrap_err();
+
+    match err.downcast_ref::<RunnerError>() {
+        Some(RunnerError::Execution(error)) => assert_eq!(error.line, Some(3)),
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+#[test]
+fn test_invalid_source_map() {
+    let err = RunOptions::new().source_map("{ not json").unwrap_err();
+    assert!(err.to_string().starts_with("Invalid source map"));
+}
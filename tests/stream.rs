@@ -0,0 +1,121 @@
+use deno_runner::serde_json::Value;
+use deno_runner::{
+    anyhow::{anyhow, Result},
+    Builder,
+};
+
+fn numbers(count: u32) -> impl futures::Stream<Item = Result<u32>> {
+    futures::stream::iter((1..=count).map(Ok))
+}
+
+#[tokio::test]
+async fn test_stream_is_async_iterable() {
+    let custom_code = r#"
+        typeof stream("numbers", 3)[Symbol.asyncIterator]
+    "#;
+
+    let mut runner = Builder::new().add_stream("numbers", numbers).build();
+    let result = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "function");
+}
+
+#[tokio::test]
+async fn test_unregistered_stream() {
+    let custom_code = r#"
+        try {
+            Deno.core.opSync("op_stream_open", "missing", null);
+        } catch (e) {
+            e.message
+        }
+    "#;
+
+    let mut runner = Builder::new()
+        .add_stream("numbers", |_: Value| numbers(1))
+        .build();
+    let result = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result,
+        "Stream 'missing' is not registered with this runner"
+    );
+}
+
+#[tokio::test]
+async fn test_for_await_drains_in_order() {
+    let custom_code = r#"
+        (async () => {
+            const items = []
+            for await (const n of stream("numbers", 5)) items.push(n)
+            return items.join(",")
+        })()
+    "#;
+
+    let mut runner = Builder::new().add_stream("numbers", numbers).build();
+    let result = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "1,2,3,4,5");
+}
+
+#[tokio::test]
+async fn test_error_part_way() {
+    let custom_code = r#"
+        (async () => {
+            const items = []
+            const pages = stream("pages")[Symbol.asyncIterator]()
+            try {
+                for (;;) {
+                    const { done, value } = await pages.next()
+                    if (done) break
+                    items.push(value)
+                }
+            } catch (e) {
+                items.push(e.message)
+            }
+            const after = await pages.next()
+            return `${items.join(",")} then done=${after.done}`
+        })()
+    "#;
+
+    let mut runner = Builder::new()
+        .add_stream("pages", |_: Value| {
+            futures::stream::iter(vec![Ok(1), Ok(2), Err(anyhow!("page 3 failed"))])
+        })
+        .build();
+    let result = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "1,2,page 3 failed then done=true");
+}
+
+#[tokio::test]
+async fn test_uncaught_error_fails_the_run() {
+    let custom_code = r#"
+        (async () => {
+            for await (const page of stream("pages")) {}
+        })()
+    "#;
+
+    let mut runner = Builder::new()
+        .add_stream("pages", |_: Value| {
+            futures::stream::iter(vec![Ok(1), Err(anyhow!("page 2 failed"))])
+        })
+        .build();
+    let err = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap_err();
+
+    assert!(format!("{:#}", err).contains("page 2 failed"));
+}
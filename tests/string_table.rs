@@ -0,0 +1,56 @@
+use deno_runner::{op, Builder, OpState, StringTable};
+use std::collections::HashMap;
+
+#[op]
+fn next_status(state: &mut OpState, status: u32) -> u32 {
+    let strings = state.borrow::<StringTable>();
+    match strings.get(status) {
+        Some("pending") => strings.id("active").unwrap(),
+        _ => strings.id("done").unwrap(),
+    }
+}
+
+fn runner() -> deno_runner::DenoRunner {
+    Builder::new()
+        .add_op(next_status::decl())
+        .string_table(StringTable::new(["pending", "active", "done"]))
+        .build()
+}
+
+#[tokio::test]
+async fn test_string_table_round_trip() {
+    let custom_code = r#"
+        let id = strings.id(status);
+        const seen = [];
+        for (let i = 0; i < 3; i++) {
+            id = next_status(id);
+            seen.push(strings.get(id));
+        }
+        seen.join(",")
+    "#;
+
+    let vars = HashMap::from([("status", "pending")]);
+    let result = runner().run(custom_code, Some(vars)).await.unwrap();
+
+    assert_eq!(result, "active,done,done");
+}
+
+#[tokio::test]
+async fn test_unknown_string() {
+    let result = runner()
+        .run::<_, String, String>("strings.id('archived')", None)
+        .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_no_table_no_global() {
+    let result = Builder::new()
+        .build()
+        .run::<_, String, String>("typeof strings", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "undefined");
+}
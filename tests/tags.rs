@@ -0,0 +1,49 @@
+use deno_runner::{Builder, RunOptions};
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn test_tags_in_report() {
+    let mut runner = Builder::new().build();
+    let options = RunOptions::new().tag("tenant", "acme").tag("job", 42);
+    let report = runner
+        .run_with_options::<_, String, String>("1 + 1", None, options)
+        .await
+        .unwrap();
+
+    assert_eq!(report.result, "2");
+    assert_eq!(report.tags["tenant"], "acme");
+    assert_eq!(report.tags["job"], "42");
+    assert_eq!(report.run_id.len(), 16);
+}
+
+#[tokio::test]
+async fn test_run_ids_are_unique() {
+    let first = Builder::new()
+        .build()
+        .run_with_options::<_, String, String>("1", None, RunOptions::new())
+        .await
+        .unwrap();
+    let second = Builder::new()
+        .build()
+        .run_with_options::<_, String, String>("1", None, RunOptions::new())
+        .await
+        .unwrap();
+
+    assert_ne!(first.run_id, second.run_id);
+}
+
+#[tokio::test]
+async fn test_tags_in_error() {
+    let mut runner = Builder::new().build();
+    let options = RunOptions::new().tag("tenant", "acme");
+    let vars = HashMap::from([("a", 1)]);
+    let err = runner
+        .run_with_options("a + missing", Some(vars), options)
+        .await
+        .unwrap_err();
+
+    let message = err.to_string();
+    assert!(message.starts_with("run "));
+    assert!(message.ends_with("(tenant=acme) failed"));
+    assert!(format!("{:#}", err).contains("missing is not defined"));
+}
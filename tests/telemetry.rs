@@ -0,0 +1,106 @@
+use deno_runner::{
+    anyhow, Builder, MemoCache, RunInfo, RunOptions, RunStats, StartupKind, TelemetryExporter,
+};
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+#[derive(Clone, Default)]
+struct Recorder(Rc<RefCell<Vec<String>>>);
+
+impl TelemetryExporter for Recorder {
+    fn run_started(&self, run: &RunInfo) {
+        self.0
+            .borrow_mut()
+            .push(format!("started tenant={}", run.tags["tenant"]));
+    }
+
+    fn run_finished(&self, _run: &RunInfo, stats: &RunStats) {
+        self.0
+            .borrow_mut()
+            .push(format!("finished exit={:?}", stats.exit_code));
+    }
+
+    fn run_failed(&self, _run: &RunInfo, _stats: &RunStats, error: &anyhow::Error) {
+        self.0.borrow_mut().push(format!("failed {:#}", error));
+    }
+}
+
+#[tokio::test]
+async fn test_run_finished() {
+    let recorder = Recorder::default();
+    let mut runner = Builder::new().telemetry(recorder.clone()).build();
+    let options = RunOptions::new().tag("tenant", "acme");
+    runner
+        .run_with_options::<_, String, String>("exit(3, 'early')", None, options)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        *recorder.0.borrow(),
+        vec!["started tenant=acme", "finished exit=Some(3)"]
+    );
+}
+
+#[tokio::test]
+async fn test_run_failed() {
+    let recorder = Recorder::default();
+    let mut runner = Builder::new().telemetry(recorder.clone()).build();
+    let options = RunOptions::new().tag("tenant", "acme");
+    let result = runner
+        .run_with_options::<_, String, String>("missing", None, options)
+        .await;
+
+    assert!(result.is_err());
+    let events = recorder.0.borrow();
+    assert_eq!(events.len(), 2);
+    assert!(events[1].starts_with("failed run "));
+    assert!(events[1].contains("missing is not defined"));
+}
+
+#[derive(Clone, Default)]
+struct StartupKinds(Rc<RefCell<Vec<StartupKind>>>);
+
+impl TelemetryExporter for StartupKinds {
+    fn run_finished(&self, _run: &RunInfo, stats: &RunStats) {
+        self.0.borrow_mut().push(stats.startup_kind);
+    }
+
+    fn run_failed(&self, _run: &RunInfo, stats: &RunStats, _error: &anyhow::Error) {
+        self.0.borrow_mut().push(stats.startup_kind);
+    }
+}
+
+#[tokio::test]
+async fn test_startup_kind() {
+    let kinds = StartupKinds::default();
+    let cache = MemoCache::new();
+    let mut runner = Builder::new()
+        .telemetry(kinds.clone())
+        .memoize(&cache, Duration::from_secs(60))
+        .build();
+    for _ in 0..2 {
+        runner
+            .run::<_, String, String>("1 + 1", None)
+            .await
+            .unwrap();
+    }
+    runner
+        .run::<_, String, String>("missing", None)
+        .await
+        .unwrap_err();
+
+    let snapshot = Builder::new().snapshot();
+    let mut runner = Builder::from_snapshot(&snapshot)
+        .telemetry(kinds.clone())
+        .build();
+    runner.run::<_, String, String>("1", None).await.unwrap();
+
+    assert_eq!(
+        *kinds.0.borrow(),
+        [
+            StartupKind::Cold,
+            StartupKind::Cached,
+            StartupKind::Warm,
+            StartupKind::Snapshot
+        ]
+    );
+}
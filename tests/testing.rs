@@ -0,0 +1,80 @@
+use deno_runner::{op, testing::TestRunner, Builder};
+use std::collections::HashMap;
+
+#[op]
+fn greet(name: String) -> String {
+    format!("hello {}", name)
+}
+
+#[tokio::test]
+async fn test_virtual_clock() {
+    let outcome = TestRunner::new()
+        .now(1_700_000_000_000)
+        .run::<_, String, String>("console.log(new Date().toISOString()); Date.now()", None)
+        .await
+        .unwrap();
+
+    outcome
+        .assert_result("1700000000000")
+        .assert_stdout(&["2023-11-14T22:13:20.000Z"]);
+}
+
+#[tokio::test]
+async fn test_explicit_dates_are_untouched() {
+    let outcome = TestRunner::new()
+        .run::<_, String, String>("new Date(0).getTime()", None)
+        .await
+        .unwrap();
+
+    outcome.assert_result("0");
+}
+
+#[tokio::test]
+async fn test_seeded_random() {
+    let code = "[Math.random(), Math.random(), Math.random()].join(',')";
+    let harness = TestRunner::new().seed(7);
+
+    let first = harness.run::<_, String, String>(code, None).await.unwrap();
+    let second = harness.run::<_, String, String>(code, None).await.unwrap();
+    let other = TestRunner::new()
+        .seed(8)
+        .run::<_, String, String>(code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(first.result, second.result);
+    assert_ne!(first.result, other.result);
+}
+
+#[tokio::test]
+async fn test_captured_console() {
+    let custom_code = r#"
+        console.log(greet(name));
+        console.group();
+        console.info("nested");
+        console.groupEnd();
+        console.warn("careful");
+        1
+    "#;
+
+    let harness = TestRunner::with_builder(Builder::new().add_op(greet::decl()));
+    let vars = HashMap::from([("name", "duyet")]);
+    let outcome = harness.run(custom_code, Some(vars)).await.unwrap();
+
+    outcome
+        .assert_result("1")
+        .assert_stdout(&["hello duyet", "  nested"])
+        .assert_stdout_contains("hello");
+    assert_eq!(outcome.stderr(), vec!["careful"]);
+}
+
+#[tokio::test]
+#[should_panic(expected = "unexpected stderr")]
+async fn test_assert_no_stderr_panics() {
+    let outcome = TestRunner::new()
+        .run::<_, String, String>("console.error('boom')", None)
+        .await
+        .unwrap();
+
+    outcome.assert_no_stderr();
+}
@@ -0,0 +1,60 @@
+use deno_runner::Builder;
+
+async fn run(code: &str) -> String {
+    Builder::new()
+        .build()
+        .run::<_, String, String>(code, None)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_round_trip() {
+    let custom_code = r#"
+        const bytes = new TextEncoder().encode("héllo €");
+        `${bytes.length} ${new TextDecoder().decode(bytes)}`
+    "#;
+
+    assert_eq!(run(custom_code).await, "10 héllo €");
+}
+
+#[tokio::test]
+async fn test_encode_into() {
+    let custom_code = r#"
+        const target = new Uint8Array(4);
+        const { read, written } = new TextEncoder().encodeInto("a€b", target);
+        `${read} ${written} ${target.join(",")}`
+    "#;
+
+    assert_eq!(run(custom_code).await, "2 4 97,226,130,172");
+}
+
+#[tokio::test]
+async fn test_fatal_decoder() {
+    let custom_code = r#"
+        const invalid = new Uint8Array([0x61, 0xff]);
+        const lossy = new TextDecoder().decode(invalid);
+        let error;
+        try {
+            new TextDecoder("utf-8", { fatal: true }).decode(invalid);
+        } catch (err) {
+            error = err.constructor.name;
+        }
+        `${lossy === "a�"} ${error}`
+    "#;
+
+    assert_eq!(run(custom_code).await, "true TypeError");
+}
+
+#[tokio::test]
+async fn test_unsupported_encoding() {
+    let custom_code = r#"
+        try {
+            new TextDecoder("latin1");
+        } catch (err) {
+            err.constructor.name;
+        }
+    "#;
+
+    assert_eq!(run(custom_code).await, "RangeError");
+}
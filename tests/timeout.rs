@@ -0,0 +1,61 @@
+use deno_runner::{op, Builder, RunOptions, RunnerError};
+use std::time::Duration;
+
+#[op]
+async fn sleep_ms(millis: u64) {
+    tokio::time::sleep(Duration::from_millis(millis)).await;
+}
+
+fn assert_timeout(err: deno_runner::anyhow::Error, limit: Duration) {
+    match err.downcast_ref::<RunnerError>() {
+        Some(RunnerError::Timeout(actual)) => assert_eq!(*actual, limit),
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_busy_loop_times_out() {
+    let limit = Duration::from_millis(100);
+    let mut runner = Builder::new().timeout(limit).build();
+
+    let err = runner
+        .run::<_, String, String>("while (true) {}", None)
+        .await
+        .unwrap_err();
+    assert_timeout(err, limit);
+
+    // The isolate is usable again
+    let result = runner
+        .run::<_, String, String>("1 + 1", None)
+        .await
+        .unwrap();
+    assert_eq!(result, "2");
+}
+
+#[tokio::test]
+async fn test_pending_promise_times_out() {
+    let limit = Duration::from_millis(100);
+    let mut runner = Builder::new().add_op(sleep_ms::decl()).build();
+
+    let err = runner
+        .run_with_options::<_, String, String>(
+            "Deno.core.opAsync('sleep_ms', 60_000)",
+            None,
+            RunOptions::new().timeout(limit),
+        )
+        .await
+        .unwrap_err();
+
+    assert_timeout(err, limit);
+}
+
+#[tokio::test]
+async fn test_run_within_limit() {
+    let mut runner = Builder::new().timeout(Duration::from_secs(5)).build();
+    let result = runner
+        .run::<_, String, String>("[1, 2, 3].reduce((a, b) => a + b)", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "6");
+}
@@ -0,0 +1,78 @@
+use deno_runner::{Builder, RunnerError};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_set_timeout() {
+    let custom_code = r#"
+        const delayed = await new Promise((resolve) => setTimeout(resolve, 20, "done"));
+        delayed
+    "#;
+
+    let mut runner = Builder::new().build();
+    let result: String = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "done");
+}
+
+#[tokio::test]
+async fn test_timers_run_before_returning() {
+    let custom_code = r#"
+        globalThis.ticks = 0;
+        const id = setInterval(() => {
+            if (++ticks === 3) clearInterval(id);
+        }, 5);
+        const cleared = setTimeout(() => { ticks = 100 }, 10_000);
+        clearTimeout(cleared);
+        "started"
+    "#;
+
+    let mut runner = Builder::new().build();
+    runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    let ticks = runner
+        .run::<_, String, String>("ticks", None)
+        .await
+        .unwrap();
+    assert_eq!(ticks, "3");
+}
+
+#[tokio::test]
+async fn test_endless_interval_times_out() {
+    let mut runner = Builder::new().timeout(Duration::from_millis(100)).build();
+    let err = runner
+        .run::<_, String, String>("setInterval(() => {}, 10); 1", None)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<RunnerError>(),
+        Some(RunnerError::Timeout(_))
+    ));
+
+    // The interval doesn't outlive the run
+    let result = runner.run::<_, String, String>("2", None).await.unwrap();
+    assert_eq!(result, "2");
+}
+
+#[tokio::test]
+async fn test_huge_delays_saturate() {
+    let custom_code = r#"
+        const ids = [1e300, Number.MAX_SAFE_INTEGER, 2 ** 40].map((delay) => setTimeout(() => {}, delay));
+        ids.forEach(clearTimeout);
+        ids.length
+    "#;
+
+    let mut runner = Builder::new().build();
+    let result = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "3");
+}
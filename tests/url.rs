@@ -0,0 +1,24 @@
+#![cfg(feature = "url")]
+
+use deno_runner::Builder;
+
+#[tokio::test]
+async fn test_url_globals() {
+    let custom_code = r#"
+        const url = new URL("/search?q=deno", "https://example.com");
+        url.searchParams.append("page", "2");
+        const params = new URLSearchParams(url.search);
+        `${url.host} ${url.pathname} ${params.get("q")} ${url.href}`
+    "#;
+
+    let mut runner = Builder::new().build();
+    let result: String = runner
+        .run::<_, String, String>(custom_code, None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result,
+        "example.com /search deno https://example.com/search?q=deno&page=2"
+    );
+}
@@ -0,0 +1,52 @@
+use deno_runner::{Builder, VirtualFs};
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn test_read_and_write() {
+    let custom_code = r#"
+        const config = JSON.parse(fs.readTextFile("/config.json"));
+        fs.writeTextFile("/out/report.txt", `${config.name}: ${total}`);
+        fs.readDir("/").join(",")
+    "#;
+
+    let fs = VirtualFs::new().with_file("/config.json", r#"{"name": "daily"}"#);
+    let mut runner = Builder::new().virtual_fs(&fs).build();
+    let vars = HashMap::from([("total", 42)]);
+    let result = runner.run(custom_code, Some(vars)).await.unwrap();
+
+    assert_eq!(result, "config.json,out");
+    assert_eq!(fs.read("/out/report.txt").unwrap(), b"daily: 42");
+}
+
+#[tokio::test]
+async fn test_sandboxed_paths() {
+    let fs = VirtualFs::new();
+    let mut runner = Builder::new().virtual_fs(&fs).build();
+    let result = runner
+        .run::<_, String, String>("fs.exists('../../etc/passwd')", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "false");
+}
+
+#[tokio::test]
+async fn test_missing_file() {
+    let mut runner = Builder::new().virtual_fs(&VirtualFs::new()).build();
+    let result = runner
+        .run::<_, String, String>("fs.readTextFile('/nope.txt')", None)
+        .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_no_fs_by_default() {
+    let result = Builder::new()
+        .build()
+        .run::<_, String, String>("typeof fs", None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "undefined");
+}
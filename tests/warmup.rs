@@ -0,0 +1,24 @@
+use deno_runner::Builder;
+
+#[tokio::test]
+async fn test_warmup_defines_functions() {
+    let mut runner = Builder::new()
+        .warmup("function score(n) { return n * 10 }\nfor (let i = 0; i < 1000; i++) score(i)")
+        .warmup("globalThis.warmedUp = true")
+        .build();
+
+    let result = runner
+        .run::<_, String, String>("`${score(4)}:${warmedUp}`", None)
+        .await
+        .unwrap();
+    assert_eq!(result, "40:true");
+
+    let report = runner.build_report();
+    assert!(report.total >= report.prelude + report.warmup);
+}
+
+#[test]
+#[should_panic(expected = "warmup script failed")]
+fn test_failing_warmup() {
+    Builder::new().warmup("undefinedFunction()").build();
+}